@@ -0,0 +1,6 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use qoi::fuzzing::{fuzz_image, FuzzImage};
+
+fuzz_target!(|image: FuzzImage| fuzz_image(&image));
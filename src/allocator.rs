@@ -0,0 +1,63 @@
+use allocator_api2::alloc::Allocator;
+use allocator_api2::vec::Vec;
+
+use crate::decode::{Decoder, Reader};
+use crate::encode::Encoder;
+use crate::error::{Error, Result};
+use crate::header::{Dimension, Header};
+
+/// Allocates a zero-filled vector in a given allocator, failing gracefully if that's impossible.
+fn try_vec_zeroed_in<A: Allocator>(len: usize, alloc: A) -> Result<Vec<u8, A>> {
+    let mut out = Vec::new_in(alloc);
+    out.try_reserve_exact(len).map_err(|_| Error::OutOfMemory)?;
+    out.resize(len, 0);
+    Ok(out)
+}
+
+/// Encode the image into a vector allocated with a custom allocator.
+///
+/// Same as [`encode_to_vec`](crate::encode_to_vec), but the output is allocated with `alloc`
+/// (e.g. an arena or a bump allocator) instead of the global allocator.
+#[inline]
+pub fn encode_to_vec_in<A: Allocator>(
+    data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension, alloc: A,
+) -> Result<Vec<u8, A>> {
+    Encoder::new(&data, width, height)?.encode_to_vec_in(alloc)
+}
+
+/// Decode the image into a vector allocated with a custom allocator.
+///
+/// Same as [`decode_to_vec`](crate::decode_to_vec), but the output is allocated with `alloc`
+/// (e.g. an arena or a bump allocator) instead of the global allocator.
+#[inline]
+pub fn decode_to_vec_in<A: Allocator>(
+    data: impl AsRef<[u8]>, alloc: A,
+) -> Result<(Header, Vec<u8, A>)> {
+    let mut decoder = Decoder::new(&data)?;
+    let out = decoder.decode_to_vec_in(alloc)?;
+    Ok((*decoder.header(), out))
+}
+
+impl Encoder<'_> {
+    /// Encodes the image into a vector allocated with a custom allocator and returns it.
+    ///
+    /// The vector is allocated once with [`Encoder::required_buf_len`] bytes and then shrunk
+    /// in length (but not in capacity) to the encoded size.
+    #[inline]
+    pub fn encode_to_vec_in<A: Allocator>(&mut self, alloc: A) -> Result<Vec<u8, A>> {
+        let mut out = try_vec_zeroed_in(self.required_buf_len(), alloc)?;
+        let size = self.encode_to_buf(&mut out)?;
+        out.truncate(size);
+        Ok(out)
+    }
+}
+
+impl<R: Reader> Decoder<R> {
+    /// Decodes the image into a vector allocated with a custom allocator and returns it.
+    #[inline]
+    pub fn decode_to_vec_in<A: Allocator>(&mut self, alloc: A) -> Result<Vec<u8, A>> {
+        let mut out = try_vec_zeroed_in(self.required_buf_len(), alloc)?;
+        let _ = self.decode_to_buf(&mut out)?;
+        Ok(out)
+    }
+}
@@ -0,0 +1,167 @@
+//! Cheap content analysis of encoded images.
+//!
+//! The functions here walk the op stream directly instead of decoding into a pixel buffer,
+//! so runs of identical pixels are handled in one step and no pixel buffer is allocated.
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::consts::{QOI_PADDING, QOI_PADDING_SIZE};
+use crate::decode::Decoder;
+use crate::error::{Error, Result};
+#[cfg(any(feature = "alloc", feature = "std"))]
+use crate::header::Header;
+use crate::ops::OpDecoder;
+#[cfg(any(feature = "alloc", feature = "std"))]
+use crate::ops::OpKind;
+use crate::rect::Rect;
+#[cfg(any(feature = "alloc", feature = "std"))]
+use crate::utils::try_vec_with_capacity;
+
+/// Side of the square blocks of pixels [`explain`] attributes the encoded bytes to.
+pub const EXPLAIN_BLOCK_SIZE: u16 = 16;
+
+/// Suggest a crop removing uniform borders (letterboxing / pillarboxing) from an image.
+///
+/// The color of the top-left pixel is taken as the border color, and the returned rect is
+/// the smallest one containing every pixel of a different color (compared exactly, alpha
+/// included). It covers the whole image if there is no border to trim, and `None` is
+/// returned if the image consists of the border color only, leaving nothing to keep.
+///
+/// Runs are skipped as a whole, so this is cheap on the typical letterboxed frame where the
+/// bars are encoded as a handful of long runs.
+pub fn suggest_crop(data: impl AsRef<[u8]>) -> Result<Option<Rect>> {
+    let decoder = Decoder::new(&data)?;
+    let header = *decoder.header();
+    let (width, n_pixels) = (header.width as usize, header.n_pixels());
+    let mut ops = OpDecoder::new(decoder.data());
+    let mut border = None;
+    // inclusive bounds of the non-border pixels as (left, top, right, bottom)
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    let mut i = 0;
+    while i < n_pixels {
+        let op = ops.next_op()?;
+        let n = op.n_pixels.min(n_pixels - i);
+        if op.px != *border.get_or_insert(op.px) {
+            let (first, last) = (i, i + n - 1);
+            let (top, bottom) = (first / width, last / width);
+            // a span reaching into the next row touches both ends of a row
+            let (left, right) =
+                if top == bottom { (first % width, last % width) } else { (0, width - 1) };
+            bounds = Some(bounds.map_or((left, top, right, bottom), |(min_x, y, max_x, _)| {
+                (min_x.min(left), y, max_x.max(right), bottom)
+            }));
+        }
+        i += n;
+    }
+
+    check_padding(decoder.data(), ops.offset(), n_pixels)?;
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(bounds.map(|(left, top, right, bottom)| Rect {
+        x: left as u16,
+        y: top as u16,
+        width: (right + 1 - left) as u16,
+        height: (bottom + 1 - top) as u16,
+    }))
+}
+
+/// Number of ops of a given kind in an image and what they cost, see [`Explanation::op_cost`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct OpCost {
+    /// Number of ops
+    pub n_ops: usize,
+    /// Number of encoded bytes taken by the ops
+    pub n_bytes: usize,
+    /// Number of pixels produced by the ops
+    pub n_pixels: usize,
+}
+
+/// Breakdown of the size of an encoded image, see [`explain`].
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+    /// Header of the image
+    pub header: Header,
+    /// Number of columns of blocks, the last one being narrower if the width isn't a multiple
+    /// of [`EXPLAIN_BLOCK_SIZE`]
+    pub cols: usize,
+    /// Number of rows of blocks, the last one being shorter if the height isn't a multiple of
+    /// [`EXPLAIN_BLOCK_SIZE`]
+    pub rows: usize,
+    /// Op stream bytes attributed to each block, row by row
+    pub block_bytes: Vec<usize>,
+    /// Bytes outside of the op stream: header, end marker and extension block
+    pub overhead: usize,
+    op_costs: [OpCost; 6],
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+impl Explanation {
+    /// Returns what the ops of a given kind cost over the whole image.
+    #[inline]
+    pub const fn op_cost(&self, kind: OpKind) -> OpCost {
+        self.op_costs[kind as usize]
+    }
+
+    /// Returns the encoded size of a block in bits per pixel, for drawing a heatmap.
+    ///
+    /// For reference, raw pixels take 24 or 32 bits; blocks well above that are made of
+    /// content QOI can't predict (noise, dithering, gradients in several channels at once).
+    #[allow(clippy::cast_precision_loss)]
+    pub fn bits_per_pixel(&self, col: usize, row: usize) -> Option<f32> {
+        if col >= self.cols || row >= self.rows {
+            return None;
+        }
+        let block = EXPLAIN_BLOCK_SIZE as usize;
+        let block_width = block.min(self.header.width as usize - col * block);
+        let block_height = block.min(self.header.height as usize - row * block);
+        let n_bits = self.block_bytes[row * self.cols + col] * 8;
+        Some(n_bits as f32 / (block_width * block_height) as f32)
+    }
+}
+
+/// Attribute the bytes of an encoded image to the regions of the image they encode.
+///
+/// The image is split into blocks of [`EXPLAIN_BLOCK_SIZE`] pixels squared, and the size of
+/// every op is added to the block containing its first pixel (a run crossing several blocks
+/// only costs a byte, so where it's counted hardly matters). Blocks with a lot of bytes per
+/// pixel are the parts of the image that defeat the compression, which makes for a quick
+/// "why is this file so big" heatmap; the totals per op kind tell the same story globally.
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub fn explain(data: impl AsRef<[u8]>) -> Result<Explanation> {
+    let data = data.as_ref();
+    let decoder = Decoder::new(data)?;
+    let header = *decoder.header();
+    let (width, n_pixels) = (header.width as usize, header.n_pixels());
+    let block = EXPLAIN_BLOCK_SIZE as usize;
+    let cols = (width + block - 1) / block;
+    let rows = (header.height as usize + block - 1) / block;
+    let mut block_bytes = try_vec_with_capacity(cols * rows)?;
+    block_bytes.resize(cols * rows, 0);
+    let mut op_costs = [OpCost::default(); 6];
+    let mut ops = OpDecoder::new(decoder.data());
+    let mut i = 0;
+    while i < n_pixels {
+        let op = ops.next_op()?;
+        let n = op.n_pixels.min(n_pixels - i);
+        let n_bytes = op.kind.n_bytes();
+        let (x, y) = (i % width, i / width);
+        block_bytes[(y / block) * cols + x / block] += n_bytes;
+        let cost = &mut op_costs[op.kind as usize];
+        cost.n_ops += 1;
+        cost.n_bytes += n_bytes;
+        cost.n_pixels += n;
+        i += n;
+    }
+    check_padding(decoder.data(), ops.offset(), n_pixels)?;
+    let overhead = data.len() - ops.offset();
+    Ok(Explanation { header, cols, rows, block_bytes, overhead, op_costs })
+}
+
+fn check_padding(data: &[u8], offset: usize, pixel: usize) -> Result<()> {
+    match data.get(offset..offset + QOI_PADDING_SIZE) {
+        None => Err(Error::UnexpectedBufferEnd { offset, pixel }),
+        Some(padding) if padding != QOI_PADDING => Err(Error::InvalidPadding { offset, pixel }),
+        Some(_) => Ok(()),
+    }
+}
@@ -0,0 +1,595 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::Write;
+
+use crate::consts::{
+    QOI_ANIM_ENTRY_SIZE, QOI_ANIM_HEADER_SIZE, QOI_ANIM_MAGIC, QOI_ANIM_TRAILER_SIZE,
+    QOI_ANIM_VERSION,
+};
+#[cfg(feature = "std")]
+use crate::consts::{QOI_ANIM_RECORD_MAGIC, QOI_ANIM_RECORD_SIZE};
+use crate::decode::Decoder;
+#[cfg(feature = "std")]
+use crate::encode::{Encoder, EncoderOptions};
+use crate::error::{Error, Result};
+use crate::header::{invalid_dimensions, Channels, Header};
+use crate::rect::Rect;
+use crate::utils::{try_vec_with_capacity, try_vec_zeroed, unlikely};
+
+const FLAG_KEYFRAME: u8 = 0x01;
+
+fn le_u32(data: &[u8], i: usize) -> u32 {
+    u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]])
+}
+
+fn le_u64(data: &[u8], i: usize) -> u64 {
+    u64::from(le_u32(data, i)) | u64::from(le_u32(data, i + 4)) << 32
+}
+
+/// Description of a frame of an animation, see [`AnimationDecoder`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FrameInfo {
+    /// How long the frame should be displayed, in milliseconds
+    pub delay_ms: u32,
+    /// Region of the canvas updated by this frame, `None` if the frame is the same as the
+    /// previous one
+    pub changed: Option<Rect>,
+    /// Whether the frame covers the whole canvas, so that decoding can start from it
+    pub keyframe: bool,
+}
+
+/// Entry of the frame table.
+#[derive(Copy, Clone)]
+pub struct Entry {
+    pub offset: u64,
+    pub length: u32,
+    pub info: FrameInfo,
+}
+
+impl Entry {
+    #[cfg(feature = "std")]
+    pub fn encode(&self) -> [u8; QOI_ANIM_ENTRY_SIZE] {
+        let rect = self.info.changed.unwrap_or_default();
+        let mut out = [0; QOI_ANIM_ENTRY_SIZE];
+        out[0..8].copy_from_slice(&self.offset.to_le_bytes());
+        out[8..12].copy_from_slice(&self.length.to_le_bytes());
+        out[12..16].copy_from_slice(&self.info.delay_ms.to_le_bytes());
+        out[16..18].copy_from_slice(&rect.x.to_le_bytes());
+        out[18..20].copy_from_slice(&rect.y.to_le_bytes());
+        out[20..22].copy_from_slice(&rect.width.to_le_bytes());
+        out[22..24].copy_from_slice(&rect.height.to_le_bytes());
+        out[24] = if self.info.keyframe { FLAG_KEYFRAME } else { 0 };
+        out
+    }
+
+    pub fn decode(data: &[u8]) -> Self {
+        let u16_at = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+        let rect = Rect { x: u16_at(16), y: u16_at(18), width: u16_at(20), height: u16_at(22) };
+        let length = le_u32(data, 8);
+        let info = FrameInfo {
+            delay_ms: le_u32(data, 12),
+            changed: if length == 0 { None } else { Some(rect) },
+            keyframe: data[24] & FLAG_KEYFRAME != 0,
+        };
+        Self { offset: le_u64(data, 0), length, info }
+    }
+}
+
+/// Writes the frame table starting at `table_offset` of the container, followed by the
+/// trailer.
+#[cfg(feature = "std")]
+pub fn write_table<W: Write>(
+    writer: &mut W, table: &[u8], table_offset: u64, n_frames: u32,
+) -> Result<()> {
+    writer.write_all(table)?;
+    let mut trailer = [0; QOI_ANIM_TRAILER_SIZE];
+    trailer[0..8].copy_from_slice(&table_offset.to_le_bytes());
+    trailer[8..12].copy_from_slice(&n_frames.to_le_bytes());
+    trailer[12..16].copy_from_slice(&QOI_ANIM_MAGIC.to_le_bytes());
+    writer.write_all(&trailer)?;
+    Ok(())
+}
+
+/// Bounding rect of the pixels that differ between two frames, `None` if they're identical.
+#[cfg(feature = "std")]
+#[allow(clippy::cast_possible_truncation)] // indices are below the frame dimensions
+fn changed_rect(prev: &[u8], frame: &[u8], width: u16, bpp: usize) -> Option<Rect> {
+    let row_len = width as usize * bpp;
+    let (mut left, mut right) = (width as usize, 0);
+    let (mut top, mut bottom) = (None, 0);
+    for (y, (a, b)) in prev.chunks_exact(row_len).zip(frame.chunks_exact(row_len)).enumerate() {
+        if a == b {
+            continue;
+        }
+        let pixels = a.chunks_exact(bpp).zip(b.chunks_exact(bpp));
+        let first = pixels.clone().position(|(p, q)| p != q).unwrap_or(0);
+        let last = width as usize - 1 - pixels.rev().position(|(p, q)| p != q).unwrap_or(0);
+        (left, right) = (left.min(first), right.max(last));
+        top.get_or_insert(y);
+        bottom = y;
+    }
+    let top = top?;
+    Some(Rect {
+        x: left as u16,
+        y: top as u16,
+        width: (right + 1 - left) as u16,
+        height: (bottom + 1 - top) as u16,
+    })
+}
+
+/// Keyframe policy of an [`AnimationEncoder`].
+///
+/// Keyframes cover the whole canvas, so that [`AnimationDecoder::seek`] can start decoding
+/// from them rather than from the first frame. By default, only the first frame (and frames
+/// where every row and column changed) are keyframes.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AnimOptions {
+    keyframe_interval: u32,
+    max_delta_percent: u8,
+}
+
+#[cfg(feature = "std")]
+impl AnimOptions {
+    /// Creates the default keyframe policy.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { keyframe_interval: 0, max_delta_percent: 0 }
+    }
+
+    /// Makes every `interval`-th frame a keyframe; 0 (the default) disables this.
+    #[inline]
+    pub const fn keyframe_interval(mut self, interval: u32) -> Self {
+        self.keyframe_interval = interval;
+        self
+    }
+
+    /// Makes a frame a keyframe when its changed region encodes to more than `percent` percent
+    /// of the size of the last keyframe; 0 (the default) disables this.
+    ///
+    /// Such frames cost little more as keyframes than as changed regions, so this adds seek
+    /// points where they're cheapest, e.g. on scene cuts. Frames are then encoded twice.
+    #[inline]
+    pub const fn max_delta_percent(mut self, percent: u8) -> Self {
+        self.max_delta_percent = percent;
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for AnimOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Encoder for animations: sequences of frames of the same size, each with its own delay.
+///
+/// Only the region that changed since the previous frame is encoded (as a regular image
+/// holding the bounding rect of the changed pixels), and frames that didn't change at all
+/// take no space besides their entry in the frame table, which makes this well suited to
+/// screen capture. Keyframes covering the whole canvas can be inserted at regular intervals
+/// or when a frame changes too much, see [`AnimOptions`], so that [`AnimationDecoder::seek`]
+/// doesn't have to go back to the first frame.
+///
+/// Frames are written as soon as they're added and the frame table is written at the end by
+/// [`AnimationEncoder::finish`], so the writer doesn't need to be seekable. See the README for
+/// the layout of the container.
+#[cfg(feature = "std")]
+pub struct AnimationEncoder<W> {
+    writer: W,
+    header: Header,
+    options: EncoderOptions,
+    anim_options: AnimOptions,
+    channels: Option<Channels>,
+    prev: Vec<u8>,
+    region: Vec<u8>,
+    out: Vec<u8>,
+    table: Vec<u8>,
+    n_frames: u32,
+    offset: u64,
+    keyframe_len: usize,
+    // whether every frame is preceded by a record holding its table entry, and where the last
+    // record starts
+    records: bool,
+    last_record: u64,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> AnimationEncoder<W> {
+    /// Creates a new animation encoder for frames of a given size.
+    #[inline]
+    pub fn new(writer: W, width: u16, height: u16) -> Result<Self> {
+        Self::with_options(writer, width, height, EncoderOptions::new())
+    }
+
+    /// Creates a new animation encoder with a given encoder configuration for the frames.
+    pub fn with_options(
+        writer: W, width: u16, height: u16, options: EncoderOptions,
+    ) -> Result<Self> {
+        Ok(Self {
+            writer,
+            header: Header::try_new(width, height, None)?,
+            options,
+            anim_options: AnimOptions::new(),
+            channels: None,
+            prev: Vec::new(),
+            region: Vec::new(),
+            out: Vec::new(),
+            table: Vec::new(),
+            n_frames: 0,
+            offset: 0,
+            keyframe_len: 0,
+            records: false,
+            last_record: 0,
+        })
+    }
+
+    /// Replaces the keyframe policy.
+    #[inline]
+    pub const fn with_anim_options(mut self, options: AnimOptions) -> Self {
+        self.anim_options = options;
+        self
+    }
+
+    /// Makes every `interval`-th frame a keyframe covering the whole canvas, see
+    /// [`AnimOptions::keyframe_interval`].
+    #[inline]
+    pub const fn keyframe_interval(mut self, interval: u32) -> Self {
+        self.anim_options = self.anim_options.keyframe_interval(interval);
+        self
+    }
+
+    /// Encodes a frame, to be displayed for `delay_ms` milliseconds, and writes it out.
+    ///
+    /// The channels are inferred from the length of the first frame, like with
+    /// [`Encoder::new`]; all following frames must have the same length.
+    pub fn add_frame(&mut self, frame: impl AsRef<[u8]>, delay_ms: u32) -> Result<()> {
+        let frame = frame.as_ref();
+        let (width, height) = (self.header.width, self.header.height);
+        let channels = match self.channels {
+            Some(_) if unlikely(frame.len() != self.prev.len()) => {
+                return Err(Error::InvalidImageLength { size: frame.len(), width, height });
+            }
+            Some(channels) => channels,
+            None => {
+                let channels = Encoder::new(frame, width, height)?.channels();
+                self.write_header(channels)?;
+                channels
+            }
+        };
+        let bpp = channels.as_u8() as usize;
+        let full = Rect { x: 0, y: 0, width, height };
+        let interval = self.anim_options.keyframe_interval;
+        let forced = self.n_frames == 0 || (interval != 0 && self.n_frames % interval == 0);
+        let mut changed =
+            if forced { Some(full) } else { changed_rect(&self.prev, frame, width, bpp) };
+        let mut n_written = match changed {
+            Some(rect) => self.encode_region(frame, rect, bpp)?,
+            None => 0,
+        };
+        if matches!(changed, Some(rect) if rect != full) && self.delta_too_large(n_written) {
+            changed = Some(full);
+            n_written = self.encode_region(frame, full, bpp)?;
+        }
+        let info = FrameInfo { delay_ms, changed, keyframe: changed == Some(full) };
+        let mut entry = Entry { offset: self.offset, length: 0, info };
+        if changed.is_some() {
+            entry.length = u32::try_from(n_written).map_err(|_| Error::SizeOverflow)?;
+        }
+        if self.records {
+            self.last_record = self.offset;
+            entry.offset += QOI_ANIM_RECORD_SIZE as u64;
+            self.writer.write_all(&QOI_ANIM_RECORD_MAGIC.to_le_bytes())?;
+            self.writer.write_all(&entry.encode())?;
+            self.offset = entry.offset;
+        }
+        if changed.is_some() {
+            self.writer.write_all(&self.out[..n_written])?;
+            self.offset += n_written as u64;
+        }
+        if info.keyframe {
+            self.keyframe_len = n_written;
+        }
+        self.table.extend_from_slice(&entry.encode());
+        self.n_frames = self.n_frames.checked_add(1).ok_or(Error::SizeOverflow)?;
+        self.prev.clear();
+        self.prev.extend_from_slice(frame);
+        Ok(())
+    }
+
+    /// Encodes the region of a frame covered by `rect` into the output buffer and returns the
+    /// number of bytes written.
+    fn encode_region(&mut self, frame: &[u8], rect: Rect, bpp: usize) -> Result<usize> {
+        let width = self.header.width;
+        let region = if rect == (Rect { x: 0, y: 0, width, height: self.header.height }) {
+            frame
+        } else {
+            let row_len = width as usize * bpp;
+            let (start, end) = (rect.x as usize * bpp, rect.right() as usize * bpp);
+            self.region.clear();
+            let rows = frame.chunks_exact(row_len).skip(rect.y as usize);
+            for row in rows.take(rect.height as usize) {
+                self.region.extend_from_slice(&row[start..end]);
+            }
+            &self.region
+        };
+        let mut encoder = Encoder::new(region, rect.width, rect.height)?.with_options(self.options);
+        self.out.resize(encoder.required_buf_len(), 0);
+        encoder.encode_to_buf(&mut self.out)
+    }
+
+    /// Whether a changed region encoded to `n_written` bytes should be a keyframe instead.
+    #[inline]
+    fn delta_too_large(&self, n_written: usize) -> bool {
+        let percent = self.anim_options.max_delta_percent;
+        percent != 0
+            && n_written.saturating_mul(100) > self.keyframe_len.saturating_mul(percent.into())
+    }
+
+    /// Writes the frame table, flushes the writer and returns it back.
+    pub fn finish(mut self) -> Result<W> {
+        if self.channels.is_none() {
+            self.write_header(Channels::Rgba)?;
+        }
+        write_table(&mut self.writer, &self.table, self.offset, self.n_frames)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Precedes every frame with a record holding its table entry, see
+    /// [`RecordingWriter`](crate::RecordingWriter).
+    #[inline]
+    pub(crate) const fn with_records(mut self) -> Self {
+        self.records = true;
+        self
+    }
+
+    /// Sets the delay of the last frame added in the frame table, and returns the offset of
+    /// the delay in its record so that the caller can patch it there too.
+    pub(crate) fn set_last_delay(&mut self, delay_ms: u32) -> Option<u64> {
+        let start = self.table.len().checked_sub(QOI_ANIM_ENTRY_SIZE)?;
+        self.table[start + 12..start + 16].copy_from_slice(&delay_ms.to_le_bytes());
+        self.records.then_some(self.last_record + 4 + 12)
+    }
+
+    #[inline]
+    pub(crate) fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    fn write_header(&mut self, channels: Channels) -> Result<()> {
+        let mut header = [0; QOI_ANIM_HEADER_SIZE];
+        header[0..4].copy_from_slice(&QOI_ANIM_MAGIC.to_le_bytes());
+        header[4..6].copy_from_slice(&self.header.width.to_le_bytes());
+        header[6..8].copy_from_slice(&self.header.height.to_le_bytes());
+        header[8] = channels.as_u8();
+        header[9] = QOI_ANIM_VERSION;
+        self.writer.write_all(&header)?;
+        self.channels = Some(channels);
+        self.offset = QOI_ANIM_HEADER_SIZE as u64;
+        Ok(())
+    }
+}
+
+/// Decoder for animations written by [`AnimationEncoder`].
+///
+/// Frames are decoded lazily, one at a time, by drawing the region each of them updates onto
+/// a canvas that holds the current frame. [`AnimationDecoder::next_frame`] does this without
+/// allocating and lends the canvas out, while the [`Iterator`] implementation yields a copy of
+/// each frame. Frames can be looked up by index or by time, see [`AnimationDecoder::seek`] and
+/// [`AnimationDecoder::seek_time`].
+pub struct AnimationDecoder<'a> {
+    data: &'a [u8],
+    table: &'a [u8],
+    header: Header,
+    channels: Channels,
+    canvas: Vec<u8>,
+    // start time of every frame in milliseconds, followed by the total duration
+    times: Vec<u64>,
+    next: usize,
+}
+
+impl<'a> AnimationDecoder<'a> {
+    /// Parses the header and the frame table of an animation.
+    ///
+    /// The canvas and the index of frame start times are allocated here, but no frame is
+    /// decoded yet.
+    pub fn new(data: &'a (impl AsRef<[u8]> + ?Sized)) -> Result<Self> {
+        let data = data.as_ref();
+        if unlikely(data.len() < QOI_ANIM_HEADER_SIZE + QOI_ANIM_TRAILER_SIZE) {
+            return Err(Error::UnexpectedBufferEnd { offset: 0, pixel: 0 });
+        }
+        let magic = le_u32(data, 0);
+        if unlikely(magic != QOI_ANIM_MAGIC) {
+            return Err(Error::InvalidMagic { magic });
+        }
+        let width = u16::from_le_bytes([data[4], data[5]]);
+        let height = u16::from_le_bytes([data[6], data[7]]);
+        let channels = match data[8] {
+            2 => Channels::La,
+            3 => Channels::Rgb,
+            4 => Channels::Rgba,
+            channels => return Err(Error::InvalidChannels { channels }),
+        };
+        if unlikely(data[9] != QOI_ANIM_VERSION) {
+            return Err(Error::UnsupportedVersion { version: data[9], flags: 0 });
+        }
+        let header = Header::try_new(width, height, None)?;
+
+        let trailer = &data[data.len() - QOI_ANIM_TRAILER_SIZE..];
+        let magic = le_u32(trailer, 12);
+        if unlikely(magic != QOI_ANIM_MAGIC) {
+            return Err(Error::InvalidMagic { magic });
+        }
+        let table_start = le_u64(trailer, 0);
+        let n_frames = le_u32(trailer, 8);
+        let table_len = (n_frames as usize).checked_mul(QOI_ANIM_ENTRY_SIZE);
+        let table_end = data.len() - QOI_ANIM_TRAILER_SIZE;
+        let table = usize::try_from(table_start)
+            .ok()
+            .zip(table_len)
+            .and_then(|(start, len)| data.get(start..table_end).filter(|t| t.len() == len))
+            .ok_or(Error::UnexpectedBufferEnd { offset: 0, pixel: 0 })?;
+
+        let canvas = try_vec_zeroed(header.n_pixels() * channels.as_u8() as usize)?;
+        let mut times = try_vec_with_capacity(n_frames as usize + 1)?;
+        times.push(0);
+        for entry in table.chunks_exact(QOI_ANIM_ENTRY_SIZE).map(Entry::decode) {
+            let end = times.last().copied().unwrap_or_default() + u64::from(entry.info.delay_ms);
+            times.push(end);
+        }
+        Ok(Self { data, table, header, channels, canvas, times, next: 0 })
+    }
+
+    /// Returns the header of the frames (their data length is not set).
+    #[inline]
+    pub const fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns the channels of the frames.
+    #[inline]
+    pub const fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    /// Returns the number of frames.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        self.table.len() / QOI_ANIM_ENTRY_SIZE
+    }
+
+    /// Returns `true` if the animation has no frames.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Returns the description of a frame, without decoding anything.
+    #[inline]
+    pub fn frame_info(&self, index: usize) -> Option<FrameInfo> {
+        self.entry(index).map(|entry| entry.info)
+    }
+
+    /// Returns the time at which a frame starts to be displayed, in milliseconds since the
+    /// start of the animation.
+    #[inline]
+    pub fn frame_start_ms(&self, index: usize) -> Option<u64> {
+        self.times.get(index).filter(|_| index < self.len()).copied()
+    }
+
+    /// Returns the total duration of the animation in milliseconds, i.e. the sum of the delays
+    /// of all frames.
+    #[inline]
+    pub fn duration_ms(&self) -> u64 {
+        self.times.last().copied().unwrap_or_default()
+    }
+
+    /// Returns the current frame, i.e. the one last returned by
+    /// [`AnimationDecoder::next_frame`] (all zeros before the first call).
+    #[inline]
+    pub fn frame(&self) -> &[u8] {
+        &self.canvas
+    }
+
+    /// Decodes the next frame, returning its description; the frame itself can be obtained
+    /// via [`AnimationDecoder::frame`].
+    ///
+    /// Returns `None` after the last frame or after an error.
+    pub fn next_frame(&mut self) -> Option<Result<FrameInfo>> {
+        let entry = self.entry(self.next)?;
+        match self.apply(entry) {
+            Ok(()) => {
+                self.next += 1;
+                Some(Ok(entry.info))
+            }
+            Err(err) => {
+                self.next = self.len();
+                Some(Err(err))
+            }
+        }
+    }
+
+    /// Positions the decoder so that the next frame returned is the one at `index`.
+    ///
+    /// This replays the frames from the closest keyframe before `index` (or from the current
+    /// position, if that's closer). Seeking past the last frame ends the iteration.
+    pub fn seek(&mut self, index: usize) -> Result<()> {
+        let index = index.min(self.len());
+        let keyframe = (0..index)
+            .rev()
+            .find(|&i| self.entry(i).map_or(false, |entry| entry.info.keyframe))
+            .unwrap_or(0);
+        if self.next <= keyframe || self.next > index {
+            self.canvas.fill(0);
+            self.next = keyframe;
+        }
+        while self.next < index {
+            if let Some(Err(err)) = self.next_frame() {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Positions the decoder so that the next frame returned is the one displayed at
+    /// `time_ms` milliseconds since the start of the animation, and returns its index.
+    ///
+    /// Times past the end of the animation seek to the last frame. Like
+    /// [`AnimationDecoder::seek`], this replays the frames from the closest keyframe.
+    pub fn seek_time(&mut self, time_ms: u64) -> Result<usize> {
+        // frames with no delay are never displayed, so the last frame starting at or before
+        // the time is the one to show
+        let n_started = self.times[..self.len()].partition_point(|&start| start <= time_ms);
+        let index = n_started.saturating_sub(1);
+        self.seek(index)?;
+        Ok(index)
+    }
+
+    fn entry(&self, index: usize) -> Option<Entry> {
+        let start = index.checked_mul(QOI_ANIM_ENTRY_SIZE)?;
+        self.table.get(start..start + QOI_ANIM_ENTRY_SIZE).map(Entry::decode)
+    }
+
+    /// Draws the region updated by a frame onto the canvas.
+    fn apply(&mut self, entry: Entry) -> Result<()> {
+        let Some(rect) = entry.info.changed else { return Ok(()) };
+        let (width, height) = (self.header.width, self.header.height);
+        let (right, bottom) =
+            (u32::from(rect.x) + u32::from(rect.width), u32::from(rect.y) + u32::from(rect.height));
+        if unlikely(right > width.into() || bottom > height.into()) {
+            return Err(invalid_dimensions(right, bottom));
+        }
+        let data = usize::try_from(entry.offset)
+            .ok()
+            .and_then(|start| self.data.get(start..start.checked_add(entry.length as usize)?))
+            .ok_or(Error::UnexpectedBufferEnd { offset: 0, pixel: 0 })?;
+        let mut decoder = Decoder::new(data)?.with_channels(self.channels);
+        let frame = decoder.header();
+        if unlikely(frame.width != rect.width || frame.height != rect.height) {
+            let (width, height) = (frame.width, frame.height);
+            return Err(Error::InvalidImageDimensions { width, height });
+        }
+        let bpp = self.channels.as_u8() as usize;
+        let (start, end) = (rect.x as usize * bpp, rect.right() as usize * bpp);
+        let mut rows = self.canvas.chunks_exact_mut(width as usize * bpp).skip(rect.y as usize);
+        decoder.decode_rows_into(|_| rows.next().map_or(&mut [][..], |row| &mut row[start..end]))
+    }
+}
+
+impl Iterator for AnimationDecoder<'_> {
+    type Item = Result<(FrameInfo, Vec<u8>)>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let info = self.next_frame()?;
+        Some(info.map(|info| (info, self.canvas.clone())))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.len() - self.next))
+    }
+}
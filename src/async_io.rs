@@ -0,0 +1,331 @@
+use std::io;
+use std::vec::Vec;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::consts::{
+    QOI_EXT_HEADER_SIZE, QOI_EXT_MAGIC, QOI_HEADER_SIZE, QOI_PADDING, QOI_PADDING_SIZE,
+    QOI_REFERENCE_HEADER_SIZE,
+};
+use crate::decode::{decode_chunk_as, DecoderOptions, StreamState};
+use crate::encode::Encoder;
+use crate::ext;
+use crate::error::{Error, Result};
+use crate::header::{Channels, Header, HeaderFormat};
+use crate::utils::{try_vec_zeroed, unlikely};
+
+/// Size of the input buffer of [`AsyncDecoder`].
+const BUF_SIZE: usize = 8192;
+
+/// Longest run a single op can encode.
+const MAX_RUN: usize = 62;
+
+impl Encoder<'_> {
+    /// Encodes the image and writes it to an async writer, returning the number of bytes
+    /// written.
+    ///
+    /// The header holds the length of the op stream, so the image is encoded into memory first
+    /// and then written in one go; the writer isn't flushed.
+    pub async fn encode_to_async_stream<W: AsyncWrite + Unpin>(
+        &mut self, writer: &mut W,
+    ) -> Result<usize> {
+        let out = self.encode_to_vec()?;
+        writer.write_all(&out).await?;
+        Ok(out.len())
+    }
+}
+
+/// Input of an [`AsyncDecoder`], read in chunks into a buffer.
+struct Input<R> {
+    reader: R,
+    buf: Vec<u8>,
+    start: usize,
+    end: usize,
+}
+
+impl<R: AsyncRead + Unpin> Input<R> {
+    /// Bytes read but not consumed yet.
+    fn data(&self) -> &[u8] {
+        &self.buf[self.start..self.end]
+    }
+
+    /// Reads at most `n_max` more bytes after what's buffered, returning the number of bytes
+    /// read, zero at the end of the reader.
+    async fn read_more(&mut self, n_max: usize) -> Result<usize> {
+        self.buf.copy_within(self.start..self.end, 0);
+        (self.start, self.end) = (0, self.end - self.start);
+        let n_max = n_max.max(1).min(self.buf.len() - self.end);
+        let n = self.reader.read(&mut self.buf[self.end..self.end + n_max]).await?;
+        self.end += n;
+        Ok(n)
+    }
+
+    /// Reads until at least `n` bytes are buffered, without reading past them, returning false
+    /// if the reader ends first.
+    async fn fill_to(&mut self, n: usize) -> Result<bool> {
+        if n > self.buf.len() {
+            self.buf.try_reserve_exact(n - self.buf.len()).map_err(|_| Error::OutOfMemory)?;
+            self.buf.resize(n, 0);
+        }
+        while self.end - self.start < n {
+            if self.read_more(n - (self.end - self.start)).await? == 0 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Decode QOI images from async readers as their bytes arrive, e.g. frames streamed over a
+/// WebSocket.
+///
+/// This works like a [`Decoder`](crate::Decoder) created with
+/// [`Decoder::from_stream`](crate::Decoder::from_stream): pixels are decoded as RGBA unless
+/// requested otherwise (or declared otherwise by a reference header), and rows are decoded
+/// with [`AsyncDecoder::decode_next_rows`] as soon as the bytes they need have been read.
+/// Input is read in chunks, but never past the end marker of the image. The extension block
+/// following it is only known once the bytes after the end marker arrive, so it's read by
+/// [`AsyncDecoder::finish`], and [`AsyncDecoder::next_image`] moves on to the next image of
+/// the stream.
+pub struct AsyncDecoder<R> {
+    input: Input<R>,
+    header: Header,
+    format: HeaderFormat,
+    options: DecoderOptions,
+    channels: Channels,
+    state: StreamState,
+    finished: bool,
+    // bytes of the op stream and pixels consumed from it so far
+    n_read: usize,
+    n_decoded: usize,
+}
+
+impl<R: AsyncRead + Unpin> AsyncDecoder<R> {
+    /// Creates a new decoder from an async reader, reading the header right away.
+    pub async fn new(reader: R) -> Result<Self> {
+        let input = Input { reader, buf: try_vec_zeroed(BUF_SIZE)?, start: 0, end: 0 };
+        Self::from_input(input).await
+    }
+
+    /// Reads the header of an image, following what's already buffered.
+    async fn from_input(mut input: Input<R>) -> Result<Self> {
+        let mut b = [0; QOI_REFERENCE_HEADER_SIZE];
+        if !input.fill_to(QOI_HEADER_SIZE).await? {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let format = HeaderFormat::detect(input.data()).unwrap_or_default();
+        if !input.fill_to(format.size()).await? {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        b[..format.size()].copy_from_slice(&input.data()[..format.size()]);
+        input.start += format.size();
+        let (header, channels) = match format {
+            HeaderFormat::GameMaker => (Header::decode(b)?, Channels::default()),
+            HeaderFormat::Reference => Header::decode_reference(b)?,
+        };
+        Ok(Self {
+            input,
+            header,
+            format,
+            options: DecoderOptions::new(),
+            channels,
+            state: StreamState::new(),
+            finished: false,
+            n_read: 0,
+            n_decoded: 0,
+        })
+    }
+
+    /// Replaces the decoder configuration.
+    #[inline]
+    pub const fn with_options(mut self, options: DecoderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Changes the layout of the decoded pixels, see
+    /// [`Decoder::with_channels`](crate::Decoder::with_channels).
+    #[inline]
+    pub const fn with_channels(mut self, channels: Channels) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Returns the layout of the decoded pixels.
+    ///
+    /// Once [`AsyncDecoder::finish`] has read the extension block, this is the layout the image
+    /// was stored with.
+    #[inline]
+    pub const fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    /// Returns the decoded image header.
+    ///
+    /// The color space and nine-patch borders of GameMaker images are stored in the extension
+    /// block, so they're only filled in by [`AsyncDecoder::finish`].
+    #[inline]
+    pub const fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Returns the format of the image header, detected from its magic bytes.
+    #[inline]
+    pub const fn header_format(&self) -> HeaderFormat {
+        self.format
+    }
+
+    /// The number of bytes the decoded image will take.
+    #[inline]
+    pub const fn required_buf_len(&self) -> usize {
+        self.header.n_pixels().saturating_mul(self.channels.as_u8() as usize)
+    }
+
+    /// Number of rows decoded so far.
+    #[inline]
+    pub const fn rows_decoded(&self) -> u16 {
+        self.state.row
+    }
+
+    /// Consumes the decoder and returns the underlying reader back.
+    ///
+    /// Once the whole image has been decoded, the reader is positioned right after its end
+    /// marker, or after the extension block once [`AsyncDecoder::finish`] has returned; bytes
+    /// already read into the input buffer are lost otherwise, use [`AsyncDecoder::next_image`]
+    /// to keep decoding the same stream.
+    #[inline]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn into_reader(self) -> R {
+        self.input.reader
+    }
+
+    /// Decodes the next rows of the image into a pre-allocated buffer and returns the number
+    /// of bytes written, or zero once the whole image has been decoded.
+    ///
+    /// As many whole rows as fit into the buffer are decoded (it must hold at least one row),
+    /// waiting for more input as needed. The end marker is checked along with the last row.
+    pub async fn decode_next_rows(&mut self, mut buf: impl AsMut<[u8]>) -> Result<usize> {
+        let buf = buf.as_mut();
+        let bpp = self.channels.as_u8() as usize;
+        let row_len = self.header.width as usize * bpp;
+        let rows_left = self.header.height - self.state.row;
+        if rows_left == 0 {
+            return Ok(0);
+        }
+        // can't truncate: capped by the number of rows left
+        #[allow(clippy::cast_possible_truncation)]
+        let n_rows = (buf.len() / row_len).min(rows_left.into()) as u16;
+        if unlikely(n_rows == 0) {
+            return Err(Error::OutputBufferTooSmall { size: buf.len(), required: row_len });
+        }
+        let out = &mut buf[..n_rows as usize * row_len];
+        let mut n_written = 0;
+        loop {
+            let (n_consumed, n_pixels) = decode_chunk_as(
+                self.input.data(),
+                &mut out[n_written..],
+                &mut self.state,
+                self.options,
+                self.channels,
+            );
+            self.input.start += n_consumed;
+            self.n_read += n_consumed;
+            self.n_decoded += n_pixels;
+            n_written += n_pixels * bpp;
+            if n_written == out.len() {
+                break;
+            }
+            self.fill_buf().await?;
+        }
+        self.state.row += n_rows;
+        if n_rows == rows_left {
+            while self.input.data().len() < QOI_PADDING_SIZE {
+                self.fill_buf().await?;
+            }
+            if unlikely(self.input.data()[..QOI_PADDING_SIZE] != QOI_PADDING) {
+                let (offset, pixel) = (self.n_read, self.header.n_pixels());
+                return Err(Error::InvalidPadding { offset, pixel });
+            }
+            self.input.start += QOI_PADDING_SIZE;
+        }
+        Ok(out.len())
+    }
+
+    /// Decodes the rest of the image into a newly allocated vector.
+    pub async fn decode_to_vec(&mut self) -> Result<Vec<u8>> {
+        let row_len = self.header.width as usize * self.channels.as_u8() as usize;
+        let n_rows = self.header.height - self.state.row;
+        let mut out = try_vec_zeroed(n_rows as usize * row_len)?;
+        if n_rows != 0 {
+            self.decode_next_rows(&mut out).await?;
+        }
+        Ok(out)
+    }
+
+    /// Skips the rows left and reads the extension block following the image, if there's one,
+    /// picking up what it stores like [`Decoder::new`](crate::Decoder::new) does: the channel
+    /// layout, color space and nine-patch borders.
+    ///
+    /// This waits for the bytes following the end marker, i.e. the block or the header of the
+    /// next image; those of a header are kept for [`AsyncDecoder::next_image`]. The reader may
+    /// also end instead.
+    ///
+    /// Checksums stored by [`Encoder::with_checksum`] aren't verified: the rows have been handed
+    /// out by then.
+    pub async fn finish(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        let mut row = try_vec_zeroed(self.header.width as usize * self.channels.as_u8() as usize)?;
+        while self.decode_next_rows(&mut row).await? != 0 {}
+        self.finished = true;
+        if self.format == HeaderFormat::Reference {
+            // no extension block, the channels are in the header
+            return Ok(());
+        }
+        let magic = QOI_EXT_MAGIC.to_le_bytes();
+        if !self.input.fill_to(QOI_EXT_HEADER_SIZE).await? || self.input.data()[..4] != magic {
+            return Ok(());
+        }
+        let len = u32::from_le_bytes(self.input.data()[4..8].try_into().unwrap_or_default());
+        let block_len = QOI_EXT_HEADER_SIZE + len as usize;
+        if unlikely(!self.input.fill_to(block_len).await?) {
+            let offset = self.n_read + QOI_PADDING_SIZE + self.input.data().len();
+            return Err(Error::UnexpectedBufferEnd { offset, pixel: self.n_decoded });
+        }
+        let block = &self.input.data()[..block_len];
+        let (width, height) = (self.header.width, self.header.height);
+        self.channels = ext::channels(block, 0).unwrap_or_default();
+        self.header.colorspace = ext::colorspace(block, 0);
+        self.header.nine_patch = ext::nine_patch(block, 0, width, height);
+        self.input.start += block_len;
+        Ok(())
+    }
+
+    /// Finishes the image and reads the header of the next one from the same reader, keeping
+    /// the decoder configuration.
+    pub async fn next_image(mut self) -> Result<Self> {
+        self.finish().await?;
+        let options = self.options;
+        Ok(Self::from_input(self.input).await?.with_options(options))
+    }
+
+    /// Reads more input after what's buffered, failing at the end of the reader.
+    async fn fill_buf(&mut self) -> Result<()> {
+        let n_buffered = self.input.data().len();
+        // never read past the end marker: GameMaker's header says where it is, otherwise the
+        // pixels left need at least one op byte for every run's worth of them
+        let n_unread = if let Some(length) = self.header.length {
+            (length as usize).saturating_sub(self.n_read + n_buffered)
+        } else {
+            let n_left = self.header.n_pixels() - self.n_decoded - self.state.run;
+            let n_ops = (n_left + MAX_RUN - 1) / MAX_RUN;
+            (n_ops + QOI_PADDING_SIZE).saturating_sub(n_buffered)
+        };
+        if unlikely(self.input.read_more(n_unread).await? == 0) {
+            let (offset, pixel) = (self.n_read + n_buffered, self.n_decoded);
+            return Err(Error::UnexpectedBufferEnd { offset, pixel });
+        }
+        Ok(())
+    }
+}
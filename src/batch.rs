@@ -0,0 +1,326 @@
+//! Converting whole directories of images to QOI.
+//!
+//! [`convert_dir`] is the driver that otherwise ends up being written around the crate for
+//! every asset pipeline: it walks a directory tree, decodes every PNG, PPM/PGM and QOI file
+//! (including images with the standard `qoif` header) and writes them as QOI files into a
+//! mirrored tree, spreading the files over rayon's thread pool.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::vec::Vec;
+
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+use crate::decode::Decoder;
+use crate::encode::{Encoder, EncoderOptions};
+use crate::error::{Error, Result};
+use crate::fs::Fsync;
+
+/// Configuration of a directory conversion with [`convert_dir`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BatchOptions {
+    encoder: EncoderOptions,
+    threads: usize,
+    fsync: Fsync,
+}
+
+impl BatchOptions {
+    /// Creates the default configuration: default encoder options, running on the current
+    /// rayon thread pool.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { encoder: EncoderOptions::new(), threads: 0, fsync: Fsync::File }
+    }
+
+    /// Sets the encoder configuration used for every output file.
+    #[inline]
+    pub const fn encoder(mut self, options: EncoderOptions) -> Self {
+        self.encoder = options;
+        self
+    }
+
+    /// Sets the number of worker threads of a pool built for the conversion (zero, the default,
+    /// runs it on the current rayon thread pool instead, which has one thread per CPU unless
+    /// configured otherwise).
+    #[inline]
+    pub const fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Sets the durability of the output files (see [`Fsync`]).
+    #[inline]
+    pub const fn fsync(mut self, fsync: Fsync) -> Self {
+        self.fsync = fsync;
+        self
+    }
+}
+
+impl Default for BatchOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Progress of a running conversion, passed to the callback after each file.
+#[derive(Copy, Clone, Debug)]
+pub struct Progress<'a> {
+    /// Input file that has just been processed, successfully or not
+    pub path: &'a Path,
+    /// Number of files processed so far, including this one
+    pub done: usize,
+    /// Total number of files to process
+    pub total: usize,
+}
+
+/// Outcome of a directory conversion.
+///
+/// Converted and skipped files are listed in directory-walk order, whatever the order in which
+/// the workers got through them.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Input files that have been converted
+    pub converted: Vec<PathBuf>,
+    /// Input files that couldn't be converted, along with the reason
+    pub failed: Vec<(PathBuf, Error)>,
+    /// Files left alone because their extension isn't one of a supported format
+    pub skipped: Vec<PathBuf>,
+}
+
+impl Report {
+    /// Returns `true` if every supported file has been converted.
+    #[inline]
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Format {
+    Png,
+    Pnm,
+    Qoi,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "png" => Some(Self::Png),
+            "ppm" | "pgm" | "pnm" => Some(Self::Pnm),
+            "qoi" => Some(Self::Qoi),
+            _ => None,
+        }
+    }
+}
+
+struct Job {
+    src: PathBuf,
+    dst: PathBuf,
+    format: Format,
+}
+
+/// Convert every supported image below `src_dir` to a QOI file below `dst_dir`.
+///
+/// Files are recognized by their extension: `.png`, `.ppm`/`.pgm`/`.pnm` (binary `P6`/`P5`)
+/// and `.qoi`, the latter being re-encoded with `options` whichever header it has. Each one
+/// is written to the same relative path under `dst_dir` with a `.qoi` extension, creating
+/// directories as needed and replacing existing files atomically. Symbolic links to
+/// directories are not followed, and neither is `dst_dir` if it lies inside `src_dir`.
+///
+/// `progress` is called from the worker threads after every file, so it should be cheap.
+/// A file failing to convert (or mapping to the same output as an earlier one, e.g.
+/// `a.png` next to `a.ppm`) doesn't stop the conversion and is recorded in the returned
+/// [`Report`]; an error is only returned if `src_dir` can't be walked, `dst_dir` created or
+/// the thread pool asked for with [`BatchOptions::threads`] built.
+pub fn convert_dir(
+    src_dir: impl AsRef<Path>, dst_dir: impl AsRef<Path>, options: BatchOptions,
+    progress: impl Fn(Progress<'_>) + Sync,
+) -> Result<Report> {
+    let (src_dir, dst_dir) = (src_dir.as_ref(), dst_dir.as_ref());
+    fs::create_dir_all(dst_dir)?;
+    let dst_root = fs::canonicalize(dst_dir)?;
+    let mut report = Report::default();
+    let mut jobs = Vec::new();
+    walk(src_dir, src_dir, dst_dir, &dst_root, &mut jobs, &mut report)?;
+
+    let mut outputs = HashSet::new();
+    jobs.retain(|job| {
+        let is_new = outputs.insert(job.dst.clone());
+        if !is_new {
+            let err = io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} is also the output of another file", job.dst.display()),
+            );
+            report.failed.push((job.src.clone(), err.into()));
+        }
+        is_new
+    });
+
+    let done = AtomicUsize::new(0);
+    let convert = || -> Vec<Result<()>> {
+        jobs.par_iter()
+            .map(|job| {
+                let result = convert_file(job, &options);
+                let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                progress(Progress { path: &job.src, done, total: jobs.len() });
+                result
+            })
+            .collect()
+    };
+    let results = match options.threads {
+        0 => convert(),
+        n => ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+            .install(convert),
+    };
+
+    for (result, job) in results.into_iter().zip(jobs) {
+        match result {
+            Ok(()) => report.converted.push(job.src),
+            Err(err) => report.failed.push((job.src, err)),
+        }
+    }
+    Ok(report)
+}
+
+/// Collects the files below `dir` in a deterministic order.
+fn walk(
+    dir: &Path, src_dir: &Path, dst_dir: &Path, dst_root: &Path, jobs: &mut Vec<Job>,
+    report: &mut Report,
+) -> Result<()> {
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(fs::DirEntry::file_name);
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            if fs::canonicalize(&path)? != dst_root {
+                walk(&path, src_dir, dst_dir, dst_root, jobs, report)?;
+            }
+            continue;
+        }
+        match Format::from_path(&path) {
+            Some(format) => {
+                let relative = path.strip_prefix(src_dir).unwrap_or(&path);
+                let dst = dst_dir.join(relative).with_extension("qoi");
+                jobs.push(Job { src: path, dst, format });
+            }
+            None => report.skipped.push(path),
+        }
+    }
+    Ok(())
+}
+
+fn convert_file(job: &Job, options: &BatchOptions) -> Result<()> {
+    let bytes = fs::read(&job.src)?;
+    let (width, height, data) = match job.format {
+        Format::Png => load_png(&bytes)?,
+        Format::Pnm => load_pnm(&bytes)?,
+        Format::Qoi => {
+            let mut decoder = Decoder::new(&bytes)?;
+            let header = *decoder.header();
+            (header.width.into(), header.height.into(), decoder.decode_to_vec()?)
+        }
+    };
+    if let Some(parent) = job.dst.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut encoder = Encoder::new(&data, width, height)?.with_options(options.encoder);
+    encoder.encode_to_file_atomic(&job.dst, options.fsync).map(|_| ())
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Decodes a PNG image as RGBA, RGB or luma + alpha pixels.
+fn load_png(bytes: &[u8]) -> io::Result<(u32, u32, Vec<u8>)> {
+    let to_io = |err| match err {
+        png::DecodingError::IoError(err) => err,
+        err => io::Error::new(io::ErrorKind::InvalidData, err),
+    };
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(to_io)?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(to_io)?;
+    buf.truncate(info.buffer_size());
+    let data = match info.color_type {
+        png::ColorType::Grayscale => buf.iter().flat_map(|&luma| [luma, 0xff]).collect(),
+        png::ColorType::GrayscaleAlpha | png::ColorType::Rgb | png::ColorType::Rgba => buf,
+        png::ColorType::Indexed => return Err(invalid_data("unexpected indexed PNG output")),
+    };
+    Ok((info.width, info.height, data))
+}
+
+/// Decodes a binary PPM (`P6`) or PGM (`P5`) image as RGB or luma + alpha pixels.
+///
+/// Samples are rescaled to 8 bits if the maximum value is anything but 255.
+fn load_pnm(bytes: &[u8]) -> io::Result<(u32, u32, Vec<u8>)> {
+    let is_gray = match bytes.get(..2) {
+        Some(b"P5") => true,
+        Some(b"P6") => false,
+        _ => return Err(invalid_data("not a binary PPM/PGM image")),
+    };
+    let mut pos = 2;
+    let width = pnm_field(bytes, &mut pos)?;
+    let height = pnm_field(bytes, &mut pos)?;
+    let max_value = pnm_field(bytes, &mut pos)?;
+    if !(1..=0xffff).contains(&max_value) {
+        return Err(invalid_data("invalid PPM/PGM maximum value"));
+    }
+    // a single whitespace byte separates the header from the raster
+    pos += 1;
+
+    let n_samples = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(if is_gray { 1 } else { 3 }));
+    let sample_size = if max_value > 0xff { 2 } else { 1 };
+    let raster = n_samples
+        .and_then(|n| n.checked_mul(sample_size))
+        .and_then(|len| bytes.get(pos..pos.checked_add(len)?))
+        .ok_or_else(|| invalid_data("truncated PPM/PGM image"))?;
+    #[allow(clippy::cast_possible_truncation)]
+    let rescale = |value: u32| ((value.min(max_value) * 0xff + max_value / 2) / max_value) as u8;
+    let samples: Vec<u8> = match (sample_size, max_value) {
+        (1, 0xff) => raster.to_vec(),
+        (1, _) => raster.iter().map(|&value| rescale(value.into())).collect(),
+        _ => raster
+            .chunks_exact(2)
+            .map(|s| rescale(u16::from_be_bytes([s[0], s[1]]).into()))
+            .collect(),
+    };
+    let data =
+        if is_gray { samples.iter().flat_map(|&luma| [luma, 0xff]).collect() } else { samples };
+    Ok((width, height, data))
+}
+
+/// Parses a decimal header field, skipping the whitespace and comments before it.
+fn pnm_field(bytes: &[u8], pos: &mut usize) -> io::Result<u32> {
+    loop {
+        match bytes.get(*pos) {
+            Some(b'#') => {
+                while !matches!(bytes.get(*pos), Some(b'\n' | b'\r') | None) {
+                    *pos += 1;
+                }
+            }
+            Some(b) if b.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+    let start = *pos;
+    while bytes.get(*pos).map_or(false, u8::is_ascii_digit) {
+        *pos += 1;
+    }
+    std::str::from_utf8(&bytes[start..*pos])
+        .ok()
+        .and_then(|field| field.parse().ok())
+        .ok_or_else(|| invalid_data("malformed PPM/PGM header"))
+}
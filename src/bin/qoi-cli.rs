@@ -0,0 +1,273 @@
+//! Command-line tool for converting and inspecting images in this crate's QOI format.
+//!
+//! Built with the `cli` feature: `cargo install qoi --features cli`.
+
+#![forbid(unsafe_code)]
+#![warn(clippy::all, clippy::pedantic, clippy::nursery)]
+#![allow(clippy::cast_precision_loss)]
+
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use qoi::{Channels, ContentId, Decoder, Encoder, EncoderOptions, Fsync};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const USAGE: &str = "\
+usage: qoi-cli <command> [options]
+
+commands:
+    encode <input.png> <output.qoi> [--restart-interval ROWS] [--max-run N]
+        encode a PNG image (grayscale images keep a luma + alpha layout)
+    decode <input.qoi> <output.png>
+        decode an image into an 8-bit RGBA or grayscale + alpha PNG
+    info <file.qoi>...
+        print the header, extension records and content id of images
+    diff <a> <b>
+        compare the pixels of two images (QOI or PNG), exits with 1 if they differ
+    bench <file>... [--seconds S]
+        measure encoding and decoding speed on images (QOI or PNG)";
+
+/// Decoded pixels of an input image.
+struct Pixels {
+    width: u32,
+    height: u32,
+    channels: Channels,
+    data: Vec<u8>,
+}
+
+impl Pixels {
+    /// Reads a QOI or PNG image, telling them apart by their contents.
+    fn load(path: &str) -> Result<Self> {
+        let bytes = fs::read(path).map_err(|err| format!("{path}: {err}"))?;
+        if let Ok(mut decoder) = Decoder::new(&bytes) {
+            let header = *decoder.header();
+            let (channels, data) = (decoder.channels(), decoder.decode_to_vec()?);
+            let (width, height) = (header.width.into(), header.height.into());
+            return Ok(Self { width, height, channels, data });
+        }
+        Self::load_png(&bytes).map_err(|err| format!("{path}: {err}").into())
+    }
+
+    fn load_png(bytes: &[u8]) -> Result<Self> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+        let buf = &buf[..info.buffer_size()];
+        let (channels, data) = match info.color_type {
+            png::ColorType::Grayscale => {
+                (Channels::La, buf.iter().flat_map(|&luma| [luma, 0xff]).collect())
+            }
+            png::ColorType::GrayscaleAlpha => (Channels::La, buf.to_vec()),
+            png::ColorType::Rgb => (Channels::Rgb, buf.to_vec()),
+            png::ColorType::Rgba => (Channels::Rgba, buf.to_vec()),
+            color_type @ png::ColorType::Indexed => {
+                return Err(format!("unsupported PNG color type: {color_type:?}").into())
+            }
+        };
+        Ok(Self { width: info.width, height: info.height, channels, data })
+    }
+
+    /// Pixels as RGBA, converting RGB or luma + alpha if needed.
+    fn to_rgba(&self) -> Vec<u8> {
+        match self.channels {
+            Channels::Rgba => self.data.clone(),
+            Channels::Rgb => {
+                self.data.chunks_exact(3).flat_map(|px| [px[0], px[1], px[2], 0xff]).collect()
+            }
+            Channels::La => {
+                self.data.chunks_exact(2).flat_map(|px| [px[0], px[0], px[0], px[1]]).collect()
+            }
+        }
+    }
+}
+
+/// Removes `--name VALUE` from the arguments and parses the value.
+fn take_option<T>(args: &mut Vec<String>, name: &str) -> Result<Option<T>>
+where
+    T: std::str::FromStr,
+    T::Err: Error + 'static,
+{
+    let Some(pos) = args.iter().position(|arg| arg == name) else {
+        return Ok(None);
+    };
+    if pos + 1 >= args.len() {
+        return Err(format!("missing value for {name}").into());
+    }
+    let value = args.remove(pos + 1);
+    args.remove(pos);
+    Ok(Some(value.parse().map_err(|err| format!("invalid value for {name}: {err}"))?))
+}
+
+/// Checks that exactly `n` positional arguments are left.
+fn positional(args: &[String], n: usize) -> Result<&[String]> {
+    if let Some(arg) = args.iter().find(|arg| arg.starts_with("--")) {
+        return Err(format!("unknown option: {arg}").into());
+    }
+    if args.len() != n {
+        return Err(format!("expected {n} arguments, got {}", args.len()).into());
+    }
+    Ok(args)
+}
+
+fn encode(mut args: Vec<String>) -> Result<()> {
+    let mut options = EncoderOptions::new();
+    if let Some(rows) = take_option(&mut args, "--restart-interval")? {
+        options = options.restart_interval(rows);
+    }
+    if let Some(max_run) = take_option(&mut args, "--max-run")? {
+        options = options.max_run(max_run);
+    }
+    let [input, output] = positional(&args, 2)? else { unreachable!() };
+    let pixels = Pixels::load(input)?;
+    let mut encoder =
+        Encoder::new(&pixels.data, pixels.width, pixels.height)?.with_options(options);
+    encoder.encode_to_file_atomic(output, Fsync::default())?;
+    Ok(())
+}
+
+fn decode(args: &[String]) -> Result<()> {
+    let [input, output] = positional(args, 2)? else { unreachable!() };
+    let pixels = Pixels::load(input)?;
+    let color = match pixels.channels {
+        Channels::Rgba => png::ColorType::Rgba,
+        Channels::Rgb => png::ColorType::Rgb,
+        Channels::La => png::ColorType::GrayscaleAlpha,
+    };
+    let mut encoder =
+        png::Encoder::new(BufWriter::new(File::create(output)?), pixels.width, pixels.height);
+    encoder.set_color(color);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels.data)?;
+    Ok(())
+}
+
+fn info(args: &[String]) -> Result<()> {
+    if args.is_empty() {
+        return Err("expected at least one file".into());
+    }
+    for path in args {
+        let bytes = fs::read(path).map_err(|err| format!("{path}: {err}"))?;
+        let decoder = Decoder::new(&bytes).map_err(|err| format!("{path}: {err}"))?;
+        let header = decoder.header();
+        let bits_per_pixel = bytes.len() as f64 * 8. / header.n_pixels() as f64;
+        println!("{path}:");
+        println!("  dimensions:  {}x{}", header.width, header.height);
+        println!("  channels:    {:?}", decoder.channels());
+        println!("  file size:   {} bytes ({bits_per_pixel:.2} bits per pixel)", bytes.len());
+        println!("  op stream:   {} bytes", header.length.unwrap_or_default());
+        if let Some(markers) = decoder.restart_markers() {
+            let (interval, n_bands) = (markers.interval(), markers.n_bands());
+            println!("  restarts:    every {interval} rows ({n_bands} bands)");
+        }
+        if let Some(nine_patch) = decoder.nine_patch() {
+            println!("  nine-patch:  {nine_patch:?}");
+        }
+        if let Some(field) = decoder.field() {
+            println!("  field:       {field:?}");
+        }
+        println!("  content id:  {}", ContentId::of(&bytes)?);
+    }
+    Ok(())
+}
+
+fn diff(args: &[String]) -> Result<bool> {
+    let [a, b] = positional(args, 2)? else { unreachable!() };
+    let (a, b) = (Pixels::load(a)?, Pixels::load(b)?);
+    if (a.width, a.height) != (b.width, b.height) {
+        println!("dimensions differ: {}x{} vs {}x{}", a.width, a.height, b.width, b.height);
+        return Ok(false);
+    }
+    let (mut n_diff, mut max_diff) = (0_usize, 0_u8);
+    let (mut min, mut max) = ((u32::MAX, u32::MAX), (0, 0));
+    for (i, (pa, pb)) in a.to_rgba().chunks_exact(4).zip(b.to_rgba().chunks_exact(4)).enumerate() {
+        if pa != pb {
+            #[allow(clippy::cast_possible_truncation)]
+            let (x, y) = ((i % a.width as usize) as u32, (i / a.width as usize) as u32);
+            n_diff += 1;
+            max_diff = pa.iter().zip(pb).map(|(ca, cb)| ca.abs_diff(*cb)).fold(max_diff, u8::max);
+            (min, max) = ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)));
+        }
+    }
+    if n_diff == 0 {
+        println!("identical");
+        return Ok(true);
+    }
+    println!(
+        "{n_diff} pixels differ, max channel difference {max_diff}, within ({}, {})..=({}, {})",
+        min.0, min.1, max.0, max.1
+    );
+    Ok(false)
+}
+
+/// Runs `f` repeatedly for about `seconds` and returns the median duration of a run.
+fn time_it<T>(seconds: f64, mut f: impl FnMut() -> Result<T>) -> Result<Duration> {
+    let mut times = Vec::new();
+    let start = Instant::now();
+    while times.is_empty() || start.elapsed().as_secs_f64() < seconds {
+        let t = Instant::now();
+        f()?;
+        times.push(t.elapsed());
+    }
+    times.sort_unstable();
+    Ok(times[times.len() / 2])
+}
+
+fn bench(mut args: Vec<String>) -> Result<()> {
+    let seconds = take_option(&mut args, "--seconds")?.unwrap_or(1.);
+    if args.is_empty() {
+        return Err("expected at least one file".into());
+    }
+    positional(&args, args.len())?;
+    println!("{:<40} {:>12} {:>8} {:>12} {:>12}", "file", "size", "ratio", "enc Mp/s", "dec Mp/s");
+    for path in &args {
+        let pixels = Pixels::load(path)?;
+        let mut encoder = Encoder::new(&pixels.data, pixels.width, pixels.height)?;
+        let qoi = encoder.encode_to_vec()?;
+        let mut buf = vec![0; encoder.required_buf_len()];
+        let encode = time_it(seconds, || Ok(encoder.encode_to_buf(&mut buf)?))?;
+        let decode = time_it(seconds, || Ok(qoi::decode_to_vec(&qoi)?))?;
+        let mpx = f64::from(pixels.width) * f64::from(pixels.height) / 1e6;
+        let ratio = qoi.len() as f64 / pixels.data.len() as f64;
+        println!(
+            "{path:<40} {:>12} {ratio:>8.3} {:>12.1} {:>12.1}",
+            format!("{}x{}", pixels.width, pixels.height),
+            mpx / encode.as_secs_f64(),
+            mpx / decode.as_secs_f64(),
+        );
+    }
+    Ok(())
+}
+
+fn run(mut args: Vec<String>) -> Result<bool> {
+    if args.is_empty() {
+        return Err("missing command".into());
+    }
+    match args.remove(0).as_str() {
+        "encode" => encode(args)?,
+        "decode" => decode(&args)?,
+        "info" => info(&args)?,
+        "diff" => return diff(&args),
+        "bench" => bench(args)?,
+        "help" | "--help" | "-h" => println!("{USAGE}"),
+        command => return Err(format!("unknown command: {command}").into()),
+    }
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    match run(env::args().skip(1).collect()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(any(feature = "std", feature = "alloc"))]
+use alloc::vec::Vec;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::decode::Decoder;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::encode::Encoder;
+use crate::error::{Error, Result};
+use crate::header::Header;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::utils::try_vec_zeroed;
+use crate::utils::unlikely;
+
+/// How [`pad_borders_to_buf`] and `pad_borders` fill the pixels outside of the source image.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BorderMode {
+    /// Repeat the nearest edge pixel (default)
+    #[default]
+    Clamp,
+    /// Reflect the image at its edges, repeating the edge pixels themselves
+    Mirror,
+    /// Tile the image, continuing from the opposite edge
+    Wrap,
+}
+
+impl BorderMode {
+    /// Maps a coordinate relative to the source image (possibly out of bounds) into it.
+    #[inline]
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    const fn source(self, i: isize, n: usize) -> usize {
+        let n = n as isize;
+        let i = match self {
+            Self::Clamp => {
+                if i < 0 {
+                    0
+                } else if i >= n {
+                    n - 1
+                } else {
+                    i
+                }
+            }
+            Self::Mirror => {
+                let i = i.rem_euclid(2 * n);
+                if i < n {
+                    i
+                } else {
+                    2 * n - 1 - i
+                }
+            }
+            Self::Wrap => i.rem_euclid(n),
+        };
+        i as usize
+    }
+}
+
+/// Pad an encoded image with `border` pixels on each side and re-encode it.
+///
+/// The image is decoded straight into the interior of the padded buffer, and the borders are
+/// then filled in place, so no intermediate copy of the image is made. The padded image keeps
+/// the [`Channels`](crate::Channels) layout of the source and uses the default encoder options.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn pad_borders(data: impl AsRef<[u8]>, border: u16, mode: BorderMode) -> Result<Vec<u8>> {
+    let mut decoder = Decoder::new(&data)?;
+    let (width, height) = (decoder.header().width, decoder.header().height);
+    let bpp = decoder.channels().as_u8() as usize;
+    let padded = padded_header(width, height, border)?;
+
+    let padded_row_len = padded.width as usize * bpp;
+    let mut out = try_vec_zeroed(padded.n_pixels() * bpp)?;
+    let mut rows = out.chunks_exact_mut(padded_row_len).skip(border as usize);
+    let left = border as usize * bpp;
+    decoder.decode_rows_into(|_| rows.next().map_or(&mut [][..], |row| &mut row[left..]))?;
+
+    fill_borders(&mut out, width, height, border, bpp, mode);
+    Encoder::new(&out, padded.width, padded.height)?.encode_to_vec()
+}
+
+/// Pad a raw RGBA image with `border` pixels on each side into a pre-allocated buffer.
+///
+/// The output buffer must hold at least `(sw + 2 * border) * (sh + 2 * border) * 4` bytes.
+pub fn pad_borders_to_buf(
+    src: impl AsRef<[u8]>, sw: u16, sh: u16, mut dst: impl AsMut<[u8]>, border: u16,
+    mode: BorderMode,
+) -> Result<()> {
+    let (src, dst) = (src.as_ref(), dst.as_mut());
+    let src_header = Header::try_new(sw, sh, None)?;
+    if unlikely(src.len() != src_header.n_bytes()) {
+        return Err(Error::InvalidImageLength { size: src.len(), width: sw, height: sh });
+    }
+    let padded = padded_header(sw, sh, border)?;
+    let required = padded.n_bytes();
+    if unlikely(dst.len() < required) {
+        return Err(Error::OutputBufferTooSmall { size: dst.len(), required });
+    }
+
+    let (row_len, left) = (sw as usize * 4, border as usize * 4);
+    let padded_rows = dst[..required].chunks_exact_mut(padded.width as usize * 4);
+    for (src_row, dst_row) in src.chunks_exact(row_len).zip(padded_rows.skip(border as usize)) {
+        dst_row[left..left + row_len].copy_from_slice(src_row);
+    }
+    fill_borders(&mut dst[..required], sw, sh, border, 4, mode);
+    Ok(())
+}
+
+/// Header of the padded image, checking that its dimensions are still valid.
+fn padded_header(width: u16, height: u16, border: u16) -> Result<Header> {
+    let extra = 2 * u32::from(border);
+    Header::from_dimensions(u32::from(width) + extra, u32::from(height) + extra, None)
+}
+
+/// Fills the borders around an image already placed in the interior of the padded buffer.
+#[allow(clippy::cast_possible_wrap)]
+fn fill_borders(
+    buf: &mut [u8], width: u16, height: u16, border: u16, bpp: usize, mode: BorderMode,
+) {
+    let (width, height, border) = (width as usize, height as usize, border as usize);
+    let padded_row_len = (width + 2 * border) * bpp;
+
+    // left and right columns of the interior rows first, so that whole rows can be copied next
+    for row in buf.chunks_exact_mut(padded_row_len).skip(border).take(height) {
+        for x in (0..border).chain(border + width..width + 2 * border) {
+            let sx = border + mode.source(x as isize - border as isize, width);
+            row.copy_within(sx * bpp..(sx + 1) * bpp, x * bpp);
+        }
+    }
+    for y in (0..border).chain(border + height..height + 2 * border) {
+        let sy = border + mode.source(y as isize - border as isize, height);
+        buf.copy_within(sy * padded_row_len..(sy + 1) * padded_row_len, y * padded_row_len);
+    }
+}
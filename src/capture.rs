@@ -0,0 +1,123 @@
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::vec::Vec;
+
+use crate::encode::{Encoder, EncoderOptions};
+use crate::error::{Error, Result};
+use crate::header::Header;
+
+/// Number of frame slots: one being filled by the caller, one being encoded.
+const N_SLOTS: usize = 2;
+
+/// Pipelined encoder for sequences of frames of the same size (e.g. video capture).
+///
+/// Frames are copied into one of two internal slots and encoded to the writer by a worker
+/// thread, so the caller can prepare the next frame while the previous one is being encoded.
+/// Encoded frames are written back to back, each being a complete image with its own header.
+///
+/// If both slots are busy, [`CaptureEncoder::submit_frame`] blocks until the worker catches
+/// up, whereas [`CaptureEncoder::try_submit_frame`] returns immediately.
+pub struct CaptureEncoder<W> {
+    width: u16,
+    height: u16,
+    free: Receiver<Vec<u8>>,
+    ready: Option<SyncSender<Vec<u8>>>,
+    worker: Option<JoinHandle<Result<W>>>,
+}
+
+impl<W: Write + Send + 'static> CaptureEncoder<W> {
+    /// Creates a new capture encoder and spawns its worker thread.
+    #[inline]
+    pub fn new(writer: W, width: u16, height: u16) -> Result<Self> {
+        Self::with_options(writer, width, height, EncoderOptions::new())
+    }
+
+    /// Creates a new capture encoder with a given encoder configuration.
+    pub fn with_options(
+        writer: W, width: u16, height: u16, options: EncoderOptions,
+    ) -> Result<Self> {
+        let _ = Header::try_new(width, height, None)?;
+        let (free_tx, free) = mpsc::sync_channel(N_SLOTS);
+        let (ready, ready_rx) = mpsc::sync_channel::<Vec<u8>>(N_SLOTS);
+        for _ in 0..N_SLOTS {
+            let _ = free_tx.send(Vec::new());
+        }
+        let worker = thread::spawn(move || {
+            let mut writer = writer;
+            let mut out = Vec::new();
+            for frame in ready_rx {
+                let mut encoder = Encoder::new(&frame, width, height)?.with_options(options);
+                out.resize(encoder.required_buf_len(), 0);
+                let n_written = encoder.encode_to_buf(&mut out)?;
+                writer.write_all(&out[..n_written])?;
+                // the receiving end is only gone if the encoder has been dropped
+                let _ = free_tx.send(frame);
+            }
+            writer.flush()?;
+            Ok(writer)
+        });
+        Ok(Self { width, height, free, ready: Some(ready), worker: Some(worker) })
+    }
+
+    /// Submits a frame for encoding, waiting for a free slot if both are busy.
+    ///
+    /// Errors that occurred while encoding previously submitted frames are reported here.
+    pub fn submit_frame(&mut self, frame: impl AsRef<[u8]>) -> Result<()> {
+        let frame = frame.as_ref();
+        let _ = Encoder::new(frame, self.width, self.height)?;
+        match self.free.recv() {
+            Ok(slot) => self.send(slot, frame),
+            Err(_) => Err(self.worker_error()),
+        }
+    }
+
+    /// Submits a frame for encoding if a slot is free, returns `false` otherwise.
+    ///
+    /// This never blocks, so the caller may decide to drop the frame instead of waiting.
+    pub fn try_submit_frame(&mut self, frame: impl AsRef<[u8]>) -> Result<bool> {
+        let frame = frame.as_ref();
+        let _ = Encoder::new(frame, self.width, self.height)?;
+        match self.free.try_recv() {
+            Ok(slot) => self.send(slot, frame).map(|()| true),
+            Err(TryRecvError::Empty) => Ok(false),
+            Err(TryRecvError::Disconnected) => Err(self.worker_error()),
+        }
+    }
+
+    /// Waits for all submitted frames to be encoded and returns the writer back.
+    pub fn finish(mut self) -> Result<W> {
+        drop(self.ready.take());
+        self.join()
+    }
+
+    fn send(&mut self, mut slot: Vec<u8>, frame: &[u8]) -> Result<()> {
+        slot.clear();
+        slot.extend_from_slice(frame);
+        match self.ready.as_ref().map(|ready| ready.send(slot)) {
+            Some(Ok(())) => Ok(()),
+            _ => Err(self.worker_error()),
+        }
+    }
+
+    /// Retrieves the error the worker thread has stopped with.
+    fn worker_error(&mut self) -> Error {
+        drop(self.ready.take());
+        match self.join() {
+            Err(err) => err,
+            Ok(_) => io::Error::new(io::ErrorKind::Other, "capture encoder has finished").into(),
+        }
+    }
+
+    fn join(&mut self) -> Result<W> {
+        match self.worker.take().map(JoinHandle::join) {
+            Some(Ok(result)) => result,
+            Some(Err(_)) => {
+                Err(io::Error::new(io::ErrorKind::Other, "capture encoder worker panicked").into())
+            }
+            None => {
+                Err(io::Error::new(io::ErrorKind::Other, "capture encoder has finished").into())
+            }
+        }
+    }
+}
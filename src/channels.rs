@@ -0,0 +1,59 @@
+//! Channel extraction and shuffling on encoded images.
+//!
+//! Each function is a single pass that decodes the image, transforms every pixel as it's
+//! being decoded and encodes the result with the default encoder options.
+
+use alloc::vec::Vec;
+
+use crate::decode::decode_to_vec_with;
+use crate::encode::encode_to_vec;
+use crate::error::Result;
+
+/// A single color channel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+    /// Red
+    R,
+    /// Green
+    G,
+    /// Blue
+    B,
+    /// Alpha
+    A,
+}
+
+impl Channel {
+    #[inline]
+    const fn index(self) -> usize {
+        match self {
+            Self::R => 0,
+            Self::G => 1,
+            Self::B => 2,
+            Self::A => 3,
+        }
+    }
+}
+
+/// Extract one channel of an encoded image as a new encoded grayscale image.
+///
+/// The channel value is written to the red, green and blue channels of the output, alpha
+/// is set to 255.
+pub fn extract(data: impl AsRef<[u8]>, channel: Channel) -> Result<Vec<u8>> {
+    let i = channel.index();
+    let (header, pixels) = decode_to_vec_with(data.as_ref(), |px| {
+        let v = <[u8; 4]>::from(px)[i];
+        [v, v, v, 0xff]
+    })?;
+    encode_to_vec(&pixels, header.width, header.height)
+}
+
+/// Swap two channels of an encoded image, returning the re-encoded image.
+pub fn swap(data: impl AsRef<[u8]>, a: Channel, b: Channel) -> Result<Vec<u8>> {
+    let (a, b) = (a.index(), b.index());
+    let (header, pixels) = decode_to_vec_with(data.as_ref(), |px| {
+        let mut px = <[u8; 4]>::from(px);
+        px.swap(a, b);
+        px
+    })?;
+    encode_to_vec(&pixels, header.width, header.height)
+}
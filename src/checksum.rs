@@ -0,0 +1,115 @@
+#[cfg(feature = "xxh3")]
+use xxhash_rust::xxh3::Xxh3Default;
+
+/// Checksum of the op stream stored in the extension block, see
+/// [`Encoder::with_checksum`](crate::Encoder::with_checksum).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ChecksumKind {
+    /// CRC-32 (IEEE), the one used by zlib and PNG
+    Crc32 = 0,
+    /// 64-bit XXH3, a lot faster on large images and less likely to miss corruption (needs the
+    /// `xxh3` feature)
+    #[cfg(feature = "xxh3")]
+    Xxh3 = 1,
+}
+
+impl ChecksumKind {
+    /// Parses the kind stored in the checksum record; kinds this build doesn't know about
+    /// (including XXH3 without the `xxh3` feature) can't be verified.
+    #[inline]
+    pub(crate) const fn from_u8(kind: u8) -> Option<Self> {
+        match kind {
+            0 => Some(Self::Crc32),
+            #[cfg(feature = "xxh3")]
+            1 => Some(Self::Xxh3),
+            _ => None,
+        }
+    }
+
+    /// Size of the checksum in bytes.
+    #[inline]
+    pub(crate) const fn len(self) -> usize {
+        match self {
+            Self::Crc32 => 4,
+            #[cfg(feature = "xxh3")]
+            Self::Xxh3 => 8,
+        }
+    }
+
+    /// Computes the checksum of `data` in one go.
+    #[inline]
+    pub(crate) fn checksum(self, data: &[u8]) -> Checksum {
+        let mut hasher = Hasher::new(self);
+        hasher.update(data);
+        hasher.finish()
+    }
+}
+
+/// Checksum value along with its kind, zero-extended to 64 bits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Checksum {
+    pub kind: ChecksumKind,
+    pub value: u64,
+}
+
+/// Running checksum of data fed in pieces.
+// the XXH3 state is large, but there's only ever one per image and boxing it would need alloc
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone)]
+pub enum Hasher {
+    Crc32(u32),
+    #[cfg(feature = "xxh3")]
+    Xxh3(Xxh3Default),
+}
+
+impl Hasher {
+    #[inline]
+    pub const fn new(kind: ChecksumKind) -> Self {
+        match kind {
+            ChecksumKind::Crc32 => Self::Crc32(!0),
+            #[cfg(feature = "xxh3")]
+            ChecksumKind::Xxh3 => Self::Xxh3(Xxh3Default::new()),
+        }
+    }
+
+    #[inline]
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Crc32(crc) => {
+                for &b in data {
+                    *crc = CRC32_TABLE[((*crc ^ u32::from(b)) & 0xff) as usize] ^ (*crc >> 8);
+                }
+            }
+            #[cfg(feature = "xxh3")]
+            Self::Xxh3(hasher) => hasher.update(data),
+        }
+    }
+
+    #[inline]
+    pub fn finish(&self) -> Checksum {
+        match self {
+            Self::Crc32(crc) => Checksum { kind: ChecksumKind::Crc32, value: (!crc).into() },
+            #[cfg(feature = "xxh3")]
+            Self::Xxh3(hasher) => Checksum { kind: ChecksumKind::Xxh3, value: hasher.digest() },
+        }
+    }
+}
+
+/// Lookup table of the reflected CRC-32 polynomial.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        #[allow(clippy::cast_possible_truncation)]
+        let mut crc = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            crc = if crc & 1 == 0 { crc >> 1 } else { 0xedb8_8320 ^ (crc >> 1) };
+            k += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
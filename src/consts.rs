@@ -7,11 +7,45 @@ pub const QOI_OP_RGBA: u8 = 0xff; // 11111111
 
 pub const QOI_MASK_2: u8 = 0xc0; // (11)000000
 
+pub const QOI_RUN_MAX: u8 = 62;
+
 pub const QOI_HEADER_SIZE: usize = 12;
+pub const QOI_REFERENCE_HEADER_SIZE: usize = 14;
 
 pub const QOI_PADDING: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 0x01]; // 7 zeros and one 0x01 marker
 pub const QOI_PADDING_SIZE: usize = 8;
 
 pub const QOI_MAGIC: u32 = u32::from_be_bytes(*b"qoif");
 
+// the header differs from the official format, so this can't be `image/qoi`
+pub const QOI_MEDIA_TYPE: &str = "image/x-gamemaker-qoi";
+
+#[cfg(not(feature = "large-images"))]
 pub const QOI_PIXELS_MAX: usize = 400_000_000;
+#[cfg(feature = "large-images")]
+pub const QOI_PIXELS_MAX: usize = u16::MAX as usize * u16::MAX as usize;
+
+pub const QOI_EXT_MAGIC: u32 = u32::from_be_bytes(*b"qoix");
+pub const QOI_EXT_HEADER_SIZE: usize = 8;
+pub const QOI_EXT_RECORD_HEADER_SIZE: usize = 5;
+
+pub const QOI_EXT_TAG_RESTART: u8 = 0x01;
+pub const QOI_EXT_TAG_CHANNELS: u8 = 0x02;
+pub const QOI_EXT_TAG_VERSION: u8 = 0x03;
+pub const QOI_EXT_TAG_NINE_PATCH: u8 = 0x04;
+pub const QOI_EXT_TAG_LOW_BITS: u8 = 0x05;
+pub const QOI_EXT_TAG_FIELD: u8 = 0x06;
+pub const QOI_EXT_TAG_COLORSPACE: u8 = 0x07;
+pub const QOI_EXT_TAG_CHECKSUM: u8 = 0x08;
+
+pub const QOI_EXT_VERSION: u8 = 1;
+pub const QOI_EXT_FLAGS_KNOWN: u32 = 0;
+pub const QOI_EXT_FLAGS_REQUIRED: u32 = 0xffff_0000;
+
+pub const QOI_ANIM_MAGIC: u32 = u32::from_be_bytes(*b"qoia");
+pub const QOI_ANIM_VERSION: u8 = 1;
+pub const QOI_ANIM_HEADER_SIZE: usize = 12;
+pub const QOI_ANIM_ENTRY_SIZE: usize = 28;
+pub const QOI_ANIM_TRAILER_SIZE: usize = 16;
+pub const QOI_ANIM_RECORD_MAGIC: u32 = u32::from_be_bytes(*b"qoir");
+pub const QOI_ANIM_RECORD_SIZE: usize = 4 + QOI_ANIM_ENTRY_SIZE;
@@ -0,0 +1,303 @@
+use core::fmt::{self, Debug, Display};
+use core::hash::{Hash, Hasher};
+
+use crate::consts::QOI_PADDING_SIZE;
+use crate::decode::Decoder;
+use crate::error::{Error, Result};
+use crate::ext;
+use crate::header::{Channels, Header};
+use crate::utils::{unlikely, Writer};
+
+/// Content address of an encoded image: a SHA-256 digest of its dimensions, channel layout and
+/// op stream.
+///
+/// The digest is computed while encoding (see [`Encoder::encode_to_vec_with_id`]), so asset
+/// stores get a stable key without hashing the output again; [`ContentId::of`] recomputes it
+/// from an encoded image, e.g. to verify stored assets. Apart from the low bits of RGB10A2
+/// images, the extension block isn't hashed, so metadata such as
+/// [`NinePatch`](crate::NinePatch) doesn't affect the id, but encoder options that change the
+/// op stream (like a restart interval) do.
+///
+/// Ids are compared in constant time, so they may be used to check untrusted content.
+///
+/// [`Encoder::encode_to_vec_with_id`]: crate::Encoder::encode_to_vec_with_id
+#[derive(Copy, Clone, Eq)]
+pub struct ContentId([u8; 32]);
+
+impl ContentId {
+    /// Computes the content id of an encoded image.
+    pub fn of(encoded: impl AsRef<[u8]>) -> Result<Self> {
+        let encoded = encoded.as_ref();
+        let decoder = Decoder::new(encoded)?;
+        let (header, body) = (decoder.header(), &encoded[decoder.header_format().size()..]);
+        // without an extension block, the image ends with the op stream
+        let ops_len = header.length.map_or(body.len(), |len| len as usize);
+        let ops = match body.get(..ops_len) {
+            Some(ops) if ops.len() >= QOI_PADDING_SIZE => ops,
+            _ => return Err(Error::UnexpectedBufferEnd { offset: 0, pixel: 0 }),
+        };
+        let mut hasher = Sha256::new();
+        hash_prefix(&mut hasher, header, decoder.channels());
+        hasher.update(ops);
+        let n_pixels = header.n_pixels();
+        hasher.update(ext::low_bits(body, ops_len, n_pixels).unwrap_or_default());
+        Ok(Self(hasher.finalize()))
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    pub const fn from_digest(digest: [u8; 32]) -> Self {
+        Self(digest)
+    }
+
+    /// Raw bytes of the digest.
+    #[inline]
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl PartialEq for ContentId {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        // no early exit, so the comparison doesn't leak the length of the common prefix
+        self.0.iter().zip(other.0.iter()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+    }
+}
+
+impl Hash for ContentId {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl Display for ContentId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+impl Debug for ContentId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ContentId({self})")
+    }
+}
+
+impl From<ContentId> for [u8; 32] {
+    #[inline]
+    fn from(id: ContentId) -> Self {
+        id.0
+    }
+}
+
+/// Hashes what precedes the op stream: the dimensions and the channel layout.
+#[doc(hidden)]
+pub fn hash_prefix(hasher: &mut Sha256, header: &Header, channels: Channels) {
+    hasher.update(&header.width.to_le_bytes());
+    hasher.update(&header.height.to_le_bytes());
+    hasher.update(&[channels.as_u8()]);
+}
+
+/// Writer that hashes everything written through it.
+#[doc(hidden)]
+pub struct HashingWriter<'a, W> {
+    writer: W,
+    hasher: &'a mut Sha256,
+}
+
+impl<'a, W: Writer> HashingWriter<'a, W> {
+    pub fn new(writer: W, hasher: &'a mut Sha256) -> Self {
+        Self { writer, hasher }
+    }
+}
+
+impl<W: Writer> Writer for HashingWriter<'_, W> {
+    #[inline]
+    fn write_one(mut self, v: u8) -> Result<Self> {
+        self.writer = self.writer.write_one(v)?;
+        self.hasher.update(&[v]);
+        Ok(self)
+    }
+
+    #[inline]
+    fn write_many(mut self, v: &[u8]) -> Result<Self> {
+        self.writer = self.writer.write_many(v)?;
+        self.hasher.update(v);
+        Ok(self)
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        self.writer.capacity()
+    }
+}
+
+const K: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+/// Incremental SHA-256 (FIPS 180-4).
+#[doc(hidden)]
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+}
+
+impl Sha256 {
+    pub const fn new() -> Self {
+        Self {
+            state: [
+                0x6a09_e667,
+                0xbb67_ae85,
+                0x3c6e_f372,
+                0xa54f_f53a,
+                0x510e_527f,
+                0x9b05_688c,
+                0x1f83_d9ab,
+                0x5be0_cd19,
+            ],
+            block: [0; 64],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total_len = self.total_len.wrapping_add(data.len() as u64);
+        if self.block_len != 0 {
+            let n = data.len().min(64 - self.block_len);
+            self.block[self.block_len..self.block_len + n].copy_from_slice(&data[..n]);
+            self.block_len += n;
+            data = &data[n..];
+            if self.block_len < 64 {
+                return;
+            }
+            let block = self.block;
+            self.compress(&block);
+            self.block_len = 0;
+        }
+        let mut blocks = data.chunks_exact(64);
+        for block in &mut blocks {
+            self.compress(block);
+        }
+        let rest = blocks.remainder();
+        self.block[..rest.len()].copy_from_slice(rest);
+        self.block_len = rest.len();
+    }
+
+    pub fn finalize(mut self) -> [u8; 32] {
+        let bit_len = self.total_len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.block_len != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bit_len.to_be_bytes());
+        let mut out = [0; 32];
+        for (chunk, word) in out.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+
+    #[allow(clippy::many_single_char_names)]
+    fn compress(&mut self, block: &[u8]) {
+        if unlikely(block.len() != 64) {
+            return;
+        }
+        let mut w = [0_u32; 64];
+        for (word, chunk) in w.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for (k, w) in K.iter().zip(w) {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(*k).wrapping_add(w);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
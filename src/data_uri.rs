@@ -0,0 +1,61 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::consts::QOI_MEDIA_TYPE;
+use crate::decode::decode_to_vec;
+use crate::encode::encode_to_vec;
+use crate::error::{Error, Result};
+use crate::header::{Dimension, Header};
+
+/// Encode an image into a `data:` URI, e.g. to embed a small asset in a JSON or HTML payload.
+///
+/// The URI has the form `data:image/x-gamemaker-qoi;base64,...` (see
+/// [`QOI_MEDIA_TYPE`](crate::consts::QOI_MEDIA_TYPE)); the base64 text is appended straight to
+/// the URI without an intermediate buffer.
+pub fn to_data_uri(
+    data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension,
+) -> Result<String> {
+    let encoded = encode_to_vec(data, width, height)?;
+    let prefix = ["data:", QOI_MEDIA_TYPE, ";base64,"];
+    let len = base64::encoded_len(encoded.len(), true).ok_or(Error::OutOfMemory)?;
+    let len = prefix.iter().map(|part| part.len()).sum::<usize>() + len;
+    let mut uri = String::new();
+    uri.try_reserve_exact(len).map_err(|_| Error::OutOfMemory)?;
+    for part in prefix {
+        uri.push_str(part);
+    }
+    STANDARD.encode_string(encoded, &mut uri);
+    Ok(uri)
+}
+
+/// Decode an image from a `data:` URI produced by [`to_data_uri`].
+///
+/// The media type must be [`QOI_MEDIA_TYPE`](crate::consts::QOI_MEDIA_TYPE) and the payload
+/// base64-encoded; whitespace in the payload (e.g. line breaks inserted by a formatter) is
+/// ignored. Anything else fails with [`Error::InvalidDataUri`].
+pub fn from_data_uri(uri: &str) -> Result<(Header, Vec<u8>)> {
+    let uri = uri.trim();
+    let rest = match (uri.get(..5), uri.get(5..)) {
+        (Some(scheme), Some(rest)) if scheme.eq_ignore_ascii_case("data:") => rest,
+        _ => return Err(Error::InvalidDataUri),
+    };
+    let (metadata, payload) = rest.split_once(',').ok_or(Error::InvalidDataUri)?;
+    let mut params = metadata.split(';');
+    let media_type = params.next().unwrap_or_default();
+    if !media_type.eq_ignore_ascii_case(QOI_MEDIA_TYPE)
+        || !params.next_back().map_or(false, |param| param.eq_ignore_ascii_case("base64"))
+    {
+        return Err(Error::InvalidDataUri);
+    }
+    let payload = if payload.bytes().any(|b| b.is_ascii_whitespace()) {
+        Cow::Owned(payload.chars().filter(|c| !c.is_ascii_whitespace()).collect())
+    } else {
+        Cow::Borrowed(payload)
+    };
+    let encoded = STANDARD.decode(payload.as_bytes()).map_err(|_| Error::InvalidDataUri)?;
+    decode_to_vec(encoded)
+}
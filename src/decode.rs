@@ -1,30 +1,57 @@
 #[cfg(any(feature = "std", feature = "alloc"))]
-use alloc::{vec, vec::Vec};
+use alloc::{collections::BTreeSet, vec::Vec};
 #[cfg(feature = "std")]
 use std::io::Read;
 
 // TODO: can be removed once https://github.com/rust-lang/rust/issues/74985 is stable
 use bytemuck::{cast_slice_mut, Pod};
 
+#[cfg(feature = "std")]
+use crate::consts::QOI_REFERENCE_HEADER_SIZE;
 use crate::consts::{
-    QOI_HEADER_SIZE, QOI_OP_DIFF, QOI_OP_INDEX, QOI_OP_LUMA, QOI_OP_RGB, QOI_OP_RGBA, QOI_OP_RUN,
-    QOI_PADDING, QOI_PADDING_SIZE,
+    QOI_EXT_FLAGS_KNOWN, QOI_EXT_FLAGS_REQUIRED, QOI_EXT_VERSION, QOI_HEADER_SIZE, QOI_OP_DIFF,
+    QOI_OP_INDEX, QOI_OP_LUMA, QOI_OP_RGB, QOI_OP_RGBA, QOI_OP_RUN, QOI_PADDING, QOI_PADDING_SIZE,
 };
 use crate::error::{Error, Result};
-use crate::header::Header;
-use crate::pixel::Pixel;
+use crate::ext::{self, restart_markers, FormatVersion, RestartMarkers};
+use crate::field::Field;
+use crate::header::{Channels, Dimension, Header, HeaderFormat};
+use crate::limits::Limits;
+#[cfg(feature = "metrics")]
+use crate::metrics;
+use crate::nine_patch::NinePatch;
+use crate::ops::OpDecoder;
+use crate::ops::OpKind;
+use crate::packed::{self, PackedFormat};
+use crate::pixel::{Pixel, PixelFormat};
+use crate::rgb10a2;
+use crate::rgb565::{self, ByteOrder};
+#[cfg(feature = "tracing")]
+use crate::trace;
+use crate::transform::StreamTransform;
 use crate::utils::{cold, unlikely};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::utils::{try_vec_with_capacity, try_vec_zeroed};
+use crate::yuv::YuvMatrix;
 
 const QOI_OP_INDEX_END: u8 = QOI_OP_INDEX | 0x3f;
 const QOI_OP_RUN_END: u8 = QOI_OP_RUN | 0x3d; // <- note, 0x3d (not 0x3f)
 const QOI_OP_DIFF_END: u8 = QOI_OP_DIFF | 0x3f;
 const QOI_OP_LUMA_END: u8 = QOI_OP_LUMA | 0x3f;
 
+/// Decodes ops until the output is filled and returns the rest of the input.
+///
+/// `map` is applied to every output pixel; it doesn't affect the decoder state. Errors are
+/// located relative to the start of `data` and `out`.
 #[inline]
-fn decode_impl_slice(data: &[u8], out: &mut [u8]) -> Result<usize> {
-    let mut pixels = cast_slice_mut::<_, [u8; 4]>(out);
-    let data_len = data.len();
-    let mut data = data;
+fn decode_ops_slice<'a, const N: usize>(
+    mut data: &'a [u8], out: &mut [u8], map: impl Fn(Pixel) -> [u8; N],
+) -> Result<&'a [u8]>
+where
+    [u8; N]: Pod,
+{
+    let mut pixels = cast_slice_mut::<_, [u8; N]>(out);
+    let (data_len, n_pixels) = (data.len(), pixels.len());
 
     let mut index = [Pixel::new(); 256];
     let mut px = Pixel::new().with_a(0xff);
@@ -36,7 +63,7 @@ fn decode_impl_slice(data: &[u8], out: &mut [u8]) -> Result<usize> {
             [b1 @ QOI_OP_INDEX..=QOI_OP_INDEX_END, dtail @ ..] => {
                 px_rgba = index[*b1 as usize];
                 px.update(px_rgba);
-                *px_out = px.into();
+                *px_out = map(px);
                 data = dtail;
                 continue;
             }
@@ -49,10 +76,10 @@ fn decode_impl_slice(data: &[u8], out: &mut [u8]) -> Result<usize> {
                 data = dtail;
             }
             [b1 @ QOI_OP_RUN..=QOI_OP_RUN_END, dtail @ ..] => {
-                *px_out = px.into();
+                *px_out = map(px);
                 let run = ((b1 & 0x3f) as usize).min(pixels.len());
                 let (phead, ptail) = pixels.split_at_mut(run); // can't panic
-                phead.fill(px.into());
+                phead.fill(*px_out);
                 pixels = ptail;
                 data = dtail;
                 continue;
@@ -68,30 +95,139 @@ fn decode_impl_slice(data: &[u8], out: &mut [u8]) -> Result<usize> {
             _ => {
                 cold();
                 if unlikely(data.len() < QOI_PADDING_SIZE) {
-                    return Err(Error::UnexpectedBufferEnd);
+                    let (offset, pixel) = (data_len - data.len(), n_pixels - pixels.len() - 1);
+                    return Err(Error::UnexpectedBufferEnd { offset, pixel });
                 }
             }
         }
 
         px_rgba = px.as_rgba();
         index[px_rgba.hash_index() as usize] = px_rgba;
-        *px_out = px.into();
+        *px_out = map(px);
+    }
+
+    Ok(data)
+}
+
+/// Decodes ops like [`decode_ops_slice`], producing pixels in a given layout.
+#[inline]
+fn decode_ops_slice_as<'a>(
+    data: &'a [u8], out: &mut [u8], options: DecoderOptions, channels: Channels,
+) -> Result<&'a [u8]> {
+    match channels {
+        Channels::Rgba if options.is_identity() => decode_ops_slice(data, out, <[u8; 4]>::from),
+        Channels::Rgba => decode_ops_slice(data, out, |px| options.map(px).into()),
+        Channels::Rgb => decode_ops_slice(data, out, |px| {
+            let px = options.map(px);
+            [px.r(), px.g(), px.b()]
+        }),
+        Channels::La => decode_ops_slice(data, out, |px| {
+            let px = options.map(px);
+            [px.luma(), px.a()]
+        }),
     }
+}
 
+/// Checks the end marker at the start of `data`, found at `offset` of the op stream after
+/// `n_pixels` pixels.
+#[inline]
+pub fn check_padding(data: &[u8], offset: usize, n_pixels: usize) -> Result<()> {
     if unlikely(data.len() < QOI_PADDING_SIZE) {
-        return Err(Error::UnexpectedBufferEnd);
+        return Err(Error::UnexpectedBufferEnd { offset, pixel: n_pixels });
     } else if unlikely(data[..QOI_PADDING_SIZE] != QOI_PADDING) {
-        return Err(Error::InvalidPadding);
+        return Err(Error::InvalidPadding { offset, pixel: n_pixels });
+    }
+    Ok(())
+}
+
+/// Checks that the ops cover exactly `n_pixels` pixels, and that the end marker following them
+/// ends the op stream at its declared length with nothing but the extension block after it.
+fn check_strict(data: &[u8], declared: Option<usize>, n_pixels: usize) -> Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("qoi::check_strict", n_pixels).entered();
+    let at_padding = |ops: &OpDecoder| data[ops.offset()..].starts_with(&QOI_PADDING);
+    let mut ops = OpDecoder::new(data);
+    let mut n_found = 0;
+    while n_found < n_pixels {
+        n_found += ops.next_op()?.n_pixels;
+    }
+    // walk on past the last pixel to tell extra ops apart from a broken end marker
+    let offset = ops.offset();
+    while !at_padding(&ops) {
+        match ops.next_op() {
+            Ok(op) => n_found += op.n_pixels,
+            Err(_) => return check_padding(&data[offset..], offset, n_pixels),
+        }
+    }
+    if unlikely(n_found != n_pixels) {
+        return Err(Error::PixelCountMismatch { expected: n_pixels, actual: n_found });
+    }
+    let ops_len = ops.offset() + QOI_PADDING_SIZE;
+    if let Some(declared) = declared.filter(|&declared| declared != ops_len) {
+        return Err(Error::LengthMismatch { declared, actual: ops_len });
+    }
+    let end = ops_len + ext::block_len(data, ops_len);
+    if unlikely(end != data.len()) {
+        return Err(Error::TrailingData { len: data.len() - end });
     }
+    Ok(())
+}
+
+/// Adds a color to the set, failing if this makes it exceed the limit.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+fn insert_color(colors: &mut BTreeSet<Pixel>, px: Pixel, limit: usize) -> Result<()> {
+    if colors.insert(px) && unlikely(colors.len() > limit) {
+        return Err(Error::TooManyColors { limit });
+    }
+    Ok(())
+}
+
+/// Counts distinct colors by walking the op stream, without producing any pixels.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn check_op_colors(data: &[u8], n_pixels: usize, limit: usize) -> Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("qoi::check_colors", limit).entered();
+    let mut colors = BTreeSet::new();
+    let mut ops = OpDecoder::new(data);
+    let mut n_left = n_pixels;
+    while n_left != 0 {
+        let op = ops.next_op()?;
+        n_left = n_left.saturating_sub(op.n_pixels);
+        insert_color(&mut colors, op.px, limit)?;
+    }
+    Ok(())
+}
 
+/// Counts distinct colors of already decoded pixels.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn check_pixel_colors(pixels: &[u8], bpp: usize, limit: usize) -> Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("qoi::check_colors", limit).entered();
+    let mut colors = BTreeSet::new();
+    let mut px = Pixel::new();
+    for chunk in pixels.chunks_exact(bpp) {
+        px.read(chunk);
+        insert_color(&mut colors, px, limit)?;
+    }
+    Ok(())
+}
+
+#[inline]
+fn decode_impl_slice(
+    data: &[u8], out: &mut [u8], options: DecoderOptions, channels: Channels,
+) -> Result<usize> {
+    let data_len = data.len();
+    let data = decode_ops_slice_as(data, out, options, channels)?;
+    check_padding(data, data_len - data.len(), out.len() / channels.as_u8() as usize)?;
     Ok(data_len.saturating_sub(data.len()).saturating_sub(QOI_PADDING_SIZE))
 }
 
 
 /// Decode the image into a pre-allocated buffer.
 ///
-/// Note: the resulting number of channels will match the header. In order to change
-/// the number of channels, use [`Decoder::with_channels`].
+/// Note: the resulting number of channels will match the layout the image has been encoded
+/// from. In order to change the number of channels, use [`Decoder::with_channels`].
 #[inline]
 pub fn decode_to_buf(buf: impl AsMut<[u8]>, data: impl AsRef<[u8]>) -> Result<Header> {
     let mut decoder = Decoder::new(&data)?;
@@ -101,8 +237,8 @@ pub fn decode_to_buf(buf: impl AsMut<[u8]>, data: impl AsRef<[u8]>) -> Result<He
 
 /// Decode the image into a newly allocated vector.
 ///
-/// Note: the resulting number of channels will match the header. In order to change
-/// the number of channels, use [`Decoder::with_channels`].
+/// Note: the resulting number of channels will match the layout the image has been encoded
+/// from. In order to change the number of channels, use [`Decoder::with_channels`].
 #[cfg(any(feature = "std", feature = "alloc"))]
 #[inline]
 pub fn decode_to_vec(data: impl AsRef<[u8]>) -> Result<(Header, Vec<u8>)> {
@@ -111,34 +247,136 @@ pub fn decode_to_vec(data: impl AsRef<[u8]>) -> Result<(Header, Vec<u8>)> {
     Ok((*decoder.header(), out))
 }
 
+/// Decode a bare op stream written by [`encode_headerless`](crate::encode_headerless) into a
+/// newly allocated vector of RGBA pixels.
+///
+/// See [`Decoder::new_headerless`] for details.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+pub fn decode_headerless(
+    data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension,
+) -> Result<Vec<u8>> {
+    Decoder::new_headerless(data.as_ref(), width, height)?.decode_to_vec()
+}
+
+/// Decode the image into a newly allocated vector of RGBA [`Pixel`]s (luma + alpha and RGB
+/// images are expanded), for code working on typed pixels rather than raw bytes.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn decode_to_pixel_vec(data: impl AsRef<[u8]>) -> Result<(Header, Vec<Pixel>)> {
+    let mut decoder = Decoder::new(&data)?.with_channels(Channels::Rgba);
+    let n_pixels = decoder.header().n_pixels();
+    let mut out = try_vec_with_capacity::<[u8; 4]>(n_pixels)?;
+    out.resize(n_pixels, [0; 4]);
+    let _ = decoder.decode_to_buf(cast_slice_mut(&mut out))?;
+    // `Pixel` can only be cast to with the `pod` feature, so the pixels are converted here
+    Ok((*decoder.header(), out.into_iter().map(Pixel::from).collect()))
+}
+
+/// Decode the image into a newly allocated vector, tolerating images written by newer versions
+/// of the crate where feasible.
+///
+/// See [`Decoder::new_compat`] for details.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+pub fn decode_compat(data: impl AsRef<[u8]>) -> Result<(Header, Vec<u8>)> {
+    let mut decoder = Decoder::new_compat(&data)?;
+    let out = decoder.decode_to_vec()?;
+    Ok((*decoder.header(), out))
+}
+
+/// Decodes a header of either format at the start of `data`, returning it along with the
+/// channels it declares and everything following it.
+#[inline]
+pub fn split_header(data: &[u8]) -> Result<(Header, Channels, &[u8])> {
+    let mut bytes = Bytes::new(data);
+    let (header, _, channels) = bytes.decode_header()?;
+    Ok((header, channels, bytes.body()))
+}
+
+/// Decode the image into a newly allocated RGBA vector, applying `map` to every pixel.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn decode_to_vec_with(
+    data: &[u8], map: impl Fn(Pixel) -> [u8; 4],
+) -> Result<(Header, Vec<u8>)> {
+    let (header, _, ops) = split_header(data)?;
+    let mut out = try_vec_zeroed(header.n_bytes())?;
+    let rest = decode_ops_slice(ops, &mut out, map)?;
+    check_padding(rest, ops.len() - rest.len(), header.n_pixels())?;
+    Ok((header, out))
+}
+
 /// Decode the image header from a slice of bytes.
+///
+/// If `data` holds the whole image, the metadata stored in the extension block after the op
+/// stream ([`Header::colorspace`] and [`Header::nine_patch`]) is read as well; it's left unset
+/// if only the start of the image is given.
 #[inline]
 pub fn decode_header(data: impl AsRef<[u8]>) -> Result<Header> {
-    Header::decode(data)
+    let (mut header, _, body) = split_header(data.as_ref())?;
+    // reference headers declare the color space themselves and have no extension block
+    if let Some(ops_len) = header.length {
+        header.colorspace = ext::colorspace(body, ops_len as usize);
+        header.nine_patch = ext::nine_patch(body, ops_len as usize, header.width, header.height);
+    }
+    Ok(header)
+}
+
+/// Decoder state carried over between chunks of a stream.
+#[derive(Clone)]
+pub struct StreamState {
+    index: [Pixel; 256],
+    px: Pixel,
+    /// Pixels of the last run that didn't fit into the previous chunk
+    pub run: usize,
+    /// Rows decoded so far by `Decoder::decode_next_rows`
+    #[cfg(feature = "std")]
+    pub row: u16,
+    /// Bytes of the op stream read so far by `decode_impl_stream`
+    #[cfg(feature = "std")]
+    pub offset: usize,
+}
+
+impl StreamState {
+    pub const fn new() -> Self {
+        Self {
+            index: [Pixel::new(); 256],
+            px: Pixel::new().with_a(0xff),
+            run: 0,
+            #[cfg(feature = "std")]
+            row: 0,
+            #[cfg(feature = "std")]
+            offset: 0,
+        }
+    }
 }
 
+/// Decodes ops pulled from a reader until the output is filled, resuming from `state`.
 #[cfg(feature = "std")]
 #[inline]
-fn decode_impl_stream<R: Read>(
-    data: &mut R, out: &mut [u8],
+fn decode_impl_stream<R: Read, const N: usize>(
+    data: &mut R, out: &mut [u8], state: &mut StreamState, map: impl Fn(Pixel) -> [u8; N],
 ) -> Result<()>
 where
-    [u8; 4]: Pod,
+    [u8; N]: Pod,
 {
-    let mut pixels = cast_slice_mut::<_, [u8; 4]>(out);
+    let mut pixels = cast_slice_mut::<_, [u8; N]>(out);
+    let StreamState { index, px, run: run_left, offset, .. } = state;
 
-    let mut index = [Pixel::new(); 256];
-    let mut px = Pixel::new().with_a(0xff);
+    let run = (*run_left).min(pixels.len());
+    let (phead, ptail) = pixels.split_at_mut(run); // can't panic
+    phead.fill(map(*px));
+    (pixels, *run_left) = (ptail, *run_left - run);
 
     while let [px_out, ptail @ ..] = pixels {
         pixels = ptail;
         let mut p = [0];
         data.read_exact(&mut p)?;
         let [b1] = p;
+        *offset += OpKind::from_byte(b1).n_bytes();
         match b1 {
             QOI_OP_INDEX..=QOI_OP_INDEX_END => {
-                px = index[b1 as usize];
-                *px_out = px.into();
+                *px = index[b1 as usize];
+                *px_out = map(*px);
                 continue;
             }
             QOI_OP_RGB => {
@@ -152,11 +390,11 @@ where
                 px.update_rgba(p[0], p[1], p[2], p[3]);
             }
             QOI_OP_RUN..=QOI_OP_RUN_END => {
-                *px_out = px.into();
+                *px_out = map(*px);
                 let run = ((b1 & 0x3f) as usize).min(pixels.len());
                 let (phead, ptail) = pixels.split_at_mut(run); // can't panic
-                phead.fill(px.into());
-                pixels = ptail;
+                phead.fill(*px_out);
+                (pixels, *run_left) = (ptail, (b1 & 0x3f) as usize - run);
                 continue;
             }
             QOI_OP_DIFF..=QOI_OP_DIFF_END => {
@@ -173,76 +411,354 @@ where
             }
         }
 
-        index[px.hash_index() as usize] = px;
-        *px_out = px.into();
+        index[px.hash_index() as usize] = *px;
+        *px_out = map(*px);
+    }
+    Ok(())
+}
+
+/// Decodes the next pixels of a stream in a given layout, resuming from `state`.
+#[cfg(feature = "std")]
+#[inline]
+fn decode_stream_as<R: Read>(
+    data: &mut R, out: &mut [u8], state: &mut StreamState, options: DecoderOptions,
+    channels: Channels,
+) -> Result<()> {
+    match channels {
+        Channels::Rgba if options.is_identity() => {
+            decode_impl_stream(data, out, state, <[u8; 4]>::from)
+        }
+        Channels::Rgba => decode_impl_stream(data, out, state, |px| options.map(px).into()),
+        Channels::Rgb => decode_impl_stream(data, out, state, |px| {
+            let px = options.map(px);
+            [px.r(), px.g(), px.b()]
+        }),
+        Channels::La => decode_impl_stream(data, out, state, |px| {
+            let px = options.map(px);
+            [px.luma(), px.a()]
+        }),
+    }
+}
+
+/// Decodes the complete ops at the start of `data` until the output is filled, resuming from
+/// `state`, and returns the number of bytes consumed and of pixels written.
+///
+/// Decoding stops early at an op that is cut off by the end of `data`, which is left for the
+/// next call along with everything following it.
+#[inline]
+fn decode_impl_chunk<const N: usize>(
+    data: &[u8], out: &mut [u8], state: &mut StreamState, map: impl Fn(Pixel) -> [u8; N],
+) -> (usize, usize)
+where
+    [u8; N]: Pod,
+{
+    let pixels = cast_slice_mut::<_, [u8; N]>(out);
+    let StreamState { index, px, run: run_left, .. } = state;
+
+    let mut n_out = (*run_left).min(pixels.len());
+    pixels[..n_out].fill(map(*px));
+    *run_left -= n_out;
+
+    let mut pos = 0;
+    while n_out < pixels.len() {
+        let Some(&b1) = data.get(pos) else { break };
+        let n_bytes = OpKind::from_byte(b1).n_bytes();
+        let Some(op) = data.get(pos..pos + n_bytes) else { break };
+        pos += n_bytes;
+        match b1 {
+            QOI_OP_INDEX..=QOI_OP_INDEX_END => {
+                *px = index[b1 as usize];
+                pixels[n_out] = map(*px);
+                n_out += 1;
+                continue;
+            }
+            QOI_OP_RGB => px.update_rgb(op[1], op[2], op[3]),
+            QOI_OP_RGBA => px.update_rgba(op[1], op[2], op[3], op[4]),
+            QOI_OP_RUN..=QOI_OP_RUN_END => {
+                let run = (b1 & 0x3f) as usize + 1;
+                let n = run.min(pixels.len() - n_out);
+                pixels[n_out..n_out + n].fill(map(*px));
+                (n_out, *run_left) = (n_out + n, run - n);
+                continue;
+            }
+            QOI_OP_DIFF..=QOI_OP_DIFF_END => px.update_diff(b1),
+            QOI_OP_LUMA..=QOI_OP_LUMA_END => px.update_luma(b1, op[1]),
+        }
+
+        index[px.hash_index() as usize] = *px;
+        pixels[n_out] = map(*px);
+        n_out += 1;
     }
+    (pos, n_out)
+}
 
+/// Decodes the next pixels from a chunk of the op stream in a given layout, resuming from
+/// `state`, see [`decode_impl_chunk`].
+#[inline]
+pub fn decode_chunk_as(
+    data: &[u8], out: &mut [u8], state: &mut StreamState, options: DecoderOptions,
+    channels: Channels,
+) -> (usize, usize) {
+    match channels {
+        Channels::Rgba if options.is_identity() => {
+            decode_impl_chunk(data, out, state, <[u8; 4]>::from)
+        }
+        Channels::Rgba => decode_impl_chunk(data, out, state, |px| options.map(px).into()),
+        Channels::Rgb => decode_impl_chunk(data, out, state, |px| {
+            let px = options.map(px);
+            [px.r(), px.g(), px.b()]
+        }),
+        Channels::La => decode_impl_chunk(data, out, state, |px| {
+            let px = options.map(px);
+            [px.luma(), px.a()]
+        }),
+    }
+}
+
+/// Reads the end marker following the op stream, found at `offset` after `n_pixels` pixels.
+#[cfg(feature = "std")]
+#[inline]
+fn read_padding<R: Read>(data: &mut R, offset: usize, n_pixels: usize) -> Result<()> {
     let mut p = [0_u8; QOI_PADDING_SIZE];
     data.read_exact(&mut p)?;
     if unlikely(p != QOI_PADDING) {
-        return Err(Error::InvalidPadding);
+        return Err(Error::InvalidPadding { offset, pixel: n_pixels });
     }
-
     Ok(())
 }
 
 
 #[doc(hidden)]
 pub trait Reader: Sized {
-    fn decode_header(&mut self) -> Result<Header>;
-    fn decode_image(&mut self, out: &mut [u8]) -> Result<()>;
+    /// Decodes the header in either format, along with the channels a reference header
+    /// declares (RGBA for the GameMaker format, whose channels are in the extension block).
+    fn decode_header(&mut self) -> Result<(Header, HeaderFormat, Channels)>;
+    fn decode_image(
+        &mut self, out: &mut [u8], options: DecoderOptions, channels: Channels,
+    ) -> Result<()>;
+    /// Total size of the encoded input including the header, if known.
+    #[inline]
+    fn input_len(&self) -> Option<usize> {
+        None
+    }
+    /// Encoded data following the header, if it's available up front.
+    #[inline]
+    fn ops(&self) -> Option<&[u8]> {
+        None
+    }
 }
 
-pub struct Bytes<'a>(&'a [u8]);
+#[derive(Clone)]
+pub struct Bytes<'a> {
+    data: &'a [u8],
+    body: &'a [u8],
+    header_size: usize,
+}
 
 impl<'a> Bytes<'a> {
     #[inline]
     pub const fn new(buf: &'a [u8]) -> Self {
-        Self(buf)
+        Self { data: buf, body: buf, header_size: 0 }
     }
 
     #[inline]
     pub const fn as_slice(&self) -> &[u8] {
-        self.0
+        self.data
+    }
+
+    /// Everything following the header, regardless of how much has been decoded.
+    #[inline]
+    pub const fn body(&self) -> &'a [u8] {
+        self.body
+    }
+
+    /// Offset of the undecoded tail, relative to the start of the op stream.
+    #[inline]
+    const fn pos(&self) -> usize {
+        self.body.len() - self.data.len()
     }
 }
 
 impl Reader for Bytes<'_> {
     #[inline]
-    fn decode_header(&mut self) -> Result<Header> {
-        let header = Header::decode(self.0)?;
-        self.0 = &self.0[QOI_HEADER_SIZE..]; // can't panic
-        Ok(header)
+    fn decode_header(&mut self) -> Result<(Header, HeaderFormat, Channels)> {
+        let format = HeaderFormat::detect(self.data).unwrap_or_default();
+        let (header, channels) = match format {
+            HeaderFormat::GameMaker => (Header::decode(self.data)?, Channels::default()),
+            HeaderFormat::Reference => Header::decode_reference(self.data)?,
+        };
+        self.header_size = format.size();
+        self.data = &self.data[self.header_size..]; // can't panic
+        self.body = self.data;
+        Ok((header, format, channels))
     }
 
     #[inline]
-    fn decode_image(&mut self, out: &mut [u8]) -> Result<()> {
-        let n_read = decode_impl_slice(self.0, out)?;
-        self.0 = &self.0[n_read..];
+    fn decode_image(
+        &mut self, out: &mut [u8], options: DecoderOptions, channels: Channels,
+    ) -> Result<()> {
+        let n_read = decode_impl_slice(self.data, out, options, channels)
+            .map_err(|err| err.offset_by(self.pos(), 0))?;
+        self.data = &self.data[n_read..];
         Ok(())
     }
+
+    #[inline]
+    fn input_len(&self) -> Option<usize> {
+        Some(self.header_size + self.body.len())
+    }
+
+    #[inline]
+    fn ops(&self) -> Option<&[u8]> {
+        Some(self.data)
+    }
 }
 
 #[cfg(feature = "std")]
 impl<R: Read> Reader for R {
     #[inline]
-    fn decode_header(&mut self) -> Result<Header> {
-        let mut b = [0; QOI_HEADER_SIZE];
-        self.read_exact(&mut b)?;
-        Header::decode(b)
+    fn decode_header(&mut self) -> Result<(Header, HeaderFormat, Channels)> {
+        let mut b = [0; QOI_REFERENCE_HEADER_SIZE];
+        self.read_exact(&mut b[..QOI_HEADER_SIZE])?;
+        match HeaderFormat::detect(b).unwrap_or_default() {
+            HeaderFormat::GameMaker => {
+                Ok((Header::decode(b)?, HeaderFormat::GameMaker, Channels::default()))
+            }
+            HeaderFormat::Reference => {
+                self.read_exact(&mut b[QOI_HEADER_SIZE..])?;
+                let (header, channels) = Header::decode_reference(b)?;
+                Ok((header, HeaderFormat::Reference, channels))
+            }
+        }
+    }
+
+    #[inline]
+    fn decode_image(
+        &mut self, out: &mut [u8], options: DecoderOptions, channels: Channels,
+    ) -> Result<()> {
+        let mut state = StreamState::new();
+        decode_stream_as(self, out, &mut state, options, channels)?;
+        read_padding(self, state.offset, out.len() / channels.as_u8() as usize)
+    }
+}
+
+/// Decoder configuration.
+///
+/// The defaults produce the pixels exactly as they were encoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DecoderOptions {
+    alpha_threshold: Option<u8>,
+    max_unique_colors: Option<usize>,
+    background: Option<[u8; 3]>,
+    strict: bool,
+}
+
+impl DecoderOptions {
+    /// Creates the default decoder configuration.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { alpha_threshold: None, max_unique_colors: None, background: None, strict: false }
+    }
+
+    /// Binarizes alpha while decoding: values below `threshold` become 0, the rest become 255.
+    ///
+    /// Meant for engines without alpha blending (1-bit transparency), so that no separate
+    /// pass over the decoded image is needed.
+    #[inline]
+    pub const fn alpha_threshold(mut self, threshold: u8) -> Self {
+        self.alpha_threshold = Some(threshold);
+        self
+    }
+
+    /// Composites the pixels onto a solid `[r, g, b]` background while decoding, so that the
+    /// output is fully opaque.
+    ///
+    /// Meant for print and PDF pipelines that have no use for alpha, so that no separate
+    /// blending pass is needed. Applied after [`DecoderOptions::alpha_threshold`] if both are
+    /// set.
+    #[inline]
+    pub const fn flatten_onto(mut self, rgb: [u8; 3]) -> Self {
+        self.background = Some(rgb);
+        self
+    }
+
+    /// Fails with [`Error::TooManyColors`] if the image has more than `n` distinct colors.
+    ///
+    /// Meant for tools that only accept palettized-looking inputs (e.g. pixel art), so that
+    /// photos are rejected quickly. When decoding a whole image from a slice (with any of the
+    /// decoding methods but [`Decoder::decode_band_to_buf`]), the colors are counted by
+    /// walking the op stream before decoding any pixels; when decoding from a stream with
+    /// [`Decoder::decode_to_buf`], the decoded pixels are checked afterwards instead.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub const fn max_unique_colors(mut self, n: usize) -> Self {
+        self.max_unique_colors = Some(n);
+        self
+    }
+
+    /// Rejects images that are malformed in ways the decoder otherwise tolerates, for inputs
+    /// from untrusted sources (e.g. fuzzed or uploaded files).
+    ///
+    /// Before decoding a whole image from a slice (with any of the decoding methods but
+    /// [`Decoder::decode_band_to_buf`]), the op stream is walked to check that:
+    /// - the ops cover exactly `width * height` pixels, with no run spilling past the last
+    ///   pixel nor ops left over before the end marker ([`Error::PixelCountMismatch`]);
+    /// - the end marker ends the op stream at the length declared in the header
+    ///   ([`Error::LengthMismatch`]);
+    /// - nothing but the extension block follows it ([`Error::TrailingData`]).
+    ///
+    /// Decoding from a stream isn't affected, since nothing past the end marker is read.
+    #[inline]
+    pub const fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Returns true if the decoded pixels are left as they are.
+    #[inline]
+    const fn is_identity(self) -> bool {
+        self.alpha_threshold.is_none() && self.background.is_none()
+    }
+
+    /// Applies the configured transformations to a decoded pixel.
+    #[inline]
+    pub(crate) fn map(self, px: Pixel) -> Pixel {
+        let px = self
+            .alpha_threshold
+            .map_or(px, |threshold| px.with_a(if px.a() >= threshold { 0xff } else { 0 }));
+        self.background.map_or(px, |[r, g, b]| match px.a() {
+            0xff => px,
+            a => {
+                // rounded `(fg * a + bg * (255 - a)) / 255`
+                let blend = |fg: u8, bg: u8| {
+                    let v = u16::from(fg) * u16::from(a) + u16::from(bg) * u16::from(0xff - a);
+                    ((v + 0x80 + ((v + 0x80) >> 8)) >> 8) as u8
+                };
+                Pixel::from([blend(px.r(), r), blend(px.g(), g), blend(px.b(), b), 0xff])
+            }
+        })
     }
+}
 
+impl Default for DecoderOptions {
     #[inline]
-    fn decode_image(&mut self, out: &mut [u8]) -> Result<()> {
-        decode_impl_stream(self, out)
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 /// Decode QOI images from slices or from streams.
+///
+/// Images with either [`HeaderFormat`] are accepted, told apart by their magic bytes.
 #[derive(Clone)]
 pub struct Decoder<R> {
     reader: R,
     header: Header,
+    format: HeaderFormat,
+    options: DecoderOptions,
+    channels: Channels,
+    #[cfg(feature = "std")]
+    stream: StreamState,
 }
 
 impl<'a> Decoder<Bytes<'a>> {
@@ -253,9 +769,101 @@ impl<'a> Decoder<Bytes<'a>> {
     /// Note: this provides the most efficient decoding, but requires the source data to
     /// be loaded in memory in order to decode it. In order to decode from a generic
     /// stream, use [`Decoder::from_stream`] instead.
+    ///
+    /// Images written by a newer version of the format, or using feature flags unknown to this
+    /// version of the crate, are rejected with [`Error::UnsupportedVersion`]; see
+    /// [`Decoder::new_compat`] for a more tolerant alternative.
     #[inline]
     pub fn new(data: &'a (impl AsRef<[u8]> + ?Sized)) -> Result<Self> {
-        Self::new_impl(Bytes::new(data.as_ref()))
+        Self::new_slice(data.as_ref(), false)
+    }
+
+    /// Creates a new decoder from a slice of bytes, tolerating images written by newer versions
+    /// of the crate where feasible.
+    ///
+    /// Unlike [`Decoder::new`], this ignores the format version as well as unknown optional
+    /// feature flags (unknown extension records are always skipped). Only images with unknown
+    /// required flags, which can't be decoded correctly without understanding them, are
+    /// rejected with [`Error::UnsupportedVersion`].
+    #[inline]
+    pub fn new_compat(data: &'a (impl AsRef<[u8]> + ?Sized)) -> Result<Self> {
+        Self::new_slice(data.as_ref(), true)
+    }
+
+    fn new_slice(data: &'a [u8], compat: bool) -> Result<Self> {
+        let mut decoder = Self::new_impl(Bytes::new(data))?;
+        if decoder.format == HeaderFormat::Reference {
+            // no extension block, the channels are in the header
+            return Ok(decoder);
+        }
+        let ops_len = decoder.header.length.unwrap_or_default() as usize;
+        let FormatVersion { version, flags } = ext::version(decoder.reader.body(), ops_len);
+        let unknown = flags & !QOI_EXT_FLAGS_KNOWN;
+        let supported = if compat {
+            unknown & QOI_EXT_FLAGS_REQUIRED == 0
+        } else {
+            version <= QOI_EXT_VERSION && unknown == 0
+        };
+        if unlikely(!supported) {
+            let err = Err(Error::UnsupportedVersion { version, flags });
+            #[cfg(feature = "metrics")]
+            metrics::record_error(&err);
+            return err;
+        }
+        decoder.channels = ext::channels(decoder.reader.body(), ops_len).unwrap_or_default();
+        let (width, height) = (decoder.header.width, decoder.header.height);
+        decoder.header.colorspace = ext::colorspace(decoder.reader.body(), ops_len);
+        decoder.header.nine_patch = ext::nine_patch(decoder.reader.body(), ops_len, width, height);
+        if let Some(checksum) = ext::checksum(decoder.reader.body(), ops_len) {
+            let ops = &decoder.reader.body()[..ops_len];
+            if unlikely(checksum.kind.checksum(ops) != checksum) {
+                let err = Err(Error::ChecksumMismatch);
+                #[cfg(feature = "metrics")]
+                metrics::record_error(&err);
+                return err;
+            }
+        }
+        Ok(decoder)
+    }
+
+    /// Creates a new decoder from a bare op stream without a header, as written by
+    /// [`Encoder::encode_headerless_to_buf`](crate::Encoder::encode_headerless_to_buf), given
+    /// the dimensions of the image.
+    ///
+    /// The stream must still end with the end marker. Pixels are decoded as RGBA unless
+    /// requested otherwise with [`Decoder::with_channels`], as the layout they have been
+    /// encoded from isn't stored anywhere.
+    #[inline]
+    pub fn new_headerless(
+        data: &'a [u8], width: impl Dimension, height: impl Dimension,
+    ) -> Result<Self> {
+        let length = u32::try_from(data.len()).map_err(|_| Error::SizeOverflow)?;
+        let header = Header::from_dimensions(width, height, Some(length))?;
+        Ok(Self {
+            reader: Bytes::new(data),
+            header,
+            format: HeaderFormat::GameMaker,
+            options: DecoderOptions::new(),
+            channels: Channels::Rgba,
+            #[cfg(feature = "std")]
+            stream: StreamState::new(),
+        })
+    }
+
+    /// Creates a new decoder from a slice of bytes produced with a [`StreamTransform`], reverting
+    /// the transform in place first.
+    ///
+    /// To decode transformed images from a stream, wrap the reader in a
+    /// [`TransformReader`](crate::TransformReader) instead.
+    #[inline]
+    pub fn new_transformed(
+        data: &'a mut [u8], mut transform: impl StreamTransform,
+    ) -> Result<Self> {
+        let header_size = HeaderFormat::detect(&*data).map_or(QOI_HEADER_SIZE, HeaderFormat::size);
+        if let Some(body) = data.get_mut(header_size..) {
+            transform.decode(0, body);
+        }
+        Self::new(&*data)
     }
 
     /// Returns the undecoded tail of the input slice of bytes.
@@ -263,6 +871,510 @@ impl<'a> Decoder<Bytes<'a>> {
     pub const fn data(&self) -> &[u8] {
         self.reader.as_slice()
     }
+
+    /// Returns the format version and feature flags stored in the extension block.
+    ///
+    /// Images without a version record (including those with a reference header) are reported
+    /// as [`FormatVersion::INITIAL`]. Records unknown to this version of the crate are skipped.
+    #[inline]
+    pub fn version(&self) -> FormatVersion {
+        let body = self.reader.body();
+        let version = |ops_len| ext::version(body, ops_len as usize);
+        self.header.length.map_or(FormatVersion::INITIAL, version)
+    }
+
+    /// Returns the restart markers stored in the extension block, if there are any.
+    ///
+    /// See [`EncoderOptions::restart_interval`](crate::EncoderOptions::restart_interval).
+    #[inline]
+    pub fn restart_markers(&self) -> Option<RestartMarkers<'a>> {
+        let ops_len = self.header.length? as usize;
+        restart_markers(self.reader.body(), ops_len, self.header.height)
+    }
+
+    /// Returns the nine-patch metadata stored in the extension block, if there is any; same as
+    /// [`Header::nine_patch`].
+    ///
+    /// See [`Encoder::with_nine_patch`](crate::Encoder::with_nine_patch).
+    #[inline]
+    pub const fn nine_patch(&self) -> Option<NinePatch> {
+        self.header.nine_patch
+    }
+
+    /// Returns which field of an interlaced frame the image holds, if it holds one.
+    ///
+    /// See [`Encoder::from_field`](crate::Encoder::from_field).
+    #[inline]
+    pub fn field(&self) -> Option<Field> {
+        let ops_len = self.header.length? as usize;
+        ext::field(self.reader.body(), ops_len)
+    }
+
+    /// Walks the op stream for the checks enabled in the options, ahead of decoding the whole
+    /// image.
+    fn check_ops(&self) -> Result<()> {
+        if self.options.strict {
+            let declared = self.header.length.map(|length| length as usize);
+            check_strict(self.reader.data, declared, self.header.n_pixels())?;
+        }
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        if let Some(limit) = self.options.max_unique_colors {
+            check_op_colors(self.reader.data, self.header.n_pixels(), limit)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes a single band of rows starting at a restart marker into a pre-allocated buffer
+    /// and returns the number of bytes written.
+    ///
+    /// Bands are independent of each other, so they may be decoded in any order (or only some
+    /// of them), e.g. to decode in parallel or to skip over corrupted parts of the image. The
+    /// checks of [`DecoderOptions::strict`] and [`DecoderOptions::max_unique_colors`] cover
+    /// whole images and aren't run here.
+    #[inline]
+    pub fn decode_band_to_buf(&self, band: usize, mut buf: impl AsMut<[u8]>) -> Result<usize> {
+        let marker = self
+            .restart_markers()
+            .and_then(|markers| markers.band(band))
+            .ok_or(Error::InvalidRestartMarker)?;
+        let buf = buf.as_mut();
+        let size = marker.n_rows as usize * self.header.width as usize * self.bytes_per_pixel();
+        if unlikely(buf.len() < size) {
+            return Err(Error::OutputBufferTooSmall { size: buf.len(), required: size });
+        }
+        let ops_len = self.header.length.unwrap_or_default() as usize;
+        let ops =
+            self.reader.body().get(marker.offset..ops_len).ok_or(Error::InvalidRestartMarker)?;
+        decode_ops_slice_as(ops, &mut buf[..size], self.options, self.channels).map_err(|err| {
+            err.offset_by(marker.offset, marker.row as usize * self.header.width as usize)
+        })?;
+        Ok(size)
+    }
+
+    /// Decodes the image row by row into buffers supplied by `provider`.
+    ///
+    /// The provider is called once for each row with its index and must return a slice of at
+    /// least `width * 4` bytes. Rows don't have to be contiguous, so this can decode directly
+    /// into non-contiguous memory, e.g. slots of a ring of staging buffers:
+    ///
+    /// ```ignore
+    /// let mut slots = staging.chunks_mut(row_pitch);
+    /// decoder.decode_rows_into(|_row| slots.next().unwrap())?;
+    /// ```
+    ///
+    /// This is somewhat slower than decoding into a single buffer.
+    pub fn decode_rows_into<'b>(
+        &mut self, provider: impl FnMut(u16) -> &'b mut [u8],
+    ) -> Result<()> {
+        #[cfg(feature = "tracing")]
+        let span = trace::decode_span("decode_rows_into", &self.header, self.channels).entered();
+        let result = self.decode_rows_into_impl(provider);
+        #[cfg(feature = "metrics")]
+        metrics::record_decoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result.map(|_| ())
+    }
+
+    fn decode_rows_into_impl<'b>(
+        &mut self, mut provider: impl FnMut(u16) -> &'b mut [u8],
+    ) -> Result<usize> {
+        self.check_ops()?;
+        let bpp = self.bytes_per_pixel();
+        let row_len = self.header.width as usize * bpp;
+        let mut ops = OpDecoder::new(self.reader.data);
+        let (mut px, mut n_left) = (Pixel::new(), 0);
+        for y in 0..self.header.height {
+            let row = provider(y);
+            if unlikely(row.len() < row_len) {
+                return Err(Error::OutputBufferTooSmall { size: row.len(), required: row_len });
+            }
+            for px_out in row[..row_len].chunks_exact_mut(bpp) {
+                if n_left == 0 {
+                    let op = ops.next_op().map_err(|err| err.offset_by(self.reader.pos(), 0))?;
+                    (px, n_left) = (self.options.map(op.px), op.n_pixels);
+                }
+                n_left -= 1;
+                match self.channels {
+                    Channels::Rgba => px_out.copy_from_slice(&<[u8; 4]>::from(px)),
+                    Channels::Rgb => px_out.copy_from_slice(&[px.r(), px.g(), px.b()]),
+                    Channels::La => px_out.copy_from_slice(&[px.luma(), px.a()]),
+                }
+            }
+        }
+
+        let data = &self.reader.data[ops.offset()..];
+        check_padding(data, ops.offset(), self.header.n_pixels())
+            .map_err(|err| err.offset_by(self.reader.pos(), 0))?;
+        self.reader.data = data;
+        Ok(row_len * self.header.height as usize)
+    }
+
+    /// Decodes the image into a pre-allocated buffer whose rows are `row_stride` bytes apart and
+    /// returns the number of bytes spanned by the image.
+    ///
+    /// Meant for surfaces with a pitch larger than `width * channels`, e.g. padded GPU staging
+    /// buffers or SDL surfaces, which are written directly instead of through a tightly packed
+    /// intermediate buffer. Bytes between the end of a row and the start of the next one are
+    /// left untouched, and the last row doesn't need to be followed by any padding.
+    pub fn decode_to_buf_with_stride(
+        &mut self, mut buf: impl AsMut<[u8]>, row_stride: usize,
+    ) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span =
+            trace::decode_span("decode_to_buf_with_stride", &self.header, self.channels).entered();
+        let result = self.decode_to_buf_with_stride_impl(buf.as_mut(), row_stride);
+        #[cfg(feature = "metrics")]
+        metrics::record_decoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result
+    }
+
+    fn decode_to_buf_with_stride_impl(
+        &mut self, buf: &mut [u8], row_stride: usize,
+    ) -> Result<usize> {
+        let row_len = self.header.width as usize * self.bytes_per_pixel();
+        if unlikely(row_stride < row_len) {
+            return Err(Error::OutputBufferTooSmall { size: row_stride, required: row_len });
+        }
+        let size = (self.header.height as usize)
+            .saturating_sub(1)
+            .checked_mul(row_stride)
+            .and_then(|n| n.checked_add(row_len))
+            .ok_or(Error::SizeOverflow)?;
+        if unlikely(buf.len() < size) {
+            return Err(Error::OutputBufferTooSmall { size: buf.len(), required: size });
+        }
+        let mut rows = buf[..size].chunks_mut(row_stride);
+        // there are exactly `height` chunks, so the default is never used
+        self.decode_rows_into_impl(|_| rows.next().unwrap_or_default())?;
+        Ok(size)
+    }
+
+    /// Decodes the image in a single pass into several buffers, each holding the next
+    /// `rows_per_dest` rows (the last one holding whatever is left), and returns the number of
+    /// bytes written.
+    ///
+    /// Meant for splitting the output across memory that isn't contiguous, e.g. per-NUMA-node
+    /// buffers or GPU tiles. Buffers may be larger than their rows, the rest being left
+    /// untouched; unused trailing buffers are ignored. Like [`Decoder::decode_rows_into`], this
+    /// is somewhat slower than decoding into a single buffer.
+    pub fn decode_scatter(&mut self, dests: &mut [&mut [u8]], rows_per_dest: u16) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span = trace::decode_span("decode_scatter", &self.header, self.channels).entered();
+        let result = self.decode_scatter_impl(dests, rows_per_dest);
+        #[cfg(feature = "metrics")]
+        metrics::record_decoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result
+    }
+
+    fn decode_scatter_impl(
+        &mut self, dests: &mut [&mut [u8]], rows_per_dest: u16,
+    ) -> Result<usize> {
+        let row_len = self.header.width as usize * self.bytes_per_pixel();
+        let height = self.header.height as usize;
+        let per_dest = rows_per_dest as usize;
+        if unlikely(per_dest == 0) {
+            return Err(Error::OutputBufferTooSmall { size: 0, required: row_len });
+        }
+        let n_dests = (height + per_dest - 1) / per_dest;
+        if unlikely(dests.len() < n_dests) {
+            let (size, required) = (dests.len() * per_dest * row_len, height * row_len);
+            return Err(Error::OutputBufferTooSmall { size, required });
+        }
+        for (i, dest) in dests[..n_dests].iter().enumerate() {
+            let required = per_dest.min(height - i * per_dest) * row_len;
+            if unlikely(dest.len() < required) {
+                return Err(Error::OutputBufferTooSmall { size: dest.len(), required });
+            }
+        }
+        let mut rows = dests[..n_dests]
+            .iter_mut()
+            .flat_map(|dest| dest.chunks_exact_mut(row_len).take(per_dest));
+        // the buffers hold exactly `height` rows, so the default is never used
+        self.decode_rows_into_impl(|_| rows.next().unwrap_or_default())
+    }
+
+    /// Decodes the image straight into RGB565 pixels and returns the number of pixels written.
+    ///
+    /// Colors are converted inside the decoding loop, so no intermediate RGBA buffer is needed,
+    /// which is meant for microcontrollers pushing images to SPI displays. The alpha channel is
+    /// dropped. With `dither`, ordered dithering hides the banding of smooth gradients; this
+    /// needs the position of every pixel and is therefore somewhat slower.
+    pub fn decode_to_rgb565(
+        &mut self, mut out: impl AsMut<[u16]>, byte_order: ByteOrder, dither: bool,
+    ) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span = trace::decode_span("decode_to_rgb565", &self.header, self.channels).entered();
+        let result = self.decode_to_rgb565_impl(out.as_mut(), byte_order, dither);
+        #[cfg(feature = "metrics")]
+        metrics::record_decoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result.map(|size| size / 2)
+    }
+
+    fn decode_to_rgb565_impl(
+        &mut self, out: &mut [u16], byte_order: ByteOrder, dither: bool,
+    ) -> Result<usize> {
+        let n_pixels = self.header.n_pixels();
+        if unlikely(out.len() < n_pixels) {
+            return Err(Error::OutputBufferTooSmall { size: out.len(), required: n_pixels });
+        }
+        self.check_ops()?;
+        let pos = self.reader.pos();
+        let data = if dither {
+            self.decode_to_rgb565_dithered(&mut out[..n_pixels], byte_order)
+        } else {
+            let bytes = cast_slice_mut(&mut out[..n_pixels]);
+            let options = self.options;
+            decode_ops_slice(self.reader.data, bytes, |px| {
+                rgb565::pack(options.map(px), byte_order)
+            })
+        }
+        .map_err(|err| err.offset_by(pos, 0))?;
+        check_padding(data, self.reader.body.len() - data.len(), n_pixels)?;
+        self.reader.data = data;
+        Ok(n_pixels * 2)
+    }
+
+    /// Decodes the image into packed `u32` pixels and returns the number of pixels written.
+    ///
+    /// Meant for 2D libraries taking `&[u32]` framebuffers (e.g. `minifb` or `softbuffer`), so
+    /// the decoded pixels can be presented without any post-processing; see [`PackedFormat`]
+    /// regarding endianness.
+    pub fn decode_to_u32_buf(
+        &mut self, mut out: impl AsMut<[u32]>, format: PackedFormat,
+    ) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span = trace::decode_span("decode_to_u32_buf", &self.header, self.channels).entered();
+        let result = self.decode_to_u32_buf_impl(out.as_mut(), format);
+        #[cfg(feature = "metrics")]
+        metrics::record_decoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result.map(|size| size / 4)
+    }
+
+    fn decode_to_u32_buf_impl(&mut self, out: &mut [u32], format: PackedFormat) -> Result<usize> {
+        let n_pixels = self.header.n_pixels();
+        if unlikely(out.len() < n_pixels) {
+            return Err(Error::OutputBufferTooSmall { size: out.len(), required: n_pixels });
+        }
+        self.check_ops()?;
+        let bytes = cast_slice_mut(&mut out[..n_pixels]);
+        let options = self.options;
+        let data =
+            decode_ops_slice(self.reader.data, bytes, |px| packed::pack(options.map(px), format))
+                .map_err(|err| err.offset_by(self.reader.pos(), 0))?;
+        check_padding(data, self.reader.body.len() - data.len(), n_pixels)?;
+        self.reader.data = data;
+        Ok(n_pixels * 4)
+    }
+
+    /// Decodes the image into a pre-allocated buffer of pixels in a given format and returns
+    /// the number of bytes written.
+    ///
+    /// Pixels are converted inside the decoding loop (see [`PixelFormat`] for how alpha is
+    /// handled), so e.g. BGRA surfaces or RGB565 displays can be filled without a second pass
+    /// over the image. The channels set with [`Decoder::with_channels`] are ignored.
+    pub fn decode_to_buf_as(
+        &mut self, mut buf: impl AsMut<[u8]>, format: PixelFormat,
+    ) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span = trace::decode_span("decode_to_buf_as", &self.header, self.channels).entered();
+        let result = self.decode_to_buf_as_impl(buf.as_mut(), format);
+        #[cfg(feature = "metrics")]
+        metrics::record_decoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result
+    }
+
+    fn decode_to_buf_as_impl(&mut self, buf: &mut [u8], format: PixelFormat) -> Result<usize> {
+        let n_pixels = self.header.n_pixels();
+        let size = n_pixels * format.bytes_per_pixel();
+        if unlikely(buf.len() < size) {
+            return Err(Error::OutputBufferTooSmall { size: buf.len(), required: size });
+        }
+        self.check_ops()?;
+        let (out, options) = (&mut buf[..size], self.options);
+        let data = match format.bytes_per_pixel() {
+            2 => decode_ops_slice(self.reader.data, out, |px| {
+                let [a, b, ..] = format.pack(options.map(px));
+                [a, b]
+            }),
+            3 => decode_ops_slice(self.reader.data, out, |px| {
+                let [a, b, c, _] = format.pack(options.map(px));
+                [a, b, c]
+            }),
+            _ => decode_ops_slice(self.reader.data, out, |px| format.pack(options.map(px))),
+        }
+        .map_err(|err| err.offset_by(self.reader.pos(), 0))?;
+        check_padding(data, self.reader.body.len() - data.len(), n_pixels)?;
+        self.reader.data = data;
+        Ok(size)
+    }
+
+    /// Decodes the image into a newly allocated vector of pixels in a given format, see
+    /// [`Decoder::decode_to_buf_as`].
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn decode_to_vec_as(&mut self, format: PixelFormat) -> Result<Vec<u8>> {
+        let mut out = try_vec_zeroed(self.header.n_pixels() * format.bytes_per_pixel())?;
+        let _ = self.decode_to_buf_as(&mut out, format)?;
+        Ok(out)
+    }
+
+    /// Decodes the image straight into planar YUV 4:2:0 (I420) and returns the number of
+    /// pixels written, for handing frames over to video encoders.
+    ///
+    /// `y` receives `width * height` luma samples, `u` and `v` one chroma sample for each 2x2
+    /// block of pixels (rounding up for odd dimensions), averaged over the block; all planes
+    /// are tightly packed. Pixels are converted with `matrix` inside the decoding loop, so no
+    /// intermediate RGBA buffer is needed. The alpha channel is dropped, see
+    /// [`DecoderOptions::flatten_onto`] to composite the pixels first.
+    pub fn decode_to_yuv420(
+        &mut self, mut y: impl AsMut<[u8]>, mut u: impl AsMut<[u8]>, mut v: impl AsMut<[u8]>,
+        matrix: YuvMatrix,
+    ) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span = trace::decode_span("decode_to_yuv420", &self.header, self.channels).entered();
+        let result = self.decode_to_yuv420_impl(y.as_mut(), u.as_mut(), v.as_mut(), matrix);
+        #[cfg(feature = "metrics")]
+        metrics::record_decoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result
+    }
+
+    fn decode_to_yuv420_impl(
+        &mut self, luma: &mut [u8], cb: &mut [u8], cr: &mut [u8], matrix: YuvMatrix,
+    ) -> Result<usize> {
+        let (width, n_pixels) = (self.header.width as usize, self.header.n_pixels());
+        let chroma_width = (width + 1) / 2;
+        let n_chroma = chroma_width * ((self.header.height as usize + 1) / 2);
+        if unlikely(luma.len() < n_pixels) {
+            return Err(Error::OutputBufferTooSmall { size: luma.len(), required: n_pixels });
+        } else if unlikely(cb.len().min(cr.len()) < n_chroma) {
+            let size = cb.len().min(cr.len());
+            return Err(Error::OutputBufferTooSmall { size, required: n_chroma });
+        }
+        self.check_ops()?;
+        let mut ops = OpDecoder::new(self.reader.data);
+        let (mut px, mut n_left) = (Pixel::new(), 0);
+        for (row, luma_row) in luma[..n_pixels].chunks_exact_mut(width).enumerate() {
+            let start = row / 2 * chroma_width;
+            let chroma_row = cb[start..].iter_mut().zip(&mut cr[start..]);
+            // can't truncate: pairs of pixels and averages of bytes
+            #[allow(clippy::cast_possible_truncation)]
+            for (pair, (cb, cr)) in luma_row.chunks_mut(2).zip(chroma_row) {
+                // chroma is averaged over each pair of pixels, then over the pairs of two rows
+                let (mut cb_sum, mut cr_sum) = (0, 0);
+                for luma in pair.iter_mut() {
+                    if n_left == 0 {
+                        let op =
+                            ops.next_op().map_err(|err| err.offset_by(self.reader.pos(), 0))?;
+                        (px, n_left) = (self.options.map(op.px), op.n_pixels);
+                    }
+                    n_left -= 1;
+                    let [y, u, v] = matrix.to_yuv(px.r(), px.g(), px.b());
+                    (*luma, cb_sum, cr_sum) = (y, cb_sum + u16::from(u), cr_sum + u16::from(v));
+                }
+                let n = pair.len() as u16;
+                let (cb_pair, cr_pair) = ((cb_sum + n / 2) / n, (cr_sum + n / 2) / n);
+                if row % 2 == 0 {
+                    (*cb, *cr) = (cb_pair as u8, cr_pair as u8);
+                } else {
+                    *cb = ((u16::from(*cb) + cb_pair + 1) / 2) as u8;
+                    *cr = ((u16::from(*cr) + cr_pair + 1) / 2) as u8;
+                }
+            }
+        }
+
+        let data = &self.reader.data[ops.offset()..];
+        check_padding(data, ops.offset(), n_pixels)
+            .map_err(|err| err.offset_by(self.reader.pos(), 0))?;
+        self.reader.data = data;
+        Ok(n_pixels)
+    }
+
+    /// Decodes the image into RGB10A2 pixels and returns the number of pixels written.
+    ///
+    /// See [`Encoder::from_rgb10a2`](crate::Encoder::from_rgb10a2) for the layout of the words.
+    /// Images that weren't encoded from RGB10A2 pixels have no low bits stored, so those are
+    /// filled by replicating the high bits of each channel instead (mapping 255 to 1023). The
+    /// same goes for images decoded with options that change the pixels (such as
+    /// [`DecoderOptions::flatten_onto`]), as the stored low bits belong to the original ones.
+    pub fn decode_to_rgb10a2(&mut self, mut out: impl AsMut<[u32]>) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span = trace::decode_span("decode_to_rgb10a2", &self.header, self.channels).entered();
+        let result = self.decode_to_rgb10a2_impl(out.as_mut());
+        #[cfg(feature = "metrics")]
+        metrics::record_decoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result.map(|size| size / 4)
+    }
+
+    fn decode_to_rgb10a2_impl(&mut self, out: &mut [u32]) -> Result<usize> {
+        let n_pixels = self.header.n_pixels();
+        if unlikely(out.len() < n_pixels) {
+            return Err(Error::OutputBufferTooSmall { size: out.len(), required: n_pixels });
+        }
+        self.check_ops()?;
+        let out = &mut out[..n_pixels];
+        let options = self.options;
+        let data = decode_ops_slice(self.reader.data, cast_slice_mut(out), |px| {
+            rgb10a2::pack(options.map(px)).to_ne_bytes()
+        })
+        .map_err(|err| err.offset_by(self.reader.pos(), 0))?;
+        check_padding(data, self.reader.body.len() - data.len(), n_pixels)?;
+        let ops_len = self.header.length.unwrap_or_default() as usize;
+        let low_bits = ext::low_bits(self.reader.body(), ops_len, n_pixels);
+        if let Some(low_bits) = low_bits.filter(|_| options.is_identity()) {
+            for (i, word) in out.iter_mut().enumerate() {
+                *word = rgb10a2::with_low_bits(*word, rgb10a2::get_low_bits(low_bits, i));
+            }
+        }
+        self.reader.data = data;
+        Ok(n_pixels * 4)
+    }
+
+    fn decode_to_rgb565_dithered(
+        &self, out: &mut [u16], byte_order: ByteOrder,
+    ) -> Result<&'a [u8]> {
+        let width = self.header.width as usize;
+        let mut ops = OpDecoder::new(self.reader.data);
+        let (mut px, mut n_left) = (Pixel::new(), 0);
+        for (i, px_out) in out.iter_mut().enumerate() {
+            if n_left == 0 {
+                let op = ops.next_op()?;
+                (px, n_left) = (self.options.map(op.px), op.n_pixels);
+            }
+            n_left -= 1;
+            let bytes = rgb565::pack_dithered(px, i % width, i / width, byte_order);
+            *px_out = u16::from_ne_bytes(bytes);
+        }
+        Ok(&self.reader.data[ops.offset()..])
+    }
+
+    /// Decodes a single band of rows starting at a restart marker into a new vector.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub fn decode_band_to_vec(&self, band: usize) -> Result<Vec<u8>> {
+        let marker = self
+            .restart_markers()
+            .and_then(|markers| markers.band(band))
+            .ok_or(Error::InvalidRestartMarker)?;
+        let n_pixels = marker.n_rows as usize * self.header.width as usize;
+        let mut out = try_vec_zeroed(n_pixels * self.bytes_per_pixel())?;
+        let _ = self.decode_band_to_buf(band, &mut out)?;
+        Ok(out)
+    }
 }
 
 #[cfg(feature = "std")]
@@ -273,6 +1385,9 @@ impl<R: Read> Decoder<R> {
     ///
     /// Note: while it's possible to pass a `&[u8]` slice here since it implements `Read`, it
     /// would be more efficient to use a specialized constructor instead: [`Decoder::new`].
+    ///
+    /// The extension block isn't read, so checksums stored by
+    /// [`Encoder::with_checksum`](crate::Encoder::with_checksum) aren't verified.
     #[inline]
     pub fn from_stream(reader: R) -> Result<Self> {
         Self::new_impl(reader)
@@ -290,13 +1405,129 @@ impl<R: Read> Decoder<R> {
     pub fn into_reader(self) -> R {
         self.reader
     }
+
+    /// Number of rows decoded so far by [`Decoder::decode_next_rows`].
+    #[inline]
+    pub const fn rows_decoded(&self) -> u16 {
+        self.stream.row
+    }
+
+    /// Decodes the next rows of the image into a pre-allocated buffer and returns the number
+    /// of bytes written, or zero once the whole image has been decoded.
+    ///
+    /// As many whole rows as fit into the buffer are decoded (it must hold at least one row),
+    /// pulling only the bytes they need from the reader, so an image arriving over a socket can
+    /// be processed as it comes in instead of buffering the whole file first. The end marker
+    /// is checked along with the last row.
+    ///
+    /// Note: this keeps track of its own position in the image, so it shouldn't be mixed with
+    /// [`Decoder::decode_to_buf`] on the same decoder. The
+    /// [`max_unique_colors`](DecoderOptions::max_unique_colors) limit isn't applied here.
+    ///
+    /// Errors are final: bytes pulled from the reader can't be given back, so after a failed
+    /// call (e.g. an I/O error in the middle of a row) the decoder is left partway through an
+    /// op and must not be used to decode more rows, as they would come out wrong. The rows
+    /// reported by [`Decoder::rows_decoded`] are the ones returned by earlier successful calls.
+    pub fn decode_next_rows(&mut self, mut buf: impl AsMut<[u8]>) -> Result<usize> {
+        let buf = buf.as_mut();
+        let row_len = self.header.width as usize * self.bytes_per_pixel();
+        let rows_left = self.header.height - self.stream.row;
+        if rows_left == 0 {
+            return Ok(0);
+        }
+        // can't truncate: capped by the number of rows left
+        #[allow(clippy::cast_possible_truncation)]
+        let n_rows = (buf.len() / row_len).min(rows_left.into()) as u16;
+        if unlikely(n_rows == 0) {
+            return Err(Error::OutputBufferTooSmall { size: buf.len(), required: row_len });
+        }
+        let out = &mut buf[..n_rows as usize * row_len];
+        decode_stream_as(&mut self.reader, out, &mut self.stream, self.options, self.channels)?;
+        self.stream.row += n_rows;
+        if n_rows == rows_left {
+            read_padding(&mut self.reader, self.stream.offset, self.header.n_pixels())?;
+        }
+        Ok(out.len())
+    }
+
+    /// Decodes up to `n_rows` next rows of the image into a newly allocated vector, which is
+    /// empty once the whole image has been decoded.
+    ///
+    /// See [`Decoder::decode_next_rows`].
+    #[inline]
+    pub fn decode_next_rows_to_vec(&mut self, n_rows: u16) -> Result<Vec<u8>> {
+        let n_rows = n_rows.min(self.header.height - self.stream.row);
+        let row_len = self.header.width as usize * self.bytes_per_pixel();
+        let mut out = try_vec_zeroed(n_rows as usize * row_len)?;
+        if n_rows != 0 {
+            self.decode_next_rows(&mut out)?;
+        }
+        Ok(out)
+    }
 }
 
 impl<R: Reader> Decoder<R> {
     #[inline]
     fn new_impl(mut reader: R) -> Result<Self> {
-        let header = reader.decode_header()?;
-        Ok(Self { reader, header })
+        let header = reader.decode_header();
+        #[cfg(feature = "metrics")]
+        metrics::record_error(&header);
+        let (header, format, channels) = header?;
+        let options = DecoderOptions::new();
+        Ok(Self {
+            reader,
+            header,
+            format,
+            options,
+            channels,
+            #[cfg(feature = "std")]
+            stream: StreamState::new(),
+        })
+    }
+
+    #[inline]
+    const fn bytes_per_pixel(&self) -> usize {
+        self.channels.as_u8() as usize
+    }
+
+    /// Checks the image against the given limits, failing with [`Error::LimitsExceeded`] if it
+    /// exceeds them.
+    ///
+    /// This only looks at the header (and the input size when decoding from a slice), so it's
+    /// meant to be called before allocating any buffers for the decoded image.
+    #[inline]
+    pub fn with_limits(self, limits: Limits) -> Result<Self> {
+        let result = limits.check(&self.header, self.reader.input_len());
+        #[cfg(feature = "metrics")]
+        metrics::record_error(&result);
+        result.map(|()| self)
+    }
+
+    /// Replaces the decoder configuration.
+    #[inline]
+    pub const fn with_options(mut self, options: DecoderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Changes the layout of the decoded pixels.
+    ///
+    /// By default, images encoded from RGB or luma + alpha data are decoded the same way when
+    /// decoding from a slice (the layout is stored in the extension block). When decoding from a
+    /// stream, the extension block hasn't been read yet, so pixels are decoded as RGBA unless
+    /// requested otherwise. Decoding RGBA images as luma + alpha converts colors to luma, and
+    /// decoding them as [`Channels::Rgb`] drops the alpha channel, producing tightly packed
+    /// 3-byte pixels (e.g. for RGB textures).
+    #[inline]
+    pub const fn with_channels(mut self, channels: Channels) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Returns the layout of the decoded pixels.
+    #[inline]
+    pub const fn channels(&self) -> Channels {
+        self.channels
     }
 
     /// Returns the decoded image header.
@@ -305,12 +1536,21 @@ impl<R: Reader> Decoder<R> {
         &self.header
     }
 
+    /// Returns the format of the image header, detected from its magic bytes.
+    ///
+    /// Images with a [reference](HeaderFormat::Reference) header have no extension block, so
+    /// their layout comes from the channels field of the header instead.
+    #[inline]
+    pub const fn header_format(&self) -> HeaderFormat {
+        self.format
+    }
+
     /// The number of bytes the decoded image will take.
     ///
     /// Can be used to pre-allocate the buffer to decode the image into.
     #[inline]
     pub const fn required_buf_len(&self) -> usize {
-        self.header.n_pixels().saturating_mul(4)
+        self.header.n_pixels().saturating_mul(self.bytes_per_pixel())
     }
 
     /// Decodes the image to a pre-allocated buffer and returns the number of bytes written.
@@ -318,12 +1558,35 @@ impl<R: Reader> Decoder<R> {
     /// The minimum size of the buffer can be found via [`Decoder::required_buf_len`].
     #[inline]
     pub fn decode_to_buf(&mut self, mut buf: impl AsMut<[u8]>) -> Result<usize> {
-        let buf = buf.as_mut();
+        #[cfg(feature = "tracing")]
+        let span = trace::decode_span("decode_to_buf", &self.header, self.channels).entered();
+        let result = self.decode_to_buf_impl(buf.as_mut());
+        #[cfg(feature = "metrics")]
+        metrics::record_decoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result
+    }
+
+    #[inline]
+    fn decode_to_buf_impl(&mut self, buf: &mut [u8]) -> Result<usize> {
         let size = self.required_buf_len();
         if unlikely(buf.len() < size) {
             return Err(Error::OutputBufferTooSmall { size: buf.len(), required: size });
         }
-        self.reader.decode_image(buf)?;
+        if let (true, Some(ops)) = (self.options.strict, self.reader.ops()) {
+            let declared = self.header.length.map(|length| length as usize);
+            check_strict(ops, declared, self.header.n_pixels())?;
+        }
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        if let (Some(limit), Some(ops)) = (self.options.max_unique_colors, self.reader.ops()) {
+            check_op_colors(ops, self.header.n_pixels(), limit)?;
+        }
+        self.reader.decode_image(buf, self.options, self.channels)?;
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        if let (Some(limit), None) = (self.options.max_unique_colors, self.reader.ops()) {
+            check_pixel_colors(&buf[..size], self.bytes_per_pixel(), limit)?;
+        }
         Ok(size)
     }
 
@@ -331,7 +1594,7 @@ impl<R: Reader> Decoder<R> {
     #[cfg(any(feature = "std", feature = "alloc"))]
     #[inline]
     pub fn decode_to_vec(&mut self) -> Result<Vec<u8>> {
-        let mut out = vec![0; self.header.n_pixels() * 4];
+        let mut out = try_vec_zeroed(self.required_buf_len())?;
         let _ = self.decode_to_buf(&mut out)?;
         Ok(out)
     }
@@ -0,0 +1,198 @@
+use crate::consts::{QOI_HEADER_SIZE, QOI_PADDING, QOI_PADDING_SIZE, QOI_REFERENCE_HEADER_SIZE};
+use crate::decode::{decode_chunk_as, DecoderOptions, StreamState};
+use crate::error::{Error, Result};
+use crate::header::{Channels, Header, HeaderFormat};
+use crate::ops::OpKind;
+use crate::utils::unlikely;
+
+/// Where a [`DecodeState`] is at.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Step {
+    Header,
+    Ops,
+    Padding,
+    Done,
+}
+
+/// Sans-io decoder fed the encoded image in chunks of any size, e.g. as it arrives through a
+/// custom network protocol or an async runtime other than tokio.
+///
+/// [`DecodeState::feed`] takes the next bytes of the image, including the header, and decodes
+/// as many pixels as they hold into the output. Headers, ops and the end marker may be split
+/// across chunks anywhere: bytes cut off at the end of a chunk are kept until the next one
+/// completes them. Pixels are decoded as RGBA unless requested otherwise (or declared
+/// otherwise by a reference header). Nothing is consumed past the end marker, so the
+/// extension block or the next image can be handled by the caller.
+///
+/// [`DecoderOptions::max_unique_colors`] doesn't apply, as the pixels are handed out as they
+/// are decoded. For the same reason, checksums stored by
+/// [`Encoder::with_checksum`](crate::Encoder::with_checksum) aren't verified.
+#[derive(Clone)]
+pub struct DecodeState {
+    options: DecoderOptions,
+    channels: Option<Channels>,
+    header: Option<Header>,
+    format: HeaderFormat,
+    stream: StreamState,
+    step: Step,
+    // bytes of the header, an op or the end marker cut off by the end of a chunk
+    pending: [u8; QOI_REFERENCE_HEADER_SIZE],
+    n_pending: usize,
+    n_read: usize,
+    n_decoded: usize,
+}
+
+impl DecodeState {
+    /// Creates the state at the start of an image.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            options: DecoderOptions::new(),
+            channels: None,
+            header: None,
+            format: HeaderFormat::GameMaker,
+            stream: StreamState::new(),
+            step: Step::Header,
+            pending: [0; QOI_REFERENCE_HEADER_SIZE],
+            n_pending: 0,
+            n_read: 0,
+            n_decoded: 0,
+        }
+    }
+
+    /// Replaces the decoder configuration.
+    #[inline]
+    pub const fn with_options(mut self, options: DecoderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Changes the layout of the decoded pixels, see
+    /// [`Decoder::with_channels`](crate::Decoder::with_channels).
+    #[inline]
+    pub const fn with_channels(mut self, channels: Channels) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    /// Returns the image header, once it has been fed.
+    #[inline]
+    pub const fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Returns the format of the image header, once it has been fed.
+    #[inline]
+    pub fn header_format(&self) -> Option<HeaderFormat> {
+        self.header.map(|_| self.format)
+    }
+
+    /// Returns the layout of the decoded pixels.
+    #[inline]
+    pub fn channels(&self) -> Channels {
+        self.channels.unwrap_or_default()
+    }
+
+    /// Number of pixels decoded so far.
+    #[inline]
+    pub const fn pixels_decoded(&self) -> usize {
+        self.n_decoded
+    }
+
+    /// Returns true once the end marker has been fed.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        self.step == Step::Done
+    }
+
+    /// Feeds the next bytes of the image and decodes as many pixels as possible into `out`,
+    /// returning the number of bytes consumed and of pixels written.
+    ///
+    /// Every byte is consumed unless `out` fills up (bytes left over are to be fed again
+    /// along with more output space) or the end marker is reached. The end marker is checked
+    /// once all its bytes have been fed.
+    pub fn feed(&mut self, data: &[u8], out: &mut [u8]) -> Result<(usize, usize)> {
+        let mut n_consumed = 0;
+        if self.step == Step::Header {
+            n_consumed += self.take(data, QOI_HEADER_SIZE);
+            let format = HeaderFormat::detect(self.pending).unwrap_or_default();
+            n_consumed += self.take(&data[n_consumed..], format.size());
+            if self.n_pending < format.size() {
+                self.n_read += n_consumed;
+                return Ok((n_consumed, 0));
+            }
+            let (header, channels) = match format {
+                HeaderFormat::GameMaker => (Header::decode(self.pending)?, Channels::default()),
+                HeaderFormat::Reference => Header::decode_reference(self.pending)?,
+            };
+            (self.header, self.format) = (Some(header), format);
+            self.channels.get_or_insert(channels);
+            (self.step, self.n_pending) = (Step::Ops, 0);
+        }
+
+        let mut n_written = 0;
+        if self.step == Step::Ops {
+            let n_pixels = self.header.map_or(0, |header| header.n_pixels());
+            let (options, channels) = (self.options, self.channels());
+            let bpp = channels.as_u8() as usize;
+            let n_max = (out.len() / bpp).min(n_pixels - self.n_decoded);
+            let out = &mut out[..n_max * bpp];
+            if self.n_pending != 0 {
+                // complete the op cut off by the end of the previous chunk
+                let n_bytes = OpKind::from_byte(self.pending[0]).n_bytes();
+                n_consumed += self.take(&data[n_consumed..], n_bytes);
+                if self.n_pending == n_bytes {
+                    let op = &self.pending[..n_bytes];
+                    let (n_op, n) = decode_chunk_as(op, out, &mut self.stream, options, channels);
+                    n_written += n;
+                    if n_op != 0 {
+                        self.n_pending = 0;
+                    }
+                }
+            }
+            if self.n_pending == 0 {
+                let (data, out) = (&data[n_consumed..], &mut out[n_written * bpp..]);
+                let (n_ops, n) = decode_chunk_as(data, out, &mut self.stream, options, channels);
+                (n_consumed, n_written) = (n_consumed + n_ops, n_written + n);
+                if n * bpp < out.len() {
+                    // what's left is the start of an op, complete it with the next chunk
+                    n_consumed += self.take(&data[n_ops..], QOI_REFERENCE_HEADER_SIZE);
+                }
+            }
+            self.n_decoded += n_written;
+            if self.n_decoded == n_pixels {
+                self.step = Step::Padding;
+            }
+        }
+
+        if self.step == Step::Padding {
+            n_consumed += self.take(&data[n_consumed..], QOI_PADDING_SIZE);
+            if self.n_pending == QOI_PADDING_SIZE {
+                if unlikely(self.pending[..QOI_PADDING_SIZE] != QOI_PADDING) {
+                    let offset = self.n_read + n_consumed - QOI_PADDING_SIZE - self.format.size();
+                    return Err(Error::InvalidPadding { offset, pixel: self.n_decoded });
+                }
+                (self.step, self.n_pending) = (Step::Done, 0);
+            }
+        }
+        self.n_read += n_consumed;
+        Ok((n_consumed, n_written))
+    }
+
+    /// Moves bytes from the start of `data` to the pending bytes until there are `len` of
+    /// them, and returns the number of bytes moved.
+    #[inline]
+    fn take(&mut self, data: &[u8], len: usize) -> usize {
+        let n = len.saturating_sub(self.n_pending).min(data.len());
+        self.pending[self.n_pending..self.n_pending + n].copy_from_slice(&data[..n]);
+        self.n_pending += n;
+        n
+    }
+}
+
+impl Default for DecodeState {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
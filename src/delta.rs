@@ -0,0 +1,145 @@
+use alloc::vec::Vec;
+
+use crate::consts::{QOI_PADDING, QOI_PADDING_SIZE};
+use crate::decode::Decoder;
+use crate::error::{Error, Result};
+use crate::header::Channels;
+use crate::ops::OpDecoder;
+use crate::rect::Rect;
+use crate::utils::unlikely;
+
+/// Unchanged pixels between two changed spans of a row below which the spans are merged.
+const MERGE_GAP: u16 = 16;
+
+/// Groups changed spans of consecutive rows into rects.
+struct Damage {
+    done: Vec<Rect>,
+    // rects that have a span in the previous row, so they may still grow downwards
+    open: Vec<Rect>,
+    extended: Vec<Rect>,
+}
+
+impl Damage {
+    const fn new() -> Self {
+        Self { done: Vec::new(), open: Vec::new(), extended: Vec::new() }
+    }
+
+    /// Adds a changed span of columns `start..end` of row `y`; rows must come in order.
+    fn add_span(&mut self, y: u16, start: u16, end: u16) {
+        let mut rect = Rect { x: start, y, width: end - start, height: 1 };
+        // merge with every rect of the previous row the span touches, and with spans of this
+        // row that were merged into one of those already; growing may make the rect reach
+        // more rects (closed ones included), so repeat until it stops growing, which keeps
+        // the rects from overlapping
+        loop {
+            let before = rect;
+            for open in [&mut self.open, &mut self.extended] {
+                open.retain(|&other| {
+                    let touches = other.overlaps(rect.x, rect.right())
+                        && other.y <= rect.bottom()
+                        && rect.y <= other.bottom();
+                    if touches {
+                        rect = rect.union(other);
+                    }
+                    !touches
+                });
+            }
+            self.done.retain(|&other| {
+                let intersects = rect.intersects(other);
+                if intersects {
+                    rect = rect.union(other);
+                }
+                !intersects
+            });
+            if rect == before {
+                break;
+            }
+        }
+        self.extended.push(rect);
+    }
+
+    /// Closes the rects that didn't grow into the row that just ended.
+    fn end_row(&mut self) {
+        self.done.append(&mut self.open);
+        core::mem::swap(&mut self.open, &mut self.extended);
+    }
+
+    fn finish(mut self) -> Vec<Rect> {
+        self.end_row();
+        self.done.sort_unstable_by_key(|rect| (rect.y, rect.x));
+        self.done
+    }
+}
+
+/// Decode an image on top of a reference frame, only writing the pixels that differ from it.
+///
+/// `reference` holds the raw pixels of the previous frame and `out` receives the changed
+/// pixels, both in the layout the image has been encoded from (RGB, RGBA or luma + alpha);
+/// unchanged pixels of `out` are left as they are, so `out` would usually be a copy of the
+/// reference frame kept by the caller, e.g. a remote framebuffer. Returns the changed regions
+/// for damage tracking: non-overlapping rects covering all changed pixels (and some unchanged
+/// ones, as nearby changes are grouped together), ordered top to bottom.
+pub fn decode_delta(
+    data: impl AsRef<[u8]>, reference: impl AsRef<[u8]>, mut out: impl AsMut<[u8]>,
+) -> Result<Vec<Rect>> {
+    let decoder = Decoder::new(&data)?;
+    let (header, channels) = (*decoder.header(), decoder.channels());
+    let (reference, out) = (reference.as_ref(), out.as_mut());
+    let size = decoder.required_buf_len();
+    if unlikely(reference.len() != size) {
+        let (width, height) = (header.width, header.height);
+        return Err(Error::InvalidImageLength { size: reference.len(), width, height });
+    }
+    if unlikely(out.len() < size) {
+        return Err(Error::OutputBufferTooSmall { size: out.len(), required: size });
+    }
+
+    let bpp = channels.as_u8() as usize;
+    let mut ops = OpDecoder::new(decoder.data());
+    let (mut px, mut n_left) = ([0; 4], 0);
+    let mut damage = Damage::new();
+    let row_len = header.width as usize * bpp;
+    let rows = reference.chunks_exact(row_len).zip(out.chunks_exact_mut(row_len));
+    for (y, (ref_row, out_row)) in (0..header.height).zip(rows) {
+        // changed span of this row that hasn't been recorded yet
+        let mut span: Option<(u16, u16)> = None;
+        for (x, (ref_px, out_px)) in
+            (0..header.width).zip(ref_row.chunks_exact(bpp).zip(out_row.chunks_exact_mut(bpp)))
+        {
+            if n_left == 0 {
+                let op = ops.next_op()?;
+                let rgba = op.px;
+                px = match channels {
+                    Channels::Rgba => rgba.into(),
+                    Channels::Rgb => [rgba.r(), rgba.g(), rgba.b(), 0],
+                    Channels::La => [rgba.luma(), rgba.a(), 0, 0],
+                };
+                n_left = op.n_pixels;
+            }
+            n_left -= 1;
+            if ref_px == &px[..bpp] {
+                continue;
+            }
+            out_px.copy_from_slice(&px[..bpp]);
+            span = match span {
+                Some((start, end)) if x - end < MERGE_GAP => Some((start, x + 1)),
+                Some((start, end)) => {
+                    damage.add_span(y, start, end);
+                    Some((x, x + 1))
+                }
+                None => Some((x, x + 1)),
+            };
+        }
+        if let Some((start, end)) = span {
+            damage.add_span(y, start, end);
+        }
+        damage.end_row();
+    }
+
+    let (offset, pixel) = (ops.offset(), header.n_pixels());
+    match decoder.data().get(offset..offset + QOI_PADDING_SIZE) {
+        None => Err(Error::UnexpectedBufferEnd { offset, pixel }),
+        Some(padding) if padding != QOI_PADDING => Err(Error::InvalidPadding { offset, pixel }),
+        Some(_) => Ok(damage.finish()),
+    }
+}
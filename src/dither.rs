@@ -0,0 +1,137 @@
+//! Dithering for reducing the color depth of raw RGBA pixels.
+//!
+//! Both methods work in place and quantize each of R, G and B to a given number of bits,
+//! keeping the result expanded to the full 8-bit range (so `0b11111` with 5 bits becomes 255).
+//! Alpha is left untouched. Truncating the dithered values afterwards, e.g. when packing them
+//! into RGB565 with `bits = [5, 6, 5]`, yields the dithered low-depth colors.
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use alloc::vec::Vec;
+
+use crate::error::{Error, Result};
+use crate::pixel::Pixel;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::utils::try_vec_with_capacity;
+use crate::utils::unlikely;
+
+/// 4x4 Bayer matrix used for ordered dithering.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Ordered dithering with a 4x4 Bayer matrix.
+///
+/// Every pixel is processed independently of the others, so this is cheap, has no memory
+/// overhead and is suitable for streaming output (see
+/// [`Decoder::decode_to_rgb565`](crate::Decoder::decode_to_rgb565)).
+pub fn ordered(mut pixels: impl AsMut<[u8]>, width: u16, bits: [u8; 3]) -> Result<()> {
+    let pixels = pixels.as_mut();
+    check_dimensions(pixels, width)?;
+    for (y, row) in pixels.chunks_exact_mut(width as usize * 4).enumerate() {
+        for (x, chunk) in row.chunks_exact_mut(4).enumerate() {
+            let mut px = Pixel::new();
+            px.read(chunk);
+            chunk.copy_from_slice(&<[u8; 4]>::from(ordered_pixel(px, x, y, bits)));
+        }
+    }
+    Ok(())
+}
+
+/// Floyd-Steinberg error diffusion.
+///
+/// This gives smoother results than [`ordered`] but needs the surrounding pixels, so it only
+/// works on complete images; it keeps two rows of accumulated errors in memory.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn floyd_steinberg(mut pixels: impl AsMut<[u8]>, width: u16, bits: [u8; 3]) -> Result<()> {
+    let pixels = pixels.as_mut();
+    check_dimensions(pixels, width)?;
+    let width = width as usize;
+    // errors of the current and of the next row, with a spare pixel on each side
+    let mut errors: Vec<[i16; 3]> = try_vec_with_capacity(2 * (width + 2))?;
+    errors.resize(2 * (width + 2), [0; 3]);
+    let (mut cur, mut next) = errors.split_at_mut(width + 2);
+
+    for row in pixels.chunks_exact_mut(width * 4) {
+        for (x, chunk) in row.chunks_exact_mut(4).enumerate() {
+            for c in 0..3 {
+                let old = (i16::from(chunk[c]) + cur[x + 1][c] / 16).clamp(0, 255);
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let new = quantize(old as u8, bits[c]);
+                chunk[c] = new;
+                let err = old - i16::from(new);
+                cur[x + 2][c] += err * 7;
+                next[x][c] += err * 3;
+                next[x + 1][c] += err * 5;
+                next[x + 2][c] += err;
+            }
+        }
+        (cur, next) = (next, cur);
+        next.fill([0; 3]);
+    }
+    Ok(())
+}
+
+fn check_dimensions(pixels: &[u8], width: u16) -> Result<()> {
+    let row_len = width as usize * 4;
+    if unlikely(row_len == 0 || pixels.len() % row_len != 0) {
+        // the height is only used for reporting and may be rounded down
+        #[allow(clippy::cast_possible_truncation)]
+        let height = (pixels.len() / row_len.max(1)).min(u16::MAX as usize) as u16;
+        return Err(Error::InvalidImageLength { size: pixels.len(), width, height });
+    }
+    Ok(())
+}
+
+/// Clamps a channel depth to `1..=8` bits.
+#[inline]
+const fn clamp_bits(bits: u8) -> u8 {
+    if bits == 0 {
+        1
+    } else if bits > 8 {
+        8
+    } else {
+        bits
+    }
+}
+
+/// Expands a quantized level back to the full 8-bit range by replicating its bits, so that
+/// truncating the result to `bits` bits gives the level back.
+#[inline]
+const fn expand(level: u8, bits: u8) -> u8 {
+    let mut out = level << (8 - bits);
+    // e.g. 0b10110 -> 0b10110101
+    let mut filled = bits;
+    while filled < 8 {
+        out |= out >> filled;
+        filled *= 2;
+    }
+    out
+}
+
+/// Rounds a channel to the nearest of the levels representable with `bits` bits.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) const fn quantize(value: u8, bits: u8) -> u8 {
+    let bits = clamp_bits(bits);
+    let max = (1_u32 << bits) - 1;
+    expand(((value as u32 * max + 127) / 255) as u8, bits)
+}
+
+/// Applies ordered dithering to a single pixel given its coordinates.
+#[doc(hidden)]
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub const fn ordered_pixel(px: Pixel, x: usize, y: usize, bits: [u8; 3]) -> Pixel {
+    // thresholds centered within their sixteenths: (2t + 1) / 32 of a level
+    let threshold = 2 * BAYER[y & 3][x & 3] as u32 + 1;
+    let mut out = [0, 0, 0, px.a()];
+    let mut c = 0;
+    while c < 3 {
+        let bits = clamp_bits(bits[c]);
+        let max = (1_u32 << bits) - 1;
+        let value = [px.r(), px.g(), px.b()][c] as u32;
+        let level = (value * max * 32 + threshold * 255) / (255 * 32);
+        out[c] = expand(level as u8, bits);
+        c += 1;
+    }
+    Pixel::from_u32(u32::from_be_bytes(out))
+}
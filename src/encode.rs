@@ -1,39 +1,399 @@
 #[cfg(any(feature = "std", feature = "alloc"))]
-use alloc::{vec, vec::Vec};
+use alloc::{borrow::Cow, boxed::Box, sync::Arc, vec::Vec};
+use core::{mem, slice};
 #[cfg(feature = "std")]
-use std::io::Write;
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{BufWriter, IntoInnerError, Seek, SeekFrom, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
 
-use bytemuck::Pod;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
-use crate::consts::{QOI_HEADER_SIZE, QOI_OP_INDEX, QOI_OP_RUN, QOI_PADDING, QOI_PADDING_SIZE};
+use crate::checksum::{ChecksumKind, Hasher};
+#[cfg(feature = "std")]
+use crate::consts::QOI_REFERENCE_HEADER_SIZE;
+use crate::consts::{
+    QOI_HEADER_SIZE, QOI_OP_INDEX, QOI_OP_RGBA, QOI_OP_RUN, QOI_PADDING, QOI_PADDING_SIZE,
+    QOI_RUN_MAX,
+};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::content_id::{hash_prefix, ContentId};
+use crate::content_id::{HashingWriter, Sha256};
+use crate::decode::Decoder;
 use crate::error::{Error, Result};
-use crate::header::Header;
-use crate::pixel::Pixel;
+use crate::ext::{self, ext_len, write_ext, BandStarts};
+use crate::field::Field;
+use crate::header::{dimensions, Channels, ColorSpace, Dimension, Header, HeaderFormat};
+#[cfg(feature = "metrics")]
+use crate::metrics;
+use crate::nine_patch::NinePatch;
+use crate::ops::OpDecoder;
+use crate::pixel::{Pixel, PixelFormat};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::rgb10a2;
+#[cfg(feature = "tracing")]
+use crate::trace;
+use crate::transform::StreamTransform;
 #[cfg(feature = "std")]
 use crate::utils::GenericWriter;
-use crate::utils::{unlikely, BytesMut, Writer};
+use crate::utils::{likely, unlikely, BytesMut, Writer};
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::utils::{try_vec_with_capacity, try_vec_zeroed};
+use crate::yuv::{Yuv420, YuvMatrix};
+
+/// Number of pixels converted at a time when encoding from a [`PixelFormat`].
+const CONVERT_BLOCK: usize = 256;
 
-#[allow(clippy::cast_possible_truncation, unused_assignments, unused_variables)]
-fn encode_impl<W: Writer>(mut buf: W, data: &[u8]) -> Result<usize>
-where
-    [u8; 4]: Pod,
-{
+/// Smallest number of pixels worth encoding on a thread of its own, see
+/// [`Encoder::encode_to_vec_parallel`].
+#[cfg(feature = "rayon")]
+const MIN_STRIP_PIXELS: usize = 1 << 16;
+
+/// Encodes the op stream including the end marker and returns its size.
+///
+/// With `restart_first`, the very first pixel starts a band too, so the output can be spliced
+/// into another op stream at a restart marker.
+///
+/// The pixel data may be split into several segments, each holding whole pixels.
+///
+/// `tolerance` is [`Tolerance::LOSSLESS`] unless lossy encoding is enabled, see
+/// [`Encoder::with_tolerance`]. With a `format`, the pixel data is in that format and gets
+/// converted to `channels` on the fly.
+#[inline]
+#[allow(clippy::too_many_arguments)]
+pub fn encode_impl<W: Writer>(
+    buf: W, segments: &[&[u8]], channels: Channels, format: Option<PixelFormat>,
+    band_pixels: usize, options: EncoderOptions, restart_first: bool, tolerance: Tolerance<'_>,
+) -> Result<usize> {
+    let bpp = format.map_or(channels.as_u8() as usize, PixelFormat::bytes_per_pixel);
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!(
+        "qoi::encode_ops",
+        n_pixels = segments.iter().map(|data| data.len()).sum::<usize>() / bpp
+    )
+    .entered();
     let cap = buf.capacity();
+    let mut state = EncodeState::new(band_pixels, restart_first);
+    let state = &mut state;
+    let buf = if let [data] = segments {
+        let pixels = data.chunks_exact(bpp);
+        encode_source(buf, pixels, channels, format, state, options, tolerance)
+    } else {
+        let pixels = segments.iter().flat_map(|data| data.chunks_exact(bpp));
+        encode_source(buf, pixels, channels, format, state, options, tolerance)
+    }?;
+    let buf = state.finish(buf)?;
+    Ok(cap.saturating_sub(buf.capacity()))
+}
 
-    let mut index = [Pixel::new(); 256];
-    let mut px_prev = Pixel::new().with_a(0xff);
-    let mut hash_prev = px_prev.hash_index();
-    let mut run = 0_u8;
-    let mut px = Pixel::new().with_a(0xff);
-    let mut index_allowed = false;
+/// Tolerance of lossy encoding for a range of pixels, see [`Encoder::with_tolerance`] and
+/// [`Encoder::with_roi`].
+#[derive(Copy, Clone)]
+pub enum Tolerance<'a> {
+    /// The same for every pixel, zero for lossless encoding
+    Uniform(u8),
+    /// One byte per pixel of the range
+    Map(&'a [u8]),
+}
+
+impl Tolerance<'_> {
+    /// Tolerance of lossless encoding.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub const LOSSLESS: Self = Self::Uniform(0);
+
+    #[inline]
+    const fn is_lossless(self) -> bool {
+        matches!(self, Self::Uniform(0))
+    }
+
+    /// Tolerance of the `i`-th pixel of the range.
+    #[inline(always)]
+    fn at(self, i: usize) -> u8 {
+        match self {
+            Self::Uniform(tolerance) => tolerance,
+            Self::Map(map) => map[i],
+        }
+    }
+
+    /// Narrows the range down to its pixels `start..end`.
+    #[inline]
+    fn range(self, start: usize, end: usize) -> Self {
+        match self {
+            Self::Uniform(_) => self,
+            Self::Map(map) => Self::Map(&map[start..end]),
+        }
+    }
+}
 
-    let n_pixels = data.len() / 4;
+/// Iterates over the pixels `start..end` of pixel data split into segments.
+#[inline]
+fn segment_pixels<'s>(
+    segments: &'s [&'s [u8]], bpp: usize, start: usize, end: usize,
+) -> impl Iterator<Item = &'s [u8]> + Clone {
+    // only the parts of the segments overlapping the range
+    let mut offset = 0;
+    segments.iter().flat_map(move |segment| {
+        let (first, last) = (offset, offset + segment.len() / bpp);
+        offset = last;
+        let range = start.clamp(first, last) - first..end.clamp(first, last) - first;
+        segment[range.start * bpp..range.end * bpp].chunks_exact(bpp)
+    })
+}
+
+/// Encoder state carried over from one chunk of pixels to the next.
+#[derive(Clone)]
+pub struct EncodeState {
+    index: [Pixel; 256],
+    px_prev: Pixel,
+    hash_prev: u8,
+    run: u8,
+    index_allowed: bool,
+    band_pixels: usize,
+    band_left: usize,
+    // index slots written since the last restart, only these may serve approximate matches
+    index_written: u64,
+    // last index slot written for each color cell, see `EncoderOptions::nearest_color_index`
+    nearest: [u8; 4096],
+}
+
+impl EncodeState {
+    /// Creates the state at the start of an image; see [`encode_impl`] for `restart_first`.
+    pub const fn new(band_pixels: usize, restart_first: bool) -> Self {
+        let px_prev = Pixel::new().with_a(0xff);
+        Self {
+            index: [Pixel::new(); 256],
+            px_prev,
+            hash_prev: px_prev.hash_index(),
+            run: 0,
+            index_allowed: false,
+            band_pixels,
+            band_left: if restart_first { 0 } else { band_pixels },
+            index_written: 0,
+            nearest: [0; 4096],
+        }
+    }
+
+    /// Flushes the pending run and writes the end marker.
+    pub fn finish<W: Writer>(&mut self, mut buf: W) -> Result<W> {
+        if self.run != 0 {
+            buf = buf.write_one(QOI_OP_RUN | (self.run - 1))?;
+            self.run = 0;
+        }
+        buf.write_many(&QOI_PADDING)
+    }
+}
+
+/// Encodes a run of pixels, continuing from `state`; the pending run isn't flushed.
+#[inline]
+pub fn encode_pixels<'a, W: Writer, P: Iterator<Item = &'a [u8]> + Clone>(
+    buf: W, pixels: P, channels: Channels, state: &mut EncodeState, options: EncoderOptions,
+    tolerance: Tolerance<'_>,
+) -> Result<W> {
+    match channels {
+        Channels::Rgba => encode_channels::<_, _, 4>(buf, pixels, state, options, tolerance),
+        Channels::Rgb => encode_channels::<_, _, 3>(buf, pixels, state, options, tolerance),
+        Channels::La => encode_channels::<_, _, 2>(buf, pixels, state, options, tolerance),
+    }
+}
+
+/// Encodes a run of pixels of `N` bytes each, see [`encode_pixels`].
+#[inline]
+fn encode_channels<'a, W: Writer, P: Iterator<Item = &'a [u8]> + Clone, const N: usize>(
+    buf: W, pixels: P, state: &mut EncodeState, options: EncoderOptions, tolerance: Tolerance<'_>,
+) -> Result<W> {
+    // most images without transparency are fully opaque, in which case the alpha channel never
+    // changes and the per-pixel alpha check can be skipped; scanning for it is a lot cheaper
+    // than encoding
+    let opaque =
+        state.px_prev.a() == 0xff && (N == 3 || pixels.clone().all(|px| px[N - 1] == 0xff));
+    match (opaque, tolerance.is_lossless()) {
+        (true, true) => {
+            encode_hinted::<_, _, N, true, false>(buf, pixels, state, options, tolerance)
+        }
+        (false, true) => {
+            encode_hinted::<_, _, N, false, false>(buf, pixels, state, options, tolerance)
+        }
+        (true, false) => {
+            encode_hinted::<_, _, N, true, true>(buf, pixels, state, options, tolerance)
+        }
+        (false, false) => {
+            encode_hinted::<_, _, N, false, true>(buf, pixels, state, options, tolerance)
+        }
+    }
+}
+
+/// Encodes a run of pixels like [`encode_pixels`], converting them from `format` first if
+/// there is one.
+#[inline]
+fn encode_source<'a, W: Writer, P: Iterator<Item = &'a [u8]> + Clone>(
+    buf: W, pixels: P, channels: Channels, format: Option<PixelFormat>, state: &mut EncodeState,
+    options: EncoderOptions, tolerance: Tolerance<'_>,
+) -> Result<W> {
+    match format {
+        None => encode_pixels(buf, pixels, channels, state, options, tolerance),
+        Some(format) => encode_converted(buf, pixels, channels, format, state, options, tolerance),
+    }
+}
 
-    for (i, chunk) in data.chunks_exact(4).enumerate() {
-        px.read(chunk);
-        if px == px_prev {
+/// Encodes a run of pixels in a given format, converting them to `channels` a block at a time
+/// so that the whole image never needs to be converted up front.
+fn encode_converted<'a, W: Writer>(
+    mut buf: W, mut pixels: impl Iterator<Item = &'a [u8]>, channels: Channels,
+    format: PixelFormat, state: &mut EncodeState, options: EncoderOptions,
+    tolerance: Tolerance<'_>,
+) -> Result<W> {
+    let bpp = channels.as_u8() as usize;
+    let mut block = [0; CONVERT_BLOCK * 4];
+    let mut start = 0;
+    loop {
+        // the block comes first, so that no pixel is consumed once it's full
+        let mut n = 0;
+        for (out, px) in block.chunks_exact_mut(bpp).zip(pixels.by_ref()) {
+            out.copy_from_slice(&format.to_rgba(px)[..bpp]);
+            n += 1;
+        }
+        if n == 0 {
+            return Ok(buf);
+        }
+        let block = block[..n * bpp].chunks_exact(bpp);
+        let tolerance = tolerance.range(start, start + n);
+        buf = encode_pixels(buf, block, channels, state, options, tolerance)?;
+        start += n;
+    }
+}
+
+/// Encodes the pixels `start..end` of a YUV frame, converting them to RGB a block at a time
+/// like [`encode_converted`].
+fn encode_yuv<W: Writer>(
+    mut buf: W, yuv: &Yuv420, start: usize, end: usize, state: &mut EncodeState,
+    options: EncoderOptions, tolerance: Tolerance<'_>,
+) -> Result<W> {
+    let mut block = [0; CONVERT_BLOCK * 3];
+    let mut pos = start;
+    while pos < end {
+        let n = CONVERT_BLOCK.min(end - pos);
+        let block = &mut block[..n * 3];
+        yuv.read(pos, block);
+        let tolerance = tolerance.range(pos - start, pos - start + n);
+        let pixels = block.chunks_exact(3);
+        buf = encode_pixels(buf, pixels, Channels::Rgb, state, options, tolerance)?;
+        pos += n;
+    }
+    Ok(buf)
+}
+
+#[inline]
+fn encode_hinted<
+    'a,
+    W: Writer,
+    P: Iterator<Item = &'a [u8]>,
+    const N: usize,
+    const OPAQUE: bool,
+    const LOSSY: bool,
+>(
+    buf: W, pixels: P, state: &mut EncodeState, options: EncoderOptions, tolerance: Tolerance<'_>,
+) -> Result<W> {
+    const AUTO: u8 = ContentHint::Auto as u8;
+    const SCREENSHOT: u8 = ContentHint::Screenshot as u8;
+    const PHOTO: u8 = ContentHint::Photo as u8;
+    const PIXEL_ART: u8 = ContentHint::PixelArt as u8;
+    match options.content_hint {
+        ContentHint::Auto => {
+            encode_ops::<_, _, N, OPAQUE, LOSSY, AUTO>(buf, pixels, state, options, tolerance)
+        }
+        ContentHint::Screenshot => {
+            encode_ops::<_, _, N, OPAQUE, LOSSY, SCREENSHOT>(buf, pixels, state, options, tolerance)
+        }
+        ContentHint::Photo => {
+            encode_ops::<_, _, N, OPAQUE, LOSSY, PHOTO>(buf, pixels, state, options, tolerance)
+        }
+        ContentHint::PixelArt => {
+            encode_ops::<_, _, N, OPAQUE, LOSSY, PIXEL_ART>(buf, pixels, state, options, tolerance)
+        }
+    }
+}
+
+/// Cell of the nearest color table: the 4 high bits of each of R, G and B.
+#[inline(always)]
+const fn color_cell(px: Pixel) -> usize {
+    (px.r() as usize >> 4) << 8 | (px.g() as usize >> 4) << 4 | px.b() as usize >> 4
+}
+
+/// Branch hint for a condition that the content hint expects to hold (or not) most of the time.
+#[inline(always)]
+const fn hint(b: bool, expected: bool, unexpected: bool) -> bool {
+    if expected {
+        likely(b)
+    } else if unexpected {
+        unlikely(b)
+    } else {
+        b
+    }
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::too_many_lines,
+    unused_assignments,
+    unused_variables
+)]
+fn encode_ops<
+    'a,
+    W: Writer,
+    P: Iterator<Item = &'a [u8]>,
+    const N: usize,
+    const OPAQUE: bool,
+    const LOSSY: bool,
+    const HINT: u8,
+>(
+    mut buf: W, pixels: P, state: &mut EncodeState, options: EncoderOptions,
+    tolerance: Tolerance<'_>,
+) -> Result<W> {
+    let photo = HINT == ContentHint::Photo as u8;
+    let pixel_art = HINT == ContentHint::PixelArt as u8;
+    let flat = pixel_art || HINT == ContentHint::Screenshot as u8;
+    let (max_run, nearest_enabled) = (options.max_run, LOSSY && options.nearest_color_index);
+    let band_pixels = state.band_pixels;
+
+    // work on local copies, which are a lot easier on the optimizer than the state behind a
+    // reference; they're written back at the end
+    let mut index = state.index;
+    let mut px_prev = state.px_prev;
+    let mut hash_prev = state.hash_prev;
+    let mut run = state.run;
+    let mut px = Pixel::new().with_a(0xff);
+    let mut index_allowed = state.index_allowed;
+    let mut band_left = state.band_left;
+    let mut index_written = state.index_written;
+    let mut nearest = state.nearest;
+
+    for (i, chunk) in pixels.enumerate() {
+        px.read_n::<N>(chunk);
+        if unlikely(band_left == 0) {
+            // restart marker: the band must decode the same way whether or not the decoder
+            // resets its state here, so flush the run and store the first pixel verbatim
+            if run != 0 {
+                buf = buf.write_one(QOI_OP_RUN | (run - 1))?;
+                run = 0;
+            }
+            index = [Pixel::new(); 256];
+            index[0] = Pixel::new().with_a(1); // hashes elsewhere, so slot 0 never matches
+            hash_prev = px.hash_index();
+            index[hash_prev as usize] = px;
+            index_written = 1 << hash_prev;
+            buf = buf.write_many(&[QOI_OP_RGBA, px.r(), px.g(), px.b(), px.a()])?;
+            px_prev = px;
+            index_allowed = true;
+            band_left = band_pixels - 1;
+            continue;
+        }
+        band_left -= 1;
+        let tolerance = if LOSSY { tolerance.at(i) } else { 0 };
+        if hint(px == px_prev || (LOSSY && px.is_close(px_prev, tolerance)), flat, photo) {
             run += 1;
-            if run == 62 || unlikely(i == n_pixels - 1) {
+            if run == max_run {
                 buf = buf.write_one(QOI_OP_RUN | (run - 1))?;
                 run = 0;
             }
@@ -41,8 +401,9 @@ where
             if run != 0 {
                 #[cfg(not(feature = "reference"))]
                 {
-                    // credits for the original idea: @zakarumych (had to be fixed though)
-                    buf = buf.write_one(if run == 1 && index_allowed {
+                    // credits for the original idea: @zakarumych (had to be fixed though);
+                    // single-pixel runs are too rare in photos to be worth the extra branch
+                    buf = buf.write_one(if !photo && run == 1 && index_allowed {
                         QOI_OP_INDEX | hash_prev
                     } else {
                         QOI_OP_RUN | (run - 1)
@@ -57,74 +418,749 @@ where
             index_allowed = true;
             let px_rgba = px.as_rgba();
             hash_prev = px_rgba.hash_index();
-            let index_px = &mut index[hash_prev as usize];
-            if *index_px == px_rgba {
+            let index_px = index[hash_prev as usize];
+            let nearest_slot = nearest[color_cell(px_rgba)];
+            if hint(index_px == px_rgba, pixel_art, photo) {
                 buf = buf.write_one(QOI_OP_INDEX | hash_prev)?;
+            } else if LOSSY
+                && index_written & (1 << hash_prev) != 0
+                && index_px.is_close(px_rgba, tolerance)
+            {
+                // the decoder will reproduce the indexed color, so that's the one to diff against
+                buf = buf.write_one(QOI_OP_INDEX | hash_prev)?;
+                px = index_px;
+            } else if nearest_enabled
+                && tolerance != 0
+                && index_written & (1 << nearest_slot) != 0
+                && index[nearest_slot as usize].is_close(px_rgba, tolerance)
+            {
+                // an INDEX op may point at any slot, not just the one the color hashes to
+                buf = buf.write_one(QOI_OP_INDEX | nearest_slot)?;
+                (px, hash_prev) = (index[nearest_slot as usize], nearest_slot);
             } else {
-                *index_px = px_rgba;
-                buf = px.encode_into(px_prev, buf)?;
+                index[hash_prev as usize] = px_rgba;
+                index_written |= 1 << hash_prev;
+                if nearest_enabled {
+                    nearest[color_cell(px_rgba)] = hash_prev;
+                }
+                // noisy content rarely fits a DIFF op, so check whether LUMA fits first
+                buf = if OPAQUE {
+                    px.encode_into_rgb(px_prev, buf, photo)?
+                } else {
+                    px.encode_into(px_prev, buf, photo)?
+                };
             }
             px_prev = px;
         }
     }
 
-    buf = buf.write_many(&QOI_PADDING)?;
-    Ok(cap.saturating_sub(buf.capacity()))
+    *state = EncodeState {
+        index,
+        px_prev,
+        hash_prev,
+        run,
+        index_allowed,
+        band_pixels,
+        band_left,
+        index_written,
+        nearest,
+    };
+    Ok(buf)
 }
 
 
 /// The maximum number of bytes the encoded image will take.
 ///
-/// Can be used to pre-allocate the buffer to encode the image into.
+/// Can be used to pre-allocate the buffer to encode the image into. Saturates at `usize::MAX`
+/// for images too large for the target, which [`Header::try_new`] rejects with
+/// [`Error::SizeOverflow`].
 #[inline]
 pub fn encode_max_len(width: u16, height: u16) -> usize {
     let (width, height) = (width as usize, height as usize);
     let n_pixels = width.saturating_mul(height);
-    QOI_HEADER_SIZE
-        + n_pixels.saturating_mul(4)
-        + n_pixels
-        + QOI_PADDING_SIZE
+    // at most 5 bytes per pixel (a QOI_OP_RGBA op)
+    n_pixels.saturating_mul(5).saturating_add(QOI_HEADER_SIZE + QOI_PADDING_SIZE)
 }
 
 /// Encode the image into a pre-allocated buffer.
 ///
 /// Returns the total number of bytes written.
 #[inline]
-pub fn encode_to_buf(buf: impl AsMut<[u8]>, data: impl AsRef<[u8]>, width: u16, height: u16) -> Result<usize> {
+pub fn encode_to_buf(
+    buf: impl AsMut<[u8]>, data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension,
+) -> Result<usize> {
     Encoder::new(&data, width, height)?.encode_to_buf(buf)
 }
 
 /// Encode the image into a newly allocated vector.
 #[cfg(any(feature = "alloc", feature = "std"))]
 #[inline]
-pub fn encode_to_vec(data: impl AsRef<[u8]>, width: u16, height: u16) -> Result<Vec<u8>> {
+pub fn encode_to_vec(
+    data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension,
+) -> Result<Vec<u8>> {
     Encoder::new(&data, width, height)?.encode_to_vec()
 }
 
+/// Encode the image into a newly allocated vector as a bare op stream, without a header.
+///
+/// See [`Encoder::encode_headerless_to_buf`] for details.
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[inline]
+pub fn encode_headerless(
+    data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension,
+) -> Result<Vec<u8>> {
+    Encoder::new(&data, width, height)?.encode_headerless_to_vec()
+}
+
+/// Encoder configuration.
+///
+/// The defaults produce the same output as a plain [`Encoder`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EncoderOptions {
+    max_run: u8,
+    restart_interval: u16,
+    content_hint: ContentHint,
+    nearest_color_index: bool,
+    verify_output: bool,
+    pub(crate) header_format: HeaderFormat,
+}
+
+/// Kind of image being encoded, used to tune the encoder for speed.
+///
+/// The hint only changes the order in which the encoder tries the different ops and which
+/// branches it expects to be taken, so the encoded size is the same whatever the hint; a
+/// wrong hint merely makes encoding a bit slower. The bytes may differ though, as
+/// [`ContentHint::Photo`] writes single-pixel runs as runs rather than as index ops.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ContentHint {
+    /// No assumptions about the content (default)
+    #[default]
+    Auto,
+    /// User interfaces and rendered documents: large flat areas, so long runs are expected
+    Screenshot,
+    /// Camera images and other noisy content: runs and exact repeats are rare, and most pixels
+    /// need a LUMA or RGB op
+    Photo,
+    /// Few distinct colors: runs and index hits are expected
+    PixelArt,
+}
+
+impl EncoderOptions {
+    /// Creates the default encoder configuration.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            max_run: QOI_RUN_MAX,
+            restart_interval: 0,
+            content_hint: ContentHint::Auto,
+            nearest_color_index: false,
+            verify_output: false,
+            header_format: HeaderFormat::GameMaker,
+        }
+    }
+
+    /// Caps the length of pixel runs (clamped to `1..=62`, the default being 62).
+    ///
+    /// Shorter runs bound the number of pixels a single op can produce, which bounds the
+    /// latency of streaming decoders at a small cost in compression.
+    #[inline]
+    pub const fn max_run(mut self, max_run: u8) -> Self {
+        self.max_run = if max_run == 0 {
+            1
+        } else if max_run > QOI_RUN_MAX {
+            QOI_RUN_MAX
+        } else {
+            max_run
+        };
+        self
+    }
+
+    /// Flushes the encoder state every `rows` rows (zero, the default, disables this).
+    ///
+    /// Every band of `rows` rows starts with a verbatim pixel and doesn't reference anything
+    /// before it, so the image still decodes as a regular QOI image, while the band offsets
+    /// are recorded as restart markers in an extension block after the end marker. This
+    /// allows decoding to start at any band, e.g. to resynchronize after packet loss.
+    #[inline]
+    pub const fn restart_interval(mut self, rows: u16) -> Self {
+        self.restart_interval = rows;
+        self
+    }
+
+    /// Tunes the encoder for a given kind of content (see [`ContentHint`]).
+    #[inline]
+    pub const fn content_hint(mut self, hint: ContentHint) -> Self {
+        self.content_hint = hint;
+        self
+    }
+
+    /// Lets lossy encoding reuse any close color in the index, not just the one in the slot
+    /// the pixel hashes to (disabled by default).
+    ///
+    /// A QOI_OP_INDEX op may reference any of the 64 slots, but a color only ever gets stored
+    /// in its own slot, so lossless encoding can't find more exact matches than it already
+    /// does. With a tolerance map (see [`Encoder::with_roi`]) though, a color within tolerance
+    /// may sit in another slot: a 4096-entry table maps every cell of 16x16x16 colors to the
+    /// last slot written with a color from that cell, which catches most of them on gradients
+    /// at the cost of a 4 KiB table and one extra lookup per missed pixel. Decoding is
+    /// unaffected; this has no effect on lossless encoding.
+    #[inline]
+    pub const fn nearest_color_index(mut self, enabled: bool) -> Self {
+        self.nearest_color_index = enabled;
+        self
+    }
+
+    /// Decodes every encoded image again and compares it with the input before returning
+    /// (disabled by default).
+    ///
+    /// If the pixels (within tolerance for lossy encoding), the restart markers or the low bits
+    /// of RGB10A2 images don't match, encoding fails with [`Error::VerificationFailed`]
+    /// instead of handing out a corrupted image. This is meant for archival pipelines where a
+    /// silent encoder bug would be unacceptable, and roughly doubles the time taken to encode
+    /// (no memory is allocated for it). Images streamed by [`Encoder::encode_to_stream`] with
+    /// a reference header are not verified, since the output can't be read back.
+    #[inline]
+    pub const fn verify_output(mut self, enabled: bool) -> Self {
+        self.verify_output = enabled;
+        self
+    }
+
+    /// Writes the header in a given format (see [`HeaderFormat`], the default being
+    /// GameMaker's).
+    ///
+    /// With [`HeaderFormat::Reference`], images can be read by any other QOI implementation.
+    /// No extension block is written then, so the restart interval, nine-patch metadata and
+    /// RGB10A2 low bits are ignored. As this header doesn't hold the length of the op stream,
+    /// it also allows [`Encoder::encode_to_stream`] to write it up front and stream the ops
+    /// without buffering the image.
+    #[inline]
+    pub const fn header_format(mut self, format: HeaderFormat) -> Self {
+        self.header_format = format;
+        self
+    }
+}
+
+impl Default for EncoderOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pixel data held by an encoder.
+#[doc(hidden)]
+#[derive(Clone)]
+pub enum PixelData<'a> {
+    Borrowed(&'a [u8]),
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    Owned(Vec<u8>),
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    Shared(Arc<[u8]>),
+}
+
+impl PixelData<'_> {
+    #[inline]
+    #[allow(clippy::missing_const_for_fn)]
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Borrowed(data) => data,
+            #[cfg(any(feature = "alloc", feature = "std"))]
+            Self::Owned(data) => data,
+            #[cfg(any(feature = "alloc", feature = "std"))]
+            Self::Shared(data) => data,
+        }
+    }
+
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    #[inline]
+    fn into_owned(self) -> PixelData<'static> {
+        match self {
+            Self::Borrowed(data) => PixelData::Owned(data.to_vec()),
+            Self::Owned(data) => PixelData::Owned(data),
+            Self::Shared(data) => PixelData::Shared(data),
+        }
+    }
+}
+
+/// Segments of pixel data held by an encoder, see [`Encoder::new_gather`].
+enum Segments<'a> {
+    Borrowed(&'a [&'a [u8]]),
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    Owned(Vec<&'a [u8]>),
+}
+
+impl<'a> Segments<'a> {
+    #[inline]
+    #[allow(clippy::missing_const_for_fn)]
+    fn as_slice(&self) -> &[&'a [u8]] {
+        match self {
+            Self::Borrowed(segments) => segments,
+            #[cfg(any(feature = "alloc", feature = "std"))]
+            Self::Owned(segments) => segments,
+        }
+    }
+}
+
+/// Pixel data that can be handed over to an [`Encoder`], either borrowed or owned.
+///
+/// This is implemented for references to anything that implements `AsRef<[u8]>`, as well as
+/// for `Vec<u8>`, `Box<[u8]>`, `Arc<[u8]>` and `Cow<[u8]>` by value (owned data is moved
+/// into the encoder without copying it).
+pub trait AsPixelData<'a> {
+    #[doc(hidden)]
+    fn into_pixel_data(self) -> PixelData<'a>;
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> AsPixelData<'a> for &'a T {
+    #[inline]
+    fn into_pixel_data(self) -> PixelData<'a> {
+        PixelData::Borrowed(self.as_ref())
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+impl<'a> AsPixelData<'a> for Vec<u8> {
+    #[inline]
+    fn into_pixel_data(self) -> PixelData<'a> {
+        PixelData::Owned(self)
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+impl<'a> AsPixelData<'a> for Box<[u8]> {
+    #[inline]
+    fn into_pixel_data(self) -> PixelData<'a> {
+        PixelData::Owned(self.into_vec())
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+impl<'a> AsPixelData<'a> for Arc<[u8]> {
+    #[inline]
+    fn into_pixel_data(self) -> PixelData<'a> {
+        PixelData::Shared(self)
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+impl<'a> AsPixelData<'a> for Cow<'a, [u8]> {
+    #[inline]
+    fn into_pixel_data(self) -> PixelData<'a> {
+        match self {
+            Cow::Borrowed(data) => PixelData::Borrowed(data),
+            Cow::Owned(data) => PixelData::Owned(data),
+        }
+    }
+}
+
 /// Encode QOI images into buffers or into streams.
 pub struct Encoder<'a> {
-    data: &'a [u8],
+    data: PixelData<'a>,
+    segments: Segments<'a>,
+    roi: PixelData<'a>,
+    tolerance: u8,
+    channels: Channels,
+    format: Option<PixelFormat>,
     header: Header,
+    options: EncoderOptions,
+    nine_patch: Option<NinePatch>,
+    field: Option<Field>,
+    low_bits: PixelData<'a>,
+    checksum: Option<ChecksumKind>,
+    yuv: Option<Yuv420<'a>>,
 }
 
 impl<'a> Encoder<'a> {
     /// Creates a new encoder from a given array of pixel data and image dimensions.
     ///
     /// The number of channels will be inferred automatically (the valid values
-    /// are 3 or 4). The color space will be set to sRGB by default.
+    /// are 2, 3 and 4, see [`Channels`]). The color space will be set to sRGB by default.
+    ///
+    /// The pixel data may be borrowed or owned, see [`AsPixelData`].
+    #[inline]
+    pub fn new(
+        data: impl AsPixelData<'a>, width: impl Dimension, height: impl Dimension,
+    ) -> Result<Self> {
+        let (width, height) = dimensions(width, height)?;
+        Self::new_impl(data.into_pixel_data(), Segments::Borrowed(&[]), width, height, None)
+    }
+
+    /// Creates a new encoder from pixel data in a given [`PixelFormat`], e.g. the BGRA pixels
+    /// handed out by screenshot APIs.
+    ///
+    /// Pixels are converted while being encoded, a small block at a time, so the data doesn't
+    /// need to be converted to RGBA first. Formats with an alpha channel are encoded as RGBA,
+    /// all others as RGB. Rows must be tightly packed, see
+    /// [`encode_from_framebuffer`](crate::encode_from_framebuffer) for padded rows.
+    #[inline]
+    pub fn new_with_format(
+        data: impl AsPixelData<'a>, width: impl Dimension, height: impl Dimension,
+        format: PixelFormat,
+    ) -> Result<Self> {
+        let (width, height) = dimensions(width, height)?;
+        let (data, segments) = (data.into_pixel_data(), Segments::Borrowed(&[]));
+        Self::new_impl(data, segments, width, height, Some(format))
+    }
+
+    /// Creates a new encoder from a planar YUV 4:2:0 frame (I420), as handed out by webcams and
+    /// hardware video decoders.
+    ///
+    /// `y` holds `width * height` luma samples, `u` and `v` one chroma sample for each 2x2
+    /// block of pixels (rounding up for odd dimensions); planes must be tightly packed.
+    /// Samples are converted to RGB while being encoded, a small block at a time, with the
+    /// BT.601 matrix unless set otherwise with [`Encoder::with_yuv_matrix`].
+    #[inline]
+    pub fn from_yuv420(
+        y: &'a [u8], u: &'a [u8], v: &'a [u8], width: impl Dimension, height: impl Dimension,
+    ) -> Result<Self> {
+        let header = Header::from_dimensions(width, height, None)?;
+        let yuv = Yuv420::planar(y, u, v, header.width, header.height, YuvMatrix::Bt601)?;
+        Ok(Self::new_yuv(yuv, header))
+    }
+
+    /// Creates a new encoder from a YUV 4:2:0 frame with interleaved chroma (NV12), see
+    /// [`Encoder::from_yuv420`].
+    ///
+    /// `uv` holds pairs of U and V samples, one pair for each 2x2 block of pixels.
+    #[inline]
+    pub fn from_nv12(
+        y: &'a [u8], uv: &'a [u8], width: impl Dimension, height: impl Dimension,
+    ) -> Result<Self> {
+        let header = Header::from_dimensions(width, height, None)?;
+        let yuv = Yuv420::nv12(y, uv, header.width, header.height, YuvMatrix::Bt601)?;
+        Ok(Self::new_yuv(yuv, header))
+    }
+
+    /// Creates a new encoder from pixel data split into several segments, e.g. a frame spread
+    /// over multiple DMA buffers, without copying them into one contiguous buffer first.
+    ///
+    /// The segments are encoded back to back as if they were a single array of pixel data, so
+    /// their total size must match the image dimensions like in [`Encoder::new`]. Pixels can't
+    /// straddle two segments: the size of each segment must be a multiple of the number of
+    /// channels, otherwise [`Error::InvalidImageLength`] is returned.
     #[inline]
-    #[allow(clippy::cast_possible_truncation)]
-    pub fn new(data: &'a (impl AsRef<[u8]> + ?Sized), width: u16, height: u16) -> Result<Self> {
-        let data = data.as_ref();
+    pub fn new_gather(
+        segments: &'a [&'a [u8]], width: impl Dimension, height: impl Dimension,
+    ) -> Result<Self> {
+        let (width, height) = dimensions(width, height)?;
+        Self::new_impl(PixelData::Borrowed(&[]), Segments::Borrowed(segments), width, height, None)
+    }
+
+    /// Creates a new encoder from an iterator over the rows of the image, e.g. rows stitched
+    /// together from tiles held in separate buffers, without copying them into one contiguous
+    /// buffer first.
+    ///
+    /// There must be exactly `height` rows, all of the same length, with the number of
+    /// channels inferred from it like in [`Encoder::new`]; otherwise
+    /// [`Error::InvalidImageLength`] is returned. Only the row slices are collected, the pixel
+    /// data stays borrowed.
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    pub fn from_rows(
+        rows: impl ExactSizeIterator<Item = &'a [u8]>, width: impl Dimension,
+        height: impl Dimension,
+    ) -> Result<Self> {
+        let (width, height) = dimensions(width, height)?;
+        let n_rows = rows.len();
+        let mut segments = try_vec_with_capacity(n_rows)?;
+        segments.extend(rows);
+        let size = segments.iter().map(|row| row.len()).sum();
+        let row_len = segments.first().map_or(0, |row| row.len());
+        if unlikely(n_rows != height as usize || segments.iter().any(|row| row.len() != row_len)) {
+            return Err(Error::InvalidImageLength { size, width, height });
+        }
+        Self::new_impl(PixelData::Borrowed(&[]), Segments::Owned(segments), width, height, None)
+    }
+
+    /// Creates a new encoder that owns its pixel data.
+    ///
+    /// The resulting encoder doesn't borrow anything, so it can be moved into a thread or a
+    /// spawned task. An `Arc<[u8]>` is taken as is, so a frame that is shared with other
+    /// consumers doesn't need to be copied; owned data can also be passed to [`Encoder::new`].
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    #[inline]
+    pub fn from_owned(
+        data: impl Into<Arc<[u8]>>, width: impl Dimension, height: impl Dimension,
+    ) -> Result<Encoder<'static>> {
+        let (width, height) = dimensions(width, height)?;
+        let (data, segments) = (PixelData::Shared(data.into()), Segments::Borrowed(&[]));
+        Encoder::new_impl(data, segments, width, height, None)
+    }
+
+    /// Creates a new encoder from RGB10A2 pixels, for more than 8 bits of color precision.
+    ///
+    /// Each word holds 10 bits of red (lowest bits), green and blue followed by 2 bits of alpha,
+    /// as in `GL_UNSIGNED_INT_2_10_10_10_REV` or `DXGI_FORMAT_R10G10B10A2_UNORM`. The 8 high
+    /// bits of each channel are encoded as usual, so any decoder can read the image as 8-bit
+    /// RGBA, while the 2 low bits of each color channel are stored uncompressed in the
+    /// extension block (6 bits per pixel); use
+    /// [`Decoder::decode_to_rgb10a2`](crate::Decoder::decode_to_rgb10a2) to get them back.
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    pub fn from_rgb10a2(
+        data: impl AsRef<[u32]>, width: impl Dimension, height: impl Dimension,
+    ) -> Result<Encoder<'static>> {
+        let header = Header::from_dimensions(width, height, None)?;
+        let (width, height, data) = (header.width, header.height, data.as_ref());
+        if unlikely(data.len() != header.n_pixels()) {
+            let size = data.len().saturating_mul(4);
+            return Err(Error::InvalidImageLength { size, width, height });
+        }
+        let mut pixels = try_vec_zeroed(header.n_bytes())?;
+        let mut low_bits = try_vec_zeroed(rgb10a2::low_bits_len(data.len()))?;
+        for (i, (&word, px)) in data.iter().zip(pixels.chunks_exact_mut(4)).enumerate() {
+            let (high, low) = rgb10a2::unpack(word);
+            px.copy_from_slice(&<[u8; 4]>::from(high));
+            rgb10a2::put_low_bits(&mut low_bits, i, low);
+        }
+        let segments = Segments::Borrowed(&[]);
+        let mut encoder =
+            Encoder::new_impl(PixelData::Owned(pixels), segments, width, height, None)?;
+        encoder.low_bits = PixelData::Owned(low_bits);
+        Ok(encoder)
+    }
+
+    /// Creates a new encoder for one field of an interlaced frame, i.e. its even or odd rows.
+    ///
+    /// `data` holds the whole frame of `width` by `height` pixels, with the number of channels
+    /// inferred like in [`Encoder::new`]. The rows of the field are copied out of it and encoded
+    /// as an image of [`Field::n_rows`] rows, tagged with the field in the extension block;
+    /// [`weave_fields`](crate::weave_fields) puts the two fields back together.
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    pub fn from_field(
+        data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension, field: Field,
+    ) -> Result<Encoder<'static>> {
+        let frame = Encoder::new(data.as_ref(), width, height)?;
+        let (width, height) = (frame.header.width, frame.header.height);
+        let row_len = width as usize * frame.channels.as_u8() as usize;
+        let n_rows = field.n_rows(height);
+        let mut rows = try_vec_with_capacity(row_len * n_rows as usize)?;
+        let frame_rows = frame.data.as_slice().chunks_exact(row_len);
+        for row in frame_rows.skip(field.first_row() as usize).step_by(2) {
+            rows.extend_from_slice(row);
+        }
+        let segments = Segments::Borrowed(&[]);
+        let mut encoder = Encoder::new_impl(PixelData::Owned(rows), segments, width, n_rows, None)?;
+        encoder.field = Some(field);
+        Ok(encoder)
+    }
+
+    /// Detaches the encoder from borrowed pixel data, copying it if necessary.
+    ///
+    /// This is meant for handing an encoder created with [`Encoder::new`] over to a thread or
+    /// a spawned task, which requires it to be `'static` (it's always `Send` and `Sync`).
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    #[inline]
+    pub fn into_owned(self) -> Encoder<'static> {
+        let data = match (self.yuv, self.segments.as_slice()) {
+            (Some(yuv), _) => {
+                let mut rgb = alloc::vec![0; self.header.n_pixels() * 3];
+                yuv.read(0, &mut rgb);
+                PixelData::Owned(rgb)
+            }
+            (None, []) => self.data.into_owned(),
+            (None, segments) => PixelData::Owned(segments.concat()),
+        };
+        let (roi, tolerance) = (self.roi.into_owned(), self.tolerance);
+        let segments = Segments::Borrowed(&[]);
+        let (channels, format, header) = (self.channels, self.format, self.header);
+        let (options, nine_patch, field, low_bits) =
+            (self.options, self.nine_patch, self.field, self.low_bits.into_owned());
+        let (checksum, yuv) = (self.checksum, None);
+        Encoder {
+            data,
+            segments,
+            roi,
+            tolerance,
+            channels,
+            format,
+            header,
+            options,
+            nine_patch,
+            field,
+            low_bits,
+            checksum,
+            yuv,
+        }
+    }
+
+    #[inline]
+    fn new_impl(
+        data: PixelData<'a>, segments: Segments<'a>, width: u16, height: u16,
+        format: Option<PixelFormat>,
+    ) -> Result<Self> {
+        let result = Self::new_checked(data, segments, width, height, format);
+        #[cfg(feature = "metrics")]
+        metrics::record_error(&result);
+        result
+    }
+
+    #[inline]
+    fn new_checked(
+        data: PixelData<'a>, segments: Segments<'a>, width: u16, height: u16,
+        format: Option<PixelFormat>,
+    ) -> Result<Self> {
         let header = Header::try_new(width, height, None)?;
-        let size = data.len();
-        let n_channels = size / header.n_pixels();
-        if header.n_pixels() * n_channels != size {
+        let segment_sizes = segments.as_slice().iter().map(|data| data.len());
+        let size = data.as_slice().len() + segment_sizes.sum::<usize>();
+        let channels = match (format, size / header.n_pixels()) {
+            (Some(format), _) => format.channels(),
+            (None, 2) => Channels::La,
+            (None, 3) => Channels::Rgb,
+            (None, 4) => Channels::Rgba,
+            _ => return Err(Error::InvalidImageLength { size, width, height }),
+        };
+        let bpp = format.map_or(channels.as_u8() as usize, PixelFormat::bytes_per_pixel);
+        if header.n_pixels().checked_mul(bpp) != Some(size)
+            || segments.as_slice().iter().any(|data| data.len() % bpp != 0)
+        {
+            return Err(Error::InvalidImageLength { size, width, height });
+        }
+        Ok(Self::from_parts(data, segments, channels, format, header))
+    }
+
+    /// Creates an encoder for a YUV frame, which is encoded as RGB.
+    #[inline]
+    fn new_yuv(yuv: Yuv420<'a>, header: Header) -> Self {
+        let (data, segments) = (PixelData::Borrowed(&[]), Segments::Borrowed(&[]));
+        let mut encoder = Self::from_parts(data, segments, Channels::Rgb, None, header);
+        encoder.yuv = Some(yuv);
+        encoder
+    }
+
+    #[inline]
+    fn from_parts(
+        data: PixelData<'a>, segments: Segments<'a>, channels: Channels,
+        format: Option<PixelFormat>, header: Header,
+    ) -> Self {
+        let (roi, low_bits) = (PixelData::Borrowed(&[]), PixelData::Borrowed(&[]));
+        let (tolerance, options) = (0, EncoderOptions::new());
+        let (nine_patch, field, checksum, yuv) = (None, None, None, None);
+        Self {
+            data,
+            segments,
+            roi,
+            tolerance,
+            channels,
+            format,
+            header,
+            options,
+            nine_patch,
+            field,
+            low_bits,
+            checksum,
+            yuv,
+        }
+    }
+
+    /// Replaces the encoder configuration.
+    #[inline]
+    pub const fn with_options(mut self, options: EncoderOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Enables lossy encoding with a per-pixel tolerance map (region-of-interest quality hints).
+    ///
+    /// The map holds one byte per pixel, in the same order as the pixel data: the maximum
+    /// difference allowed between each of the R, G and B channels of the source pixel and the
+    /// decoded one. Pixels within tolerance of the previous pixel extend its run, and pixels
+    /// within tolerance of a color in the index reuse it. Alpha is always preserved exactly,
+    /// and a tolerance of zero keeps a pixel lossless.
+    ///
+    /// For screen sharing, this allows keeping text regions lossless while flattening video
+    /// regions: fill rectangles of the map with the tolerance of each region. The map may be
+    /// borrowed or owned, see [`AsPixelData`].
+    #[inline]
+    pub fn with_roi(mut self, map: impl AsPixelData<'a>) -> Result<Self> {
+        let roi = map.into_pixel_data();
+        let size = roi.as_slice().len();
+        if unlikely(size != self.header.n_pixels()) {
+            let (width, height) = (self.header.width, self.header.height);
             return Err(Error::InvalidImageLength { size, width, height });
         }
-        Ok(Self { data, header })
+        self.roi = roi;
+        Ok(self)
+    }
+
+    /// Enables lossy encoding with the same tolerance for every pixel, like a
+    /// [`Encoder::with_roi`] map filled with `tolerance`, replacing any map set before.
+    ///
+    /// Noisy camera frames rarely repeat a pixel exactly, so runs and index hits are scarce;
+    /// letting every channel be off by a few units can make them a lot smaller at little visual
+    /// cost. Alpha is always preserved exactly, and a tolerance of zero (the default) keeps
+    /// the image lossless.
+    #[inline]
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        (self.roi, self.tolerance) = (PixelData::Borrowed(&[]), tolerance);
+        self
+    }
+
+    /// Tolerance of lossy encoding for the whole image.
+    #[inline]
+    fn tolerance(&self) -> Tolerance<'_> {
+        match self.roi.as_slice() {
+            [] => Tolerance::Uniform(self.tolerance),
+            map => Tolerance::Map(map),
+        }
+    }
+
+    /// Stores nine-patch metadata in the extension block, for scalable UI assets.
+    ///
+    /// Fails with [`Error::InvalidNinePatch`] if any of the ranges is empty or exceeds the
+    /// image dimensions. The metadata can be read back with
+    /// [`Decoder::nine_patch`](crate::Decoder::nine_patch).
+    #[inline]
+    pub fn with_nine_patch(mut self, nine_patch: NinePatch) -> Result<Self> {
+        nine_patch.check(self.header.width, self.header.height)?;
+        self.nine_patch = Some(nine_patch);
+        Ok(self)
+    }
+
+    /// Declares the color space of the pixels, stored in the extension block (or in the header
+    /// of the [reference](HeaderFormat::Reference) format) and read back by
+    /// [`Decoder::header`](crate::Decoder::header).
+    ///
+    /// This doesn't affect how the pixels are encoded. Reference headers always declare a color
+    /// space, sRGB unless set otherwise.
+    #[inline]
+    pub const fn with_colorspace(mut self, colorspace: ColorSpace) -> Self {
+        self.header.colorspace = Some(colorspace);
+        self
+    }
+
+    /// Stores a checksum of the op stream in the extension block, so that corruption (e.g. in
+    /// transit or on disk) is caught by [`Decoder::new`](crate::Decoder::new) with
+    /// [`Error::ChecksumMismatch`] rather than decoding to garbage.
+    ///
+    /// The checksum covers every op byte up to and including the end marker. Images with a
+    /// [reference](HeaderFormat::Reference) header have no extension block to store it in.
+    ///
+    /// Only decoders reading from a slice verify it. Streaming decoders
+    /// ([`Decoder::from_stream`](crate::Decoder::from_stream), `AsyncDecoder` and
+    /// [`DecodeState`](crate::DecodeState)) hand out pixels before reaching the block, so they
+    /// don't: corruption goes unnoticed there.
+    #[inline]
+    pub const fn with_checksum(mut self, kind: ChecksumKind) -> Self {
+        self.checksum = Some(kind);
+        self
+    }
+
+    /// Sets the matrix converting YUV samples to RGB when encoding a frame created with
+    /// [`Encoder::from_yuv420`] or [`Encoder::from_nv12`]; this has no effect otherwise.
+    #[inline]
+    pub fn with_yuv_matrix(mut self, matrix: YuvMatrix) -> Self {
+        if let Some(yuv) = &mut self.yuv {
+            yuv.set_matrix(matrix);
+        }
+        self
     }
-    
+
+    /// Returns the layout of the pixel data, inferred from its size.
+    #[inline]
+    pub const fn channels(&self) -> Channels {
+        self.channels
+    }
+
     /// Returns the header that will be stored in the encoded image.
     #[inline]
     pub const fn header(&self) -> &Header {
@@ -136,7 +1172,69 @@ impl<'a> Encoder<'a> {
     /// Can be used to pre-allocate the buffer to encode the image into.
     #[inline]
     pub fn required_buf_len(&self) -> usize {
-        self.header.encode_max_len()
+        let header_size = self.options.header_format.size();
+        self.header.encode_max_len() - QOI_HEADER_SIZE + header_size + self.ext_len()
+    }
+
+    /// Whether an extension block follows the op stream (only with GameMaker's header).
+    #[inline]
+    const fn has_ext(&self) -> bool {
+        matches!(self.options.header_format, HeaderFormat::GameMaker)
+    }
+
+    /// Restart interval in effect, as the markers can only be stored in the extension block.
+    #[inline]
+    const fn restart_interval(&self) -> u16 {
+        if self.has_ext() {
+            self.options.restart_interval
+        } else {
+            0
+        }
+    }
+
+    /// Size of the extension block, zero if there's none.
+    #[inline]
+    fn ext_len(&self) -> usize {
+        if !self.has_ext() {
+            return 0;
+        }
+        let (height, interval) = (self.header.height, self.restart_interval());
+        let (nine_patch, low_bits) = (self.nine_patch.as_ref(), self.low_bits.as_slice());
+        let (field, colorspace, checksum) = (self.field, self.header.colorspace, self.checksum);
+        ext_len(height, interval, self.channels, nine_patch, field, colorspace, low_bits, checksum)
+    }
+
+    /// Writes the header in the configured format into `head`, which is exactly as long as
+    /// the header, once the op stream has been written.
+    fn write_header(&mut self, head: &mut [u8], ops_len: usize) -> Result<()> {
+        match self.options.header_format {
+            HeaderFormat::GameMaker => {
+                // the op stream of a 400Mp image is below 2GB, but not with `large-images`
+                let length = u32::try_from(ops_len).map_err(|_| Error::SizeOverflow)?;
+                self.header.length = Some(length);
+                head.copy_from_slice(&self.header.encode()?);
+            }
+            HeaderFormat::Reference => {
+                head.copy_from_slice(&self.header.encode_reference(self.channels));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of bytes per pixel of the pixel data, which differs from the channels when
+    /// encoding from a [`PixelFormat`].
+    #[inline]
+    fn source_bpp(&self) -> usize {
+        self.format.map_or(self.channels.as_u8() as usize, PixelFormat::bytes_per_pixel)
+    }
+
+    /// Number of pixels between restart markers (effectively infinite if disabled).
+    #[inline]
+    const fn band_pixels(&self) -> usize {
+        match self.restart_interval() {
+            0 => usize::MAX,
+            rows => rows as usize * self.header.width as usize,
+        }
     }
 
     /// Encodes the image to a pre-allocated buffer and returns the number of bytes written.
@@ -144,37 +1242,693 @@ impl<'a> Encoder<'a> {
     /// The minimum size of the buffer can be found via [`Encoder::required_buf_len`].
     #[inline]
     pub fn encode_to_buf(&mut self, mut buf: impl AsMut<[u8]>) -> Result<usize> {
-        let buf = buf.as_mut();
+        #[cfg(feature = "tracing")]
+        let span = trace::encode_span("encode_to_buf", &self.header, self.channels).entered();
+        let result = self.encode_to_buf_impl(buf.as_mut(), None);
+        #[cfg(feature = "metrics")]
+        metrics::record_encoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result
+    }
+
+    /// Encodes the image a chunk of pixels at a time into a pre-allocated buffer, e.g. to
+    /// interleave encoding with other work in a game loop or a single-threaded async runtime.
+    ///
+    /// Every step of the returned iterator encodes the next `chunk_pixels` pixels (at least
+    /// one) and yields the op bytes written for them, which may be empty when the pixels only
+    /// extend a run. Then the extension block, if there is one, and finally the header are
+    /// yielded: the header holds the length of the op stream, so it's only filled in at the
+    /// end, in the space set aside for it at the start of `buf`. Once the iterator is
+    /// exhausted, `buf` holds the whole image, whose size is the total length of all parts.
+    ///
+    /// The buffer must be at least [`Encoder::required_buf_len`] bytes long, otherwise the
+    /// first step fails with [`Error::OutputBufferTooSmall`]. Note:
+    /// [`EncoderOptions::verify_output`] doesn't apply here, as the parts are handed out as
+    /// they are encoded.
+    #[inline]
+    pub fn encode_chunks<'b>(
+        &'b mut self, buf: &'b mut [u8], chunk_pixels: usize,
+    ) -> EncodeChunks<'b, 'a> {
+        EncodeChunks::new(self, buf, chunk_pixels)
+    }
+
+    /// Encodes the op stream of the whole image through a given writer.
+    #[inline]
+    fn encode_ops<W: Writer>(&self, buf: W) -> Result<usize> {
+        if self.yuv.is_some() {
+            return self.encode_yuv_ops(buf, self.band_pixels());
+        }
+        let (data, tolerance) = (self.data.as_slice(), self.tolerance());
+        let segments = match self.segments.as_slice() {
+            [] => slice::from_ref(&data),
+            segments => segments,
+        };
+        let (channels, format, options) = (self.channels, self.format, self.options);
+        encode_impl(buf, segments, channels, format, self.band_pixels(), options, false, tolerance)
+    }
+
+    /// Encodes the op stream of a YUV frame including the end marker and returns its size.
+    fn encode_yuv_ops<W: Writer>(&self, buf: W, band_pixels: usize) -> Result<usize> {
+        let cap = buf.capacity();
+        let state = &mut EncodeState::new(band_pixels, false);
+        let buf = self.encode_range(buf, 0, self.header.n_pixels(), state, self.tolerance())?;
+        let buf = state.finish(buf)?;
+        Ok(cap.saturating_sub(buf.capacity()))
+    }
+
+    /// Encodes the pixels `start..end`, continuing from `state`; `tolerance` covers only these
+    /// pixels.
+    fn encode_range<W: Writer>(
+        &self, buf: W, start: usize, end: usize, state: &mut EncodeState, tolerance: Tolerance<'_>,
+    ) -> Result<W> {
+        let bpp = self.source_bpp();
+        let (channels, format, options) = (self.channels, self.format, self.options);
+        if let Some(yuv) = &self.yuv {
+            encode_yuv(buf, yuv, start, end, state, options, tolerance)
+        } else if self.segments.as_slice().is_empty() {
+            let pixels = self.data.as_slice()[start * bpp..end * bpp].chunks_exact(bpp);
+            encode_source(buf, pixels, channels, format, state, options, tolerance)
+        } else {
+            let pixels = segment_pixels(self.segments.as_slice(), bpp, start, end);
+            encode_source(buf, pixels, channels, format, state, options, tolerance)
+        }
+    }
+
+    /// Encodes the image to a buffer, feeding the op stream to `hasher` if there is one.
+    #[inline]
+    fn encode_to_buf_impl(&mut self, buf: &mut [u8], hasher: Option<&mut Sha256>) -> Result<usize> {
         let size_required = self.required_buf_len();
         if unlikely(buf.len() < size_required) {
             return Err(Error::OutputBufferTooSmall { size: buf.len(), required: size_required });
         }
-        let (head, tail) = buf.split_at_mut(QOI_HEADER_SIZE); // can't panic
-        let n_written = encode_impl(BytesMut::new(tail), self.data)?;
-        self.header.length = Some(tail.len() as u32);
-        head.copy_from_slice(&self.header.encode()?);
-        Ok(QOI_HEADER_SIZE + n_written)
+        let tail = &mut buf[self.options.header_format.size()..]; // can't panic
+        let n_written = match hasher {
+            Some(hasher) => self.encode_ops(HashingWriter::new(BytesMut::new(tail), hasher))?,
+            None => self.encode_ops(BytesMut::new(tail))?,
+        };
+        self.finish_buf(buf, n_written)
+    }
+
+    /// Writes the extension block and the header around an op stream of `n_written` bytes
+    /// placed right after the space for the header, then verifies the image if requested.
+    fn finish_buf(&mut self, buf: &mut [u8], n_written: usize) -> Result<usize> {
+        let (head, tail) = buf.split_at_mut(self.options.header_format.size()); // can't panic
+        let (ops, tail) = tail.split_at_mut(n_written); // can't panic
+        let n_ext = if self.has_ext() {
+            let (width, height) = (self.header.width, self.header.height);
+            let interval = self.restart_interval();
+            let (channels, nine_patch) = (self.channels, self.nine_patch.as_ref());
+            let (field, low_bits) = (self.field, self.low_bits.as_slice());
+            let colorspace = self.header.colorspace;
+            let checksum = self.checksum.map(|kind| kind.checksum(ops));
+            write_ext(
+                ops, tail, width, height, interval, channels, nine_patch, field, colorspace,
+                low_bits, checksum,
+            )
+        } else {
+            0
+        };
+        self.write_header(head, n_written)?;
+        let size = head.len() + n_written + n_ext;
+        if self.options.verify_output {
+            self.verify(&buf[..size])?;
+        }
+        Ok(size)
+    }
+
+    /// Checks that a freshly encoded image decodes back to the input.
+    fn verify(&self, encoded: &[u8]) -> Result<()> {
+        let decoder = Decoder::new(encoded).map_err(|_| Error::VerificationFailed)?;
+        let header = decoder.header();
+        // the reference header can't tell luma + alpha images apart
+        let channels = match self.channels {
+            Channels::La if !self.has_ext() => Channels::Rgba,
+            channels => channels,
+        };
+        if (header.width, header.height) != (self.header.width, self.header.height)
+            || decoder.channels() != channels
+        {
+            return Err(Error::VerificationFailed);
+        }
+        let (data, tolerance) = (self.data.as_slice(), self.tolerance());
+        let segments = match self.segments.as_slice() {
+            [] => slice::from_ref(&data),
+            segments => segments,
+        };
+        let bpp = self.source_bpp();
+        let pixels = segments.iter().flat_map(|segment| segment.chunks_exact(bpp));
+        let pixels = pixels.map(|chunk| {
+            let mut px = Pixel::new();
+            match self.format {
+                Some(format) => px = format.to_rgba(chunk).into(),
+                None => px.read(chunk),
+            }
+            px
+        });
+        // a YUV frame has no pixel data, only one of the two is ever non-empty
+        let n_pixels = self.header.n_pixels();
+        let yuv_pixels = self.yuv.iter().flat_map(|yuv| {
+            (0..n_pixels).map(|i| {
+                let [r, g, b] = yuv.pixel(i);
+                Pixel::from([r, g, b, 0xff])
+            })
+        });
+        let markers = decoder.restart_markers();
+        let interval = markers.map_or(0, |markers| markers.interval());
+        if interval != self.restart_interval() {
+            return Err(Error::VerificationFailed);
+        }
+
+        let (body, band_pixels) = (decoder.data(), self.band_pixels());
+        let mut ops = OpDecoder::new(body);
+        let (mut decoded, mut n_left) = (Pixel::new(), 0);
+        for (i, px) in pixels.chain(yuv_pixels).enumerate() {
+            if n_left == 0 {
+                // every band has to start with a fresh op at the offset of its marker
+                if let Some(markers) = markers.as_ref().filter(|_| i % band_pixels == 0) {
+                    let marker = markers.band(i / band_pixels);
+                    if marker.map(|marker| marker.offset) != Some(ops.offset()) {
+                        return Err(Error::VerificationFailed);
+                    }
+                }
+                let op = ops.next_op().map_err(|_| Error::VerificationFailed)?;
+                (decoded, n_left) = (op.px, op.n_pixels);
+            }
+            n_left -= 1;
+            if !decoded.is_close(px.as_rgba(), tolerance.at(i)) {
+                return Err(Error::VerificationFailed);
+            }
+        }
+
+        // without an extension block, the image ends with the op stream
+        let (end, ops_len) = (ops.offset(), header.length.map_or(body.len(), |len| len as usize));
+        let low_bits = ext::low_bits(body, ops_len, header.n_pixels()).unwrap_or_default();
+        let expected_low_bits = if self.has_ext() { self.low_bits.as_slice() } else { &[] };
+        if n_left != 0
+            || end + QOI_PADDING_SIZE != ops_len
+            || body.get(end..ops_len) != Some(&QOI_PADDING[..])
+            || low_bits != expected_low_bits
+        {
+            return Err(Error::VerificationFailed);
+        }
+        Ok(())
     }
 
     /// Encodes the image into a newly allocated vector of bytes and returns it.
     #[cfg(any(feature = "alloc", feature = "std"))]
     #[inline]
     pub fn encode_to_vec(&mut self) -> Result<Vec<u8>> {
-        let mut out = vec![0_u8; self.required_buf_len()];
+        let mut out = try_vec_zeroed(self.required_buf_len())?;
         let size = self.encode_to_buf(&mut out)?;
         out.truncate(size);
         Ok(out)
     }
 
+    /// Encodes the image as a bare op stream (ending with the usual end marker) into a
+    /// pre-allocated buffer and returns the number of bytes written.
+    ///
+    /// Neither the header nor the extension block are written, for protocols carrying the
+    /// dimensions out-of-band, e.g. fixed camera streams; such images are decoded with
+    /// [`Decoder::new_headerless`]. Everything only stored in the header or the extension
+    /// block (restart markers, channels, color space, ...) is dropped. The buffer must be at
+    /// least [`Encoder::required_buf_len`] minus [`HeaderFormat::size`] bytes long.
+    #[inline]
+    pub fn encode_headerless_to_buf(&mut self, mut buf: impl AsMut<[u8]>) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span =
+            trace::encode_span("encode_headerless_to_buf", &self.header, self.channels).entered();
+        let result = self.encode_headerless_impl(buf.as_mut());
+        #[cfg(feature = "metrics")]
+        metrics::record_encoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result
+    }
+
+    #[inline]
+    fn encode_headerless_impl(&self, buf: &mut [u8]) -> Result<usize> {
+        let required = self.header.encode_max_len() - QOI_HEADER_SIZE;
+        if unlikely(buf.len() < required) {
+            return Err(Error::OutputBufferTooSmall { size: buf.len(), required });
+        }
+        // no restart markers, there's nowhere to store them
+        if self.yuv.is_some() {
+            return self.encode_yuv_ops(BytesMut::new(buf), usize::MAX);
+        }
+        let (data, tolerance) = (self.data.as_slice(), self.tolerance());
+        let segments = match self.segments.as_slice() {
+            [] => slice::from_ref(&data),
+            segments => segments,
+        };
+        let (buf, options) = (BytesMut::new(buf), self.options);
+        let (channels, format) = (self.channels, self.format);
+        encode_impl(buf, segments, channels, format, usize::MAX, options, false, tolerance)
+    }
+
+    /// Encodes the image as a bare op stream into a newly allocated vector of bytes and
+    /// returns it, see [`Encoder::encode_headerless_to_buf`].
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    #[inline]
+    pub fn encode_headerless_to_vec(&mut self) -> Result<Vec<u8>> {
+        let mut out = try_vec_zeroed(self.header.encode_max_len() - QOI_HEADER_SIZE)?;
+        let size = self.encode_headerless_to_buf(&mut out)?;
+        out.truncate(size);
+        Ok(out)
+    }
+
+    /// Encodes the image into a newly allocated vector of bytes and returns it along with its
+    /// [`ContentId`].
+    ///
+    /// The id is computed from the op stream as it is being written, without a second pass over
+    /// the output.
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    #[inline]
+    pub fn encode_to_vec_with_id(&mut self) -> Result<(Vec<u8>, ContentId)> {
+        let mut out = try_vec_zeroed(self.required_buf_len())?;
+        let mut hasher = Sha256::new();
+        hash_prefix(&mut hasher, &self.header, self.channels);
+        #[cfg(feature = "tracing")]
+        let span =
+            trace::encode_span("encode_to_vec_with_id", &self.header, self.channels).entered();
+        let result = self.encode_to_buf_impl(&mut out, Some(&mut hasher));
+        #[cfg(feature = "metrics")]
+        metrics::record_encoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        out.truncate(result?);
+        hasher.update(self.low_bits.as_slice());
+        Ok((out, ContentId::from_digest(hasher.finalize())))
+    }
+
+    /// Encodes the image into a newly allocated vector of bytes on several threads.
+    ///
+    /// The image is split into horizontal strips of whole restart bands, one per thread of the
+    /// current rayon thread pool, which are encoded in parallel and then concatenated; as every
+    /// band starts from a fresh encoder state, the result is a regular image, just like with
+    /// [`EncoderOptions::restart_interval`]. If no restart interval is set, one is picked so
+    /// that each strip is a single band, which lets decoders split the work the same way (with
+    /// the reference header, which has no room for restart markers, the strips are simply
+    /// encoded back to back). Compression suffers a little at every band boundary.
+    ///
+    /// The number of strips, and with it the output, depends on the number of threads: call it
+    /// inside [`rayon::ThreadPool::install`] to use a pool of a given size. Images too small to
+    /// be worth splitting are encoded on the calling thread.
+    #[cfg(feature = "rayon")]
+    pub fn encode_to_vec_parallel(&mut self) -> Result<Vec<u8>> {
+        let n_threads = rayon::current_num_threads();
+        let n_strips = n_threads.min(self.header.n_pixels() / MIN_STRIP_PIXELS);
+        if n_strips <= 1 {
+            return self.encode_to_vec();
+        }
+        #[cfg(feature = "tracing")]
+        let span =
+            trace::encode_span("encode_to_vec_parallel", &self.header, self.channels).entered();
+        let options = self.options;
+        let height = self.header.height as usize;
+        let strip_rows = match self.restart_interval() {
+            0 => {
+                let strip_rows = (height + n_strips - 1) / n_strips;
+                if self.has_ext() {
+                    // can't truncate: the strips are at most as high as the image
+                    #[allow(clippy::cast_possible_truncation)]
+                    let interval = strip_rows as u16;
+                    self.options.restart_interval = interval;
+                }
+                strip_rows
+            }
+            interval => {
+                let n_bands = ext::n_bands(self.header.height, interval);
+                interval as usize * ((n_bands + n_strips - 1) / n_strips)
+            }
+        };
+        let mut out = Vec::new();
+        let result = try_vec_zeroed(self.required_buf_len()).and_then(|buf| {
+            out = buf;
+            self.encode_strips(&mut out, strip_rows)
+        });
+        self.options = options;
+        #[cfg(feature = "metrics")]
+        metrics::record_encoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        out.truncate(result?);
+        Ok(out)
+    }
+
+    /// Encodes strips of `strip_rows` rows on the rayon thread pool into `buf`, see
+    /// [`Encoder::encode_to_vec_parallel`].
+    #[cfg(feature = "rayon")]
+    fn encode_strips(&mut self, buf: &mut [u8], strip_rows: usize) -> Result<usize> {
+        let (width, n_pixels) = (self.header.width as usize, self.header.n_pixels());
+        let strip_pixels = strip_rows * width;
+        let encoder = &*self;
+        let strips: Vec<Result<Vec<u8>>> = (0..n_pixels)
+            .into_par_iter()
+            .step_by(strip_pixels)
+            .map(|start| encoder.encode_strip(start, n_pixels.min(start + strip_pixels)))
+            .collect();
+
+        let ops = &mut buf[self.options.header_format.size()..]; // can't panic
+        let mut n_written = 0;
+        for strip in strips {
+            let strip = strip?;
+            ops[n_written..n_written + strip.len()].copy_from_slice(&strip);
+            n_written += strip.len();
+        }
+        self.finish_buf(buf, n_written)
+    }
+
+    /// Encodes the pixels `start..end` as a strip starting with a restart, returns its ops
+    /// (followed by the end marker only for the last strip).
+    #[cfg(feature = "rayon")]
+    fn encode_strip(&self, start: usize, end: usize) -> Result<Vec<u8>> {
+        let n_pixels = self.header.n_pixels();
+        // can't truncate: the strip is at most as high as the image
+        #[allow(clippy::cast_possible_truncation)]
+        let n_rows = ((end - start) / self.header.width as usize) as u16;
+        let mut out = try_vec_zeroed(encode_max_len(self.header.width, n_rows))?;
+        let tolerance = self.tolerance().range(start, end);
+        let buf = BytesMut::new(&mut out);
+        let cap = buf.capacity();
+        let state = &mut EncodeState::new(self.band_pixels(), start != 0);
+        let buf = self.encode_range(buf, start, end, state, tolerance)?;
+        let n_written = cap - state.finish(buf)?.capacity();
+        out.truncate(if end == n_pixels { n_written } else { n_written - QOI_PADDING_SIZE });
+        Ok(out)
+    }
+
+    /// Encodes the image to a pre-allocated buffer like [`Encoder::encode_to_buf`], then applies
+    /// a [`StreamTransform`] to everything following the header in place.
+    #[inline]
+    pub fn encode_to_buf_transformed(
+        &mut self, mut buf: impl AsMut<[u8]>, mut transform: impl StreamTransform,
+    ) -> Result<usize> {
+        let buf = buf.as_mut();
+        let size = self.encode_to_buf(&mut *buf)?;
+        transform.encode(0, &mut buf[self.options.header_format.size()..size]);
+        Ok(size)
+    }
+
+    /// Encodes the image into a newly allocated vector of bytes, then applies a
+    /// [`StreamTransform`] to everything following the header in place.
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    #[inline]
+    pub fn encode_to_vec_transformed(
+        &mut self, mut transform: impl StreamTransform,
+    ) -> Result<Vec<u8>> {
+        let mut out = self.encode_to_vec()?;
+        transform.encode(0, &mut out[self.options.header_format.size()..]);
+        Ok(out)
+    }
+
     /// Encodes the image directly to a generic writer that implements [`Write`](Write).
     ///
+    /// GameMaker headers hold the length of the op stream, which is followed by the extension
+    /// block, so such images are encoded into memory first and then written in one go; only
+    /// images with a [reference header](HeaderFormat::Reference) are streamed as they are
+    /// encoded. The writer isn't flushed.
+    ///
     /// Note: while it's possible to pass a `&mut [u8]` slice here since it implements `Write`,
     /// it would more effficient to use a specialized method instead: [`Encoder::encode_to_buf`].
     #[cfg(feature = "std")]
     #[inline]
-    pub fn encode_to_stream<W: Write>(&self, writer: &mut W) -> Result<usize> {
-        writer.write_all(&self.header.encode()?)?;
-        let n_written = encode_impl(GenericWriter::new(writer), self.data)?;
-        Ok(n_written + QOI_HEADER_SIZE)
+    pub fn encode_to_stream<W: Write>(&mut self, writer: &mut W) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span = trace::encode_span("encode_to_stream", &self.header, self.channels).entered();
+        let result = self.encode_to_stream_impl(writer);
+        #[cfg(feature = "metrics")]
+        metrics::record_encoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result
+    }
+
+    #[cfg(feature = "std")]
+    fn encode_to_stream_impl<W: Write>(&mut self, writer: &mut W) -> Result<usize> {
+        match self.options.header_format {
+            HeaderFormat::GameMaker => {
+                let mut out = try_vec_zeroed(self.required_buf_len())?;
+                let size = self.encode_to_buf_impl(&mut out, None)?;
+                writer.write_all(&out[..size])?;
+                Ok(size)
+            }
+            HeaderFormat::Reference => {
+                writer.write_all(&self.header.encode_reference(self.channels))?;
+                let n_written = self.encode_ops(GenericWriter::new(writer))?;
+                Ok(n_written + self.options.header_format.size())
+            }
+        }
+    }
+
+    /// Encodes the image straight into a file and returns the number of bytes written.
+    ///
+    /// The op stream goes through a buffered writer and the header is filled in last, once
+    /// the length is known, so even giant images never need an in-memory output buffer.
+    /// (With the `mmap` feature, `Encoder::encode_to_file_mmap` encodes into a mapped file.)
+    ///
+    /// Note: with restart markers enabled, the image is still encoded in memory first, since
+    /// the band offsets are recovered from the op stream; the same goes for
+    /// [`EncoderOptions::verify_output`], which needs to read the image back, and for
+    /// [`Encoder::with_checksum`], which needs the whole op stream.
+    #[cfg(feature = "std")]
+    pub fn encode_to_file(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        #[cfg(feature = "tracing")]
+        let span = trace::encode_span("encode_to_file", &self.header, self.channels).entered();
+        let result = self.encode_to_file_impl(path.as_ref());
+        #[cfg(feature = "metrics")]
+        metrics::record_encoded(&result);
+        #[cfg(feature = "tracing")]
+        trace::record(&span, &result);
+        result
+    }
+
+    #[cfg(feature = "std")]
+    fn encode_to_file_impl(&mut self, path: &Path) -> Result<usize> {
+        let mut file = File::create(path)?;
+        if self.restart_interval() != 0
+            || (self.has_ext() && !self.low_bits.as_slice().is_empty())
+            || (self.has_ext() && self.checksum.is_some())
+            || self.options.verify_output
+        {
+            let mut out = try_vec_zeroed(self.required_buf_len())?;
+            let size = self.encode_to_buf_impl(&mut out, None)?;
+            file.write_all(&out[..size])?;
+            return Ok(size);
+        }
+        self.encode_to_file_streamed(file)
+    }
+
+    #[cfg(feature = "std")]
+    fn encode_to_file_streamed(&mut self, file: File) -> Result<usize> {
+        // without restart markers, low bits and checksums, the extension block has a bounded
+        // size and doesn't depend on the op stream
+        const MAX_EXT_LEN: usize = ext_len(
+            u16::MAX,
+            0,
+            Channels::La,
+            Some(&NinePatch::new((0, 0), (0, 0))),
+            Some(Field::Top),
+            Some(ColorSpace::Linear),
+            &[],
+            None,
+        );
+        let mut writer = BufWriter::new(file);
+        let mut head = [0; QOI_REFERENCE_HEADER_SIZE];
+        let head = &mut head[..self.options.header_format.size()];
+        writer.write_all(head)?;
+        let n_written = self.encode_ops(GenericWriter::new(&mut writer))?;
+        let (width, height) = (self.header.width, self.header.height);
+        let mut ext = [0; MAX_EXT_LEN];
+        let (nine_patch, field) = (self.nine_patch.as_ref(), self.field);
+        let (channels, colorspace) = (self.channels, self.header.colorspace);
+        let n_ext = if self.has_ext() {
+            write_ext(
+                &[],
+                &mut ext,
+                width,
+                height,
+                0,
+                channels,
+                nine_patch,
+                field,
+                colorspace,
+                &[],
+                None,
+            )
+        } else {
+            0
+        };
+        writer.write_all(&ext[..n_ext])?;
+        self.write_header(head, n_written)?;
+        let mut file = writer.into_inner().map_err(IntoInnerError::into_error)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(head)?;
+        Ok(head.len() + n_written + n_ext)
+    }
+}
+
+/// Where [`EncodeChunks`] is at.
+enum ChunkStep {
+    Pixels,
+    Ext,
+    Header,
+    Done,
+}
+
+/// Iterator over the parts of an image encoded a chunk of pixels at a time, see
+/// [`Encoder::encode_chunks`].
+pub struct EncodeChunks<'b, 'a> {
+    encoder: &'b mut Encoder<'a>,
+    head: &'b mut [u8],
+    // the part of the buffer following what has been yielded; the last `n_ext` bytes of the
+    // required length collect the extension block until the op stream is complete
+    rest: &'b mut [u8],
+    state: EncodeState,
+    band_starts: BandStarts,
+    n_offsets_found: usize,
+    chunk_pixels: usize,
+    n_encoded: usize,
+    ops_len: usize,
+    // running checksum of the op stream yielded so far
+    hasher: Option<Hasher>,
+    n_ext: usize,
+    step: ChunkStep,
+    error: Option<Error>,
+}
+
+impl<'b, 'a> EncodeChunks<'b, 'a> {
+    fn new(encoder: &'b mut Encoder<'a>, buf: &'b mut [u8], chunk_pixels: usize) -> Self {
+        let (width, height) = (encoder.header.width, encoder.header.height);
+        let (interval, n_ext) = (encoder.restart_interval(), encoder.ext_len());
+        let required = encoder.required_buf_len();
+        let error = (buf.len() < required)
+            .then_some(Error::OutputBufferTooSmall { size: buf.len(), required });
+        let header_size = encoder.options.header_format.size();
+        let (head, rest) = buf.split_at_mut(header_size.min(buf.len()));
+        let rest_len = required.saturating_sub(header_size).min(rest.len());
+        let hasher = encoder.checksum.map(Hasher::new);
+        Self {
+            state: EncodeState::new(encoder.band_pixels(), false),
+            band_starts: BandStarts::new(width, height, interval),
+            encoder,
+            head,
+            rest: &mut rest[..rest_len],
+            n_offsets_found: 0,
+            chunk_pixels: chunk_pixels.max(1),
+            n_encoded: 0,
+            ops_len: 0,
+            hasher,
+            n_ext,
+            step: ChunkStep::Pixels,
+            error,
+        }
+    }
+
+    /// Splits off the first `n` bytes of the rest of the buffer to be yielded.
+    #[inline]
+    fn take(&mut self, n: usize) -> &'b [u8] {
+        let (part, rest) = mem::take(&mut self.rest).split_at_mut(n);
+        self.rest = rest;
+        part
+    }
+
+    /// Encodes the next chunk of pixels and returns the number of bytes written.
+    fn encode_chunk(&mut self) -> Result<usize> {
+        let encoder = &*self.encoder;
+        let n_pixels = encoder.header.n_pixels();
+        let (start, end) = (self.n_encoded, n_pixels.min(self.n_encoded + self.chunk_pixels));
+        let tolerance = encoder.tolerance().range(start, end);
+        let ops_area = self.rest.len() - self.n_ext;
+        let buf = BytesMut::new(&mut self.rest[..ops_area]);
+        let cap = buf.capacity();
+        let state = &mut self.state;
+        let buf = encoder.encode_range(buf, start, end, state, tolerance)?;
+        let buf = if end == n_pixels { state.finish(buf)? } else { buf };
+        let n_written = cap - buf.capacity();
+        self.n_encoded = end;
+
+        // record the band starts in the restart record collected at the end of the buffer
+        let (ops, tail) = self.rest.split_at_mut(n_written);
+        let slots_start = tail.len() - 4 * (self.band_starts.n_offsets() - self.n_offsets_found);
+        let mut slots = tail[slots_start..].chunks_exact_mut(4);
+        let n_found = &mut self.n_offsets_found;
+        self.band_starts.feed(ops, |pos| {
+            if let Some(slot) = slots.next() {
+                // can't truncate: the op stream length is checked before writing the header
+                #[allow(clippy::cast_possible_truncation)]
+                slot.copy_from_slice(&(pos as u32).to_le_bytes());
+                *n_found += 1;
+            }
+        });
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(ops);
+        }
+        self.ops_len += n_written;
+        Ok(n_written)
+    }
+}
+
+impl<'b> Iterator for EncodeChunks<'b, '_> {
+    type Item = Result<&'b [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            self.step = ChunkStep::Done;
+            return Some(Err(err));
+        }
+        match self.step {
+            ChunkStep::Pixels => {
+                let result = self.encode_chunk();
+                if result.is_err() {
+                    self.step = ChunkStep::Done;
+                } else if self.n_encoded == self.encoder.header.n_pixels() {
+                    self.step = ChunkStep::Ext;
+                }
+                Some(result.map(|n_written| self.take(n_written)))
+            }
+            ChunkStep::Ext => {
+                self.step = ChunkStep::Header;
+                if self.n_ext == 0 {
+                    return self.next();
+                }
+                let encoder = &*self.encoder;
+                let (width, height) = (encoder.header.width, encoder.header.height);
+                let (interval, channels) = (encoder.restart_interval(), encoder.channels);
+                let (nine_patch, field, low_bits) =
+                    (encoder.nine_patch.as_ref(), encoder.field, encoder.low_bits.as_slice());
+                let colorspace = encoder.header.colorspace;
+                let checksum = self.hasher.as_ref().map(Hasher::finish);
+                // the band offsets are in place already, this fills in the rest of the block
+                let ext_start = self.rest.len() - self.n_ext;
+                let out = &mut self.rest[ext_start..];
+                write_ext(
+                    &[],
+                    out,
+                    width,
+                    height,
+                    interval,
+                    channels,
+                    nine_patch,
+                    field,
+                    colorspace,
+                    low_bits,
+                    checksum,
+                );
+                self.rest.copy_within(ext_start.., 0);
+                Some(Ok(self.take(self.n_ext)))
+            }
+            ChunkStep::Header => {
+                self.step = ChunkStep::Done;
+                let result = self
+                    .encoder
+                    .write_header(self.head, self.ops_len)
+                    .map(|()| self.head.len() + self.ops_len + self.n_ext);
+                #[cfg(feature = "metrics")]
+                metrics::record_encoded(&result);
+                Some(result.map(|_| &*mem::take(&mut self.head)))
+            }
+            ChunkStep::Done => None,
+        }
     }
 }
@@ -1,14 +1,19 @@
 use core::convert::Infallible;
 use core::fmt::{self, Display};
 
-use crate::consts::QOI_MAGIC;
+#[cfg(not(feature = "compact-errors"))]
+use crate::consts::{QOI_MAGIC, QOI_MEDIA_TYPE};
 
 /// Errors that can occur during encoding or decoding.
+///
+/// New variants may be added in minor releases, so matches on it need a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// Leading 4 magic bytes don't match when decoding
     InvalidMagic { magic: u32 },
     /// Invalid image dimensions: can't be empty or have a width/height larger than 65535
+    /// (dimensions given as wider integers are reported as 65535 if they don't fit)
     InvalidImageDimensions { width: u16, height: u16 },
     /// Image dimensions are inconsistent with image buffer length
     InvalidImageLength { size: usize, width: u16, height: u16 },
@@ -16,10 +21,52 @@ pub enum Error {
     DataLengthNotSet,
     /// Output buffer is too small to fit encoded/decoded image
     OutputBufferTooSmall { size: usize, required: usize },
-    /// Input buffer ended unexpectedly before decoding was finished
-    UnexpectedBufferEnd,
-    /// Invalid stream end marker encountered when decoding
-    InvalidPadding,
+    /// Input buffer ended unexpectedly before decoding was finished, see [`Error::at_offset`]
+    UnexpectedBufferEnd { offset: usize, pixel: usize },
+    /// Invalid stream end marker encountered when decoding, see [`Error::at_offset`]
+    InvalidPadding { offset: usize, pixel: usize },
+    /// Restart marker is missing or points outside of the op stream
+    InvalidRestartMarker,
+    /// Image dimensions or input size exceed the decoding limits
+    LimitsExceeded,
+    /// Memory allocation for the output or an intermediate buffer failed
+    OutOfMemory,
+    /// Image has been written by a newer version of the format, or uses features this version
+    /// of the crate doesn't know about
+    UnsupportedVersion { version: u8, flags: u32 },
+    /// Image has more distinct colors than allowed by
+    /// [`DecoderOptions::max_unique_colors`](crate::DecoderOptions::max_unique_colors)
+    TooManyColors { limit: usize },
+    /// Nine-patch ranges are empty or exceed the image dimensions
+    InvalidNinePatch,
+    /// Encoded image doesn't decode back to the input, see
+    /// [`EncoderOptions::verify_output`](crate::EncoderOptions::verify_output)
+    VerificationFailed,
+    /// String isn't a base64 `data:` URI of an image in this format
+    InvalidDataUri,
+    /// Invalid number of channels in a reference header: expected 3 or 4
+    InvalidChannels { channels: u8 },
+    /// Invalid color space in a reference header: expected 0 or 1
+    InvalidColorSpace { colorspace: u8 },
+    /// Sizes derived from the image dimensions (e.g. the maximum encoded size) don't fit in
+    /// `usize` on this target, or the op stream is too long for the length field of a
+    /// GameMaker header
+    SizeOverflow,
+    /// Images can't be woven into an interlaced frame: different widths, heights that don't
+    /// add up to a frame, or fields tagged the other way around
+    FieldMismatch,
+    /// Op stream doesn't match the checksum stored along with it, see
+    /// [`Encoder::with_checksum`](crate::Encoder::with_checksum)
+    ChecksumMismatch,
+    /// Ops don't cover exactly `width * height` pixels, see
+    /// [`DecoderOptions::strict`](crate::DecoderOptions::strict)
+    PixelCountMismatch { expected: usize, actual: usize },
+    /// End marker isn't where the length declared in the header says the op stream ends, see
+    /// [`DecoderOptions::strict`](crate::DecoderOptions::strict)
+    LengthMismatch { declared: usize, actual: usize },
+    /// Bytes other than the extension block follow the op stream, see
+    /// [`DecoderOptions::strict`](crate::DecoderOptions::strict)
+    TrailingData { len: usize },
     #[cfg(feature = "std")]
     /// Generic I/O error from the wrapped reader/writer
     IoError(std::io::Error),
@@ -28,6 +75,82 @@ pub enum Error {
 /// Alias for [`Result`](std::result::Result) with the error type of [`Error`].
 pub type Result<T> = core::result::Result<T, Error>;
 
+impl Error {
+    /// Short static description of the error, without any of the details.
+    ///
+    /// Unlike [`Display`], this doesn't pull in any formatting code.
+    pub const fn as_str(&self) -> &'static str {
+        match *self {
+            Self::InvalidMagic { .. } => "invalid magic",
+            Self::InvalidImageDimensions { .. } => "invalid image dimensions",
+            Self::InvalidImageLength { .. } => "invalid image length",
+            Self::DataLengthNotSet => "header data length not set",
+            Self::OutputBufferTooSmall { .. } => "output buffer size too small",
+            Self::UnexpectedBufferEnd { .. } => "unexpected input buffer end while decoding",
+            Self::InvalidPadding { .. } => "invalid padding (stream end marker mismatch)",
+            Self::InvalidRestartMarker => "missing or invalid restart marker",
+            Self::LimitsExceeded => "image exceeds decoding limits",
+            Self::OutOfMemory => "out of memory",
+            Self::UnsupportedVersion { .. } => "unsupported format version or flags",
+            Self::TooManyColors { .. } => "too many distinct colors",
+            Self::InvalidNinePatch => "invalid nine-patch ranges",
+            Self::VerificationFailed => "encoded image doesn't match the input",
+            Self::InvalidDataUri => "invalid data URI",
+            Self::InvalidChannels { .. } => "invalid number of channels",
+            Self::InvalidColorSpace { .. } => "invalid color space",
+            Self::SizeOverflow => "image size overflows usize",
+            Self::FieldMismatch => "fields don't make up a frame",
+            Self::ChecksumMismatch => "checksum mismatch",
+            Self::PixelCountMismatch { .. } => "ops don't match the number of pixels",
+            Self::LengthMismatch { .. } => "op stream doesn't match its declared length",
+            Self::TrailingData { .. } => "trailing data after the image",
+            #[cfg(feature = "std")]
+            Self::IoError(_) => "i/o error",
+        }
+    }
+
+    /// Returns where in the op stream decoding failed: the byte offset of the op that couldn't
+    /// be decoded (or of the end marker), relative to the start of the op stream like
+    /// [`Op::offset`](crate::ops::Op::offset), and the index of the first pixel that couldn't be
+    /// decoded (the number of pixels for a bad end marker).
+    ///
+    /// This is set for [`Error::UnexpectedBufferEnd`] and [`Error::InvalidPadding`]; both values
+    /// are zero when the input ends before the op stream, e.g. in the header. Decoding from a
+    /// stream reports truncated input as an `UnexpectedEof` I/O error instead.
+    #[inline]
+    pub const fn at_offset(&self) -> Option<(usize, usize)> {
+        match *self {
+            Self::UnexpectedBufferEnd { offset, pixel }
+            | Self::InvalidPadding { offset, pixel } => Some((offset, pixel)),
+            _ => None,
+        }
+    }
+
+    /// Shifts the location of an error reported by decoding a part of the op stream that starts
+    /// `offset` bytes and `pixel` pixels into the image.
+    #[inline]
+    pub(crate) fn offset_by(self, offset: usize, pixel: usize) -> Self {
+        match self {
+            Self::UnexpectedBufferEnd { offset: o, pixel: p } => {
+                Self::UnexpectedBufferEnd { offset: o + offset, pixel: p + pixel }
+            }
+            Self::InvalidPadding { offset: o, pixel: p } => {
+                Self::InvalidPadding { offset: o + offset, pixel: p + pixel }
+            }
+            err => err,
+        }
+    }
+}
+
+#[cfg(feature = "compact-errors")]
+impl Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(not(feature = "compact-errors"))]
 impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -46,11 +169,60 @@ impl Display for Error {
             Self::OutputBufferTooSmall { size, required } => {
                 write!(f, "output buffer size too small: {size} (required: {required})")
             }
-            Self::UnexpectedBufferEnd => {
-                write!(f, "unexpected input buffer end while decoding")
+            Self::UnexpectedBufferEnd { offset, pixel } => {
+                write!(f, "unexpected input buffer end at op offset {offset}, pixel {pixel}")
+            }
+            Self::InvalidPadding { offset, pixel } => {
+                let msg = "invalid padding (stream end marker mismatch)";
+                write!(f, "{msg} at op offset {offset}, after {pixel} pixels")
+            }
+            Self::InvalidRestartMarker => {
+                write!(f, "missing or invalid restart marker")
+            }
+            Self::LimitsExceeded => {
+                write!(f, "image exceeds decoding limits")
+            }
+            Self::OutOfMemory => {
+                write!(f, "out of memory")
+            }
+            Self::UnsupportedVersion { version, flags } => {
+                write!(f, "unsupported format version {version} or flags {flags:#010x}")
+            }
+            Self::TooManyColors { limit } => {
+                write!(f, "too many distinct colors (limit: {limit})")
+            }
+            Self::InvalidNinePatch => {
+                write!(f, "invalid nine-patch ranges")
+            }
+            Self::VerificationFailed => {
+                write!(f, "encoded image doesn't decode back to the input")
+            }
+            Self::InvalidDataUri => {
+                write!(f, "invalid data URI (expected base64 data of type {QOI_MEDIA_TYPE})")
+            }
+            Self::InvalidChannels { channels } => {
+                write!(f, "invalid number of channels: {channels}")
+            }
+            Self::InvalidColorSpace { colorspace } => {
+                write!(f, "invalid color space: {colorspace} (expected 0 or 1)")
+            }
+            Self::SizeOverflow => {
+                write!(f, "image size overflows usize on this target")
+            }
+            Self::FieldMismatch => {
+                write!(f, "fields don't make up an interlaced frame")
+            }
+            Self::ChecksumMismatch => {
+                write!(f, "op stream doesn't match its checksum")
+            }
+            Self::PixelCountMismatch { expected, actual } => {
+                write!(f, "ops cover {actual} pixels instead of {expected}")
+            }
+            Self::LengthMismatch { declared, actual } => {
+                write!(f, "op stream is {actual} bytes long but declared as {declared}")
             }
-            Self::InvalidPadding => {
-                write!(f, "invalid padding (stream end marker mismatch)")
+            Self::TrailingData { len } => {
+                write!(f, "{len} bytes of trailing data after the image")
             }
             #[cfg(feature = "std")]
             Self::IoError(ref err) => {
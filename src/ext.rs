@@ -0,0 +1,455 @@
+//! Optional extension block stored right after the end marker of the op stream.
+//!
+//! Layout (all integers are little-endian, like the header):
+//! * `"xioq"` magic (byte-swapped like the header magic) and a `u32` byte length of all records;
+//! * a sequence of records, each being a `u8` tag, a `u32` payload length and the payload.
+//!
+//! The header `length` field only covers the op stream, so decoders that don't know about
+//! extensions stop at the end marker and never look at this block.
+//!
+//! The 12 bytes of the GameMaker header are all taken by the magic, the dimensions and the
+//! length, and GameMaker itself reads them, so there's no room for a version byte there.
+//! Instead, the format version and feature flags are stored in a version record, which is
+//! written first whenever the block is; images without a block are version 1 with no flags.
+//!
+//! The block comes after the op stream, out of reach of [`Header::decode`](crate::Header::decode),
+//! which only sees the header bytes. Records are therefore read here, from the whole image,
+//! by the decoder; records with unknown tags are skipped using their payload length.
+
+use crate::checksum::{Checksum, ChecksumKind};
+use crate::consts::{
+    QOI_EXT_HEADER_SIZE, QOI_EXT_MAGIC, QOI_EXT_RECORD_HEADER_SIZE, QOI_EXT_TAG_CHANNELS,
+    QOI_EXT_TAG_CHECKSUM, QOI_EXT_TAG_COLORSPACE, QOI_EXT_TAG_FIELD, QOI_EXT_TAG_LOW_BITS,
+    QOI_EXT_TAG_NINE_PATCH, QOI_EXT_TAG_RESTART, QOI_EXT_TAG_VERSION, QOI_EXT_VERSION,
+};
+use crate::field::Field;
+use crate::header::{Channels, ColorSpace};
+use crate::nine_patch::NinePatch;
+use crate::ops::OpKind;
+use crate::rgb10a2;
+use crate::utils::BytesMut;
+
+/// Format version and feature flags read from the extension block of an image, see
+/// [`Decoder::version`](crate::Decoder::version).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FormatVersion {
+    /// Format version the image was written with
+    pub version: u8,
+    /// Feature flags; the high 16 bits mark features a decoder must support to read the image
+    pub flags: u32,
+}
+
+impl FormatVersion {
+    /// Version of images without a version record, which predate it.
+    pub const INITIAL: Self = Self { version: 1, flags: 0 };
+}
+
+/// Restart markers read from the extension block of an image.
+///
+/// Each marker delimits a band of rows that can be decoded independently of the rest of the
+/// image, see [`EncoderOptions::restart_interval`](crate::EncoderOptions::restart_interval).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RestartMarkers<'a> {
+    interval: u16,
+    height: u16,
+    offsets: &'a [u8],
+}
+
+/// Start of an independently decodable band of rows.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RestartMarker {
+    /// First row of the band
+    pub row: u16,
+    /// Number of rows in the band
+    pub n_rows: u16,
+    /// Offset of the first op of the band, relative to the start of the op stream
+    pub offset: usize,
+}
+
+impl<'a> RestartMarkers<'a> {
+    /// Parses the restart record payload, returns `None` if it's malformed.
+    fn parse(payload: &'a [u8], height: u16) -> Option<Self> {
+        let (interval, offsets) = match payload {
+            [a, b, offsets @ ..] => (u16::from_le_bytes([*a, *b]), offsets),
+            _ => return None,
+        };
+        if interval == 0 || payload.len() != restart_payload_len(n_bands(height, interval)) {
+            return None;
+        }
+        Some(Self { interval, height, offsets })
+    }
+
+    /// Number of rows between two consecutive markers.
+    #[inline]
+    pub const fn interval(&self) -> u16 {
+        self.interval
+    }
+
+    /// Number of bands (the first band always starts at the beginning of the op stream).
+    #[inline]
+    pub const fn n_bands(&self) -> usize {
+        self.offsets.len() / 4 + 1
+    }
+
+    /// Returns the marker at the start of a given band.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn band(&self, band: usize) -> Option<RestartMarker> {
+        let offset = if band == 0 {
+            0
+        } else {
+            let b = self.offsets.get((band - 1) * 4..band * 4)?;
+            u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize
+        };
+        // can't truncate: band < n_bands, so the row is below the image height
+        let row = (band * self.interval as usize) as u16;
+        let n_rows = self.interval.min(self.height - row);
+        Some(RestartMarker { row, n_rows, offset })
+    }
+
+    /// Iterates over all markers, including the implicit one for the first band.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = RestartMarker> + 'a {
+        let markers = *self;
+        (0..self.n_bands()).filter_map(move |band| markers.band(band))
+    }
+}
+
+/// Locates the extension block following an op stream of a given length.
+fn find_records(data: &[u8], ops_len: usize) -> Option<&[u8]> {
+    let block = data.get(ops_len..)?;
+    let (magic, rest) = (block.get(..4)?, block.get(4..)?);
+    if magic != QOI_EXT_MAGIC.to_le_bytes() {
+        return None;
+    }
+    let len = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+    rest.get(4..)?.get(..len)
+}
+
+/// Size of the extension block following an op stream of a given length, zero if there's none.
+pub fn block_len(data: &[u8], ops_len: usize) -> usize {
+    find_records(data, ops_len).map_or(0, |records| QOI_EXT_HEADER_SIZE + records.len())
+}
+
+/// Looks up the payload of the first record with a given tag.
+fn find_record(mut records: &[u8], tag: u8) -> Option<&[u8]> {
+    while let [record_tag, a, b, c, d, rest @ ..] = records {
+        let len = u32::from_le_bytes([*a, *b, *c, *d]) as usize;
+        let payload = rest.get(..len)?;
+        if *record_tag == tag {
+            return Some(payload);
+        }
+        records = &rest[len..];
+    }
+    None
+}
+
+/// Reads the channel layout of an image given its data following the header.
+pub fn channels(data: &[u8], ops_len: usize) -> Option<Channels> {
+    match find_record(find_records(data, ops_len)?, QOI_EXT_TAG_CHANNELS)? {
+        [2] => Some(Channels::La),
+        [3] => Some(Channels::Rgb),
+        [4] => Some(Channels::Rgba),
+        _ => None,
+    }
+}
+
+/// Reads the format version and feature flags of an image given its data following the header.
+///
+/// Images without a version record predate it and are reported as version 1 with no flags.
+pub fn version(data: &[u8], ops_len: usize) -> FormatVersion {
+    match find_records(data, ops_len).and_then(|records| find_record(records, QOI_EXT_TAG_VERSION))
+    {
+        Some([version, a, b, c, d, ..]) => {
+            FormatVersion { version: *version, flags: u32::from_le_bytes([*a, *b, *c, *d]) }
+        }
+        _ => FormatVersion::INITIAL,
+    }
+}
+
+/// Reads the nine-patch metadata of an image given its data following the header.
+///
+/// Metadata that doesn't fit the image dimensions is ignored.
+pub fn nine_patch(data: &[u8], ops_len: usize, width: u16, height: u16) -> Option<NinePatch> {
+    let payload = find_record(find_records(data, ops_len)?, QOI_EXT_TAG_NINE_PATCH)?;
+    NinePatch::from_bytes(payload).filter(|nine_patch| nine_patch.check(width, height).is_ok())
+}
+
+/// Reads which field of an interlaced frame an image holds, given its data following the header.
+pub fn field(data: &[u8], ops_len: usize) -> Option<Field> {
+    match find_record(find_records(data, ops_len)?, QOI_EXT_TAG_FIELD)? {
+        [0] => Some(Field::Top),
+        [1] => Some(Field::Bottom),
+        _ => None,
+    }
+}
+
+/// Reads the color space of an image given its data following the header.
+pub fn colorspace(data: &[u8], ops_len: usize) -> Option<ColorSpace> {
+    match find_record(find_records(data, ops_len)?, QOI_EXT_TAG_COLORSPACE)? {
+        [0] => Some(ColorSpace::Srgb),
+        [1] => Some(ColorSpace::Linear),
+        _ => None,
+    }
+}
+
+/// Reads the checksum of the op stream given the data following the header.
+///
+/// A record of an unknown kind or of the wrong size is ignored.
+pub fn checksum(data: &[u8], ops_len: usize) -> Option<Checksum> {
+    let payload = find_record(find_records(data, ops_len)?, QOI_EXT_TAG_CHECKSUM)?;
+    let (&kind, value) = payload.split_first()?;
+    let kind = ChecksumKind::from_u8(kind).filter(|kind| kind.len() == value.len())?;
+    let mut b = [0; 8];
+    b[..value.len()].copy_from_slice(value);
+    Some(Checksum { kind, value: u64::from_le_bytes(b) })
+}
+
+/// Reads the packed low bits of an RGB10A2 image given its data following the header.
+///
+/// A record of the wrong size is ignored.
+pub fn low_bits(data: &[u8], ops_len: usize, n_pixels: usize) -> Option<&[u8]> {
+    let payload = find_record(find_records(data, ops_len)?, QOI_EXT_TAG_LOW_BITS)?;
+    Some(payload).filter(|payload| payload.len() == rgb10a2::low_bits_len(n_pixels))
+}
+
+/// Reads the restart markers of an image given its data following the header.
+pub fn restart_markers(data: &[u8], ops_len: usize, height: u16) -> Option<RestartMarkers<'_>> {
+    let payload = find_record(find_records(data, ops_len)?, QOI_EXT_TAG_RESTART)?;
+    RestartMarkers::parse(payload, height)
+}
+
+/// Number of bytes taken by the restart record payload for a given number of bands.
+#[inline]
+const fn restart_payload_len(n_bands: usize) -> usize {
+    2 + 4 * n_bands.saturating_sub(1)
+}
+
+/// Number of bands an image of `height` rows is split into with the given restart interval.
+#[inline]
+pub const fn n_bands(height: u16, interval: u16) -> usize {
+    if interval == 0 {
+        1
+    } else {
+        (height as usize + interval as usize - 1) / interval as usize
+    }
+}
+
+/// Size of the restart record including its header, or zero if restart markers are disabled.
+#[inline]
+const fn restart_record_len(height: u16, restart_interval: u16) -> usize {
+    if restart_interval == 0 {
+        return 0;
+    }
+    QOI_EXT_RECORD_HEADER_SIZE + restart_payload_len(n_bands(height, restart_interval))
+}
+
+/// Size of the channels record including its header, or zero for the default RGBA layout.
+#[inline]
+const fn channels_record_len(channels: Channels) -> usize {
+    match channels {
+        Channels::Rgba => 0,
+        Channels::La | Channels::Rgb => QOI_EXT_RECORD_HEADER_SIZE + 1,
+    }
+}
+
+/// Size of the nine-patch record including its header, or zero if there's no such metadata.
+#[inline]
+const fn nine_patch_record_len(nine_patch: Option<&NinePatch>) -> usize {
+    match nine_patch {
+        Some(_) => QOI_EXT_RECORD_HEADER_SIZE + 16,
+        None => 0,
+    }
+}
+
+/// Size of the field record including its header, or zero for progressive images.
+#[inline]
+const fn field_record_len(field: Option<Field>) -> usize {
+    match field {
+        Some(_) => QOI_EXT_RECORD_HEADER_SIZE + 1,
+        None => 0,
+    }
+}
+
+/// Size of the color space record including its header, or zero if none is declared.
+#[inline]
+const fn colorspace_record_len(colorspace: Option<ColorSpace>) -> usize {
+    match colorspace {
+        Some(_) => QOI_EXT_RECORD_HEADER_SIZE + 1,
+        None => 0,
+    }
+}
+
+/// Size of the low bits record including its header, or zero if there are no low bits.
+#[inline]
+const fn low_bits_record_len(low_bits: &[u8]) -> usize {
+    match low_bits.len() {
+        0 => 0,
+        len => QOI_EXT_RECORD_HEADER_SIZE + len,
+    }
+}
+
+/// Size of the checksum record including its header, or zero if there's no checksum.
+#[inline]
+const fn checksum_record_len(checksum: Option<ChecksumKind>) -> usize {
+    match checksum {
+        Some(kind) => QOI_EXT_RECORD_HEADER_SIZE + 1 + kind.len(),
+        None => 0,
+    }
+}
+
+/// Size of the version record including its header.
+const VERSION_RECORD_LEN: usize = QOI_EXT_RECORD_HEADER_SIZE + 5;
+
+/// Total size of the extension block, or zero if no extension records are needed.
+///
+/// The version record is only written along with other records, never on its own.
+#[inline]
+#[allow(clippy::too_many_arguments)]
+pub const fn ext_len(
+    height: u16, restart_interval: u16, channels: Channels, nine_patch: Option<&NinePatch>,
+    field: Option<Field>, colorspace: Option<ColorSpace>, low_bits: &[u8],
+    checksum: Option<ChecksumKind>,
+) -> usize {
+    let records = restart_record_len(height, restart_interval)
+        + channels_record_len(channels)
+        + nine_patch_record_len(nine_patch)
+        + field_record_len(field)
+        + colorspace_record_len(colorspace)
+        + low_bits_record_len(low_bits)
+        + checksum_record_len(checksum);
+    if records == 0 {
+        return 0;
+    }
+    QOI_EXT_HEADER_SIZE + VERSION_RECORD_LEN + records
+}
+
+/// Finds the offsets of the restart bands in an op stream, which may be fed in pieces.
+///
+/// The encoder always starts a new band with a fresh op, so every band start lands exactly on
+/// an op boundary; pieces must hold whole ops as well.
+pub struct BandStarts {
+    band_pixels: usize,
+    last_band_start: usize,
+    // offset of the next op and of the end of the pieces fed so far
+    pos: usize,
+    end: usize,
+    n_pixels: usize,
+}
+
+impl BandStarts {
+    /// Creates a walker for an image of a given size (no bands are found without restarts).
+    #[inline]
+    pub const fn new(width: u16, height: u16, restart_interval: u16) -> Self {
+        let band_pixels = restart_interval as usize * width as usize;
+        let last_band_start = band_pixels * (n_bands(height, restart_interval) - 1);
+        Self { band_pixels, last_band_start, pos: 0, end: 0, n_pixels: 0 }
+    }
+
+    /// Number of band offsets stored in the restart record (all but the first band's).
+    #[inline]
+    pub const fn n_offsets(&self) -> usize {
+        match self.band_pixels {
+            0 => 0,
+            band_pixels => self.last_band_start / band_pixels,
+        }
+    }
+
+    /// Walks the next piece of the op stream, calling `f` with the offset of every band start.
+    #[inline]
+    pub fn feed(&mut self, ops: &[u8], mut f: impl FnMut(usize)) {
+        let start = self.end;
+        self.end += ops.len();
+        while self.pos < self.end && self.n_pixels < self.last_band_start {
+            let b1 = ops[self.pos - start];
+            let kind = OpKind::from_byte(b1);
+            self.n_pixels += if kind == OpKind::Run { (b1 & 0x3f) as usize + 1 } else { 1 };
+            self.pos += kind.n_bytes();
+            if self.n_pixels % self.band_pixels == 0 {
+                f(self.pos);
+            }
+        }
+    }
+}
+
+/// Writes the extension block for a freshly encoded op stream and returns its size.
+///
+/// Band offsets are recovered by walking the op stream with [`BandStarts`]; with an empty op
+/// stream, the slots for them at the end of the block are left untouched.
+#[allow(clippy::cast_possible_truncation, clippy::too_many_arguments)]
+pub fn write_ext(
+    ops: &[u8], out: &mut [u8], width: u16, height: u16, restart_interval: u16, channels: Channels,
+    nine_patch: Option<&NinePatch>, field: Option<Field>, colorspace: Option<ColorSpace>,
+    low_bits: &[u8], checksum: Option<Checksum>,
+) -> usize {
+    let kind = checksum.map(|checksum| checksum.kind);
+    let size =
+        ext_len(height, restart_interval, channels, nine_patch, field, colorspace, low_bits, kind);
+    if size == 0 {
+        return 0;
+    }
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("qoi::write_ext", size).entered();
+    let mut buf = BytesMut::new(&mut out[..size]);
+    buf = buf.write_many(&QOI_EXT_MAGIC.to_le_bytes());
+    buf = buf.write_many(&((size - QOI_EXT_HEADER_SIZE) as u32).to_le_bytes());
+
+    buf = buf.write_one(QOI_EXT_TAG_VERSION);
+    buf = buf.write_many(&5_u32.to_le_bytes());
+    buf = buf.write_one(QOI_EXT_VERSION);
+    buf = buf.write_many(&0_u32.to_le_bytes());
+
+    if channels != Channels::Rgba {
+        buf = buf.write_one(QOI_EXT_TAG_CHANNELS);
+        buf = buf.write_many(&1_u32.to_le_bytes());
+        buf = buf.write_one(channels as u8);
+    }
+
+    if let Some(nine_patch) = nine_patch {
+        buf = buf.write_one(QOI_EXT_TAG_NINE_PATCH);
+        buf = buf.write_many(&16_u32.to_le_bytes());
+        buf = buf.write_many(&nine_patch.to_bytes());
+    }
+
+    if let Some(field) = field {
+        buf = buf.write_one(QOI_EXT_TAG_FIELD);
+        buf = buf.write_many(&1_u32.to_le_bytes());
+        buf = buf.write_one(field as u8);
+    }
+
+    if let Some(colorspace) = colorspace {
+        buf = buf.write_one(QOI_EXT_TAG_COLORSPACE);
+        buf = buf.write_many(&1_u32.to_le_bytes());
+        buf = buf.write_one(colorspace as u8);
+    }
+
+    if !low_bits.is_empty() {
+        buf = buf.write_one(QOI_EXT_TAG_LOW_BITS);
+        buf = buf.write_many(&(low_bits.len() as u32).to_le_bytes());
+        buf = buf.write_many(low_bits);
+    }
+
+    if let Some(Checksum { kind, value }) = checksum {
+        buf = buf.write_one(QOI_EXT_TAG_CHECKSUM);
+        buf = buf.write_many(&((1 + kind.len()) as u32).to_le_bytes());
+        buf = buf.write_one(kind as u8);
+        buf = buf.write_many(&value.to_le_bytes()[..kind.len()]);
+    }
+
+    if restart_interval != 0 {
+        let payload_len = restart_payload_len(n_bands(height, restart_interval));
+        buf = buf.write_one(QOI_EXT_TAG_RESTART);
+        buf = buf.write_many(&(payload_len as u32).to_le_bytes());
+        let _ = buf.write_many(&restart_interval.to_le_bytes());
+
+        let mut starts = BandStarts::new(width, height, restart_interval);
+        let offsets_start = size - 4 * starts.n_offsets();
+        let mut slots = out[offsets_start..size].chunks_exact_mut(4);
+        starts.feed(ops, |pos| {
+            if let Some(slot) = slots.next() {
+                slot.copy_from_slice(&(pos as u32).to_le_bytes());
+            }
+        });
+    }
+    size
+}
@@ -0,0 +1,208 @@
+//! C API for linking the crate from C and C++.
+//!
+//! Build the crate as a `cdylib` or `staticlib` with the `ffi` feature (e.g.
+//! `cargo rustc --release --features ffi --crate-type cdylib`) and generate a header with
+//! `cbindgen --lang c --crate qoi`. All functions return a [`QoiStatus`]; buffers allocated by
+//! the library are handed out as [`QoiBuffer`]s and must be released with [`qoi_free`].
+
+// raw pointers coming from C can't be checked by the compiler
+#![allow(unsafe_code)]
+
+use core::ptr;
+use core::slice;
+use std::boxed::Box;
+use std::vec::Vec;
+
+use crate::decode::Decoder;
+use crate::encode::Encoder;
+use crate::error::Error;
+use crate::header::{Channels, Header};
+
+/// Outcome of a call, `Ok` (zero) on success.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QoiStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer argument is null, or the requested number of channels is invalid
+    InvalidArgument,
+    /// Leading magic bytes don't match
+    InvalidMagic,
+    /// Image dimensions are empty or too large
+    InvalidImageDimensions,
+    /// Pixel data length doesn't match the image dimensions
+    InvalidImageLength,
+    /// Input ended before decoding was finished
+    UnexpectedBufferEnd,
+    /// Stream end marker doesn't match
+    InvalidPadding,
+    /// Memory allocation failed
+    OutOfMemory,
+    /// Any other error, see [`Error`]
+    Other,
+}
+
+impl From<Error> for QoiStatus {
+    #[inline]
+    fn from(err: Error) -> Self {
+        match err {
+            Error::InvalidMagic { .. } => Self::InvalidMagic,
+            Error::InvalidImageDimensions { .. } => Self::InvalidImageDimensions,
+            Error::InvalidImageLength { .. } => Self::InvalidImageLength,
+            Error::UnexpectedBufferEnd { .. } => Self::UnexpectedBufferEnd,
+            Error::InvalidPadding { .. } => Self::InvalidPadding,
+            Error::OutOfMemory => Self::OutOfMemory,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Image header as seen from C.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct QoiHeader {
+    /// Image width in pixels
+    pub width: u16,
+    /// Image height in pixels
+    pub height: u16,
+    /// Bytes per pixel of the decoded image: 2 (luma + alpha), 3 (RGB) or 4 (RGBA)
+    pub channels: u8,
+    /// Length of the op stream in bytes, zero if the header doesn't store it
+    pub length: u32,
+}
+
+impl QoiHeader {
+    #[inline]
+    fn new(header: &Header, channels: Channels) -> Self {
+        Self {
+            width: header.width,
+            height: header.height,
+            channels: channels.as_u8(),
+            length: header.length.unwrap_or_default(),
+        }
+    }
+}
+
+/// Buffer allocated by the library, to be released with [`qoi_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct QoiBuffer {
+    /// Start of the buffer
+    pub data: *mut u8,
+    /// Length of the buffer in bytes
+    pub len: usize,
+}
+
+impl QoiBuffer {
+    #[inline]
+    fn new(vec: Vec<u8>) -> Self {
+        let len = vec.len();
+        let data = Box::into_raw(vec.into_boxed_slice()).cast::<u8>();
+        Self { data, len }
+    }
+}
+
+/// Reborrows a pointer and length passed from C as a slice.
+///
+/// # Safety
+///
+/// `data` must be null or point to `len` readable bytes living for `'a`.
+#[inline]
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], QoiStatus> {
+    if data.is_null() {
+        return Err(QoiStatus::InvalidArgument);
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+#[inline]
+fn status(result: Result<(), QoiStatus>) -> QoiStatus {
+    result.err().unwrap_or(QoiStatus::Ok)
+}
+
+/// Encodes an image into a newly allocated buffer.
+///
+/// The number of channels (2, 3 or 4) is inferred from `len`, which must be `width * height`
+/// times that. On success, the encoded image is stored in `out`.
+///
+/// # Safety
+///
+/// `pixels` must point to `len` readable bytes and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn qoi_encode(
+    pixels: *const u8, len: usize, width: u32, height: u32, out: *mut QoiBuffer,
+) -> QoiStatus {
+    status((|| {
+        let pixels = input(pixels, len)?;
+        let out = out.as_mut().ok_or(QoiStatus::InvalidArgument)?;
+        let encoded = Encoder::new(pixels, width, height)?.encode_to_vec()?;
+        *out = QoiBuffer::new(encoded);
+        Ok(())
+    })())
+}
+
+/// Decodes an image into a newly allocated buffer.
+///
+/// `channels` is the number of bytes per pixel of the output (2, 3 or 4), or zero to decode
+/// the image in the layout it has been encoded from. On success, the pixels are stored in
+/// `out` and, unless it's null, the header in `header`.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, `out` must be valid for writes and `header`
+/// must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn qoi_decode(
+    data: *const u8, len: usize, channels: u8, header: *mut QoiHeader, out: *mut QoiBuffer,
+) -> QoiStatus {
+    status((|| {
+        let data = input(data, len)?;
+        let out = out.as_mut().ok_or(QoiStatus::InvalidArgument)?;
+        let mut decoder = Decoder::new(data)?;
+        decoder = match channels {
+            0 => decoder,
+            2 => decoder.with_channels(Channels::La),
+            3 => decoder.with_channels(Channels::Rgb),
+            4 => decoder.with_channels(Channels::Rgba),
+            _ => return Err(QoiStatus::InvalidArgument),
+        };
+        let pixels = decoder.decode_to_vec()?;
+        if let Some(header) = header.as_mut() {
+            *header = QoiHeader::new(decoder.header(), decoder.channels());
+        }
+        *out = QoiBuffer::new(pixels);
+        Ok(())
+    })())
+}
+
+/// Reads the header of an encoded image without decoding it.
+///
+/// `header.channels` is the layout the image would be decoded in by default.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `header` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn qoi_decode_header(
+    data: *const u8, len: usize, header: *mut QoiHeader,
+) -> QoiStatus {
+    status((|| {
+        let data = input(data, len)?;
+        let header = header.as_mut().ok_or(QoiStatus::InvalidArgument)?;
+        let decoder = Decoder::new(data)?;
+        *header = QoiHeader::new(decoder.header(), decoder.channels());
+        Ok(())
+    })())
+}
+
+/// Releases a buffer returned by [`qoi_encode`] or [`qoi_decode`]; null buffers are ignored.
+///
+/// # Safety
+///
+/// `buf` must have been returned by this library and not been freed before.
+#[no_mangle]
+pub unsafe extern "C" fn qoi_free(buf: QoiBuffer) {
+    if !buf.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buf.data, buf.len)));
+    }
+}
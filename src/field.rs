@@ -0,0 +1,102 @@
+#[cfg(any(feature = "alloc", feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::decode::{Bytes, Decoder};
+use crate::error::{Error, Result};
+use crate::header::Header;
+#[cfg(any(feature = "alloc", feature = "std"))]
+use crate::utils::try_vec_zeroed;
+use crate::utils::unlikely;
+
+/// One of the two fields of an interlaced frame, i.e. every other row of it.
+///
+/// A field is encoded as a regular image of half the height of the frame (see
+/// [`Encoder::from_field`](crate::Encoder::from_field)), so any decoder can read it; which
+/// field it holds is stored in the extension block and reported by
+/// [`Decoder::field`](crate::Decoder::field). [`weave_fields`] puts two fields back together.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Field {
+    /// Even rows (0, 2, 4, ...)
+    Top = 0,
+    /// Odd rows (1, 3, 5, ...)
+    Bottom = 1,
+}
+
+impl Field {
+    /// Index of the first row of the frame belonging to this field.
+    #[inline]
+    pub const fn first_row(self) -> u16 {
+        self as u16
+    }
+
+    /// Number of rows of a frame of a given height belonging to this field.
+    #[inline]
+    pub const fn n_rows(self, frame_height: u16) -> u16 {
+        match self {
+            Self::Top => frame_height / 2 + frame_height % 2,
+            Self::Bottom => frame_height / 2,
+        }
+    }
+}
+
+/// Opens both fields and checks that they make up a frame, whose header is returned.
+fn field_decoders<'a>(
+    top: &'a [u8], bottom: &'a [u8],
+) -> Result<(Decoder<Bytes<'a>>, Decoder<Bytes<'a>>, Header)> {
+    let top = Decoder::new(top)?;
+    let bottom = Decoder::new(bottom)?.with_channels(top.channels());
+    let (top_height, bottom_height) = (top.header().height, bottom.header().height);
+    if unlikely(
+        top.header().width != bottom.header().width
+            || top_height.checked_sub(bottom_height).map_or(true, |diff| diff > 1)
+            || top.field() == Some(Field::Bottom)
+            || bottom.field() == Some(Field::Top),
+    ) {
+        return Err(Error::FieldMismatch);
+    }
+    let height = u32::from(top_height) + u32::from(bottom_height);
+    let header = Header::from_dimensions(top.header().width, height, None)?;
+    Ok((top, bottom, header))
+}
+
+fn weave_impl(
+    buf: &mut [u8], mut top: Decoder<Bytes<'_>>, mut bottom: Decoder<Bytes<'_>>, header: Header,
+) -> Result<()> {
+    let row_len = header.width as usize * top.channels().as_u8() as usize;
+    let size = row_len * header.height as usize;
+    if unlikely(buf.len() < size) {
+        return Err(Error::OutputBufferTooSmall { size: buf.len(), required: size });
+    }
+    let mut rows = buf[..size].chunks_exact_mut(row_len).step_by(2);
+    top.decode_rows_into(|_| rows.next().unwrap_or_default())?;
+    let mut rows = buf[row_len..size].chunks_exact_mut(row_len).step_by(2);
+    bottom.decode_rows_into(|_| rows.next().unwrap_or_default())
+}
+
+/// Weave the top and bottom fields of an interlaced frame into a pre-allocated buffer.
+///
+/// The fields must have the same width, and the top field must have as many rows as the bottom
+/// one or one more; fields tagged the other way around (see [`Decoder::field`]) are rejected
+/// with [`Error::FieldMismatch`], while untagged images are taken as they are given. Each
+/// field is decoded straight into its rows of the frame, with the channels of the top field.
+///
+/// Returns the header of the woven frame.
+pub fn weave_fields_to_buf(
+    mut buf: impl AsMut<[u8]>, top: impl AsRef<[u8]>, bottom: impl AsRef<[u8]>,
+) -> Result<Header> {
+    let (top, bottom, header) = field_decoders(top.as_ref(), bottom.as_ref())?;
+    weave_impl(buf.as_mut(), top, bottom, header)?;
+    Ok(header)
+}
+
+/// Weave the top and bottom fields of an interlaced frame into a newly allocated vector.
+///
+/// See [`weave_fields_to_buf`] for the requirements on the fields.
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub fn weave_fields(top: impl AsRef<[u8]>, bottom: impl AsRef<[u8]>) -> Result<(Header, Vec<u8>)> {
+    let (top, bottom, header) = field_decoders(top.as_ref(), bottom.as_ref())?;
+    let mut out = try_vec_zeroed(header.n_pixels() * top.channels().as_u8() as usize)?;
+    weave_impl(&mut out, top, bottom, header)?;
+    Ok((header, out))
+}
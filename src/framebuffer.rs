@@ -0,0 +1,53 @@
+use alloc::vec::Vec;
+
+use bytemuck::cast_slice;
+
+use crate::encode::encode_to_vec;
+use crate::error::{Error, Result};
+use crate::header::{dimensions, Dimension, Header};
+use crate::pixel::PixelFormat;
+use crate::utils::{try_vec_zeroed, unlikely};
+
+/// Encode a raw framebuffer (e.g. a window surface or a screenshot) into a newly allocated vector.
+///
+/// `stride` is the distance between the starts of two consecutive rows in bytes, which may
+/// include padding; the last row doesn't need to be padded. Rows are converted to RGBA
+/// according to `format` while being copied, so the framebuffer is only read once.
+pub fn encode_from_framebuffer(
+    data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension, stride: usize,
+    format: PixelFormat,
+) -> Result<Vec<u8>> {
+    let data = data.as_ref();
+    let (width, height) = dimensions(width, height)?;
+    let header = Header::try_new(width, height, None)?;
+    let row_len = width as usize * format.bytes_per_pixel();
+    let required = stride.saturating_mul(height as usize - 1).saturating_add(row_len);
+    if unlikely(stride < row_len || data.len() < required) {
+        return Err(Error::InvalidImageLength { size: data.len(), width, height });
+    }
+
+    let mut pixels = try_vec_zeroed(header.n_bytes())?;
+    let bpp = format.bytes_per_pixel();
+    for (y, out_row) in pixels.chunks_exact_mut(width as usize * 4).enumerate() {
+        let row = &data[y * stride..y * stride + row_len];
+        for (px, px_out) in row.chunks_exact(bpp).zip(out_row.chunks_exact_mut(4)) {
+            px_out.copy_from_slice(&format.to_rgba(px));
+        }
+    }
+    encode_to_vec(&pixels, width, height)
+}
+
+/// Encode a raw framebuffer of `u32` words into a newly allocated vector.
+///
+/// Same as [`encode_from_framebuffer`], but the pixels come as words, with `stride` counted in
+/// words as well. The bytes of each word are interpreted in native order, so word formats such
+/// as [`PixelFormat::RgbaU32Be`] give the same result on every target, while byte formats
+/// describe the words as they are laid out in memory.
+#[inline]
+pub fn encode_from_framebuffer_u32(
+    data: impl AsRef<[u32]>, width: impl Dimension, height: impl Dimension, stride: usize,
+    format: PixelFormat,
+) -> Result<Vec<u8>> {
+    let stride = stride.checked_mul(4).ok_or(Error::SizeOverflow)?;
+    encode_from_framebuffer(cast_slice::<_, u8>(data.as_ref()), width, height, stride, format)
+}
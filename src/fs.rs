@@ -0,0 +1,103 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::encode::Encoder;
+use crate::error::Result;
+use crate::header::Dimension;
+
+/// Number of temporary file names tried before giving up, in case stale files from earlier
+/// crashed writes are in the way.
+const MAX_TMP_ATTEMPTS: u32 = 100;
+
+/// Sequence number making the temporary file names unique within the process.
+static TMP_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// How hard [`write_file_atomic`] tries to make the written file durable.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Fsync {
+    /// Leave flushing to the operating system
+    Never,
+    /// Sync the file contents before renaming it into place (default)
+    #[default]
+    File,
+    /// Also sync the parent directory after renaming, so the rename itself survives a crash
+    /// (only has an effect on Unix)
+    FileAndDir,
+}
+
+/// Encode the image into a file, atomically replacing it if it already exists.
+///
+/// The image is written to a temporary file next to `path` which is then renamed into place,
+/// so a crash midway leaves either the old file or the new one, but never a truncated image.
+/// Concurrent writes to the same path (from any thread or process) each get a temporary file
+/// of their own, and the last one renamed into place wins. Returns the total number of bytes
+/// written.
+///
+/// Any path type implementing `AsRef<Path>` may be used, including `camino::Utf8Path`.
+#[inline]
+pub fn write_file_atomic(
+    path: impl AsRef<Path>, data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension,
+) -> Result<usize> {
+    Encoder::new(&data, width, height)?.encode_to_file_atomic(path, Fsync::default())
+}
+
+impl Encoder<'_> {
+    /// Encodes the image into a file, atomically replacing it if it already exists.
+    ///
+    /// See [`write_file_atomic`] for details.
+    pub fn encode_to_file_atomic(&mut self, path: impl AsRef<Path>, fsync: Fsync) -> Result<usize> {
+        let path = path.as_ref();
+        let encoded = self.encode_to_vec()?;
+        let (tmp_path, file) = create_tmp(path)?;
+        let result = write_and_rename(file, &tmp_path, path, &encoded, fsync);
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result.map(|()| encoded.len())
+    }
+}
+
+/// Hidden temporary file in the same directory, so that renaming it doesn't cross filesystems.
+fn tmp_path(path: &Path, n: u32) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}.{n}.tmp", process::id()));
+    path.with_file_name(name)
+}
+
+/// Creates a temporary file that didn't exist yet, skipping names taken by concurrent writes
+/// or left behind by crashed ones.
+fn create_tmp(path: &Path) -> Result<(PathBuf, File)> {
+    let mut attempt = 0;
+    loop {
+        let tmp_path = tmp_path(path, TMP_COUNTER.fetch_add(1, Ordering::Relaxed));
+        match OpenOptions::new().write(true).create_new(true).open(&tmp_path) {
+            Ok(file) => return Ok((tmp_path, file)),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists && attempt < MAX_TMP_ATTEMPTS => {
+                attempt += 1;
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+fn write_and_rename(
+    mut file: File, tmp_path: &Path, path: &Path, data: &[u8], fsync: Fsync,
+) -> Result<()> {
+    file.write_all(data)?;
+    if fsync != Fsync::Never {
+        file.sync_all()?;
+    }
+    drop(file);
+    fs::rename(tmp_path, path)?;
+    #[cfg(unix)]
+    if fsync == Fsync::FileAndDir {
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        File::open(dir.unwrap_or_else(|| Path::new(".")))?.sync_all()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,167 @@
+//! Entry points for fuzzing the crate (requires the `fuzzing` feature).
+//!
+//! Each function takes fuzzer-provided input, exercises a part of the API and panics if an
+//! invariant is broken, so a cargo-fuzz or OSS-Fuzz target is a one-liner:
+//!
+//! ```ignore
+//! #![no_main]
+//! libfuzzer_sys::fuzz_target!(|data: &[u8]| qoi::fuzzing::fuzz_decode(data));
+//! ```
+//!
+//! Structured fuzzing goes through [`FuzzImage`], which implements [`Arbitrary`]:
+//!
+//! ```ignore
+//! #![no_main]
+//! libfuzzer_sys::fuzz_target!(|image: qoi::fuzzing::FuzzImage| qoi::fuzzing::fuzz_image(&image));
+//! ```
+//!
+//! Decoded images are capped at [`MAX_PIXELS`], so that fuzzers don't run out of memory on
+//! headers claiming huge dimensions.
+
+use std::io::Cursor;
+
+use arbitrary::{Arbitrary, Result as ArbitraryResult, Unstructured};
+
+use crate::decode::{decode_header, Decoder};
+use crate::encode::{ContentHint, Encoder, EncoderOptions};
+use crate::header::Channels;
+use crate::limits::Limits;
+
+/// Largest number of pixels decoded by the fuzzing entry points.
+pub const MAX_PIXELS: usize = 1 << 20;
+
+/// Decodes arbitrary bytes, checking that decoding never panics and is self-consistent.
+///
+/// Images decoded from a slice and from a stream must be identical, and the decoded size
+/// must match the header.
+///
+/// # Panics
+///
+/// Panics if any of these invariants is broken.
+pub fn fuzz_decode(data: &[u8]) {
+    let Ok(header) = decode_header(data) else {
+        return;
+    };
+    let limits = Limits::new().max_pixels(MAX_PIXELS);
+    let Ok(decoder) = Decoder::new(data).and_then(|decoder| decoder.with_limits(limits)) else {
+        return;
+    };
+
+    let mut decoder = decoder.with_channels(Channels::Rgba);
+    let decoded = decoder.decode_to_vec();
+    if let Ok(ref pixels) = decoded {
+        assert_eq!(*decoder.header(), header);
+        assert_eq!(pixels.len(), header.n_pixels() * 4);
+    }
+    if let Ok(mut stream) = Decoder::from_stream(Cursor::new(data)) {
+        assert_eq!(*stream.header(), header);
+        if let (Ok(expected), Ok(pixels)) = (&decoded, stream.decode_to_vec()) {
+            assert_eq!(*expected, pixels, "slice and stream decoding differ");
+        }
+    }
+
+    if let Some(markers) = decoder.restart_markers() {
+        for band in 0..markers.n_bands() {
+            let _ = decoder.decode_band_to_vec(band);
+        }
+    }
+}
+
+/// Encodes arbitrary bytes as RGBA pixels and checks that they decode back unchanged.
+///
+/// The first byte picks the image width, the rest are the pixels; trailing bytes that don't
+/// make up a complete row are ignored.
+///
+/// # Panics
+///
+/// Panics if encoding fails or the image doesn't decode back unchanged.
+pub fn fuzz_roundtrip(data: &[u8]) {
+    let Some((&w_frac, pixels)) = data.split_first() else {
+        return;
+    };
+    let n_pixels = (pixels.len() / 4).min(MAX_PIXELS);
+    if n_pixels == 0 {
+        return;
+    }
+    let width = ((n_pixels * (1 + w_frac as usize)) / 256).clamp(1, u16::MAX as usize);
+    let height = (n_pixels / width).min(u16::MAX as usize);
+    let pixels = &pixels[..width * height * 4];
+    roundtrip(pixels, width, height, Channels::Rgba, EncoderOptions::new());
+}
+
+/// Encodes a structured image and checks that it decodes back unchanged, including each of
+/// its bands when restart markers are enabled.
+///
+/// # Panics
+///
+/// Panics if encoding fails or the image doesn't decode back unchanged.
+pub fn fuzz_image(image: &FuzzImage) {
+    let (width, height) = (image.width as usize, image.height as usize);
+    roundtrip(&image.pixels, width, height, image.channels, image.options);
+}
+
+fn roundtrip(pixels: &[u8], width: usize, height: usize, channels: Channels, opts: EncoderOptions) {
+    let mut encoder = Encoder::new(pixels, width, height).expect("valid image").with_options(opts);
+    assert_eq!(encoder.channels(), channels);
+    let encoded = encoder.encode_to_vec().expect("encoding failed");
+    assert!(encoded.len() <= encoder.required_buf_len());
+
+    let mut decoder = Decoder::new(&encoded).expect("invalid header");
+    assert_eq!(decoder.channels(), channels);
+    let decoded = decoder.decode_to_vec().expect("decoding failed");
+    assert!(decoded == pixels, "decoded image differs");
+
+    if let Some(markers) = decoder.restart_markers() {
+        let row_len = width * channels.as_u8() as usize;
+        for marker in markers.iter() {
+            let band = marker.row as usize / markers.interval() as usize;
+            let start = marker.row as usize * row_len;
+            let expected = &pixels[start..start + marker.n_rows as usize * row_len];
+            let decoded = decoder.decode_band_to_vec(band).expect("band decoding failed");
+            assert!(decoded == expected, "decoded band {band} differs");
+        }
+    }
+}
+
+/// Image with encoder options generated from fuzzer input.
+///
+/// Pixels are built by repeating the input bytes, so that runs and index hits occur often
+/// enough to exercise all ops.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FuzzImage {
+    /// Image width, in `1..=256`
+    pub width: u16,
+    /// Image height, in `1..=256`
+    pub height: u16,
+    /// Layout of the pixel data
+    pub channels: Channels,
+    /// Raw pixel data
+    pub pixels: Vec<u8>,
+    /// Encoder configuration
+    pub options: EncoderOptions,
+}
+
+impl<'a> Arbitrary<'a> for FuzzImage {
+    fn arbitrary(u: &mut Unstructured<'a>) -> ArbitraryResult<Self> {
+        let width = u.int_in_range(1..=256)?;
+        let height = u.int_in_range(1..=256)?;
+        let channels = *u.choose(&[Channels::Rgba, Channels::Rgb, Channels::La])?;
+        let hints =
+            [ContentHint::Auto, ContentHint::Screenshot, ContentHint::Photo, ContentHint::PixelArt];
+        let options = EncoderOptions::new()
+            .max_run(u.arbitrary()?)
+            .restart_interval(u.int_in_range(0..=height)?)
+            .content_hint(*u.choose(&hints)?);
+        let n_bytes = width as usize * height as usize * channels.as_u8() as usize;
+        let source = u.bytes(u.len().min(n_bytes))?;
+        let pixels = match source.len() {
+            0 => vec![0; n_bytes],
+            _ => source.iter().copied().cycle().take(n_bytes).collect(),
+        };
+        Ok(Self { width, height, channels, pixels, options })
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (6, None)
+    }
+}
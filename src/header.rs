@@ -1,16 +1,96 @@
 use core::convert::TryInto;
 
-use crate::consts::{QOI_HEADER_SIZE, QOI_MAGIC, QOI_PIXELS_MAX};
+use crate::consts::{QOI_HEADER_SIZE, QOI_MAGIC, QOI_PIXELS_MAX, QOI_REFERENCE_HEADER_SIZE};
 use crate::encode_max_len;
 use crate::error::{Error, Result};
+use crate::nine_patch::NinePatch;
 use crate::utils::unlikely;
 
+/// Layout of the raw pixel data passed to the encoder or produced by the decoder.
+///
+/// Images are always encoded as RGBA; RGB and luma + alpha images are expanded when encoding
+/// and flagged in the extension block, so that decoders can produce them in the same layout.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Channels {
+    /// Luma and alpha, 2 bytes per pixel
+    La = 2,
+    /// Red, green and blue, 3 bytes per pixel (alpha is always 255)
+    Rgb = 3,
+    /// Red, green, blue and alpha, 4 bytes per pixel (default)
+    #[default]
+    Rgba = 4,
+}
+
+impl Channels {
+    /// Number of bytes per pixel.
+    #[inline]
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+/// Color space of the pixel data, stored in the extension block (or in the header of the
+/// reference format).
+///
+/// This is purely informative: pixels are encoded and decoded as they are either way.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ColorSpace {
+    /// sRGB with linear alpha (default)
+    #[default]
+    Srgb = 0,
+    /// All channels linear
+    Linear = 1,
+}
+
+/// Layout of the header at the start of an encoded image.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum HeaderFormat {
+    /// GameMaker's 12-byte header: `fioq` magic, little-endian `u16` dimensions and the length
+    /// of the op stream, followed by an extension block after the image (default)
+    #[default]
+    GameMaker,
+    /// The 14-byte header of the QOI specification: `qoif` magic, big-endian `u32` dimensions,
+    /// channels and color space. There's no extension block, so images can be read by any
+    /// other QOI implementation, but restart markers, nine-patch metadata and RGB10A2 low bits
+    /// aren't stored, and luma + alpha images are stored as RGBA.
+    Reference,
+}
+
+impl HeaderFormat {
+    /// Size of the header in bytes.
+    #[inline]
+    pub const fn size(self) -> usize {
+        match self {
+            Self::GameMaker => QOI_HEADER_SIZE,
+            Self::Reference => QOI_REFERENCE_HEADER_SIZE,
+        }
+    }
+
+    /// Tells the formats apart by the magic bytes at the start of `data`.
+    #[inline]
+    pub fn detect(data: impl AsRef<[u8]>) -> Option<Self> {
+        match data.as_ref().get(..4)? {
+            magic if magic == QOI_MAGIC.to_le_bytes() => Some(Self::GameMaker),
+            magic if magic == QOI_MAGIC.to_be_bytes() => Some(Self::Reference),
+            _ => None,
+        }
+    }
+}
+
 /// Image header: dimensions, channels, color space.
 ///
 /// ### Notes
 /// A valid image header must satisfy the following conditions:
 /// * Both width and height must be non-zero.
-/// * Maximum number of pixels is 400Mp (=4e8 pixels).
+/// * Maximum number of pixels is 400Mp (=4e8 pixels), unless the `large-images` feature is
+///   enabled, in which case any width and height fitting in `u16` are accepted.
+///
+/// Decoders can be restricted further at runtime via [`Limits`](crate::Limits).
+///
+/// The `colorspace` and `nine_patch` fields were added in 0.5.0, which breaks struct literals
+/// listing only the dimensions and length; [`Header::try_new`] builds a header without them.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Header {
     /// Image width in pixels
@@ -19,6 +99,18 @@ pub struct Header {
     pub height: u16,
     /// Image data length in bytes
     pub length: Option<u32>,
+    /// Color space of the pixels, if the image declares one
+    ///
+    /// Reference headers declare it, but GameMaker images store it in the extension block after
+    /// the op stream, so for those it's filled in by [`Decoder::new`](crate::Decoder::new) and
+    /// [`decode_header`](crate::decode_header), but never by [`Header::decode`].
+    pub colorspace: Option<ColorSpace>,
+    /// Nine-patch metadata, if the image has any (see [`NinePatch`])
+    ///
+    /// Stored in the extension block after the op stream, so it's filled in by
+    /// [`Decoder::new`](crate::Decoder::new) and [`decode_header`](crate::decode_header), but
+    /// never by [`Header::decode`].
+    pub nine_patch: Option<NinePatch>,
 }
 
 // impl Default for Header {
@@ -33,17 +125,76 @@ pub struct Header {
 //     }
 // }
 
+/// Integer types accepted as image dimensions (e.g. `u16`, `u32` or `usize`).
+///
+/// Dimensions are stored as `u16` in the header, anything larger is rejected with
+/// [`Error::InvalidImageDimensions`].
+pub trait Dimension: TryInto<u16> + TryInto<u32> + Copy {}
+
+impl<T: TryInto<u16> + TryInto<u32> + Copy> Dimension for T {}
+
+/// Converts generic image dimensions to the `u16` values stored in the header.
+#[inline]
+pub fn dimensions(width: impl Dimension, height: impl Dimension) -> Result<(u16, u16)> {
+    if let (Ok(width), Ok(height)) = (width.try_into(), height.try_into()) {
+        return Ok((width, height));
+    }
+    let width = TryInto::<u32>::try_into(width).unwrap_or(u32::MAX);
+    let height = TryInto::<u32>::try_into(height).unwrap_or(u32::MAX);
+    Err(invalid_dimensions(width, height))
+}
+
+/// Reports dimensions that may not fit in the header, saturating them to 65535.
+#[inline]
+pub fn invalid_dimensions(width: u32, height: u32) -> Error {
+    let saturate = |v| u16::try_from(v).unwrap_or(u16::MAX);
+    Error::InvalidImageDimensions { width: saturate(width), height: saturate(height) }
+}
+
+/// Upper bound on the size in bytes of anything derived from an image: raw pixels (at most 4
+/// bytes each), the encoded image (at most 5 bytes per pixel, plus 6 bits per pixel of RGB10A2
+/// low bits) and its extension block (4 bytes per restart marker and a few small records).
+///
+/// Checking it once when creating a header means that none of these sizes can overflow later
+/// on, e.g. on 32-bit targets with the `large-images` feature.
+#[inline]
+const fn max_derived_size(n_pixels: usize, height: u16) -> Option<usize> {
+    match (n_pixels.checked_mul(6), (height as usize).checked_mul(4)) {
+        (Some(pixels), Some(markers)) => match pixels.checked_add(markers) {
+            Some(size) => size.checked_add(256),
+            None => None,
+        },
+        _ => None,
+    }
+}
+
 impl Header {
     /// Creates a new header and validates image dimensions.
+    ///
+    /// Fails with [`Error::SizeOverflow`] if the sizes derived from the dimensions don't fit in
+    /// `usize` on the target.
     #[inline]
     pub const fn try_new(width: u16, height: u16, length: Option<u32>) -> Result<Self> {
         let n_pixels = (width as usize).saturating_mul(height as usize);
         if unlikely(n_pixels == 0 || n_pixels > QOI_PIXELS_MAX) {
             return Err(Error::InvalidImageDimensions { width, height });
         }
-        Ok(Self { width, height, length })
+        if unlikely(max_derived_size(n_pixels, height).is_none()) {
+            return Err(Error::SizeOverflow);
+        }
+        Ok(Self { width, height, length, colorspace: None, nine_patch: None })
     }
     
+    /// Creates a new header like [`Header::try_new`] from dimensions of any integer type
+    /// (see [`Dimension`]).
+    #[inline]
+    pub fn from_dimensions(
+        width: impl Dimension, height: impl Dimension, length: Option<u32>,
+    ) -> Result<Self> {
+        let (width, height) = dimensions(width, height)?;
+        Self::try_new(width, height, length)
+    }
+
     /// Serializes the header into a bytes array.
     #[inline]
     pub fn encode(&self) -> Result<[u8; QOI_HEADER_SIZE]> {
@@ -58,11 +209,16 @@ impl Header {
     }
 
     /// Deserializes the header from a byte array.
+    ///
+    /// Only the header bytes are read. The format version and the extension records follow
+    /// the op stream, so the `colorspace` and `nine_patch` fields are left unset; see
+    /// [`Decoder::new`](crate::Decoder::new) and [`Decoder::version`](crate::Decoder::version)
+    /// to read them along with the image.
     #[inline]
     pub fn decode(data: impl AsRef<[u8]>) -> Result<Self> {
         let data = data.as_ref();
         if unlikely(data.len() < QOI_HEADER_SIZE) {
-            return Err(Error::UnexpectedBufferEnd);
+            return Err(Error::UnexpectedBufferEnd { offset: 0, pixel: 0 });
         }
         let magic = u32::from_le_bytes(data[0..4].try_into().unwrap());
         let width = u16::from_le_bytes(data[4..6].try_into().unwrap());
@@ -74,6 +230,53 @@ impl Header {
         Self::try_new(width, height, Some(length))
     }
 
+    /// Serializes the header in the [reference](HeaderFormat::Reference) format.
+    ///
+    /// The channels field is 3 for RGB data and 4 otherwise; the color space is sRGB unless
+    /// declared otherwise.
+    #[inline]
+    pub fn encode_reference(&self, channels: Channels) -> [u8; QOI_REFERENCE_HEADER_SIZE] {
+        let mut out = [0; QOI_REFERENCE_HEADER_SIZE];
+        out[..4].copy_from_slice(&QOI_MAGIC.to_be_bytes());
+        out[4..8].copy_from_slice(&u32::from(self.width).to_be_bytes());
+        out[8..12].copy_from_slice(&u32::from(self.height).to_be_bytes());
+        out[12] = if channels == Channels::Rgb { 3 } else { 4 };
+        out[13] = self.colorspace.unwrap_or_default() as u8;
+        out
+    }
+
+    /// Deserializes a header in the [reference](HeaderFormat::Reference) format, returning it
+    /// along with the channels it declares.
+    ///
+    /// The data length of the returned header isn't set. Images wider or taller than 65535
+    /// pixels are rejected with [`Error::InvalidImageDimensions`].
+    #[inline]
+    pub fn decode_reference(data: impl AsRef<[u8]>) -> Result<(Self, Channels)> {
+        let data = data.as_ref();
+        if unlikely(data.len() < QOI_REFERENCE_HEADER_SIZE) {
+            return Err(Error::UnexpectedBufferEnd { offset: 0, pixel: 0 });
+        }
+        let be_u32 =
+            |i: usize| u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        let (magic, width, height) = (be_u32(0), be_u32(4), be_u32(8));
+        let (channels, colorspace) = (data[12], data[13]);
+        if unlikely(magic != QOI_MAGIC) {
+            return Err(Error::InvalidMagic { magic });
+        }
+        let channels = match channels {
+            3 => Channels::Rgb,
+            4 => Channels::Rgba,
+            _ => return Err(Error::InvalidChannels { channels }),
+        };
+        let colorspace = match colorspace {
+            0 => ColorSpace::Srgb,
+            1 => ColorSpace::Linear,
+            _ => return Err(Error::InvalidColorSpace { colorspace }),
+        };
+        let header = Self::from_dimensions(width, height, None)?;
+        Ok((Self { colorspace: Some(colorspace), ..header }, channels))
+    }
+
     /// Returns a number of pixels in the image.
     #[inline]
     pub const fn n_pixels(&self) -> usize {
@@ -0,0 +1,115 @@
+//! Content negotiation and response bodies for serving images over HTTP.
+//!
+//! Images are only worth sending to clients that explicitly list [`CONTENT_TYPE`] in their
+//! `Accept` header; everyone else gets a fallback format:
+//!
+//! ```ignore
+//! if qoi::http::accepts(request.header("Accept").unwrap_or_default()) {
+//!     let body = qoi::http::Body::encode(&mut qoi::Encoder::new(&pixels, width, height)?)?;
+//!     // respond with `Content-Type: body.content_type()` and `Vary: Accept`
+//! } else {
+//!     // respond with PNG
+//! }
+//! ```
+//!
+//! Since the response depends on the `Accept` header, it should be sent with `Vary: Accept`
+//! so that caches don't serve it to other clients.
+
+use std::io::{self, Read};
+use std::vec::Vec;
+
+use crate::consts::QOI_MEDIA_TYPE;
+use crate::encode::Encoder;
+use crate::error::Result;
+
+/// Media type of images in this crate's format.
+///
+/// The header differs from the official QOI format, so `image/qoi` would make generic QOI
+/// decoders choke on these images; a private type is used instead.
+pub const CONTENT_TYPE: &str = QOI_MEDIA_TYPE;
+
+/// File extension of images in this crate's format, e.g. for `Content-Disposition`.
+pub const FILE_EXTENSION: &str = "qoi";
+
+/// Returns true if an `Accept` header value allows a response of type [`CONTENT_TYPE`].
+///
+/// Only an explicit media range for [`CONTENT_TYPE`] with a non-zero quality counts: browsers
+/// send `image/*` or `*/*` without being able to decode this format, so wildcards are ignored.
+pub fn accepts(accept: &str) -> bool {
+    accept.split(',').any(|range| {
+        let mut params = range.split(';');
+        let media_type = params.next().unwrap_or_default().trim();
+        media_type.eq_ignore_ascii_case(CONTENT_TYPE)
+            && params.all(|param| match param.split_once('=') {
+                Some((name, value)) if name.trim().eq_ignore_ascii_case("q") => {
+                    value.trim().parse::<f32>().map_or(false, |q| q > 0.)
+                }
+                _ => true,
+            })
+    })
+}
+
+/// Encoded image ready to be sent as a response body.
+///
+/// The format stores the length of the op stream in the header, so an image can't be sent
+/// before it's fully encoded; the body is encoded up front and then read in chunks, which
+/// fits both blocking servers taking a [`Read`] along with a length and async ones taking a
+/// stream of chunks (see [`Body::into_chunks`]).
+#[derive(Clone, Debug)]
+pub struct Body {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl Body {
+    /// Encodes an image into a response body.
+    #[inline]
+    pub fn encode(encoder: &mut Encoder) -> Result<Self> {
+        encoder.encode_to_vec().map(Self::from)
+    }
+
+    /// Value of the `Content-Type` header, i.e. [`CONTENT_TYPE`].
+    #[inline]
+    pub const fn content_type(&self) -> &'static str {
+        CONTENT_TYPE
+    }
+
+    /// Value of the `Content-Length` header: the number of bytes left to be read.
+    #[inline]
+    pub fn content_length(&self) -> u64 {
+        (self.data.len() - self.pos) as u64
+    }
+
+    /// Splits the rest of the body into chunks of at most `chunk_size` bytes (clamped to 1).
+    pub fn into_chunks(self, chunk_size: usize) -> impl Iterator<Item = Vec<u8>> + Send {
+        let chunk_size = chunk_size.max(1);
+        let mut rest = self.data;
+        rest.drain(..self.pos);
+        let n_chunks = (rest.len() + chunk_size - 1) / chunk_size;
+        (0..n_chunks)
+            .map(move |i| rest[i * chunk_size..rest.len().min((i + 1) * chunk_size)].to_vec())
+    }
+
+    /// Returns the whole encoded image.
+    #[inline]
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    /// Wraps an already encoded image.
+    #[inline]
+    fn from(data: Vec<u8>) -> Self {
+        Self { data, pos: 0 }
+    }
+}
+
+impl Read for Body {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = (&self.data[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,80 @@
+//! App icon and favicon export.
+//!
+//! The source image is decoded once and halved repeatedly into a chain of mips (box filtered
+//! in linear light), and every requested size is resized from the smallest mip that is still
+//! at least as large, so a whole set of icons costs little more than the largest of them.
+
+use alloc::vec::Vec;
+
+use crate::decode::Decoder;
+use crate::encode::encode_to_vec;
+use crate::error::Result;
+use crate::header::{Channels, Header};
+use crate::scale::resize_box;
+use crate::utils::try_vec_zeroed;
+
+/// An RGBA image at one level of the mip chain.
+struct Mip {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Mip {
+    /// Downscales the image to half its size, rounding down.
+    fn halve(&self) -> Result<Self> {
+        let (width, height) = (self.width / 2, self.height / 2);
+        let mut pixels = try_vec_zeroed(width * height * 4)?;
+        resize_box(&self.pixels, self.width, self.height, &mut pixels, width, height, true);
+        Ok(Self { width, height, pixels })
+    }
+}
+
+/// Dimensions of an image of `width` by `height` pixels scaled to fit a square of `size`.
+fn fit(width: usize, height: usize, size: usize) -> (usize, usize) {
+    let scale = |short: usize, long: usize| ((short * size + long / 2) / long).max(1);
+    if width >= height {
+        (size, scale(height, width))
+    } else {
+        (scale(width, height), size)
+    }
+}
+
+/// Export an image as square RGBA icons of the given sizes, e.g. `&[16, 32, 48, 256]`.
+///
+/// Returns the encoded icons along with their sizes, in the order of `sizes`. Images that
+/// aren't square are scaled to fit and centered, leaving the rest of the icon transparent.
+/// Icons are meant to be smaller than the source image; larger ones are upscaled with
+/// nearest-neighbor sampling. A size of zero fails with [`Error::InvalidImageDimensions`].
+///
+/// [`Error::InvalidImageDimensions`]: crate::Error::InvalidImageDimensions
+pub fn export(data: impl AsRef<[u8]>, sizes: &[u16]) -> Result<Vec<(u16, Vec<u8>)>> {
+    for &size in sizes {
+        Header::try_new(size, size, None)?;
+    }
+    let mut decoder = Decoder::new(&data)?.with_channels(Channels::Rgba);
+    let (width, height) = (decoder.header().width as usize, decoder.header().height as usize);
+    let mut mip = Mip { width, height, pixels: decoder.decode_to_vec()? };
+
+    // largest first, so that each mip is only computed once
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| core::cmp::Reverse(sizes[i]));
+    let mut icons: Vec<(u16, Vec<u8>)> = sizes.iter().map(|&size| (size, Vec::new())).collect();
+    for i in order {
+        let size = sizes[i] as usize;
+        let (fw, fh) = fit(width, height, size);
+        while mip.width / 2 >= fw && mip.height / 2 >= fh {
+            mip = mip.halve()?;
+        }
+        let mut scaled = try_vec_zeroed(fw * fh * 4)?;
+        resize_box(&mip.pixels, mip.width, mip.height, &mut scaled, fw, fh, true);
+        let mut icon = try_vec_zeroed(size * size * 4)?;
+        let (x0, y0) = ((size - fw) / 2, (size - fh) / 2);
+        for (src, dst) in scaled.chunks_exact(fw * 4).zip(icon.chunks_exact_mut(size * 4).skip(y0))
+        {
+            dst[x0 * 4..(x0 + fw) * 4].copy_from_slice(src);
+        }
+        icons[i].1 = encode_to_vec(&icon, sizes[i], sizes[i])?;
+    }
+    Ok(icons)
+}
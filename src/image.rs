@@ -0,0 +1,161 @@
+use alloc::vec::Vec;
+
+use bytemuck::cast_slice_mut;
+
+use crate::decode::Decoder;
+use crate::encode::encode_to_vec;
+use crate::error::{Error, Result};
+use crate::header::{invalid_dimensions, Channels, Dimension, Header};
+use crate::pixel::Pixel;
+use crate::scale::scale_nn;
+use crate::utils::{try_vec_with_capacity, try_vec_zeroed, unlikely};
+
+/// An owned RGBA image, for simple manipulations without pulling in another image crate.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Image {
+    header: Header,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Creates a new image with all pixels set to transparent black.
+    #[inline]
+    pub fn new(width: impl Dimension, height: impl Dimension) -> Result<Self> {
+        let header = Header::from_dimensions(width, height, None)?;
+        Ok(Self { header, pixels: try_vec_zeroed(header.n_bytes())? })
+    }
+
+    /// Creates an image from raw RGBA pixel data.
+    #[inline]
+    pub fn from_pixels(
+        pixels: impl Into<Vec<u8>>, width: impl Dimension, height: impl Dimension,
+    ) -> Result<Self> {
+        let header = Header::from_dimensions(width, height, None)?;
+        let pixels = pixels.into();
+        if unlikely(pixels.len() != header.n_bytes()) {
+            let (size, width, height) = (pixels.len(), header.width, header.height);
+            return Err(Error::InvalidImageLength { size, width, height });
+        }
+        Ok(Self { header, pixels })
+    }
+
+    /// Decodes an image (luma + alpha images are expanded to RGBA).
+    #[inline]
+    pub fn decode(data: impl AsRef<[u8]>) -> Result<Self> {
+        let mut decoder = Decoder::new(&data)?.with_channels(Channels::Rgba);
+        let pixels = decoder.decode_to_vec()?;
+        let header = Header::try_new(decoder.header().width, decoder.header().height, None)?;
+        Ok(Self { header, pixels })
+    }
+
+    /// Encodes the image with the default encoder options.
+    #[inline]
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        encode_to_vec(&self.pixels, self.header.width, self.header.height)
+    }
+
+    /// Image header (the data length is never set).
+    #[inline]
+    pub const fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Image width in pixels.
+    #[inline]
+    pub const fn width(&self) -> u16 {
+        self.header.width
+    }
+
+    /// Image height in pixels.
+    #[inline]
+    pub const fn height(&self) -> u16 {
+        self.header.height
+    }
+
+    /// Raw RGBA pixel data.
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Mutable raw RGBA pixel data.
+    #[inline]
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    /// Consumes the image and returns the raw RGBA pixel data.
+    #[inline]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    #[inline]
+    const fn offset(&self, x: u16, y: u16) -> Option<usize> {
+        if x < self.header.width && y < self.header.height {
+            Some((y as usize * self.header.width as usize + x as usize) * 4)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the pixel at given coordinates, or `None` if they are out of bounds.
+    #[inline]
+    pub fn get_pixel(&self, x: u16, y: u16) -> Option<Pixel> {
+        let i = self.offset(x, y)?;
+        let px: [u8; 4] = self.pixels[i..i + 4].try_into().ok()?;
+        Some(px.into())
+    }
+
+    /// Sets the pixel at given coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are out of bounds.
+    #[inline]
+    pub fn set_pixel(&mut self, x: u16, y: u16, px: impl Into<Pixel>) {
+        let i = self.offset(x, y).expect("pixel coordinates out of bounds");
+        self.pixels[i..i + 4].copy_from_slice(&<[u8; 4]>::from(px.into()));
+    }
+
+    /// Copies a rectangular region into a new image.
+    pub fn crop(&self, x: u16, y: u16, width: u16, height: u16) -> Result<Self> {
+        let (x_end, y_end) = (u32::from(x) + u32::from(width), u32::from(y) + u32::from(height));
+        if unlikely(x_end > self.header.width.into() || y_end > self.header.height.into()) {
+            return Err(invalid_dimensions(x_end, y_end));
+        }
+        let header = Header::try_new(width, height, None)?;
+        let row_len = self.header.width as usize * 4;
+        let mut pixels = try_vec_with_capacity(header.n_bytes())?;
+        for row in self.pixels.chunks_exact(row_len).skip(y as usize).take(height as usize) {
+            pixels.extend_from_slice(&row[x as usize * 4..(x as usize + width as usize) * 4]);
+        }
+        Ok(Self { header, pixels })
+    }
+
+    /// Mirrors the image left to right.
+    pub fn flip_horizontal(&mut self) {
+        let row_len = self.header.width as usize * 4;
+        for row in self.pixels.chunks_exact_mut(row_len) {
+            cast_slice_mut::<_, [u8; 4]>(row).reverse();
+        }
+    }
+
+    /// Mirrors the image top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let row_len = self.header.width as usize * 4;
+        let mut rows = self.pixels.chunks_exact_mut(row_len);
+        while let (Some(top), Some(bottom)) = (rows.next(), rows.next_back()) {
+            top.swap_with_slice(bottom);
+        }
+    }
+
+    /// Scales the image into a new one using nearest-neighbor sampling, see [`scale_nn`].
+    pub fn resize_nn(&self, width: impl Dimension, height: impl Dimension) -> Result<Self> {
+        let mut out = Self::new(width, height)?;
+        let (sw, sh, dw, dh) = (self.width(), self.height(), out.width(), out.height());
+        scale_nn(&self.pixels, sw, sh, &mut out.pixels, dw, dh)?;
+        Ok(out)
+    }
+}
@@ -0,0 +1,140 @@
+//! Integration with the `image` crate.
+//!
+//! [`Decoder`] implements [`image::ImageDecoder`], so images can be loaded with
+//! `image::DynamicImage::from_decoder`, and [`ImageWriter`] implements [`image::ImageEncoder`].
+//! [`Image`] converts to and from `image::RgbaImage` without copying the pixels.
+
+use std::io::Write;
+use std::vec::Vec;
+
+use image::error::{
+    DecodingError, EncodingError, ImageFormatHint, ParameterError, ParameterErrorKind,
+    UnsupportedError, UnsupportedErrorKind,
+};
+use image::{ColorType, ExtendedColorType, ImageError, ImageResult, RgbaImage};
+
+use crate::consts::QOI_MEDIA_TYPE;
+use crate::decode::{Decoder, Reader};
+use crate::encode::{Encoder, EncoderOptions};
+use crate::error::Error;
+use crate::header::Channels;
+use crate::image::Image;
+
+#[inline]
+fn format_hint() -> ImageFormatHint {
+    ImageFormatHint::Name(QOI_MEDIA_TYPE.into())
+}
+
+#[inline]
+fn decoding_error(err: Error) -> ImageError {
+    match err {
+        Error::IoError(err) => ImageError::IoError(err),
+        err => ImageError::Decoding(DecodingError::new(format_hint(), err)),
+    }
+}
+
+#[inline]
+fn encoding_error(err: Error) -> ImageError {
+    match err {
+        Error::IoError(err) => ImageError::IoError(err),
+        err => ImageError::Encoding(EncodingError::new(format_hint(), err)),
+    }
+}
+
+impl<R: Reader> image::ImageDecoder for Decoder<R> {
+    #[inline]
+    fn dimensions(&self) -> (u32, u32) {
+        (self.header().width.into(), self.header().height.into())
+    }
+
+    #[inline]
+    fn color_type(&self) -> ColorType {
+        match self.channels() {
+            Channels::Rgba => ColorType::Rgba8,
+            Channels::Rgb => ColorType::Rgb8,
+            Channels::La => ColorType::La8,
+        }
+    }
+
+    #[inline]
+    fn read_image(mut self, buf: &mut [u8]) -> ImageResult<()> {
+        self.decode_to_buf(buf).map(|_| ()).map_err(decoding_error)
+    }
+
+    #[inline]
+    fn read_image_boxed(self: Box<Self>, buf: &mut [u8]) -> ImageResult<()> {
+        (*self).read_image(buf)
+    }
+}
+
+/// Writes images handed over by the `image` crate to a writer, see [`image::ImageEncoder`].
+///
+/// RGBA, RGB and luma + alpha pixels with 8 bits per channel are supported.
+pub struct ImageWriter<W> {
+    writer: W,
+    options: EncoderOptions,
+}
+
+impl<W: Write> ImageWriter<W> {
+    /// Creates a new image writer with the default encoder options.
+    #[inline]
+    pub const fn new(writer: W) -> Self {
+        Self { writer, options: EncoderOptions::new() }
+    }
+
+    /// Replaces the encoder configuration.
+    #[inline]
+    pub const fn with_options(mut self, options: EncoderOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl<W: Write> image::ImageEncoder for ImageWriter<W> {
+    fn write_image(
+        mut self, buf: &[u8], width: u32, height: u32, color_type: ExtendedColorType,
+    ) -> ImageResult<()> {
+        if !matches!(
+            color_type,
+            ExtendedColorType::Rgba8 | ExtendedColorType::Rgb8 | ExtendedColorType::La8
+        ) {
+            let kind = UnsupportedErrorKind::Color(color_type);
+            return Err(ImageError::Unsupported(UnsupportedError::from_format_and_kind(
+                format_hint(),
+                kind,
+            )));
+        }
+        if u64::from(width) * u64::from(height) * u64::from(color_type.bits_per_pixel() / 8)
+            != buf.len() as u64
+        {
+            let kind = ParameterErrorKind::DimensionMismatch;
+            return Err(ImageError::Parameter(ParameterError::from_kind(kind)));
+        }
+        // the header holds the length of the op stream, so the image is encoded in memory first
+        let encoder = Encoder::new(buf, width, height).map_err(encoding_error)?;
+        let encoded = encoder.with_options(self.options).encode_to_vec().map_err(encoding_error)?;
+        self.writer.write_all(&encoded).map_err(ImageError::IoError)
+    }
+}
+
+impl TryFrom<RgbaImage> for Image {
+    type Error = Error;
+
+    /// Takes over the pixels of an `image::RgbaImage`, failing if it's too large.
+    #[inline]
+    fn try_from(image: RgbaImage) -> Result<Self, Error> {
+        let (width, height) = image.dimensions();
+        Self::from_pixels(image.into_raw(), width, height)
+    }
+}
+
+impl From<Image> for RgbaImage {
+    /// Hands over the pixels of an image to an `image::RgbaImage`.
+    #[inline]
+    fn from(image: Image) -> Self {
+        let (width, height) = (image.width().into(), image.height().into());
+        let pixels: Vec<u8> = image.into_pixels();
+        // can't fail: the buffer holds exactly `width * height` RGBA pixels
+        Self::from_raw(width, height, pixels).unwrap_or_default()
+    }
+}
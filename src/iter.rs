@@ -0,0 +1,123 @@
+use crate::consts::{QOI_PADDING, QOI_PADDING_SIZE};
+use crate::decode::Decoder;
+use crate::error::{Error, Result};
+use crate::header::Header;
+use crate::ops::OpDecoder;
+use crate::pixel::Pixel;
+
+/// Iterator over the pixels of an encoded image, see [`decode_iter`].
+#[derive(Clone)]
+pub struct PixelIter<'a> {
+    header: Header,
+    data: &'a [u8],
+    ops: OpDecoder<'a>,
+    px: Pixel,
+    n_run: usize,
+    n_left: usize,
+    done: bool,
+}
+
+impl PixelIter<'_> {
+    /// Returns the header of the image being decoded.
+    #[inline]
+    pub const fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Checks the padding once all pixels have been produced.
+    fn finish(&mut self) -> Option<Result<[u8; 4]>> {
+        self.done = true;
+        check_padding(self.data, self.ops.offset(), self.header.n_pixels()).err().map(Err)
+    }
+}
+
+/// Checks the end marker found at `offset` of the op stream, after `n_pixels` pixels.
+#[inline]
+fn check_padding(data: &[u8], offset: usize, n_pixels: usize) -> Result<()> {
+    let pixel = n_pixels;
+    match data.get(offset..offset + QOI_PADDING_SIZE) {
+        None => Err(Error::UnexpectedBufferEnd { offset, pixel }),
+        Some(padding) if padding != QOI_PADDING => Err(Error::InvalidPadding { offset, pixel }),
+        Some(_) => Ok(()),
+    }
+}
+
+impl Iterator for PixelIter<'_> {
+    type Item = Result<[u8; 4]>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        } else if self.n_left == 0 {
+            return self.finish();
+        }
+        if self.n_run == 0 {
+            match self.ops.next_op() {
+                Ok(op) => (self.px, self.n_run) = (op.px, op.n_pixels),
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        self.n_run -= 1;
+        self.n_left -= 1;
+        Some(Ok(self.px.into()))
+    }
+}
+
+/// Decode an image pixel by pixel, without allocating or needing an output buffer.
+///
+/// Pixels are yielded as RGBA in row-major order whatever the channels of the image, so they
+/// can be streamed straight into a framebuffer or processed one at a time. A decoding error
+/// is yielded in place of the pixel it affects and ends the iteration; an invalid end marker
+/// is reported as an extra error item after the last pixel.
+///
+/// This walks the op stream one op at a time, which is slower than decoding into a buffer
+/// with [`decode_to_buf`](crate::decode_to_buf) when that is an option.
+#[inline]
+pub fn decode_iter(data: &[u8]) -> Result<PixelIter<'_>> {
+    let decoder = Decoder::new(data)?;
+    let header = *decoder.header();
+    let data = &data[decoder.header_format().size()..];
+    Ok(PixelIter {
+        header,
+        data,
+        ops: OpDecoder::new(data),
+        px: Pixel::new(),
+        n_run: 0,
+        n_left: header.n_pixels(),
+        done: false,
+    })
+}
+
+/// Decode an image by handing every pixel to `visit` along with its coordinates, without
+/// allocating or needing an output buffer, and return its header.
+///
+/// Meant for analytics passes (histograms, checksums, ...) that consume the pixels right
+/// away. Pixels are visited as RGBA in row-major order whatever the channels of the image.
+/// Unlike [`decode_iter`], this walks the op stream without stopping after every pixel, so
+/// runs are handed out in a tight loop; a decoding error stops the walk, after the pixels
+/// preceding it have been visited.
+pub fn decode_visit(data: &[u8], mut visit: impl FnMut(u16, u16, [u8; 4])) -> Result<Header> {
+    let decoder = Decoder::new(data)?;
+    let header = *decoder.header();
+    let data = &data[decoder.header_format().size()..];
+    let mut ops = OpDecoder::new(data);
+    let (mut x, mut y, mut n_left) = (0, 0, header.n_pixels());
+    while n_left != 0 {
+        let op = ops.next_op()?;
+        let (px, n_pixels) = (op.px.into(), op.n_pixels.min(n_left));
+        for _ in 0..n_pixels {
+            visit(x, y, px);
+            x += 1;
+            if x == header.width {
+                (x, y) = (0, y + 1);
+            }
+        }
+        n_left -= n_pixels;
+    }
+    check_padding(data, ops.offset(), header.n_pixels())?;
+    Ok(header)
+}
@@ -0,0 +1,158 @@
+use alloc::vec::Vec;
+
+use crate::decode::{Bytes, Decoder, DecoderOptions};
+use crate::error::Result;
+use crate::header::{Channels, Header};
+use crate::ops::OpDecoder;
+use crate::pixel::Pixel;
+use crate::utils::{try_vec_with_capacity, try_vec_zeroed};
+
+/// Encoded image that is only decoded as far as needed to access the requested rows.
+///
+/// Decoded rows are cached, so accessing the same or earlier rows again is free. If the image
+/// has restart markers (see [`EncoderOptions::restart_interval`](crate::EncoderOptions)),
+/// only the bands containing the requested rows are decoded; otherwise, decoding proceeds
+/// from the top of the image up to the requested row.
+///
+/// This is meant for code that samples a few pixels (e.g. thumbnails or hit testing), which
+/// shouldn't pay for decoding the whole image.
+#[derive(Clone)]
+pub struct LazyImage<'a> {
+    decoder: Decoder<Bytes<'a>>,
+    options: DecoderOptions,
+    /// Op stream, for decoding from the top of the image again
+    data: &'a [u8],
+    pixels: Vec<u8>,
+    /// Which bands have been decoded, empty if the image has no restart markers
+    bands: Vec<bool>,
+    ops: OpDecoder<'a>,
+    px: Pixel,
+    n_left: usize,
+    n_rows: u16,
+}
+
+impl<'a> LazyImage<'a> {
+    /// Parses the header of an encoded image without decoding any pixels.
+    ///
+    /// Pixels are produced in the layout the image has been encoded from (see [`Channels`]).
+    pub fn new(data: &'a (impl AsRef<[u8]> + ?Sized)) -> Result<Self> {
+        let data = data.as_ref();
+        let decoder = Decoder::new(data)?;
+        let pixels = try_vec_zeroed(decoder.required_buf_len())?;
+        let n_bands = decoder.restart_markers().map_or(0, |markers| markers.n_bands());
+        let mut bands = try_vec_with_capacity(n_bands)?;
+        bands.resize(n_bands, false);
+        let (options, data) = (DecoderOptions::new(), &data[decoder.header_format().size()..]);
+        let ops = OpDecoder::new(data);
+        Ok(Self {
+            decoder,
+            options,
+            data,
+            pixels,
+            bands,
+            ops,
+            px: Pixel::new(),
+            n_left: 0,
+            n_rows: 0,
+        })
+    }
+
+    /// Replaces the decoder configuration, see [`Decoder::with_options`].
+    ///
+    /// The per-pixel options ([`DecoderOptions::alpha_threshold`] and
+    /// [`DecoderOptions::flatten_onto`]) are applied whether or not the image has restart
+    /// markers; the others need the whole image and are ignored. Rows decoded before are
+    /// dropped from the cache.
+    pub fn with_options(mut self, options: DecoderOptions) -> Self {
+        self.decoder = self.decoder.with_options(options);
+        self.options = options;
+        self.bands.fill(false);
+        (self.ops, self.n_left, self.n_rows) = (OpDecoder::new(self.data), 0, 0);
+        self
+    }
+
+    /// Returns the image header.
+    #[inline]
+    pub const fn header(&self) -> &Header {
+        self.decoder.header()
+    }
+
+    /// Returns the layout of the decoded pixels.
+    #[inline]
+    pub const fn channels(&self) -> Channels {
+        self.decoder.channels()
+    }
+
+    #[inline]
+    const fn row_len(&self) -> usize {
+        self.header().width as usize * self.channels().as_u8() as usize
+    }
+
+    /// Returns a decoded row, or `None` if it's out of bounds.
+    pub fn row(&mut self, y: u16) -> Result<Option<&[u8]>> {
+        if y >= self.header().height {
+            return Ok(None);
+        }
+        self.decode_row(y)?;
+        let row_len = self.row_len();
+        Ok(Some(&self.pixels[y as usize * row_len..(y as usize + 1) * row_len]))
+    }
+
+    /// Returns the pixel at given coordinates, or `None` if they are out of bounds.
+    ///
+    /// Luma + alpha pixels are expanded to gray RGBA.
+    pub fn get_pixel(&mut self, x: u16, y: u16) -> Result<Option<Pixel>> {
+        let bpp = self.channels().as_u8() as usize;
+        if x >= self.header().width {
+            return Ok(None);
+        }
+        Ok(self.row(y)?.map(|row| {
+            let mut px = Pixel::new();
+            px.read(&row[x as usize * bpp..(x as usize + 1) * bpp]);
+            px
+        }))
+    }
+
+    /// Decodes the rest of the image and returns all of its pixels.
+    pub fn into_pixels(mut self) -> Result<Vec<u8>> {
+        for y in 0..self.header().height {
+            self.decode_row(y)?;
+        }
+        Ok(self.pixels)
+    }
+
+    /// Makes sure that a given row (within bounds) has been decoded.
+    fn decode_row(&mut self, y: u16) -> Result<()> {
+        let row_len = self.row_len();
+        if let Some(markers) = self.decoder.restart_markers() {
+            let band = (y / markers.interval()) as usize;
+            if !self.bands[band] {
+                let start = band * markers.interval() as usize * row_len;
+                self.decoder.decode_band_to_buf(band, &mut self.pixels[start..])?;
+                self.bands[band] = true;
+            }
+            return Ok(());
+        }
+
+        let bpp = self.channels().as_u8() as usize;
+        while self.n_rows <= y {
+            let start = self.n_rows as usize * row_len;
+            for px_out in self.pixels[start..start + row_len].chunks_exact_mut(bpp) {
+                if self.n_left == 0 {
+                    let op = self.ops.next_op()?;
+                    (self.px, self.n_left) = (self.options.map(op.px), op.n_pixels);
+                }
+                self.n_left -= 1;
+                match self.decoder.channels() {
+                    Channels::Rgba => px_out.copy_from_slice(&<[u8; 4]>::from(self.px)),
+                    Channels::Rgb => {
+                        px_out.copy_from_slice(&[self.px.r(), self.px.g(), self.px.b()]);
+                    }
+                    Channels::La => px_out.copy_from_slice(&[self.px.luma(), self.px.a()]),
+                }
+            }
+            self.n_rows += 1;
+        }
+        Ok(())
+    }
+}
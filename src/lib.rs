@@ -5,7 +5,9 @@
 //!
 //! - One of the [fastest](#benchmarks) QOI encoders/decoders out there.
 //! - Compliant with the [latest](https://qoiformat.org/qoi-specification.pdf) QOI format specification.
-//! - Zero unsafe code.
+//! - Zero unsafe code (save for the opt-in `bytemuck::Pod` impl of [`Pixel`], see the `pod`
+//!   feature, the opt-in memory-mapped file I/O, see the `mmap` feature, the opt-in C API, see
+//!   the `ffi` feature, and the opt-in `wasm-bindgen` glue, see the `wasm` feature).
 //! - Supports decoding from / encoding to `std::io` streams directly.
 //! - `no_std` support.
 //! - Roundtrip-tested vs the reference C implementation; fuzz-tested.
@@ -55,8 +57,23 @@
 //! In that case anything related to `std::io`, `std::error::Error` and heap
 //! allocations is disabled. There is an additional `alloc` feature that can
 //! be activated to bring back the support for heap allocations.
+//!
+//! ### Portability
+//!
+//! Encoded images are byte-identical on all targets: multi-byte header and extension fields
+//! are always little-endian, and nothing in the encoder depends on the native byte order.
+//! The only native-endian output is that of
+//! [`Decoder::decode_to_u32_buf`](crate::Decoder::decode_to_u32_buf), whose words are meant
+//! to be consumed as integers (see [`PackedFormat`]).
 
-#![forbid(unsafe_code)]
+#![cfg_attr(
+    not(any(feature = "ffi", feature = "pod", feature = "mmap", feature = "wasm")),
+    forbid(unsafe_code)
+)]
+#![cfg_attr(
+    any(feature = "ffi", feature = "pod", feature = "mmap", feature = "wasm"),
+    deny(unsafe_code)
+)]
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::cargo)]
 #![allow(
     clippy::inline_always,
@@ -74,23 +91,247 @@ extern crate alloc;
 #[cfg(any(feature = "std", test))]
 extern crate std as alloc;
 
+#[cfg(feature = "allocator-api2")]
+mod allocator;
+pub mod analyze;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod anim;
+#[cfg(feature = "tokio")]
+mod async_io;
+#[cfg(feature = "batch")]
+pub mod batch;
+mod border;
+#[cfg(feature = "std")]
+mod capture;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod channels;
+mod checksum;
+mod content_id;
+#[cfg(feature = "base64")]
+mod data_uri;
 mod decode;
+mod decode_state;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod delta;
+pub mod dither;
 mod encode;
 mod error;
+mod ext;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod field;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod framebuffer;
+#[cfg(feature = "std")]
+mod fs;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
 mod header;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub mod icons;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod image;
+#[cfg(feature = "image")]
+mod image_crate;
+mod iter;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod lazy;
+mod limits;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod nine_patch;
+pub mod ops;
+mod packed;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod patch;
 mod pixel;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod quantize;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod recolor;
+#[cfg(feature = "std")]
+mod recording;
+mod rect;
+mod rgb10a2;
+mod rgb565;
+#[cfg(any(feature = "alloc", feature = "std"))]
+mod sanitize;
+mod scale;
+#[cfg(feature = "std")]
+mod stream_encode;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "tracing")]
+mod trace;
+mod transform;
 mod utils;
+mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod yuv;
 
 #[doc(hidden)]
 pub mod consts;
 
+#[cfg(feature = "allocator-api2")]
+pub use crate::allocator::{decode_to_vec_in, encode_to_vec_in};
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::analyze::{explain, Explanation, OpCost};
+#[cfg(feature = "std")]
+pub use crate::anim::{AnimOptions, AnimationEncoder};
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::anim::{AnimationDecoder, FrameInfo};
+#[cfg(feature = "tokio")]
+pub use crate::async_io::AsyncDecoder;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::border::pad_borders;
+pub use crate::border::{pad_borders_to_buf, BorderMode};
+#[cfg(feature = "std")]
+pub use crate::capture::CaptureEncoder;
+pub use crate::checksum::ChecksumKind;
+#[doc(hidden)]
+pub use crate::checksum::Hasher as ChecksumHasher;
+pub use crate::content_id::ContentId;
+#[doc(hidden)]
+pub use crate::content_id::Sha256;
+#[cfg(feature = "base64")]
+pub use crate::data_uri::{from_data_uri, to_data_uri};
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::decode::{decode_compat, decode_headerless, decode_to_pixel_vec, decode_to_vec};
+pub use crate::decode::{decode_header, decode_to_buf, Decoder, DecoderOptions};
+pub use crate::decode_state::DecodeState;
 #[cfg(any(feature = "alloc", feature = "std"))]
-pub use crate::decode::decode_to_vec;
-pub use crate::decode::{decode_header, decode_to_buf, Decoder};
+pub use crate::delta::decode_delta;
 
 #[cfg(any(feature = "alloc", feature = "std"))]
-pub use crate::encode::encode_to_vec;
-pub use crate::encode::{encode_max_len, encode_to_buf, Encoder};
+pub use crate::encode::{encode_headerless, encode_to_vec};
+pub use crate::encode::{
+    encode_max_len, encode_to_buf, AsPixelData, ContentHint, EncodeChunks, Encoder, EncoderOptions,
+};
 
 pub use crate::error::{Error, Result};
-pub use crate::header::Header;
+pub use crate::ext::{FormatVersion, RestartMarker, RestartMarkers};
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::field::weave_fields;
+pub use crate::field::{weave_fields_to_buf, Field};
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::framebuffer::{encode_from_framebuffer, encode_from_framebuffer_u32};
+#[cfg(feature = "std")]
+pub use crate::fs::{write_file_atomic, Fsync};
+pub use crate::header::{Channels, ColorSpace, Dimension, Header, HeaderFormat};
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::image::Image;
+#[cfg(feature = "image")]
+pub use crate::image_crate::ImageWriter;
+pub use crate::iter::{decode_iter, decode_visit, PixelIter};
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::lazy::LazyImage;
+pub use crate::limits::Limits;
+#[cfg(feature = "mmap")]
+pub use crate::mmap::{decode_file, MappedImage};
+pub use crate::nine_patch::NinePatch;
+pub use crate::packed::PackedFormat;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::patch::patch_region;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::pixel::{pixels_from_bytes, pixels_to_bytes};
+pub use crate::pixel::{Pixel, PixelFormat};
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::quantize::{quantize, QuantizeReport};
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::recolor::recolor;
+#[cfg(feature = "std")]
+pub use crate::recording::RecordingWriter;
+pub use crate::rect::Rect;
+pub use crate::rgb565::ByteOrder;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::sanitize::sanitize;
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub use crate::scale::decode_resized;
+pub use crate::scale::{scale_nn, ResizeFilter};
+#[cfg(feature = "std")]
+pub use crate::stream_encode::StreamEncoder;
+#[cfg(feature = "std")]
+pub use crate::transform::TransformReader;
+pub use crate::transform::{StreamTransform, XorTransform};
+pub use crate::view::PixelsView;
+pub use crate::yuv::YuvMatrix;
+
+// Compile-time check that the public types can be shared across threads, so that any change
+// making them `!Send` or `!Sync` is caught here rather than in downstream async code.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    #[cfg(feature = "std")]
+    const fn assert_send<T: Send>() {}
+
+    assert_send_sync::<Encoder<'static>>();
+    assert_send_sync::<EncoderOptions>();
+    assert_send_sync::<Decoder<decode::Bytes<'static>>>();
+    assert_send_sync::<DecoderOptions>();
+    assert_send_sync::<Header>();
+    assert_send_sync::<ChecksumKind>();
+    assert_send_sync::<HeaderFormat>();
+    #[cfg(feature = "http")]
+    assert_send_sync::<http::Body>();
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    assert_send_sync::<Image>();
+    #[cfg(feature = "image")]
+    assert_send_sync::<ImageWriter<std::fs::File>>();
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    assert_send_sync::<LazyImage<'static>>();
+    #[cfg(feature = "batch")]
+    assert_send_sync::<batch::BatchOptions>();
+    #[cfg(feature = "batch")]
+    assert_send_sync::<batch::Report>();
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    assert_send_sync::<Explanation>();
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    assert_send_sync::<AnimationDecoder<'static>>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<AnimOptions>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<RecordingWriter<std::fs::File>>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<StreamEncoder<std::fs::File>>();
+    assert_send_sync::<BorderMode>();
+    assert_send_sync::<ByteOrder>();
+    assert_send_sync::<YuvMatrix>();
+    assert_send_sync::<Channels>();
+    assert_send_sync::<ColorSpace>();
+    assert_send_sync::<ContentHint>();
+    assert_send_sync::<ContentId>();
+    assert_send_sync::<DecodeState>();
+    assert_send_sync::<Field>();
+    assert_send_sync::<Limits>();
+    #[cfg(feature = "metrics")]
+    assert_send_sync::<metrics::Snapshot>();
+    assert_send_sync::<NinePatch>();
+    assert_send_sync::<PackedFormat>();
+    assert_send_sync::<Pixel>();
+    assert_send_sync::<PixelFormat>();
+    assert_send_sync::<PixelIter<'static>>();
+    assert_send_sync::<PixelsView<'static>>();
+    #[cfg(any(feature = "alloc", feature = "std"))]
+    assert_send_sync::<QuantizeReport>();
+    assert_send_sync::<Rect>();
+    assert_send_sync::<ResizeFilter>();
+    assert_send_sync::<RestartMarkers<'static>>();
+    assert_send_sync::<XorTransform<&'static [u8]>>();
+    #[cfg(feature = "fuzzing")]
+    assert_send_sync::<fuzzing::FuzzImage>();
+    assert_send_sync::<Error>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<Decoder<std::fs::File>>();
+    #[cfg(feature = "tokio")]
+    assert_send_sync::<AsyncDecoder<&'static [u8]>>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<TransformReader<std::fs::File, XorTransform<&'static [u8]>>>();
+    // the frame queue receiver is `!Sync`, so the capture encoder can only be moved
+    #[cfg(feature = "std")]
+    assert_send::<CaptureEncoder<std::fs::File>>();
+};
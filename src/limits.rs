@@ -0,0 +1,78 @@
+use crate::consts::QOI_PIXELS_MAX;
+use crate::error::{Error, Result};
+use crate::header::Header;
+use crate::utils::unlikely;
+
+/// Upper bounds on the images accepted when decoding untrusted data.
+///
+/// All limits default to the largest values allowed by the format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Limits {
+    width: u16,
+    height: u16,
+    n_pixels: usize,
+    input_len: usize,
+}
+
+impl Limits {
+    /// Creates limits that only enforce the format's own constraints.
+    #[inline]
+    pub const fn new() -> Self {
+        Self { width: u16::MAX, height: u16::MAX, n_pixels: QOI_PIXELS_MAX, input_len: usize::MAX }
+    }
+
+    /// Maximum image width.
+    #[inline]
+    pub const fn max_width(mut self, width: u16) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Maximum image height.
+    #[inline]
+    pub const fn max_height(mut self, height: u16) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Maximum number of pixels (this can't be raised above the format's limit).
+    #[inline]
+    pub const fn max_pixels(mut self, n_pixels: usize) -> Self {
+        self.n_pixels = if n_pixels < QOI_PIXELS_MAX { n_pixels } else { QOI_PIXELS_MAX };
+        self
+    }
+
+    /// Maximum size of the encoded input in bytes, including the header.
+    #[inline]
+    pub const fn max_input_len(mut self, len: usize) -> Self {
+        self.input_len = len;
+        self
+    }
+
+    /// Checks a decoded header and the size of the encoded input against the limits.
+    ///
+    /// The input size is only checked if it's known (it isn't when decoding from a stream).
+    #[inline]
+    pub const fn check(&self, header: &Header, input_len: Option<usize>) -> Result<()> {
+        let input_len = match input_len {
+            Some(len) => len,
+            None => 0,
+        };
+        if unlikely(
+            header.width > self.width
+                || header.height > self.height
+                || header.n_pixels() > self.n_pixels
+                || input_len > self.input_len,
+        ) {
+            return Err(Error::LimitsExceeded);
+        }
+        Ok(())
+    }
+}
+
+impl Default for Limits {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
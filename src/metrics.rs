@@ -0,0 +1,164 @@
+//! Global performance counters (requires the `metrics` feature).
+//!
+//! Every [`Encoder`](crate::Encoder) and [`Decoder`](crate::Decoder) in the process updates
+//! a set of atomic counters, so services can export metrics about image processing (e.g. to
+//! Prometheus) without wrapping every call site. Nothing is ever sent anywhere: the counters
+//! are only read through [`snapshot`].
+//!
+//! Counters use relaxed atomics, so a snapshot taken while other threads are encoding or
+//! decoding may be slightly out of sync across counters, but never loses updates.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::{Error, Result};
+
+/// Labels of the error kinds, in the order of [`Snapshot::errors`].
+const ERROR_KINDS: [&str; 24] = [
+    "invalid_magic",
+    "invalid_image_dimensions",
+    "invalid_image_length",
+    "data_length_not_set",
+    "output_buffer_too_small",
+    "unexpected_buffer_end",
+    "invalid_padding",
+    "invalid_restart_marker",
+    "limits_exceeded",
+    "out_of_memory",
+    "unsupported_version",
+    "too_many_colors",
+    "invalid_nine_patch",
+    "verification_failed",
+    "invalid_data_uri",
+    "invalid_channels",
+    "invalid_color_space",
+    "size_overflow",
+    "field_mismatch",
+    "checksum_mismatch",
+    "pixel_count_mismatch",
+    "length_mismatch",
+    "trailing_data",
+    "io_error",
+];
+
+static IMAGES_ENCODED: AtomicU64 = AtomicU64::new(0);
+static IMAGES_DECODED: AtomicU64 = AtomicU64::new(0);
+static BYTES_ENCODED: AtomicU64 = AtomicU64::new(0);
+static BYTES_DECODED: AtomicU64 = AtomicU64::new(0);
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU64 = AtomicU64::new(0);
+static ERRORS: [AtomicU64; ERROR_KINDS.len()] = [ZERO; ERROR_KINDS.len()];
+
+/// Point-in-time copy of the global counters.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+    /// Number of images encoded successfully
+    pub images_encoded: u64,
+    /// Number of images decoded successfully
+    pub images_decoded: u64,
+    /// Total size of the encoded images in bytes, including headers
+    pub bytes_encoded: u64,
+    /// Total size of the decoded pixel data in bytes
+    pub bytes_decoded: u64,
+    errors: [u64; ERROR_KINDS.len()],
+}
+
+impl Snapshot {
+    /// Iterates over the number of errors of each kind, labeled in `snake_case`
+    /// (e.g. `"invalid_magic"`), including kinds that haven't occurred.
+    #[inline]
+    pub fn errors(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        ERROR_KINDS.iter().copied().zip(self.errors.iter().copied())
+    }
+
+    /// Total number of errors of all kinds.
+    #[inline]
+    pub fn total_errors(&self) -> u64 {
+        self.errors.iter().sum()
+    }
+}
+
+/// Reads the current values of all counters.
+pub fn snapshot() -> Snapshot {
+    let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+    let mut errors = [0; ERROR_KINDS.len()];
+    for (out, counter) in errors.iter_mut().zip(&ERRORS) {
+        *out = load(counter);
+    }
+    Snapshot {
+        images_encoded: load(&IMAGES_ENCODED),
+        images_decoded: load(&IMAGES_DECODED),
+        bytes_encoded: load(&BYTES_ENCODED),
+        bytes_decoded: load(&BYTES_DECODED),
+        errors,
+    }
+}
+
+/// Resets all counters to zero.
+pub fn reset() {
+    let counters = [&IMAGES_ENCODED, &IMAGES_DECODED, &BYTES_ENCODED, &BYTES_DECODED];
+    for counter in counters.into_iter().chain(&ERRORS) {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+#[inline]
+const fn error_index(err: &Error) -> usize {
+    match *err {
+        Error::InvalidMagic { .. } => 0,
+        Error::InvalidImageDimensions { .. } => 1,
+        Error::InvalidImageLength { .. } => 2,
+        Error::DataLengthNotSet => 3,
+        Error::OutputBufferTooSmall { .. } => 4,
+        Error::UnexpectedBufferEnd { .. } => 5,
+        Error::InvalidPadding { .. } => 6,
+        Error::InvalidRestartMarker => 7,
+        Error::LimitsExceeded => 8,
+        Error::OutOfMemory => 9,
+        Error::UnsupportedVersion { .. } => 10,
+        Error::TooManyColors { .. } => 11,
+        Error::InvalidNinePatch => 12,
+        Error::VerificationFailed => 13,
+        Error::InvalidDataUri => 14,
+        Error::InvalidChannels { .. } => 15,
+        Error::InvalidColorSpace { .. } => 16,
+        Error::SizeOverflow => 17,
+        Error::FieldMismatch => 18,
+        Error::ChecksumMismatch => 19,
+        Error::PixelCountMismatch { .. } => 20,
+        Error::LengthMismatch { .. } => 21,
+        Error::TrailingData { .. } => 22,
+        #[cfg(feature = "std")]
+        Error::IoError(_) => 23,
+    }
+}
+
+/// Counts the error of a failed operation, if any.
+#[doc(hidden)]
+#[inline]
+pub fn record_error<T>(result: &Result<T>) {
+    if let Err(err) = result {
+        ERRORS[error_index(err)].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Counts an encoded image given the number of bytes written.
+#[doc(hidden)]
+#[inline]
+pub fn record_encoded(result: &Result<usize>) {
+    if let Ok(size) = *result {
+        IMAGES_ENCODED.fetch_add(1, Ordering::Relaxed);
+        BYTES_ENCODED.fetch_add(size as u64, Ordering::Relaxed);
+    }
+    record_error(result);
+}
+
+/// Counts a decoded image given the number of bytes written.
+#[doc(hidden)]
+#[inline]
+pub fn record_decoded(result: &Result<usize>) {
+    if let Ok(size) = *result {
+        IMAGES_DECODED.fetch_add(1, Ordering::Relaxed);
+        BYTES_DECODED.fetch_add(size as u64, Ordering::Relaxed);
+    }
+    record_error(result);
+}
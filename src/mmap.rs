@@ -0,0 +1,182 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+#[cfg(unix)]
+use memmap2::Advice;
+use memmap2::{Mmap, MmapMut};
+
+use crate::decode::{Bytes, Decoder};
+use crate::encode::Encoder;
+use crate::error::{Error, Result};
+use crate::header::Header;
+
+/// Maps a file for reading.
+fn map(file: &File) -> io::Result<Mmap> {
+    // SAFETY: the mapping is only ever read through shared slices while it lives, and decoders
+    // check every offset, so other processes writing to the file can only make decoding fail;
+    // truncation can't be guarded against, which the public functions document
+    #[allow(unsafe_code)]
+    unsafe {
+        Mmap::map(file)
+    }
+}
+
+/// Encoded image mapped into memory from a file, see [`Decoder::open_mmap`].
+///
+/// The pages of the file are only read from disk as they are accessed, so decoding a few bands
+/// of a huge image doesn't read the rest of it. Dereferences to the encoded bytes.
+pub struct MappedImage {
+    map: Mmap,
+}
+
+impl MappedImage {
+    /// Creates a decoder reading from the mapping.
+    ///
+    /// This only parses the header and the extension block again, which [`Decoder::open_mmap`]
+    /// already validated; it can still fail if the file has been modified since.
+    #[inline]
+    pub fn decoder(&self) -> Result<Decoder<Bytes<'_>>> {
+        Decoder::new(&*self.map)
+    }
+
+    /// Returns the encoded bytes of the image.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Hints the operating system that a band of the image (see
+    /// [`Decoder::decode_band_to_buf`]) is going to be decoded soon, so that its pages are read
+    /// ahead in the background.
+    ///
+    /// This only has an effect on Unix; elsewhere, or if the hint is rejected, the pages are
+    /// read when the band is decoded as usual.
+    pub fn prefetch_band(&self, band: usize) -> Result<()> {
+        let decoder = self.decoder()?;
+        let markers = decoder.restart_markers().ok_or(Error::InvalidRestartMarker)?;
+        let start = markers.band(band).ok_or(Error::InvalidRestartMarker)?.offset;
+        let ops_len = decoder.header().length.unwrap_or_default() as usize;
+        let end = markers.band(band + 1).map_or(ops_len, |next| next.offset);
+        let (offset, len) = (decoder.header_format().size() + start, end.saturating_sub(start));
+        #[cfg(unix)]
+        let _ = self.map.advise_range(Advice::WillNeed, offset, len);
+        #[cfg(not(unix))]
+        let _ = (offset, len);
+        Ok(())
+    }
+}
+
+impl core::ops::Deref for MappedImage {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+impl AsRef<[u8]> for MappedImage {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+/// Decodes an image file into a newly allocated vector, reading the file through a mapping
+/// rather than into an intermediate buffer.
+///
+/// Files that can't be mapped (pipes, some network filesystems) are read into memory instead.
+/// As with [`Decoder::open_mmap`], the file must not be truncated while it is being decoded.
+///
+/// Note: the resulting number of channels will match the layout the image has been encoded
+/// from. In order to change the number of channels, use [`Decoder::with_channels`].
+pub fn decode_file(path: impl AsRef<Path>) -> Result<(Header, Vec<u8>)> {
+    let file = File::open(path)?;
+    match map(&file) {
+        Ok(map) => {
+            #[cfg(unix)]
+            let _ = map.advise(Advice::Sequential);
+            crate::decode_to_vec(&*map)
+        }
+        Err(_) => crate::decode_to_vec(read_to_end(file)?),
+    }
+}
+
+/// Reads the rest of a file that couldn't be mapped.
+fn read_to_end(mut file: File) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    io::Read::read_to_end(&mut file, &mut data)?;
+    Ok(data)
+}
+
+impl Decoder<Bytes<'_>> {
+    /// Maps an encoded image from a file into memory, for decoding it without reading it
+    /// whole first.
+    ///
+    /// The header and the extension block are validated right away. Images without restart
+    /// markers can only be decoded front to back, so the operating system is told to read them
+    /// ahead sequentially; bands of images with restart markers can be prefetched one by one
+    /// with [`MappedImage::prefetch_band`]. Files that can't be mapped (pipes, some network
+    /// filesystems) are an error, as reading them into memory instead would defeat the purpose.
+    ///
+    /// The file must not be truncated while it is mapped, which may crash the process;
+    /// changed bytes decode to garbage or fail with an error.
+    pub fn open_mmap(path: impl AsRef<Path>) -> Result<MappedImage> {
+        let image = MappedImage { map: map(&File::open(path)?)? };
+        let is_banded = image.decoder()?.restart_markers().is_some();
+        #[cfg(unix)]
+        if !is_banded {
+            let _ = image.map.advise(Advice::Sequential);
+        }
+        #[cfg(not(unix))]
+        let _ = is_banded;
+        Ok(image)
+    }
+}
+
+impl Encoder<'_> {
+    /// Encodes the image into a memory-mapped file and returns the number of bytes written.
+    ///
+    /// The file is sized for the largest possible output, mapped, encoded into and then
+    /// truncated to the bytes written, so even giant images never need an in-memory output
+    /// buffer, whatever the [`EncoderOptions`](crate::EncoderOptions). Files that can't be
+    /// resized or mapped (pipes, character devices, some network filesystems) are written
+    /// through a buffer by [`Encoder::encode_to_file`] instead.
+    ///
+    /// Any path type implementing `AsRef<Path>` may be used, including `camino::Utf8Path`.
+    pub fn encode_to_file_mmap(&mut self, path: impl AsRef<Path>) -> Result<usize> {
+        let path = path.as_ref();
+        encode_to_file(self, &create(path)?).unwrap_or_else(|_| self.encode_to_file(path))
+    }
+}
+
+/// Creates or truncates a file for [`encode_to_file`], which needs to read it as well to map it.
+pub fn create(path: impl AsRef<Path>) -> io::Result<File> {
+    OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)
+}
+
+/// Encodes the image into `file` (see [`create`]) through a writable mapping sized for the
+/// largest possible output, then truncates the file to the bytes written.
+///
+/// Fails with the outer error without writing anything if the file can't be resized or mapped.
+pub fn encode_to_file(encoder: &mut Encoder, file: &File) -> io::Result<Result<usize>> {
+    let max_len = encoder.required_buf_len();
+    file.set_len(max_len as u64)?;
+    // SAFETY: the file was just created by the caller and isn't shared with anything else while
+    // the mapping lives, as the mapping is dropped before returning
+    #[allow(unsafe_code)]
+    let map = unsafe { MmapMut::map_mut(file) };
+    let mut map = match map {
+        Ok(map) => map,
+        Err(err) => {
+            let _ = file.set_len(0);
+            return Err(err);
+        }
+    };
+    let result = encoder.encode_to_buf(&mut map[..]);
+    drop(map);
+    // on failure, leave the file empty as the buffered path does, not full of zeros
+    let len = result.as_ref().map_or(0, |&n| n);
+    Ok(file.set_len(len as u64).map_err(Error::from).and(result))
+}
@@ -0,0 +1,84 @@
+use crate::error::{Error, Result};
+use crate::utils::unlikely;
+
+/// Nine-patch (9-slice) metadata of a scalable UI asset, like in Android's `.9.png` images.
+///
+/// The stretchable columns and rows split the image into nine slices: the corners are drawn
+/// as is, the edges are stretched along one axis and the center along both. The padding box
+/// is the area where content (e.g. the label of a button) goes once the image is scaled.
+///
+/// All ranges are `start..end` pixel coordinates, `end` being exclusive. Stored in the
+/// extension block, see [`Encoder::with_nine_patch`](crate::Encoder::with_nine_patch) and
+/// [`Header::nine_patch`](crate::Header::nine_patch).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NinePatch {
+    /// Columns that are stretched horizontally
+    pub stretch_x: (u16, u16),
+    /// Rows that are stretched vertically
+    pub stretch_y: (u16, u16),
+    /// Columns of the padding box
+    pub padding_x: (u16, u16),
+    /// Rows of the padding box
+    pub padding_y: (u16, u16),
+}
+
+impl NinePatch {
+    /// Creates nine-patch metadata with given stretchable ranges, the padding box being the
+    /// same as the stretchable area.
+    #[inline]
+    pub const fn new(stretch_x: (u16, u16), stretch_y: (u16, u16)) -> Self {
+        Self { stretch_x, stretch_y, padding_x: stretch_x, padding_y: stretch_y }
+    }
+
+    /// Replaces the padding box.
+    #[inline]
+    pub const fn with_padding(mut self, padding_x: (u16, u16), padding_y: (u16, u16)) -> Self {
+        self.padding_x = padding_x;
+        self.padding_y = padding_y;
+        self
+    }
+
+    /// Checks that all ranges are non-empty and lie within an image of given dimensions.
+    #[inline]
+    pub const fn check(&self, width: u16, height: u16) -> Result<()> {
+        const fn is_valid((start, end): (u16, u16), len: u16) -> bool {
+            start < end && end <= len
+        }
+        if unlikely(
+            !is_valid(self.stretch_x, width)
+                || !is_valid(self.stretch_y, height)
+                || !is_valid(self.padding_x, width)
+                || !is_valid(self.padding_y, height),
+        ) {
+            return Err(Error::InvalidNinePatch);
+        }
+        Ok(())
+    }
+
+    /// Serializes the ranges as little-endian `u16` pairs.
+    #[doc(hidden)]
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let ranges = [self.stretch_x, self.stretch_y, self.padding_x, self.padding_y];
+        let mut out = [0; 16];
+        for (chunk, (start, end)) in out.chunks_exact_mut(4).zip(ranges) {
+            chunk[..2].copy_from_slice(&start.to_le_bytes());
+            chunk[2..].copy_from_slice(&end.to_le_bytes());
+        }
+        out
+    }
+
+    /// Deserializes the ranges, returns `None` if the payload has the wrong size.
+    #[doc(hidden)]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 16 {
+            return None;
+        }
+        let mut ranges = [(0, 0); 4];
+        for (range, chunk) in ranges.iter_mut().zip(bytes.chunks_exact(4)) {
+            let start = u16::from_le_bytes([chunk[0], chunk[1]]);
+            *range = (start, u16::from_le_bytes([chunk[2], chunk[3]]));
+        }
+        let [stretch_x, stretch_y, padding_x, padding_y] = ranges;
+        Some(Self { stretch_x, stretch_y, padding_x, padding_y })
+    }
+}
@@ -0,0 +1,171 @@
+//! Low-level access to the op stream of encoded images.
+//!
+//! This is meant for tools that need to look at the structure of the encoded data (indexes,
+//! visualizations, integrity checks) rather than just at the decoded pixels.
+
+use crate::consts::{QOI_MASK_2, QOI_OP_DIFF, QOI_OP_INDEX, QOI_OP_LUMA, QOI_OP_RGB, QOI_OP_RGBA};
+use crate::decode::split_header;
+use crate::error::{Error, Result};
+use crate::pixel::Pixel;
+
+/// Kind of a single op in the encoded stream.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OpKind {
+    /// `QOI_OP_INDEX`: pixel from the color index
+    Index,
+    /// `QOI_OP_DIFF`: small difference from the previous pixel
+    Diff,
+    /// `QOI_OP_LUMA`: green-relative difference from the previous pixel
+    Luma,
+    /// `QOI_OP_RUN`: the previous pixel, repeated
+    Run,
+    /// `QOI_OP_RGB`: literal color, alpha of the previous pixel
+    Rgb,
+    /// `QOI_OP_RGBA`: literal color with alpha
+    Rgba,
+}
+
+impl OpKind {
+    /// Determines the op kind from its first byte.
+    #[inline]
+    pub const fn from_byte(b1: u8) -> Self {
+        match b1 {
+            QOI_OP_RGB => Self::Rgb,
+            QOI_OP_RGBA => Self::Rgba,
+            _ => match b1 & QOI_MASK_2 {
+                QOI_OP_INDEX => Self::Index,
+                QOI_OP_DIFF => Self::Diff,
+                QOI_OP_LUMA => Self::Luma,
+                _ => Self::Run,
+            },
+        }
+    }
+
+    /// Number of bytes the op takes in the encoded stream.
+    #[inline]
+    pub const fn n_bytes(self) -> usize {
+        match self {
+            Self::Index | Self::Diff | Self::Run => 1,
+            Self::Luma => 2,
+            Self::Rgb => 4,
+            Self::Rgba => 5,
+        }
+    }
+}
+
+/// A single decoded op.
+#[derive(Copy, Clone, Debug)]
+pub struct Op {
+    /// Byte offset of the op, relative to the start of the op stream
+    pub offset: usize,
+    /// Op kind
+    pub kind: OpKind,
+    /// Pixel value produced by the op
+    pub px: Pixel,
+    /// Number of pixels produced by the op (only runs produce more than one)
+    pub n_pixels: usize,
+}
+
+/// Decodes the op stream one op at a time, keeping track of byte offsets.
+///
+/// This is a lot slower than the regular decoding loop and is meant for tools that need to
+/// look at the structure of the op stream rather than just at the pixels.
+#[derive(Clone)]
+pub struct OpDecoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+    index: [Pixel; 256],
+    px: Pixel,
+    n_pixels: usize,
+}
+
+impl<'a> OpDecoder<'a> {
+    /// Creates an op decoder over the op stream (data following the header).
+    #[inline]
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            index: [Pixel::new(); 256],
+            px: Pixel::new().with_a(0xff),
+            n_pixels: 0,
+        }
+    }
+
+    /// Byte offset of the next op, relative to the start of the op stream.
+    #[inline]
+    pub const fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// Decodes the next op.
+    #[inline]
+    pub fn next_op(&mut self) -> Result<Op> {
+        let offset = self.pos;
+        let pixel = self.n_pixels;
+        let end = || Error::UnexpectedBufferEnd { offset, pixel };
+        let b1 = *self.data.get(offset).ok_or_else(end)?;
+        let kind = OpKind::from_byte(b1);
+        let op = self.data.get(offset..offset + kind.n_bytes()).ok_or_else(end)?;
+        let mut n_pixels = 1;
+        match kind {
+            OpKind::Index => self.px = self.index[b1 as usize],
+            OpKind::Diff => self.px.update_diff(b1),
+            OpKind::Luma => self.px.update_luma(b1, op[1]),
+            OpKind::Run => n_pixels = (b1 & 0x3f) as usize + 1,
+            OpKind::Rgb => self.px.update_rgb(op[1], op[2], op[3]),
+            OpKind::Rgba => self.px.update_rgba(op[1], op[2], op[3], op[4]),
+        }
+        if !matches!(kind, OpKind::Index | OpKind::Run) {
+            self.index[self.px.hash_index() as usize] = self.px;
+        }
+        self.pos += kind.n_bytes();
+        self.n_pixels += n_pixels;
+        Ok(Op { offset, kind, px: self.px, n_pixels })
+    }
+}
+
+/// Iterator over the ops of an encoded image, see [`offsets`].
+#[derive(Clone, Debug)]
+pub struct Offsets<'a> {
+    data: &'a [u8],
+    pos: usize,
+    n_left: usize,
+}
+
+impl Iterator for Offsets<'_> {
+    type Item = (usize, OpKind, u32);
+
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.n_left == 0 {
+            return None;
+        }
+        let offset = self.pos;
+        let b1 = *self.data.get(offset)?;
+        let kind = OpKind::from_byte(b1);
+        if offset + kind.n_bytes() > self.data.len() {
+            return None;
+        }
+        let n_pixels = match kind {
+            OpKind::Run => ((b1 & 0x3f) as usize + 1).min(self.n_left),
+            _ => 1,
+        };
+        self.pos += kind.n_bytes();
+        self.n_left -= n_pixels;
+        // can't truncate: runs are at most 62 pixels long
+        Some((offset, kind, n_pixels as u32))
+    }
+}
+
+/// Iterate over the ops of an encoded image without decoding any pixel values.
+///
+/// Yields the byte offset of each op (relative to the start of `data`, i.e. including the
+/// header), its kind and the number of pixels it produces. Iteration stops once all pixels of
+/// the image are covered, or early if the op stream is truncated.
+#[inline]
+pub fn offsets(data: &[u8]) -> Result<Offsets<'_>> {
+    let (header, _, body) = split_header(data)?;
+    Ok(Offsets { data, pos: data.len() - body.len(), n_left: header.n_pixels() })
+}
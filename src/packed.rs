@@ -0,0 +1,30 @@
+use crate::pixel::Pixel;
+
+/// Layout of the `u32` words written by
+/// [`Decoder::decode_to_u32_buf`](crate::Decoder::decode_to_u32_buf).
+///
+/// Formats describe the value of each word, not its bytes in memory, so they hold regardless of
+/// the endianness of the target: `0xAARRGGBB` is stored as `B, G, R, A` bytes on little-endian
+/// targets and as `A, R, G, B` bytes on big-endian ones.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PackedFormat {
+    /// `0xAARRGGBB`, as used by `minifb` and `softbuffer` (which ignore the alpha byte)
+    #[default]
+    Argb,
+    /// `0xRRGGBBAA`, same as [`Pixel::to_u32`]
+    Rgba,
+    /// `0xAABBGGRR`, i.e. `R, G, B, A` bytes in memory on little-endian targets
+    Abgr,
+}
+
+/// Packs a pixel into the native-endian bytes of a word in the given format.
+#[inline]
+pub const fn pack(px: Pixel, format: PackedFormat) -> [u8; 4] {
+    let (r, g, b, a) = (px.r() as u32, px.g() as u32, px.b() as u32, px.a() as u32);
+    let value = match format {
+        PackedFormat::Argb => (a << 24) | (r << 16) | (g << 8) | b,
+        PackedFormat::Rgba => (r << 24) | (g << 16) | (b << 8) | a,
+        PackedFormat::Abgr => (a << 24) | (b << 16) | (g << 8) | r,
+    };
+    value.to_ne_bytes()
+}
@@ -0,0 +1,104 @@
+use alloc::vec::Vec;
+
+use crate::consts::{QOI_HEADER_SIZE, QOI_PADDING, QOI_PADDING_SIZE};
+use crate::decode::Decoder;
+use crate::encode::{encode_impl, encode_max_len, EncoderOptions, Tolerance};
+use crate::error::{Error, Result};
+use crate::ext::{self, ext_len, write_ext};
+use crate::utils::{try_vec_with_capacity, try_vec_zeroed, unlikely, BytesMut};
+
+/// Overwrite a rectangular region of an encoded image with raw pixels.
+///
+/// The patch must use the same [`Channels`](crate::Channels) layout as the image itself: RGB,
+/// RGBA or luma + alpha, whichever the image has been encoded from.
+///
+/// Only the bands of rows touched by the region are decoded and re-encoded; the op stream of
+/// all other bands is copied over as is. This requires the image to have been encoded with
+/// restart markers (see [`EncoderOptions::restart_interval`](crate::EncoderOptions)), which
+/// are kept in the output along with any nine-patch metadata; a checksum of the op stream is
+/// recomputed, and other extension records are dropped.
+pub fn patch_region(
+    data: impl AsRef<[u8]>, x: u16, y: u16, patch: impl AsRef<[u8]>, pw: u16, ph: u16,
+) -> Result<Vec<u8>> {
+    let (data, patch) = (data.as_ref(), patch.as_ref());
+    let decoder = Decoder::new(data)?;
+    let mut header = *decoder.header();
+    let (width, height) = (header.width, header.height);
+    if unlikely(
+        pw == 0
+            || ph == 0
+            || u32::from(x) + u32::from(pw) > u32::from(width)
+            || u32::from(y) + u32::from(ph) > u32::from(height),
+    ) {
+        return Err(Error::InvalidImageDimensions { width: pw, height: ph });
+    }
+    let (channels, nine_patch, field) = (decoder.channels(), decoder.nine_patch(), decoder.field());
+    let colorspace = header.colorspace;
+    let bpp = channels.as_u8() as usize;
+    let patch_row_len = pw as usize * bpp;
+    if unlikely(patch.len() != patch_row_len * ph as usize) {
+        return Err(Error::InvalidImageLength { size: patch.len(), width: pw, height: ph });
+    }
+
+    let markers = decoder.restart_markers().ok_or(Error::InvalidRestartMarker)?;
+    let interval = markers.interval();
+    let (first, last) = ((y / interval) as usize, ((y + (ph - 1)) / interval) as usize);
+    let start = markers.band(first).ok_or(Error::InvalidRestartMarker)?;
+    let ops_len = header.length.unwrap_or_default() as usize;
+    let ops = data[QOI_HEADER_SIZE..]
+        .get(..ops_len.saturating_sub(QOI_PADDING_SIZE))
+        .ok_or(Error::UnexpectedBufferEnd { offset: 0, pixel: 0 })?;
+    let checksum = ext::checksum(&data[QOI_HEADER_SIZE..], ops_len).map(|checksum| checksum.kind);
+    let end = markers.band(last + 1).map_or(ops.len(), |marker| marker.offset);
+    let head = ops.get(..start.offset).ok_or(Error::InvalidRestartMarker)?;
+    let tail = ops.get(end..).ok_or(Error::InvalidRestartMarker)?;
+
+    let row_len = width as usize * bpp;
+    let end_row = markers.band(last).map_or(height, |marker| marker.row + marker.n_rows);
+    let mut rows = try_vec_zeroed((end_row - start.row) as usize * row_len)?;
+    let mut n_decoded = 0;
+    for band in first..=last {
+        n_decoded += decoder.decode_band_to_buf(band, &mut rows[n_decoded..])?;
+    }
+    for (py, src) in patch.chunks_exact(patch_row_len).enumerate() {
+        let dst = (y - start.row) as usize * row_len + py * row_len + x as usize * bpp;
+        rows[dst..dst + patch_row_len].copy_from_slice(src);
+    }
+
+    let mut band_ops = try_vec_zeroed(encode_max_len(width, interval) * (last - first + 1))?;
+    let band_pixels = interval as usize * width as usize;
+    let (buf, options) = (BytesMut::new(&mut band_ops), EncoderOptions::new());
+    let (rows, restart_first, tolerance) = (rows.as_slice(), first != 0, Tolerance::LOSSLESS);
+    let n_band_ops =
+        encode_impl(buf, &[rows], channels, None, band_pixels, options, restart_first, tolerance)?;
+    let band_ops = &band_ops[..n_band_ops - QOI_PADDING_SIZE];
+
+    let new_ops_len = head.len() + band_ops.len() + tail.len() + QOI_PADDING_SIZE;
+    header.length = Some(u32::try_from(new_ops_len).map_err(|_| Error::SizeOverflow)?);
+    let n_ext =
+        ext_len(height, interval, channels, nine_patch.as_ref(), field, colorspace, &[], checksum);
+    let out_len = QOI_HEADER_SIZE + new_ops_len + n_ext;
+    let mut out = try_vec_with_capacity(out_len)?;
+    out.extend_from_slice(&header.encode()?);
+    out.extend_from_slice(head);
+    out.extend_from_slice(band_ops);
+    out.extend_from_slice(tail);
+    out.extend_from_slice(&QOI_PADDING);
+    out.resize(out_len, 0);
+    let (ops, ext) = out[QOI_HEADER_SIZE..].split_at_mut(new_ops_len);
+    let (nine_patch, checksum) = (nine_patch.as_ref(), checksum.map(|kind| kind.checksum(ops)));
+    let _ = write_ext(
+        ops,
+        ext,
+        width,
+        height,
+        interval,
+        channels,
+        nine_patch,
+        field,
+        colorspace,
+        &[],
+        checksum,
+    );
+    Ok(out)
+}
@@ -1,18 +1,63 @@
+#[cfg(any(feature = "alloc", feature = "std"))]
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+use bytemuck::cast_slice;
+
 use crate::consts::{QOI_OP_DIFF, QOI_OP_LUMA, QOI_OP_RGB, QOI_OP_RGBA};
+#[cfg(any(feature = "alloc", feature = "std"))]
+use crate::error::Error;
 use crate::error::Result;
+use crate::header::Channels;
+#[cfg(any(feature = "alloc", feature = "std"))]
+use crate::header::{Dimension, Header};
+use crate::rgb565::{self, ByteOrder};
 use crate::utils::Writer;
-use bytemuck::{cast, Pod};
+#[cfg(any(feature = "alloc", feature = "std"))]
+use crate::utils::{try_vec_with_capacity, unlikely};
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// A single RGBA pixel.
+///
+/// Pixels are ordered and hashed by their packed `0xRRGGBBAA` value (see [`Pixel::to_u32`]),
+/// so they can be used as keys in maps and sets directly.
+///
+/// The layout is guaranteed as part of the public API: `Pixel` is `#[repr(transparent)]` over
+/// `[u8; 4]` holding R, G, B and A in this order, with a size of 4 and an alignment of 1.
+/// With the `pod` feature, it implements `bytemuck::Pod` and `Zeroable`, so RGBA pixel data
+/// can be cast to `&[Pixel]` and back with `bytemuck::cast_slice` without copying.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 #[repr(transparent)]
 pub struct Pixel([u8; 4]);
 
+// SAFETY: `Pixel` is `repr(transparent)` over `[u8; 4]`, which is `Zeroable` and `Pod`.
+#[cfg(feature = "pod")]
+#[allow(unsafe_code)]
+unsafe impl bytemuck::Zeroable for Pixel {}
+#[cfg(feature = "pod")]
+#[allow(unsafe_code)]
+unsafe impl bytemuck::Pod for Pixel {}
+
 impl Pixel {
+    /// Creates a new pixel with all channels set to zero.
     #[inline]
     pub const fn new() -> Self {
         Self([0; 4])
     }
 
+    /// Packs the pixel into a `u32` as `0xRRGGBBAA`.
+    #[inline]
+    pub const fn to_u32(self) -> u32 {
+        u32::from_be_bytes(self.0)
+    }
+
+    /// Unpacks a pixel from a `u32` laid out as `0xRRGGBBAA`.
+    #[inline]
+    pub const fn from_u32(value: u32) -> Self {
+        Self(value.to_be_bytes())
+    }
+
+    #[doc(hidden)]
     #[inline]
     pub fn read(&mut self, s: &[u8]) {
         if s.len() == 4 {
@@ -21,11 +66,29 @@ impl Pixel {
                 self.0[i] = s[i];
                 i += 1;
             }
+        } else if s.len() == 3 {
+            self.0 = [s[0], s[1], s[2], 0xff];
+        } else if s.len() == 2 {
+            self.0 = [s[0], s[0], s[0], s[1]];
         } else {
             unreachable!();
         }
     }
 
+    /// Reads a pixel of `N` bytes (2, 3 or 4) like [`Pixel::read`], the layout being known at
+    /// compile time.
+    #[doc(hidden)]
+    #[inline(always)]
+    pub fn read_n<const N: usize>(&mut self, s: &[u8]) {
+        match N {
+            4 => self.0 = [s[0], s[1], s[2], s[3]],
+            3 => self.0 = [s[0], s[1], s[2], 0xff],
+            2 => self.0 = [s[0], s[0], s[0], s[1]],
+            _ => unreachable!(),
+        }
+    }
+
+    #[doc(hidden)]
     #[inline]
     pub fn update(&mut self, px: Pixel) {
         for i in 0..4 {
@@ -33,6 +96,7 @@ impl Pixel {
         }
     }
 
+    #[doc(hidden)]
     #[inline]
     pub fn update_rgb(&mut self, r: u8, g: u8, b: u8) {
         self.0[0] = r;
@@ -40,6 +104,7 @@ impl Pixel {
         self.0[2] = b;
     }
 
+    #[doc(hidden)]
     #[inline]
     pub fn update_rgba(&mut self, r: u8, g: u8, b: u8, a: u8) {
         self.0[0] = r;
@@ -48,6 +113,7 @@ impl Pixel {
         self.0[3] = a;
     }
 
+    #[doc(hidden)]
     #[inline]
     pub fn update_diff(&mut self, b1: u8) {
         self.0[0] = self.0[0].wrapping_add((b1 >> 4) & 0x03).wrapping_sub(2);
@@ -55,6 +121,7 @@ impl Pixel {
         self.0[2] = self.0[2].wrapping_add(b1 & 0x03).wrapping_sub(2);
     }
 
+    #[doc(hidden)]
     #[inline]
     pub fn update_luma(&mut self, b1: u8, b2: u8) {
         let vg = (b1 & 0x3f).wrapping_sub(32);
@@ -66,6 +133,7 @@ impl Pixel {
         self.0[2] = self.0[2].wrapping_add(vb);
     }
 
+    #[doc(hidden)]
     #[inline]
     pub const fn as_rgba(self) -> Pixel {
         let mut i = 0;
@@ -77,44 +145,68 @@ impl Pixel {
         out
     }
 
+    /// Red channel.
     #[inline]
     pub const fn r(self) -> u8 {
         self.0[0]
     }
 
+    /// Green channel.
     #[inline]
     pub const fn g(self) -> u8 {
         self.0[1]
     }
 
+    /// Blue channel.
     #[inline]
     pub const fn b(self) -> u8 {
         self.0[2]
     }
 
+    /// Alpha channel.
     #[inline]
     pub const fn a(self) -> u8 {
         self.0[3]
     }
 
+    /// Luma of the color (integer approximation of BT.601 weights).
+    ///
+    /// Gray pixels map exactly to their common channel value.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn luma(self) -> u8 {
+        ((77 * self.r() as u32 + 150 * self.g() as u32 + 29 * self.b() as u32) >> 8) as u8
+    }
+
+    /// Returns a copy of the pixel with the alpha channel replaced.
     #[inline]
     pub const fn with_a(mut self, value: u8) -> Self {
         self.0[3] = value;
         self
     }
 
+    #[doc(hidden)]
     #[inline]
     #[allow(clippy::cast_lossless, clippy::cast_possible_truncation)]
-    pub fn hash_index(self) -> u8
-    where
-        [u8; 4]: Pod,
-    {
-        // credits for the initial idea: @zakarumych
-        let v = u32::from_le_bytes(cast(self.0)) as u64;
+    pub const fn hash_index(self) -> u8 {
+        // credits for the initial idea: @zakarumych; the bytes are explicitly read as a
+        // little-endian word, so the hash is the same on big-endian targets
+        let v = u32::from_le_bytes(self.0) as u64;
         let s = ((v & 0xff00_ff00) << 32) | (v & 0x00ff_00ff);
         (s.wrapping_mul(0x0300_0700_0005_000b_u64) >> 56) as u8 & 63
     }
 
+    /// Whether the colors differ by at most `tolerance` in each of R, G and B, with equal alpha.
+    #[doc(hidden)]
+    #[inline]
+    pub const fn is_close(self, other: Self, tolerance: u8) -> bool {
+        self.0[0].abs_diff(other.0[0]) <= tolerance
+            && self.0[1].abs_diff(other.0[1]) <= tolerance
+            && self.0[2].abs_diff(other.0[2]) <= tolerance
+            && self.0[3] == other.0[3]
+    }
+
+    #[doc(hidden)]
     #[inline]
     pub fn rgb_add(&mut self, r: u8, g: u8, b: u8) {
         self.0[0] = self.0[0].wrapping_add(r);
@@ -122,33 +214,45 @@ impl Pixel {
         self.0[2] = self.0[2].wrapping_add(b);
     }
 
+    /// Encodes the pixel as a DIFF, LUMA, RGB or RGBA op, whichever is the smallest.
+    ///
+    /// With `luma_first`, the range of LUMA is checked before DIFF, which is faster when most
+    /// pixels don't fit either; the op chosen is the same.
+    #[doc(hidden)]
     #[inline]
-    pub fn encode_into<W: Writer>(&self, px_prev: Self, buf: W) -> Result<W> {
+    pub fn encode_into<W: Writer>(&self, px_prev: Self, buf: W, luma_first: bool) -> Result<W> {
         if self.a() == px_prev.0[3] {
-            let vg = self.g().wrapping_sub(px_prev.g());
-            let vg_32 = vg.wrapping_add(32);
-            if vg_32 | 63 == 63 {
-                let vr = self.r().wrapping_sub(px_prev.r());
-                let vb = self.b().wrapping_sub(px_prev.b());
-                let vg_r = vr.wrapping_sub(vg);
-                let vg_b = vb.wrapping_sub(vg);
-                let (vr_2, vg_2, vb_2) =
-                    (vr.wrapping_add(2), vg.wrapping_add(2), vb.wrapping_add(2));
-                if vr_2 | vg_2 | vb_2 | 3 == 3 {
-                    buf.write_one(QOI_OP_DIFF | (vr_2 << 4) | (vg_2 << 2) | vb_2)
-                } else {
-                    let (vg_r_8, vg_b_8) = (vg_r.wrapping_add(8), vg_b.wrapping_add(8));
-                    if vg_r_8 | vg_b_8 | 15 == 15 {
-                        buf.write_many(&[QOI_OP_LUMA | vg_32, (vg_r_8 << 4) | vg_b_8])
-                    } else {
-                        buf.write_many(&[QOI_OP_RGB, self.r(), self.g(), self.b()])
-                    }
-                }
+            self.encode_into_rgb(px_prev, buf, luma_first)
+        } else {
+            buf.write_many(&[QOI_OP_RGBA, self.r(), self.g(), self.b(), self.a()])
+        }
+    }
+
+    /// Same as `encode_into`, but assumes the alpha channel is the same as in `px_prev`.
+    #[doc(hidden)]
+    #[inline]
+    pub fn encode_into_rgb<W: Writer>(&self, px_prev: Self, buf: W, luma_first: bool) -> Result<W> {
+        let vg = self.g().wrapping_sub(px_prev.g());
+        let vg_32 = vg.wrapping_add(32);
+        if vg_32 | 63 == 63 {
+            let vr = self.r().wrapping_sub(px_prev.r());
+            let vb = self.b().wrapping_sub(px_prev.b());
+            let vg_r = vr.wrapping_sub(vg);
+            let vg_b = vb.wrapping_sub(vg);
+            let (vg_r_8, vg_b_8) = (vg_r.wrapping_add(8), vg_b.wrapping_add(8));
+            if luma_first && vg_r_8 | vg_b_8 | 15 != 15 {
+                return buf.write_many(&[QOI_OP_RGB, self.r(), self.g(), self.b()]);
+            }
+            let (vr_2, vg_2, vb_2) = (vr.wrapping_add(2), vg.wrapping_add(2), vb.wrapping_add(2));
+            if vr_2 | vg_2 | vb_2 | 3 == 3 {
+                buf.write_one(QOI_OP_DIFF | (vr_2 << 4) | (vg_2 << 2) | vb_2)
+            } else if vg_r_8 | vg_b_8 | 15 == 15 {
+                buf.write_many(&[QOI_OP_LUMA | vg_32, (vg_r_8 << 4) | vg_b_8])
             } else {
                 buf.write_many(&[QOI_OP_RGB, self.r(), self.g(), self.b()])
             }
         } else {
-            buf.write_many(&[QOI_OP_RGBA, self.r(), self.g(), self.b(), self.a()])
+            buf.write_many(&[QOI_OP_RGB, self.r(), self.g(), self.b()])
         }
     }
 }
@@ -159,9 +263,179 @@ impl Default for Pixel {
     }
 }
 
+impl Hash for Pixel {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u32(self.to_u32());
+    }
+}
+
+impl From<[u8; 4]> for Pixel {
+    #[inline(always)]
+    fn from(px: [u8; 4]) -> Self {
+        Self(px)
+    }
+}
+
 impl From<Pixel> for [u8; 4] {
     #[inline(always)]
     fn from(px: Pixel) -> Self {
         px.0
     }
 }
+
+/// Converts raw RGBA pixel data into pixels, the counterpart of [`pixels_to_bytes`].
+///
+/// This copies the data; with the `pod` feature, `bytemuck::cast_slice` gives a view of it as
+/// pixels instead. Fails with [`Error::InvalidImageLength`] if the data doesn't match the
+/// dimensions.
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub fn pixels_from_bytes(
+    data: impl AsRef<[u8]>, width: impl Dimension, height: impl Dimension,
+) -> Result<Vec<Pixel>> {
+    let (data, header) = (data.as_ref(), Header::from_dimensions(width, height, None)?);
+    if unlikely(data.len() != header.n_bytes()) {
+        let (size, width, height) = (data.len(), header.width, header.height);
+        return Err(Error::InvalidImageLength { size, width, height });
+    }
+    let mut out = try_vec_with_capacity(header.n_pixels())?;
+    out.extend(cast_slice::<_, [u8; 4]>(data).iter().map(|&px| Pixel(px)));
+    Ok(out)
+}
+
+/// Converts pixels into raw RGBA pixel data, e.g. to pass them to
+/// [`Encoder::new`](crate::Encoder::new).
+#[cfg(any(feature = "alloc", feature = "std"))]
+pub fn pixels_to_bytes(pixels: &[Pixel]) -> Result<Vec<u8>> {
+    let mut out = try_vec_with_capacity(pixels.len() * 4)?;
+    out.extend(pixels.iter().flat_map(|px| px.0));
+    Ok(out)
+}
+
+/// Byte order of the pixels in a raw framebuffer, see
+/// [`encode_from_framebuffer`](crate::encode_from_framebuffer) and
+/// [`Decoder::decode_to_buf_as`](crate::Decoder::decode_to_buf_as).
+///
+/// Formats with an `X` component carry an unused byte which is encoded as opaque alpha and
+/// decoded as `0xff`. Formats without alpha drop it when decoding.
+/// Note that surfaces exposing pixels as native-endian `u32` values (e.g. `0x00RRGGBB` in
+/// `softbuffer`) are [`PixelFormat::Bgrx`] on little-endian targets.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    /// `R, G, B, A` bytes
+    Rgba,
+    /// `R, G, B` bytes followed by an unused byte
+    Rgbx,
+    /// `B, G, R, A` bytes
+    Bgra,
+    /// `B, G, R` bytes followed by an unused byte
+    Bgrx,
+    /// `A, R, G, B` bytes
+    Argb,
+    /// An unused byte followed by `R, G, B` bytes
+    Xrgb,
+    /// `R, G, B` bytes
+    Rgb,
+    /// `B, G, R` bytes
+    Bgr,
+    /// Native-endian `u32` words whose big-endian bytes are `R, G, B, A`, i.e. with a value of
+    /// `0xRRGGBBAA` (e.g. Java/Android `int` arrays of RGBA pixels)
+    RgbaU32Be,
+    /// Native-endian `u32` words whose little-endian bytes are `R, G, B, A`, i.e. with a value
+    /// of `0xAABBGGRR`
+    RgbaU32Le,
+    /// 16-bit RGB565 words, low byte first
+    Rgb565Le,
+    /// 16-bit RGB565 words, high byte first (as expected by most SPI displays)
+    Rgb565Be,
+}
+
+impl PixelFormat {
+    /// Number of bytes per pixel.
+    #[inline]
+    pub const fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgb565Le | Self::Rgb565Be => 2,
+            Self::Rgb | Self::Bgr => 3,
+            _ => 4,
+        }
+    }
+
+    /// Channels the pixels are encoded as: RGBA for formats with an alpha channel, RGB for the
+    /// others.
+    #[inline]
+    pub(crate) const fn channels(self) -> Channels {
+        match self {
+            Self::Rgba | Self::Bgra | Self::Argb | Self::RgbaU32Be | Self::RgbaU32Le => {
+                Channels::Rgba
+            }
+            _ => Channels::Rgb,
+        }
+    }
+
+    /// Converts a pixel in this format, the first [`PixelFormat::bytes_per_pixel`] bytes of
+    /// `px`, to RGBA.
+    #[inline]
+    pub(crate) const fn to_rgba(self, px: &[u8]) -> [u8; 4] {
+        match self {
+            Self::Rgba => [px[0], px[1], px[2], px[3]],
+            Self::Rgbx | Self::Rgb => [px[0], px[1], px[2], 0xff],
+            Self::Bgra => [px[2], px[1], px[0], px[3]],
+            Self::Bgrx | Self::Bgr => [px[2], px[1], px[0], 0xff],
+            Self::Argb => [px[1], px[2], px[3], px[0]],
+            Self::Xrgb => [px[1], px[2], px[3], 0xff],
+            Self::RgbaU32Be => u32::from_ne_bytes([px[0], px[1], px[2], px[3]]).to_be_bytes(),
+            Self::RgbaU32Le => u32::from_ne_bytes([px[0], px[1], px[2], px[3]]).to_le_bytes(),
+            Self::Rgb565Le => rgb565::unpack([px[0], px[1]], ByteOrder::LittleEndian),
+            Self::Rgb565Be => rgb565::unpack([px[0], px[1]], ByteOrder::BigEndian),
+        }
+    }
+
+    /// Converts an RGBA pixel to this format, in the first [`PixelFormat::bytes_per_pixel`]
+    /// bytes of the result.
+    #[inline]
+    pub(crate) const fn pack(self, px: Pixel) -> [u8; 4] {
+        let [r, g, b, a] = px.0;
+        match self {
+            Self::Rgba => [r, g, b, a],
+            Self::Rgbx => [r, g, b, 0xff],
+            Self::Bgra => [b, g, r, a],
+            Self::Bgrx => [b, g, r, 0xff],
+            Self::Argb => [a, r, g, b],
+            Self::Xrgb => [0xff, r, g, b],
+            Self::Rgb => [r, g, b, 0],
+            Self::Bgr => [b, g, r, 0],
+            Self::RgbaU32Be => u32::from_be_bytes(px.0).to_ne_bytes(),
+            Self::RgbaU32Le => u32::from_le_bytes(px.0).to_ne_bytes(),
+            Self::Rgb565Le | Self::Rgb565Be => {
+                let byte_order = match self {
+                    Self::Rgb565Be => ByteOrder::BigEndian,
+                    _ => ByteOrder::LittleEndian,
+                };
+                let [lo, hi] = rgb565::pack(px, byte_order);
+                [lo, hi, 0, 0]
+            }
+        }
+    }
+}
+
+// Compile-time check of the layout guarantees documented on `Pixel`.
+const _: () = {
+    assert!(core::mem::size_of::<Pixel>() == 4);
+    assert!(core::mem::align_of::<Pixel>() == 1);
+    assert!(Pixel::new().with_a(0xff).to_u32() == 0xff);
+};
+
+// Compile-time check that the index hash matches the definition from the spec,
+// `(r * 3 + g * 5 + b * 7 + a * 11) % 64`. Being evaluated for the target the crate is built
+// for, this catches endianness issues by merely building for a big-endian target.
+const _: () = {
+    let samples = [[0, 0, 0, 0], [255, 255, 255, 255], [1, 2, 3, 4], [0x12, 0x34, 0x56, 0x78]];
+    let mut i = 0;
+    while i < samples.len() {
+        let [r, g, b, a] = samples[i];
+        let expected = (r as u32 * 3 + g as u32 * 5 + b as u32 * 7 + a as u32 * 11) % 64;
+        assert!(Pixel(samples[i]).hash_index() as u32 == expected);
+        i += 1;
+    }
+};
@@ -0,0 +1,37 @@
+use alloc::vec::Vec;
+
+use crate::dither;
+use crate::error::Result;
+use crate::utils::try_vec_with_capacity;
+
+/// How much [`quantize`] changed the pixel data.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct QuantizeReport {
+    /// Largest difference between an input and an output byte
+    pub max_error: u8,
+    /// Average difference between input and output bytes
+    pub mean_error: f64,
+}
+
+/// Reduce the color depth of raw pixel data to `bits_per_channel` bits (clamped to `1..=8`),
+/// returning the quantized data along with the error this introduced.
+///
+/// Every channel is rounded to the nearest representable level and expanded back to the full
+/// 8-bit range (see [`dither`](crate::dither)), so the result can be encoded as usual; fewer
+/// distinct values make runs and index hits more likely, which shrinks the encoded image at
+/// the cost of slight loss. Bytes are processed independently of the layout, so alpha is
+/// quantized as well, but fully opaque and fully transparent pixels stay that way.
+pub fn quantize(data: impl AsRef<[u8]>, bits_per_channel: u8) -> Result<(Vec<u8>, QuantizeReport)> {
+    let data = data.as_ref();
+    let mut out = try_vec_with_capacity(data.len())?;
+    let (mut max_error, mut sum) = (0, 0_u64);
+    out.extend(data.iter().map(|&value| {
+        let quantized = dither::quantize(value, bits_per_channel);
+        let error = value.abs_diff(quantized);
+        (max_error, sum) = (max_error.max(error), sum + u64::from(error));
+        quantized
+    }));
+    #[allow(clippy::cast_precision_loss)]
+    let mean_error = if data.is_empty() { 0.0 } else { sum as f64 / data.len() as f64 };
+    Ok((out, QuantizeReport { max_error, mean_error }))
+}
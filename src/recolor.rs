@@ -0,0 +1,108 @@
+use alloc::vec::Vec;
+
+use crate::decode::{split_header, Decoder};
+use crate::encode::{Encoder, EncoderOptions};
+use crate::error::Result;
+use crate::ext::{self, ext_len, write_ext};
+use crate::header::Channels;
+use crate::ops::{OpDecoder, OpKind};
+use crate::utils::try_vec_with_capacity;
+
+/// Replace every pixel of one exact color with another one in an encoded image.
+///
+/// Where possible, literal `RGB`/`RGBA` ops are rewritten in place, which leaves the rest of
+/// the encoded stream (including any extension block) untouched. This is only valid if no
+/// other op depends on the replaced pixels, so the rewritten stream is verified by decoding it
+/// alongside the original one; if the check fails, the image is decoded, recolored and
+/// re-encoded with the default encoder options instead.
+///
+/// A checksum of the op stream (see [`Encoder::with_checksum`]) is recomputed after rewriting
+/// the ops in place, which writes the extension block anew: records unknown to this version
+/// of the crate are dropped then.
+///
+/// Note: that fallback isn't a single fused pass. The whole image is decoded into memory
+/// first, and the re-encoded image only keeps the dimensions and header format of the
+/// original one: it is encoded as RGBA, and the records of the extension block (restart
+/// markers, nine-patch metadata, color space, checksum, ...) are dropped.
+pub fn recolor(data: impl AsRef<[u8]>, from: [u8; 4], to: [u8; 4]) -> Result<Vec<u8>> {
+    let data = data.as_ref();
+    if let Some(out) = recolor_in_place(data, from, to)? {
+        return Ok(out);
+    }
+    let mut decoder = Decoder::new(data)?.with_channels(Channels::Rgba);
+    let (header, mut pixels) = (*decoder.header(), decoder.decode_to_vec()?);
+    for px in pixels.chunks_exact_mut(4) {
+        if px == from {
+            px.copy_from_slice(&to);
+        }
+    }
+    let options = EncoderOptions::new().header_format(decoder.header_format());
+    Encoder::new(&pixels, header.width, header.height)?.with_options(options).encode_to_vec()
+}
+
+/// Rewrites literal ops in place, returns `None` if that doesn't produce the right image.
+fn recolor_in_place(data: &[u8], from: [u8; 4], to: [u8; 4]) -> Result<Option<Vec<u8>>> {
+    let (header, _, ops) = split_header(data)?;
+    let header_size = data.len() - ops.len();
+    let mut out = try_vec_with_capacity(data.len())?;
+    out.extend_from_slice(data);
+    if from == to {
+        return Ok(Some(out));
+    }
+
+    let mut decoder = OpDecoder::new(ops);
+    let mut n_left = header.n_pixels();
+    while n_left != 0 {
+        let op = decoder.next_op()?;
+        n_left = n_left.saturating_sub(op.n_pixels);
+        if <[u8; 4]>::from(op.px) != from {
+            continue;
+        }
+        let op_start = header_size + op.offset + 1;
+        match op.kind {
+            OpKind::Rgba => out[op_start..op_start + 4].copy_from_slice(&to),
+            OpKind::Rgb if from[3] == to[3] => {
+                out[op_start..op_start + 3].copy_from_slice(&to[..3]);
+            }
+            OpKind::Rgb => return Ok(None),
+            _ => {} // runs repeat the rewritten pixel, anything else is caught below
+        }
+    }
+
+    // op kinds are unchanged, so both streams can be walked in lockstep
+    let mut original = OpDecoder::new(ops);
+    let mut rewritten = OpDecoder::new(&out[header_size..]);
+    let mut n_left = header.n_pixels();
+    while n_left != 0 {
+        let (expected, actual) = (original.next_op()?, rewritten.next_op()?);
+        n_left = n_left.saturating_sub(expected.n_pixels);
+        let expected = <[u8; 4]>::from(expected.px);
+        let expected = if expected == from { to } else { expected };
+        if <[u8; 4]>::from(actual.px) != expected {
+            return Ok(None);
+        }
+    }
+
+    // the literal ops have changed, so a checksum of the op stream has to be recomputed
+    let ops_len = header.length.unwrap_or_default() as usize;
+    if let Some(checksum) = ext::checksum(ops, ops_len) {
+        let (width, height) = (header.width, header.height);
+        let interval = ext::restart_markers(ops, ops_len, height).map_or(0, |m| m.interval());
+        let channels = ext::channels(ops, ops_len).unwrap_or_default();
+        let nine_patch = ext::nine_patch(ops, ops_len, width, height);
+        let (field, colorspace) = (ext::field(ops, ops_len), ext::colorspace(ops, ops_len));
+        let low_bits = ext::low_bits(ops, ops_len, header.n_pixels()).unwrap_or_default();
+        let (nine_patch, kind) = (nine_patch.as_ref(), Some(checksum.kind));
+        let n_ext =
+            ext_len(height, interval, channels, nine_patch, field, colorspace, low_bits, kind);
+        out.truncate(header_size + ops_len);
+        out.resize(header_size + ops_len + n_ext, 0);
+        let (ops, ext) = out[header_size..].split_at_mut(ops_len);
+        let checksum = Some(checksum.kind.checksum(ops));
+        let _ = write_ext(
+            ops, ext, width, height, interval, channels, nine_patch, field, colorspace, low_bits,
+            checksum,
+        );
+    }
+    Ok(Some(out))
+}
@@ -0,0 +1,129 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::vec::Vec;
+
+use crate::anim::{write_table, AnimOptions, AnimationEncoder, Entry};
+use crate::consts::{
+    QOI_ANIM_ENTRY_SIZE, QOI_ANIM_HEADER_SIZE, QOI_ANIM_MAGIC, QOI_ANIM_RECORD_MAGIC,
+    QOI_ANIM_RECORD_SIZE,
+};
+use crate::encode::EncoderOptions;
+use crate::error::{Error, Result};
+
+/// Screen recorder writing timestamped frames to an animation container as they come in.
+///
+/// Frames are encoded like with an [`AnimationEncoder`]: only the region that changed since
+/// the previous frame is stored, with keyframes inserted according to [`AnimOptions`]. The
+/// delay of each frame is the time until the next one, so it's patched in place when the next
+/// frame arrives, hence the seekable writer.
+///
+/// Every frame is preceded by a copy of its frame table entry. If the recording is cut short
+/// before [`RecordingWriter::finish`] (e.g. the recorder crashed), the file can be made
+/// readable again with [`RecordingWriter::recover`], which rebuilds the frame table from them.
+pub struct RecordingWriter<W: Write + Seek> {
+    encoder: AnimationEncoder<W>,
+    // position of the container in the writer
+    start: u64,
+    last_ms: Option<u64>,
+}
+
+impl<W: Write + Seek> RecordingWriter<W> {
+    /// Creates a new recording writing to `writer` at its current position.
+    #[inline]
+    pub fn new(writer: W, width: u16, height: u16) -> Result<Self> {
+        Self::with_options(writer, width, height, EncoderOptions::new())
+    }
+
+    /// Creates a new recording with a given encoder configuration.
+    pub fn with_options(
+        mut writer: W, width: u16, height: u16, options: EncoderOptions,
+    ) -> Result<Self> {
+        let start = writer.stream_position()?;
+        let encoder = AnimationEncoder::with_options(writer, width, height, options)?;
+        Ok(Self { encoder: encoder.with_records(), start, last_ms: None })
+    }
+
+    /// Sets when keyframes are inserted, see [`AnimationEncoder::with_anim_options`].
+    #[inline]
+    #[must_use]
+    pub fn with_anim_options(self, options: AnimOptions) -> Self {
+        let Self { encoder, start, last_ms } = self;
+        Self { encoder: encoder.with_anim_options(options), start, last_ms }
+    }
+
+    /// Adds a frame captured at `timestamp_ms`, in milliseconds on any monotonic clock.
+    ///
+    /// The previous frame lasts until then; timestamps going backwards give it a zero delay.
+    pub fn add_frame(&mut self, frame: impl AsRef<[u8]>, timestamp_ms: u64) -> Result<()> {
+        self.end_last_frame(timestamp_ms)?;
+        self.encoder.add_frame(frame, 0)?;
+        self.last_ms = Some(timestamp_ms);
+        Ok(())
+    }
+
+    /// Ends the last frame at `timestamp_ms`, writes the frame table, flushes the writer and
+    /// returns it back.
+    pub fn finish(mut self, timestamp_ms: u64) -> Result<W> {
+        self.end_last_frame(timestamp_ms)?;
+        self.encoder.finish()
+    }
+
+    /// Sets the delay of the last frame in its table entry and its record.
+    fn end_last_frame(&mut self, timestamp_ms: u64) -> Result<()> {
+        let Some(last_ms) = self.last_ms else {
+            return Ok(());
+        };
+        let delay = u32::try_from(timestamp_ms.saturating_sub(last_ms)).unwrap_or(u32::MAX);
+        if let Some(pos) = self.encoder.set_last_delay(delay) {
+            let writer = self.encoder.writer_mut();
+            let end = writer.stream_position()?;
+            writer.seek(SeekFrom::Start(self.start + pos))?;
+            writer.write_all(&delay.to_le_bytes())?;
+            writer.seek(SeekFrom::Start(end))?;
+        }
+        Ok(())
+    }
+}
+
+impl RecordingWriter<File> {
+    /// Finalizes a recording that has been cut short, returning the number of frames kept.
+    ///
+    /// The file must hold the recording alone. Frames are kept up to the first one that hasn't
+    /// been entirely written, the rest of the file is dropped, and the frame table is written
+    /// after them. The last frame kept has a zero delay unless a later frame was written.
+    pub fn recover(path: impl AsRef<Path>) -> Result<u32> {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut header = [0; QOI_ANIM_HEADER_SIZE];
+        file.read_exact(&mut header)?;
+        let magic = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        if magic != QOI_ANIM_MAGIC {
+            return Err(Error::InvalidMagic { magic });
+        }
+        let len = file.seek(SeekFrom::End(0))?;
+        let mut pos = QOI_ANIM_HEADER_SIZE as u64;
+        let mut table = Vec::new();
+        let mut record = [0; QOI_ANIM_RECORD_SIZE];
+        while pos + QOI_ANIM_RECORD_SIZE as u64 <= len {
+            file.seek(SeekFrom::Start(pos))?;
+            file.read_exact(&mut record)?;
+            let magic = u32::from_le_bytes([record[0], record[1], record[2], record[3]]);
+            let entry = Entry::decode(&record[4..]);
+            if magic != QOI_ANIM_RECORD_MAGIC || entry.offset != pos + QOI_ANIM_RECORD_SIZE as u64 {
+                break;
+            }
+            match entry.offset.checked_add(u64::from(entry.length)) {
+                Some(end) if end <= len => pos = end,
+                _ => break,
+            }
+            table.extend_from_slice(&record[4..]);
+        }
+        let n_frames =
+            u32::try_from(table.len() / QOI_ANIM_ENTRY_SIZE).map_err(|_| Error::SizeOverflow)?;
+        file.set_len(pos)?;
+        file.seek(SeekFrom::Start(pos))?;
+        write_table(&mut file, &table, pos, n_frames)?;
+        file.sync_all()?;
+        Ok(n_frames)
+    }
+}
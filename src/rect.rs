@@ -0,0 +1,52 @@
+/// Rectangular region of an image.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    /// Left column
+    pub x: u16,
+    /// Top row
+    pub y: u16,
+    /// Width in pixels
+    pub width: u16,
+    /// Height in pixels
+    pub height: u16,
+}
+
+impl Rect {
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub(crate) const fn right(self) -> u16 {
+        self.x + self.width
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub(crate) const fn bottom(self) -> u16 {
+        self.y + self.height
+    }
+
+    /// Whether the column range `start..end` touches this rect's columns.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub(crate) const fn overlaps(self, start: u16, end: u16) -> bool {
+        start <= self.right() && self.x <= end
+    }
+
+    /// Whether the two rects share at least one pixel.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub(crate) const fn intersects(self, other: Self) -> bool {
+        self.x < other.right()
+            && other.x < self.right()
+            && self.y < other.bottom()
+            && other.y < self.bottom()
+    }
+
+    /// Smallest rect covering both rects.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    #[inline]
+    pub(crate) fn union(self, other: Self) -> Self {
+        let (x, y) = (self.x.min(other.x), self.y.min(other.y));
+        let (right, bottom) = (self.right().max(other.right()), self.bottom().max(other.bottom()));
+        Self { x, y, width: right - x, height: bottom - y }
+    }
+}
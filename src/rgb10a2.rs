@@ -0,0 +1,69 @@
+//! RGB10A2 pixels: 10 bits per color channel and 2 bits of alpha packed into a `u32` word.
+//!
+//! The op stream holds the 8 high bits of each channel (alpha being expanded to 8 bits), so
+//! any decoder sees a regular 8-bit image. The 2 low bits of R, G and B are stored in the
+//! extension block as a bitstream of 6 bits per pixel, least significant bits first.
+
+use crate::pixel::Pixel;
+
+#[cfg(any(feature = "alloc", feature = "std"))]
+const MASK_10: u32 = 0x3ff;
+
+/// Number of bytes taken by the low bits of a given number of pixels.
+#[inline]
+pub const fn low_bits_len(n_pixels: usize) -> usize {
+    (n_pixels * 6 + 7) / 8
+}
+
+/// Splits a word into a pixel holding the high bits of each channel and the 6 low bits.
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn unpack(word: u32) -> (Pixel, u8) {
+    let (r, g, b) = (word & MASK_10, (word >> 10) & MASK_10, (word >> 20) & MASK_10);
+    let a = (word >> 30) as u8 * 0x55;
+    let px = Pixel::from([(r >> 2) as u8, (g >> 2) as u8, (b >> 2) as u8, a]);
+    (px, ((r & 3) | (g & 3) << 2 | (b & 3) << 4) as u8)
+}
+
+/// Packs a pixel into a word, filling the low bits of each channel by replicating its high
+/// bits (so that e.g. 255 maps to 1023).
+#[inline]
+pub const fn pack(px: Pixel) -> u32 {
+    const fn expand(v: u8) -> u32 {
+        (v as u32) << 2 | (v as u32) >> 6
+    }
+    expand(px.r()) | expand(px.g()) << 10 | expand(px.b()) << 20 | (px.a() as u32 >> 6) << 30
+}
+
+/// Replaces the low bits of each color channel of a word packed with [`pack`].
+#[inline]
+pub const fn with_low_bits(word: u32, low: u8) -> u32 {
+    let low = low as u32;
+    (word & !(3 | 3 << 10 | 3 << 20)) | (low & 3) | (low >> 2 & 3) << 10 | (low >> 4 & 3) << 20
+}
+
+/// Writes the 6 low bits of the `i`-th pixel into a zero-initialized bitstream.
+#[cfg(any(feature = "alloc", feature = "std"))]
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn put_low_bits(stream: &mut [u8], i: usize, low: u8) {
+    let (byte, shift) = (i * 6 / 8, i * 6 % 8);
+    let value = u16::from(low) << shift;
+    stream[byte] |= value as u8;
+    if shift > 2 {
+        stream[byte + 1] |= (value >> 8) as u8;
+    }
+}
+
+/// Reads the 6 low bits of the `i`-th pixel from a bitstream.
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+pub fn get_low_bits(stream: &[u8], i: usize) -> u8 {
+    let (byte, shift) = (i * 6 / 8, i * 6 % 8);
+    let mut value = u16::from(stream[byte]);
+    if shift > 2 {
+        value |= u16::from(stream[byte + 1]) << 8;
+    }
+    (value >> shift) as u8 & 0x3f
+}
@@ -0,0 +1,43 @@
+use crate::dither;
+use crate::pixel::Pixel;
+
+/// Order of the two bytes of each 16-bit pixel in memory.
+///
+/// SPI displays usually expect the high byte first, i.e. [`ByteOrder::BigEndian`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ByteOrder {
+    /// Low byte first (default)
+    #[default]
+    LittleEndian,
+    /// High byte first
+    BigEndian,
+}
+
+/// Packs a pixel into RGB565 bytes, dropping the alpha channel.
+#[inline]
+pub const fn pack(px: Pixel, byte_order: ByteOrder) -> [u8; 2] {
+    let value = ((px.r() as u16 >> 3) << 11) | ((px.g() as u16 >> 2) << 5) | (px.b() as u16 >> 3);
+    match byte_order {
+        ByteOrder::LittleEndian => value.to_le_bytes(),
+        ByteOrder::BigEndian => value.to_be_bytes(),
+    }
+}
+
+/// Unpacks RGB565 bytes into opaque RGBA bytes, replicating the high bits of each channel into
+/// its low bits so that the full range is covered.
+#[inline]
+#[allow(clippy::cast_possible_truncation)] // every channel is masked to 6 bits at most
+pub const fn unpack(bytes: [u8; 2], byte_order: ByteOrder) -> [u8; 4] {
+    let value = match byte_order {
+        ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+        ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+    };
+    let (r, g, b) = ((value >> 11) as u8, (value >> 5) as u8 & 0x3f, value as u8 & 0x1f);
+    [(r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2), 0xff]
+}
+
+/// Packs a pixel into RGB565 bytes with ordered dithering based on its coordinates.
+#[inline]
+pub const fn pack_dithered(px: Pixel, x: usize, y: usize, byte_order: ByteOrder) -> [u8; 2] {
+    pack(dither::ordered_pixel(px, x, y, [5, 6, 5]), byte_order)
+}
@@ -0,0 +1,19 @@
+use alloc::vec::Vec;
+
+use crate::decode::Decoder;
+use crate::encode::encode_to_vec;
+use crate::error::Result;
+use crate::limits::Limits;
+
+/// Decode an untrusted image within the given limits and re-encode it canonically.
+///
+/// The input is rejected before anything gets allocated if its header or size exceed
+/// `limits`. The output is produced by the encoder with the default options, keeping only the
+/// channels of the input: RGB and luma + alpha images get the extension block recording them,
+/// but no other extension record or trailing data of the input is carried over.
+pub fn sanitize(data: impl AsRef<[u8]>, limits: Limits) -> Result<Vec<u8>> {
+    let data = data.as_ref();
+    let mut decoder = Decoder::new(data)?.with_limits(limits)?;
+    let pixels = decoder.decode_to_vec()?;
+    encode_to_vec(&pixels, decoder.header().width, decoder.header().height)
+}
@@ -0,0 +1,278 @@
+#[cfg(any(feature = "std", feature = "alloc"))]
+use alloc::vec::Vec;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::decode::{check_padding, Decoder};
+use crate::error::{Error, Result};
+use crate::header::Header;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::ops::OpDecoder;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::utils::try_vec_zeroed;
+use crate::utils::unlikely;
+
+/// Scale an RGBA image into a pre-allocated buffer using nearest-neighbor sampling.
+///
+/// Only integer arithmetic is used, so this is suitable for `no_std` targets without an FPU
+/// (e.g. fitting a decoded image to a smaller LCD). Each destination pixel samples the source
+/// pixel under its center.
+#[inline]
+pub fn scale_nn(
+    src: impl AsRef<[u8]>, sw: u16, sh: u16, mut dst: impl AsMut<[u8]>, dw: u16, dh: u16,
+) -> Result<()> {
+    let (src, dst) = (src.as_ref(), dst.as_mut());
+    let src_header = Header::try_new(sw, sh, None)?;
+    let dst_header = Header::try_new(dw, dh, None)?;
+    if unlikely(src.len() != src_header.n_bytes()) {
+        return Err(Error::InvalidImageLength { size: src.len(), width: sw, height: sh });
+    }
+    let required = dst_header.n_bytes();
+    if unlikely(dst.len() < required) {
+        return Err(Error::OutputBufferTooSmall { size: dst.len(), required });
+    }
+
+    let (sw, sh, dw, dh) = (sw as usize, sh as usize, dw as usize, dh as usize);
+    for (dy, dst_row) in dst[..required].chunks_exact_mut(dw * 4).enumerate() {
+        let sy = nn_index(dy, sh, dh);
+        let src_row = &src[sy * sw * 4..(sy + 1) * sw * 4];
+        for (dx, px_out) in dst_row.chunks_exact_mut(4).enumerate() {
+            let sx = nn_index(dx, sw, dw);
+            px_out.copy_from_slice(&src_row[sx * 4..sx * 4 + 4]);
+        }
+    }
+    Ok(())
+}
+
+/// Maps a destination coordinate to the source coordinate under the destination pixel center.
+#[inline(always)]
+#[allow(clippy::cast_possible_truncation)]
+const fn nn_index(d: usize, src_len: usize, dst_len: usize) -> usize {
+    // all values fit in u16, so the u64 product can't overflow; the result is < src_len
+    ((2 * d as u64 + 1) * src_len as u64 / (2 * dst_len as u64)) as usize
+}
+
+/// How `decode_resized` computes the destination pixels.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ResizeFilter {
+    /// Sample the source pixel under each destination pixel center, like [`scale_nn`]
+    Nearest,
+    /// Average the source pixels covered by each destination pixel (default)
+    #[default]
+    Box,
+    /// Like [`ResizeFilter::Box`], but average in linear light rather than on sRGB values
+    ///
+    /// Averaging sRGB values darkens fine detail (a black and white checkerboard becomes
+    /// `#808080` instead of `#bcbcbc`), so this gives more faithful thumbnails of photos and
+    /// text at the cost of a couple of table lookups per channel.
+    BoxLinear,
+}
+
+/// sRGB values mapped to linear light in `0..=65535`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+const SRGB_TO_LINEAR: [u16; 256] = [
+    0, 20, 40, 60, 80, 99, 119, 139, 159, 179, 199, 219, 241, 264, 288, 313, 340, 367, 396, 427,
+    458, 491, 526, 562, 599, 637, 677, 718, 761, 805, 851, 898, 947, 997, 1048, 1101, 1156, 1212,
+    1270, 1330, 1391, 1453, 1517, 1583, 1651, 1720, 1790, 1863, 1937, 2013, 2090, 2170, 2250, 2333,
+    2418, 2504, 2592, 2681, 2773, 2866, 2961, 3058, 3157, 3258, 3360, 3464, 3570, 3678, 3788, 3900,
+    4014, 4129, 4247, 4366, 4488, 4611, 4736, 4864, 4993, 5124, 5257, 5392, 5530, 5669, 5810, 5953,
+    6099, 6246, 6395, 6547, 6700, 6856, 7014, 7174, 7335, 7500, 7666, 7834, 8004, 8177, 8352, 8528,
+    8708, 8889, 9072, 9258, 9445, 9635, 9828, 10022, 10219, 10417, 10619, 10822, 11028, 11235,
+    11446, 11658, 11873, 12090, 12309, 12530, 12754, 12980, 13209, 13440, 13673, 13909, 14146,
+    14387, 14629, 14874, 15122, 15371, 15623, 15878, 16135, 16394, 16656, 16920, 17187, 17456,
+    17727, 18001, 18277, 18556, 18837, 19121, 19407, 19696, 19987, 20281, 20577, 20876, 21177,
+    21481, 21787, 22096, 22407, 22721, 23038, 23357, 23678, 24002, 24329, 24658, 24990, 25325,
+    25662, 26001, 26344, 26688, 27036, 27386, 27739, 28094, 28452, 28813, 29176, 29542, 29911,
+    30282, 30656, 31033, 31412, 31794, 32179, 32567, 32957, 33350, 33745, 34143, 34544, 34948,
+    35355, 35764, 36176, 36591, 37008, 37429, 37852, 38278, 38706, 39138, 39572, 40009, 40449,
+    40891, 41337, 41785, 42236, 42690, 43147, 43606, 44069, 44534, 45002, 45473, 45947, 46423,
+    46903, 47385, 47871, 48359, 48850, 49344, 49841, 50341, 50844, 51349, 51858, 52369, 52884,
+    53401, 53921, 54445, 54971, 55500, 56032, 56567, 57105, 57646, 58190, 58737, 59287, 59840,
+    60396, 60955, 61517, 62082, 62650, 63221, 63795, 64372, 64952, 65535,
+];
+
+/// Linear light values (in `0..=65535`) halfway between consecutive sRGB values, so that
+/// the number of thresholds not above a linear value is that value rounded to sRGB.
+#[cfg(any(feature = "std", feature = "alloc"))]
+const LINEAR_TO_SRGB: [u16; 255] = [
+    10, 30, 50, 70, 90, 109, 129, 149, 169, 189, 209, 230, 252, 276, 300, 326, 353, 382, 411, 442,
+    475, 508, 543, 580, 618, 657, 697, 739, 783, 828, 874, 922, 971, 1022, 1075, 1129, 1184, 1241,
+    1300, 1360, 1422, 1485, 1550, 1617, 1685, 1755, 1826, 1900, 1975, 2051, 2130, 2210, 2292, 2375,
+    2460, 2547, 2636, 2727, 2819, 2914, 3010, 3107, 3207, 3309, 3412, 3517, 3624, 3733, 3844, 3957,
+    4071, 4188, 4306, 4427, 4549, 4673, 4800, 4928, 5058, 5190, 5325, 5461, 5599, 5739, 5881, 6026,
+    6172, 6320, 6471, 6623, 6778, 6935, 7093, 7254, 7417, 7582, 7750, 7919, 8090, 8264, 8440, 8618,
+    8798, 8980, 9165, 9351, 9540, 9731, 9925, 10120, 10318, 10518, 10720, 10924, 11131, 11340,
+    11551, 11765, 11981, 12199, 12419, 12642, 12867, 13094, 13324, 13556, 13790, 14027, 14266,
+    14508, 14751, 14998, 15246, 15497, 15750, 16006, 16264, 16525, 16788, 17053, 17321, 17591,
+    17864, 18139, 18416, 18696, 18979, 19264, 19551, 19841, 20134, 20429, 20726, 21026, 21329,
+    21634, 21941, 22251, 22564, 22879, 23197, 23517, 23840, 24165, 24493, 24824, 25157, 25493,
+    25831, 26172, 26516, 26862, 27211, 27562, 27916, 28273, 28632, 28994, 29359, 29726, 30096,
+    30469, 30844, 31222, 31603, 31986, 32372, 32761, 33153, 33547, 33944, 34344, 34746, 35151,
+    35559, 35970, 36383, 36799, 37218, 37640, 38064, 38492, 38922, 39354, 39790, 40228, 40670,
+    41114, 41560, 42010, 42463, 42918, 43376, 43837, 44301, 44768, 45237, 45709, 46185, 46663,
+    47144, 47628, 48114, 48604, 49097, 49592, 50091, 50592, 51096, 51603, 52113, 52626, 53142,
+    53661, 54183, 54707, 55235, 55766, 56299, 56836, 57375, 57918, 58463, 59012, 59563, 60118,
+    60675, 61235, 61799, 62365, 62935, 63507, 64083, 64661, 65243,
+];
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline(always)]
+fn srgb_to_linear(v: u8) -> u32 {
+    SRGB_TO_LINEAR[v as usize].into()
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+#[allow(clippy::cast_possible_truncation)]
+fn linear_to_srgb(v: u32) -> u8 {
+    // the table has 255 entries, so the result fits in u8
+    LINEAR_TO_SRGB.partition_point(|&t| u32::from(t) <= v) as u8
+}
+
+/// Range of source coordinates covered by a destination coordinate.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+const fn source_range(
+    d: usize, src_len: usize, dst_len: usize, filter: ResizeFilter,
+) -> (usize, usize) {
+    if matches!(filter, ResizeFilter::Nearest) {
+        let s = nn_index(d, src_len, dst_len);
+        return (s, s + 1);
+    }
+    // when downscaling the ranges partition the source, when upscaling they are single pixels
+    let start = d * src_len / dst_len;
+    let end = (d + 1) * src_len / dst_len;
+    (start, if end > start { end } else { start + 1 })
+}
+
+/// Alpha-weighted channel sums of the source pixels covered by a destination pixel.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Copy, Clone, Default)]
+struct Sums {
+    rgb: [u64; 3],
+    a: u64,
+    n: u64,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Sums {
+    #[inline]
+    fn add(&mut self, px: &[u8], linear: bool) {
+        let a = u64::from(px[3]);
+        for (sum, &c) in self.rgb.iter_mut().zip(px) {
+            let c = if linear { srgb_to_linear(c) } else { c.into() };
+            *sum += u64::from(c) * a;
+        }
+        self.a += a;
+        self.n += 1;
+    }
+
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    fn average(&self, linear: bool) -> [u8; 4] {
+        // colors are weighted by alpha so that transparent pixels don't bleed into the result
+        let mut out = [0; 4];
+        for (out, &sum) in out.iter_mut().zip(&self.rgb) {
+            let c = (sum + self.a / 2).checked_div(self.a).unwrap_or_default();
+            *out = if linear { linear_to_srgb(c as u32) } else { c as u8 };
+        }
+        out[3] = ((self.a + self.n / 2) / self.n) as u8;
+        out
+    }
+}
+
+/// Resize an RGBA image into a pre-allocated buffer of `dw * dh` pixels, averaging the source
+/// pixels covered by each destination pixel like [`ResizeFilter::Box`].
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn resize_box(
+    src: &[u8], sw: usize, sh: usize, dst: &mut [u8], dw: usize, dh: usize, linear: bool,
+) {
+    let columns: Vec<_> = (0..dw).map(|dx| source_range(dx, sw, dw, ResizeFilter::Box)).collect();
+    for (dy, dst_row) in dst.chunks_exact_mut(dw * 4).enumerate() {
+        let (y0, y1) = source_range(dy, sh, dh, ResizeFilter::Box);
+        for (px_out, &(x0, x1)) in dst_row.chunks_exact_mut(4).zip(&columns) {
+            let mut sum = Sums::default();
+            for y in y0..y1 {
+                for px in src[(y * sw + x0) * 4..(y * sw + x1) * 4].chunks_exact(4) {
+                    sum.add(px, linear);
+                }
+            }
+            px_out.copy_from_slice(&sum.average(linear));
+        }
+    }
+}
+
+/// Decode an image straight into a resized RGBA image.
+///
+/// Rows are resized as they are decoded, so the full-size image is never held in memory,
+/// which makes this suitable for thumbnailing large images. The [`Box`](ResizeFilter::Box)
+/// filters are meant for downscaling; when upscaling along an axis they fall back to
+/// nearest-neighbor sampling along it. Images encoded from luma + alpha data are expanded
+/// to RGBA.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn decode_resized(
+    data: impl AsRef<[u8]>, width: u16, height: u16, filter: ResizeFilter,
+) -> Result<Vec<u8>> {
+    let decoder = Decoder::new(&data)?;
+    let src_header = *decoder.header();
+    let dst_header = Header::try_new(width, height, None)?;
+    let mut out = try_vec_zeroed(dst_header.n_bytes())?;
+    let (sw, sh) = (src_header.width as usize, src_header.height as usize);
+    let (dw, dh) = (width as usize, height as usize);
+    let nearest = matches!(filter, ResizeFilter::Nearest);
+    let linear = matches!(filter, ResizeFilter::BoxLinear);
+    let columns: Vec<_> = (0..dw).map(|dx| source_range(dx, sw, dw, filter)).collect();
+
+    let mut ops = OpDecoder::new(decoder.data());
+    let (mut px, mut n_left) = ([0; 4], 0);
+    let mut row = try_vec_zeroed(sw * 4)?;
+    let mut sums = Vec::new();
+    sums.resize(dw, Sums::default());
+    let mut dst_rows = out.chunks_exact_mut(dw * 4);
+    let mut dy = 0;
+    for sy in 0..sh {
+        for px_out in row.chunks_exact_mut(4) {
+            if n_left == 0 {
+                let op = ops.next_op()?;
+                px = op.px.into();
+                n_left = op.n_pixels;
+            }
+            n_left -= 1;
+            px_out.copy_from_slice(&px);
+        }
+        let (start, end) = source_range(dy, sh, dh, filter);
+        if dy >= dh || sy < start {
+            // skipped by nearest-neighbor sampling
+            continue;
+        }
+        if !nearest {
+            for (sum, &(x0, x1)) in sums.iter_mut().zip(&columns) {
+                for px in row[x0 * 4..x1 * 4].chunks_exact(4) {
+                    sum.add(px, linear);
+                }
+            }
+        }
+        if sy + 1 < end {
+            continue;
+        }
+        // emit every destination row ending here; when upscaling, several rows share this one
+        while dy < dh && source_range(dy, sh, dh, filter).0 == start {
+            if let Some(dst_row) = dst_rows.next() {
+                for ((px_out, sum), &(x0, _)) in
+                    dst_row.chunks_exact_mut(4).zip(&sums).zip(&columns)
+                {
+                    if nearest {
+                        px_out.copy_from_slice(&row[x0 * 4..x0 * 4 + 4]);
+                    } else {
+                        px_out.copy_from_slice(&sum.average(linear));
+                    }
+                }
+            }
+            dy += 1;
+        }
+        sums.fill(Sums::default());
+    }
+
+    let offset = ops.offset();
+    check_padding(&decoder.data()[offset..], offset, src_header.n_pixels())?;
+    Ok(out)
+}
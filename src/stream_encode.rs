@@ -0,0 +1,177 @@
+use std::io::{BufWriter, IntoInnerError, Seek, SeekFrom, Write};
+
+use crate::consts::QOI_REFERENCE_HEADER_SIZE;
+use crate::encode::{encode_pixels, EncodeState, EncoderOptions, Tolerance};
+use crate::error::{Error, Result};
+use crate::ext::{ext_len, write_ext};
+use crate::header::{Channels, ColorSpace, Header, HeaderFormat};
+use crate::utils::{unlikely, GenericWriter, Writer};
+
+/// Largest extension block a stream encoder writes: no restart markers nor low bits.
+const MAX_EXT_LEN: usize =
+    ext_len(u16::MAX, 0, Channels::La, None, None, Some(ColorSpace::Linear), &[], None);
+
+/// Push-based encoder for pixels arriving a bit at a time, e.g. scanlines from a camera driver
+/// or a renderer.
+///
+/// Pixels are fed with [`StreamEncoder::push_pixels`] in any amounts, even splitting pixels,
+/// and encoded right away: the index, previous pixel and pending run are carried over from
+/// one push to the next, so the output is the same as encoding the whole frame at once. Ops
+/// go through a buffered writer, and [`StreamEncoder::finish`] writes the extension block
+/// and then seeks back to fill in the header, which holds the length of the op stream.
+///
+/// The restart interval and output verification of [`EncoderOptions`] are ignored, as both
+/// need the whole op stream; there's no checksum for the same reason.
+pub struct StreamEncoder<W: Write + Seek> {
+    writer: BufWriter<W>,
+    header: Header,
+    channels: Channels,
+    options: EncoderOptions,
+    state: EncodeState,
+    // position of the image in the writer
+    start: u64,
+    // bytes of a pixel split between pushes
+    partial: [u8; 4],
+    n_partial: usize,
+    n_pushed: usize,
+    ops_len: usize,
+}
+
+impl<W: Write + Seek> StreamEncoder<W> {
+    /// Creates a new encoder writing an image to `writer` at its current position.
+    #[inline]
+    pub fn new(writer: W, width: u16, height: u16, channels: Channels) -> Result<Self> {
+        Self::with_options(writer, width, height, channels, EncoderOptions::new())
+    }
+
+    /// Creates a new encoder with a given encoder configuration.
+    pub fn with_options(
+        mut writer: W, width: u16, height: u16, channels: Channels, options: EncoderOptions,
+    ) -> Result<Self> {
+        let header = Header::try_new(width, height, None)?;
+        let start = writer.stream_position()?;
+        let mut writer = BufWriter::new(writer);
+        // room for the header, filled in by `finish`
+        writer.write_all(&[0; QOI_REFERENCE_HEADER_SIZE][..options.header_format.size()])?;
+        Ok(Self {
+            writer,
+            header,
+            channels,
+            options,
+            state: EncodeState::new(usize::MAX, false),
+            start,
+            partial: [0; 4],
+            n_partial: 0,
+            n_pushed: 0,
+            ops_len: 0,
+        })
+    }
+
+    /// Returns the image header; its length is only set once the image is finished.
+    #[inline]
+    pub const fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Number of whole pixels pushed so far.
+    #[inline]
+    pub const fn pixels_pushed(&self) -> usize {
+        self.n_pushed
+    }
+
+    /// Encodes the next pixels of the image, laid out like the channels given on creation.
+    ///
+    /// Pushing more pixels than the image holds fails with [`Error::InvalidImageLength`].
+    pub fn push_pixels(&mut self, mut data: &[u8]) -> Result<()> {
+        let bpp = self.channels.as_u8() as usize;
+        let (n_pushed, n_left) = (self.n_pushed * bpp + self.n_partial, self.bytes_left());
+        if unlikely(data.len() > n_left) {
+            let (width, height) = (self.header.width, self.header.height);
+            return Err(Error::InvalidImageLength { size: n_pushed + data.len(), width, height });
+        }
+        if self.n_partial != 0 {
+            let n = (bpp - self.n_partial).min(data.len());
+            self.partial[self.n_partial..self.n_partial + n].copy_from_slice(&data[..n]);
+            (self.n_partial, data) = (self.n_partial + n, &data[n..]);
+            if self.n_partial < bpp {
+                return Ok(());
+            }
+            let partial = self.partial;
+            self.encode(&partial[..bpp])?;
+            self.n_partial = 0;
+        }
+        let (whole, rest) = data.split_at(data.len() / bpp * bpp);
+        self.encode(whole)?;
+        self.partial[..rest.len()].copy_from_slice(rest);
+        self.n_partial = rest.len();
+        Ok(())
+    }
+
+    /// Writes the end of the image once all its pixels have been pushed, fills in the header,
+    /// flushes the writer and returns it back.
+    pub fn finish(mut self) -> Result<W> {
+        if unlikely(self.bytes_left() != 0) {
+            let size = self.n_pushed * self.channels.as_u8() as usize + self.n_partial;
+            let (width, height) = (self.header.width, self.header.height);
+            return Err(Error::InvalidImageLength { size, width, height });
+        }
+        let buf = self.state.finish(GenericWriter::new(&mut self.writer))?;
+        self.ops_len += usize::MAX - buf.capacity();
+        let (width, height) = (self.header.width, self.header.height);
+        let mut head = [0; QOI_REFERENCE_HEADER_SIZE];
+        let head = &mut head[..self.options.header_format.size()];
+        match self.options.header_format {
+            HeaderFormat::GameMaker => {
+                let mut ext = [0; MAX_EXT_LEN];
+                let (channels, colorspace) = (self.channels, self.header.colorspace);
+                let n_ext = write_ext(
+                    &[],
+                    &mut ext,
+                    width,
+                    height,
+                    0,
+                    channels,
+                    None,
+                    None,
+                    colorspace,
+                    &[],
+                    None,
+                );
+                self.writer.write_all(&ext[..n_ext])?;
+                let length = u32::try_from(self.ops_len).map_err(|_| Error::SizeOverflow)?;
+                self.header.length = Some(length);
+                head.copy_from_slice(&self.header.encode()?);
+            }
+            HeaderFormat::Reference => {
+                head.copy_from_slice(&self.header.encode_reference(self.channels));
+            }
+        }
+        let mut writer = self.writer.into_inner().map_err(IntoInnerError::into_error)?;
+        let end = writer.stream_position()?;
+        writer.seek(SeekFrom::Start(self.start))?;
+        writer.write_all(head)?;
+        writer.seek(SeekFrom::Start(end))?;
+        writer.flush()?;
+        Ok(writer)
+    }
+
+    /// Number of bytes of pixel data still expected.
+    #[inline]
+    const fn bytes_left(&self) -> usize {
+        let n_left = self.header.n_pixels() - self.n_pushed;
+        n_left * self.channels.as_u8() as usize - self.n_partial
+    }
+
+    /// Encodes whole pixels, continuing from the state left by the previous ones.
+    fn encode(&mut self, data: &[u8]) -> Result<()> {
+        let bpp = self.channels.as_u8() as usize;
+        let (channels, options) = (self.channels, self.options);
+        let buf = GenericWriter::new(&mut self.writer);
+        let pixels = data.chunks_exact(bpp);
+        let state = &mut self.state;
+        let buf = encode_pixels(buf, pixels, channels, state, options, Tolerance::LOSSLESS)?;
+        self.ops_len += usize::MAX - buf.capacity();
+        self.n_pushed += data.len() / bpp;
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+//! Snapshot testing against golden images (requires the `testing` feature).
+//!
+//! Downstream test suites can compare rendered pixels with a reference image checked into
+//! the repository:
+//!
+//! ```ignore
+//! #[test]
+//! fn renders_sprite() {
+//!     let pixels = render_sprite();
+//!     qoi::testing::assert_matches_golden(&pixels, 64, 64, "tests/golden/sprite.qoi");
+//! }
+//! ```
+//!
+//! Running the tests with [`UPDATE_ENV_VAR`] set (e.g. `QOI_UPDATE_GOLDEN=1 cargo test`)
+//! writes the actual pixels as the new golden images instead of comparing them.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use crate::decode::decode_to_vec;
+use crate::encode::{encode_to_vec, Encoder};
+use crate::fs::write_file_atomic;
+use crate::pixel::Pixel;
+
+/// Environment variable that turns [`assert_matches_golden`] into updating the golden files.
+///
+/// Any value other than an empty string or `0` enables the update mode.
+pub const UPDATE_ENV_VAR: &str = "QOI_UPDATE_GOLDEN";
+
+/// Asserts that raw pixels match a golden image encoded in a file.
+///
+/// The pixels may be RGBA, RGB or luma + alpha, like in [`Encoder::new`](crate::Encoder::new);
+/// they are compared with the golden image as RGBA, so the golden file may use either layout.
+///
+/// On a mismatch, two images are written next to the golden file before panicking:
+/// `<name>.actual.qoi` with the actual pixels, and `<name>.diff.qoi` where differing pixels
+/// are red and all others are dimmed gray. The panic message lists the number of differing
+/// pixels, their bounding box and the first difference.
+///
+/// If [`UPDATE_ENV_VAR`] is set, the actual pixels are written to the golden file instead,
+/// creating missing parent directories, and the assertion always passes.
+///
+/// # Panics
+///
+/// Panics if the pixels don't match the golden image, if the golden file can't be read or
+/// decoded, or if the pixel data is invalid for the given dimensions.
+#[track_caller]
+pub fn assert_matches_golden(
+    actual: impl AsRef<[u8]>, width: u16, height: u16, golden: impl AsRef<Path>,
+) {
+    let (actual, golden) = (actual.as_ref(), golden.as_ref());
+    let channels = match Encoder::new(actual, width, height) {
+        Ok(encoder) => encoder.channels(),
+        Err(err) => panic!("invalid pixel data for {width}x{height} image: {err}"),
+    };
+
+    if update_mode() {
+        if let Some(dir) = golden.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            if let Err(err) = fs::create_dir_all(dir) {
+                panic!("failed to create {}: {err}", dir.display());
+            }
+        }
+        if let Err(err) = write_file_atomic(golden, actual, width, height) {
+            panic!("failed to update golden image {}: {err}", golden.display());
+        }
+        return;
+    }
+
+    let encoded = match fs::read(golden) {
+        Ok(encoded) => encoded,
+        Err(err) => panic!(
+            "failed to read golden image {}: {err} (run with {UPDATE_ENV_VAR}=1 to create it)",
+            golden.display()
+        ),
+    };
+    let (header, expected) = match decode_to_vec(encoded) {
+        Ok(decoded) => decoded,
+        Err(err) => panic!("failed to decode golden image {}: {err}", golden.display()),
+    };
+    if (header.width, header.height) != (width, height) {
+        let actual_path = write_sibling(golden, "actual", actual, width, height);
+        panic!(
+            "golden image {} is {}x{}, but the actual image is {width}x{height} (written to {})",
+            golden.display(),
+            header.width,
+            header.height,
+            actual_path.display()
+        );
+    }
+
+    let expected_bpp = expected.len() / header.n_pixels();
+    let actual_bpp = channels.as_u8() as usize;
+    let mut diff = Vec::with_capacity(header.n_pixels() * 4);
+    let mut n_diff = 0_usize;
+    let mut first = None;
+    let (mut min, mut max) = ((width, height), (0, 0));
+    let pairs = actual.chunks_exact(actual_bpp).zip(expected.chunks_exact(expected_bpp));
+    for (i, (a, e)) in pairs.enumerate() {
+        let (mut px_actual, mut px_expected) = (Pixel::new(), Pixel::new());
+        px_actual.read(a);
+        px_expected.read(e);
+        if px_actual == px_expected {
+            let gray = px_expected.luma() / 4 + 64;
+            diff.extend_from_slice(&[gray, gray, gray, 255]);
+            continue;
+        }
+        diff.extend_from_slice(&[255, 0, 0, 255]);
+        // can't truncate: both coordinates are within the u16 dimensions
+        #[allow(clippy::cast_possible_truncation)]
+        let (x, y) = ((i % width as usize) as u16, (i / width as usize) as u16);
+        n_diff += 1;
+        first = first.or(Some((x, y, px_actual, px_expected)));
+        min = (min.0.min(x), min.1.min(y));
+        max = (max.0.max(x), max.1.max(y));
+    }
+
+    if let Some((x, y, px_actual, px_expected)) = first {
+        let actual_path = write_sibling(golden, "actual", actual, width, height);
+        let diff_path = write_sibling(golden, "diff", &diff, width, height);
+        let mut msg = format!(
+            "image doesn't match golden image {}: {n_diff} of {} pixels differ",
+            golden.display(),
+            header.n_pixels()
+        );
+        let _ = write!(msg, " within ({}, {})..=({}, {})", min.0, min.1, max.0, max.1);
+        let _ = write!(
+            msg,
+            "\nfirst difference at ({x}, {y}): actual {:?}, expected {:?}",
+            <[u8; 4]>::from(px_actual),
+            <[u8; 4]>::from(px_expected)
+        );
+        let _ = write!(msg, "\nactual: {}\ndiff: {}", actual_path.display(), diff_path.display());
+        let _ = write!(msg, "\n(run with {UPDATE_ENV_VAR}=1 to accept the changes)");
+        panic!("{msg}");
+    }
+}
+
+fn update_mode() -> bool {
+    env::var_os(UPDATE_ENV_VAR).map_or(false, |value| !value.is_empty() && value != "0")
+}
+
+/// Writes an image next to the golden file, e.g. `sprite.qoi` -> `sprite.diff.qoi`.
+///
+/// Failures are ignored since this only happens on the way to a panic anyway.
+fn write_sibling(golden: &Path, suffix: &str, data: &[u8], width: u16, height: u16) -> PathBuf {
+    let mut name = golden.file_stem().unwrap_or_default().to_os_string();
+    name.push(format!(".{suffix}.qoi"));
+    let path = golden.with_file_name(name);
+    if let Ok(encoded) = encode_to_vec(data, width, height) {
+        let _ = fs::write(&path, encoded);
+    }
+    path
+}
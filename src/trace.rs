@@ -0,0 +1,45 @@
+use tracing::field::Empty;
+use tracing::Span;
+
+use crate::error::Result;
+use crate::header::{Channels, Header};
+
+/// Span of a whole encoding call, named after the public entry point.
+///
+/// Durations are left to the subscriber, which measures them from the span being entered
+/// and exited.
+#[inline]
+pub fn encode_span(name: &'static str, header: &Header, channels: Channels) -> Span {
+    tracing::debug_span!(
+        "qoi::encode",
+        method = name,
+        width = header.width,
+        height = header.height,
+        channels = channels.as_u8(),
+        bytes = Empty,
+        error = Empty,
+    )
+}
+
+/// Span of a whole decoding call, named after the public entry point.
+#[inline]
+pub fn decode_span(name: &'static str, header: &Header, channels: Channels) -> Span {
+    tracing::debug_span!(
+        "qoi::decode",
+        method = name,
+        width = header.width,
+        height = header.height,
+        channels = channels.as_u8(),
+        bytes = Empty,
+        error = Empty,
+    )
+}
+
+/// Records the number of bytes written, or the error, of a finished call.
+#[inline]
+pub fn record(span: &Span, result: &Result<usize>) {
+    match *result {
+        Ok(size) => span.record("bytes", size),
+        Err(ref err) => span.record("error", err.as_str()),
+    };
+}
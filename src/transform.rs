@@ -0,0 +1,117 @@
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+
+#[cfg(feature = "std")]
+use crate::consts::QOI_HEADER_SIZE;
+#[cfg(feature = "std")]
+use crate::header::HeaderFormat;
+
+/// Reversible transformation of the encoded bytes following the header (e.g. encryption).
+///
+/// The header itself is never transformed, so images can still be identified and sized without
+/// reverting the transform. Both methods work in place and receive the offset of `data`
+/// relative to the end of the header, so chunks may be processed one at a time.
+pub trait StreamTransform {
+    /// Applies the transform to freshly encoded bytes.
+    fn encode(&mut self, offset: usize, data: &mut [u8]);
+    /// Reverts [`StreamTransform::encode`] before decoding.
+    fn decode(&mut self, offset: usize, data: &mut [u8]);
+}
+
+impl<T: StreamTransform + ?Sized> StreamTransform for &mut T {
+    #[inline]
+    fn encode(&mut self, offset: usize, data: &mut [u8]) {
+        (**self).encode(offset, data);
+    }
+
+    #[inline]
+    fn decode(&mut self, offset: usize, data: &mut [u8]) {
+        (**self).decode(offset, data);
+    }
+}
+
+/// XOR with a repeating key.
+///
+/// This provides no real security and is meant for testing transform integrations.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct XorTransform<K> {
+    key: K,
+}
+
+impl<K: AsRef<[u8]>> XorTransform<K> {
+    /// Creates a new transform with a given key (an empty key leaves the data unchanged).
+    #[inline]
+    pub const fn new(key: K) -> Self {
+        Self { key }
+    }
+
+    #[inline]
+    fn apply(&self, offset: usize, data: &mut [u8]) {
+        let key = self.key.as_ref();
+        if key.is_empty() {
+            return;
+        }
+        let key = key.iter().cycle().skip(offset % key.len());
+        for (b, k) in data.iter_mut().zip(key) {
+            *b ^= k;
+        }
+    }
+}
+
+impl<K: AsRef<[u8]>> StreamTransform for XorTransform<K> {
+    #[inline]
+    fn encode(&mut self, offset: usize, data: &mut [u8]) {
+        self.apply(offset, data);
+    }
+
+    #[inline]
+    fn decode(&mut self, offset: usize, data: &mut [u8]) {
+        self.apply(offset, data);
+    }
+}
+
+/// Reader adapter that reverts a [`StreamTransform`] on the fly.
+///
+/// Wrap a reader with it and pass it to [`Decoder::from_stream`](crate::Decoder::from_stream)
+/// to decode transformed images without buffering them.
+#[cfg(feature = "std")]
+pub struct TransformReader<R, T> {
+    reader: R,
+    transform: T,
+    pos: usize,
+    magic: [u8; 4],
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, T: StreamTransform> TransformReader<R, T> {
+    /// Creates a new adapter, the reader must be positioned at the start of the image.
+    #[inline]
+    pub const fn new(reader: R, transform: T) -> Self {
+        Self { reader, transform, pos: 0, magic: [0; 4] }
+    }
+
+    /// Consumes the adapter and returns the underlying reader back.
+    #[inline]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, T: StreamTransform> Read for TransformReader<R, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        // the magic is complete before the first byte past the header is reached
+        let magic = self.magic.get_mut(self.pos..).unwrap_or_default();
+        let n_magic = magic.len().min(n);
+        magic[..n_magic].copy_from_slice(&buf[..n_magic]);
+        let header_size =
+            HeaderFormat::detect(self.magic).map_or(QOI_HEADER_SIZE, HeaderFormat::size);
+        let header_left = header_size.saturating_sub(self.pos).min(n);
+        let offset = (self.pos + header_left).saturating_sub(header_size);
+        self.transform.decode(offset, &mut buf[header_left..n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
@@ -1,6 +1,10 @@
+#[cfg(any(feature = "std", feature = "alloc"))]
+use alloc::vec::Vec;
 #[cfg(feature = "std")]
 use std::io::Write;
 
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::error::Error;
 use crate::error::Result;
 
 #[inline(always)]
@@ -24,6 +28,24 @@ pub const fn unlikely(b: bool) -> bool {
     b
 }
 
+/// Allocates an empty vector with a given capacity, failing gracefully if that's impossible.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+pub fn try_vec_with_capacity<T>(capacity: usize) -> Result<Vec<T>> {
+    let mut out = Vec::new();
+    out.try_reserve_exact(capacity).map_err(|_| Error::OutOfMemory)?;
+    Ok(out)
+}
+
+/// Allocates a zero-filled vector, failing gracefully if that's impossible.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[inline]
+pub fn try_vec_zeroed(len: usize) -> Result<Vec<u8>> {
+    let mut out = try_vec_with_capacity(len)?;
+    out.resize(len, 0);
+    Ok(out)
+}
+
 pub trait Writer: Sized {
     fn write_one(self, v: u8) -> Result<Self>;
     fn write_many(self, v: &[u8]) -> Result<Self>;
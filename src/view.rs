@@ -0,0 +1,106 @@
+use crate::error::{Error, Result};
+use crate::header::{Channels, Header};
+use crate::pixel::Pixel;
+use crate::utils::unlikely;
+
+/// Read-only two-dimensional view over decoded pixel data.
+///
+/// All bounds checks and row arithmetic happen here, so code handling decoded output doesn't
+/// have to compute offsets by hand. RGBA, RGB and luma + alpha data are supported, the layout
+/// being inferred from the buffer length like in [`Encoder::new`](crate::Encoder::new).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PixelsView<'a> {
+    data: &'a [u8],
+    width: u16,
+    height: u16,
+    channels: Channels,
+}
+
+impl<'a> PixelsView<'a> {
+    /// Creates a view over decoded pixel data with the dimensions from `header`.
+    #[inline]
+    pub fn new(data: &'a [u8], header: &Header) -> Result<Self> {
+        let (width, height, size) = (header.width, header.height, data.len());
+        let header = Header::try_new(width, height, None)?;
+        let channels = match size / header.n_pixels() {
+            2 => Channels::La,
+            3 => Channels::Rgb,
+            4 => Channels::Rgba,
+            _ => return Err(Error::InvalidImageLength { size, width, height }),
+        };
+        if unlikely(header.n_pixels().checked_mul(channels.as_u8() as usize) != Some(size)) {
+            return Err(Error::InvalidImageLength { size, width, height });
+        }
+        Ok(Self { data, width, height, channels })
+    }
+
+    /// Image width in pixels.
+    #[inline]
+    pub const fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Image height in pixels.
+    #[inline]
+    pub const fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Layout of the pixel data.
+    #[inline]
+    pub const fn channels(&self) -> Channels {
+        self.channels
+    }
+
+    /// Raw pixel data.
+    #[inline]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    #[inline]
+    const fn row_len(&self) -> usize {
+        self.width as usize * self.channels.as_u8() as usize
+    }
+
+    /// Raw bytes of a row, or `None` if it's out of bounds.
+    #[inline]
+    pub fn row(&self, y: u16) -> Option<&'a [u8]> {
+        let row_len = self.row_len();
+        self.data.get(y as usize * row_len..(y as usize + 1) * row_len)
+    }
+
+    /// Returns the pixel at given coordinates, or `None` if they are out of bounds.
+    ///
+    /// Luma + alpha pixels are expanded to gray RGBA.
+    #[inline]
+    pub fn get(&self, x: u16, y: u16) -> Option<Pixel> {
+        if x >= self.width {
+            return None;
+        }
+        let bpp = self.channels.as_u8() as usize;
+        let row = self.row(y)?;
+        let mut px = Pixel::new();
+        px.read(&row[x as usize * bpp..(x as usize + 1) * bpp]);
+        Some(px)
+    }
+
+    /// Iterates over the raw bytes of all rows, top to bottom.
+    #[inline]
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = &'a [u8]> + 'a {
+        self.data.chunks_exact(self.row_len())
+    }
+
+    /// Iterates over all pixels in row-major order along with their coordinates.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn pixels(&self) -> impl Iterator<Item = (u16, u16, Pixel)> + 'a {
+        let (width, bpp) = (self.width as usize, self.channels.as_u8() as usize);
+        self.data.chunks_exact(bpp).enumerate().map(move |(i, chunk)| {
+            let mut px = Pixel::new();
+            px.read(chunk);
+            // can't truncate: both coordinates are within the u16 dimensions
+            ((i % width) as u16, (i / width) as u16, px)
+        })
+    }
+}
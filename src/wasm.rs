@@ -0,0 +1,65 @@
+//! JavaScript bindings for WebAssembly builds.
+//!
+//! Build the crate for `wasm32-unknown-unknown` with the `wasm` feature and run `wasm-bindgen`
+//! on the output to get `encode`, `decode` and `decodeHeader` functions taking and returning
+//! `Uint8Array`s. Errors are thrown as JavaScript `Error`s holding the message of [`Error`].
+//!
+//! [`Error`]: crate::Error
+
+// the generated glue code contains unsafe blocks
+#![allow(unsafe_code)]
+
+use std::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+
+use crate::decode::Decoder;
+use crate::encode::Encoder;
+use crate::header::Channels;
+
+/// Image header as seen from JavaScript, see [`decode_header`].
+#[wasm_bindgen]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ImageInfo {
+    /// Image width in pixels
+    pub width: u16,
+    /// Image height in pixels
+    pub height: u16,
+    /// Bytes per pixel the image is decoded to by default: 2 (luma + alpha), 3 (RGB) or 4
+    /// (RGBA)
+    pub channels: u8,
+}
+
+/// Encodes an image, inferring the number of channels (2, 3 or 4) from the length of
+/// `pixels`.
+#[wasm_bindgen]
+pub fn encode(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsError> {
+    Ok(Encoder::new(pixels, width, height)?.encode_to_vec()?)
+}
+
+/// Decodes an image to `channels` bytes per pixel (2, 3 or 4), or to the layout it has been
+/// encoded from if `channels` is zero.
+#[wasm_bindgen]
+pub fn decode(data: &[u8], channels: u8) -> Result<Vec<u8>, JsError> {
+    let decoder = Decoder::new(data)?;
+    let mut decoder = match channels {
+        0 => decoder,
+        2 => decoder.with_channels(Channels::La),
+        3 => decoder.with_channels(Channels::Rgb),
+        4 => decoder.with_channels(Channels::Rgba),
+        _ => return Err(JsError::new("invalid number of channels: expected 0, 2, 3 or 4")),
+    };
+    Ok(decoder.decode_to_vec()?)
+}
+
+/// Reads the header of an encoded image without decoding it.
+#[wasm_bindgen(js_name = decodeHeader)]
+pub fn decode_header(data: &[u8]) -> Result<ImageInfo, JsError> {
+    let decoder = Decoder::new(data)?;
+    let header = decoder.header();
+    Ok(ImageInfo {
+        width: header.width,
+        height: header.height,
+        channels: decoder.channels().as_u8(),
+    })
+}
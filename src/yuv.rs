@@ -0,0 +1,174 @@
+use crate::error::{Error, Result};
+
+/// Coefficients relating YUV to RGB, with the limited (studio) range of video: Y spans 16-235
+/// and U/V span 16-240.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum YuvMatrix {
+    /// ITU-R BT.601, used for SD video and by most webcams (default)
+    #[default]
+    Bt601,
+    /// ITU-R BT.709, used for HD video
+    Bt709,
+}
+
+impl YuvMatrix {
+    /// Fixed-point (8 fractional bits) factors of V for R, U and V for G, and U for B.
+    #[inline]
+    const fn factors(self) -> [i32; 4] {
+        match self {
+            Self::Bt601 => [409, 100, 208, 516],
+            Self::Bt709 => [459, 55, 136, 541],
+        }
+    }
+
+    /// Fixed-point (8 fractional bits) factors of R, G and B for Y, U and V.
+    #[inline]
+    const fn inverse_factors(self) -> [[i32; 3]; 3] {
+        match self {
+            Self::Bt601 => [[66, 129, 25], [-38, -74, 112], [112, -94, -18]],
+            Self::Bt709 => [[47, 157, 16], [-26, -87, 112], [112, -102, -10]],
+        }
+    }
+
+    /// Converts an RGB pixel to a YUV sample.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // always in range
+    pub const fn to_yuv(self, r: u8, g: u8, b: u8) -> [u8; 3] {
+        let [fy, fu, fv] = self.inverse_factors();
+        let rgb = [r as i32, g as i32, b as i32];
+        [(dot(fy, rgb) + 16) as u8, (dot(fu, rgb) + 128) as u8, (dot(fv, rgb) + 128) as u8]
+    }
+
+    /// Converts a YUV sample to RGB.
+    #[inline]
+    pub const fn to_rgb(self, y: u8, u: u8, v: u8) -> [u8; 3] {
+        let [rv, gu, gv, bu] = self.factors();
+        let (luma, cb, cr) = (298 * (y as i32 - 16) + 128, u as i32 - 128, v as i32 - 128);
+        [clamp(luma + rv * cr), clamp(luma - gu * cb - gv * cr), clamp(luma + bu * cb)]
+    }
+}
+
+/// Applies fixed-point factors to RGB channels, rounding to the nearest integer.
+#[inline]
+const fn dot(f: [i32; 3], rgb: [i32; 3]) -> i32 {
+    (f[0] * rgb[0] + f[1] * rgb[1] + f[2] * rgb[2] + 128) >> 8
+}
+
+/// Drops the fractional bits of a fixed-point channel value and clamps it to 0-255.
+#[inline]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+const fn clamp(x: i32) -> u8 {
+    let x = x >> 8;
+    (if x < 0 {
+        0
+    } else if x > 0xff {
+        0xff
+    } else {
+        x
+    }) as u8
+}
+
+/// Chroma planes of a 4:2:0 frame.
+#[derive(Copy, Clone)]
+enum Chroma<'a> {
+    /// Separate U and V planes (I420)
+    Planar(&'a [u8], &'a [u8]),
+    /// One plane of interleaved U and V samples (NV12)
+    Interleaved(&'a [u8]),
+}
+
+/// Borrowed YUV 4:2:0 frame, converted to RGB as it's read.
+///
+/// Every 2x2 block of pixels shares a chroma sample; with an odd width or height, the last
+/// column or row of chroma samples covers a single column or row of pixels. Planes are tightly
+/// packed, without padding at the end of the rows.
+#[derive(Copy, Clone)]
+pub struct Yuv420<'a> {
+    y: &'a [u8],
+    chroma: Chroma<'a>,
+    width: usize,
+    matrix: YuvMatrix,
+}
+
+impl<'a> Yuv420<'a> {
+    /// Takes separate Y, U and V planes.
+    pub const fn planar(
+        y: &'a [u8], u: &'a [u8], v: &'a [u8], width: u16, height: u16, matrix: YuvMatrix,
+    ) -> Result<Self> {
+        let n_chroma = chroma_len(width, height);
+        if u.len() != n_chroma || v.len() != n_chroma {
+            let size = y.len() + u.len() + v.len();
+            return Err(Error::InvalidImageLength { size, width, height });
+        }
+        Self::new(y, Chroma::Planar(u, v), width, height, matrix)
+    }
+
+    /// Takes a Y plane followed by a plane of interleaved U and V samples.
+    pub const fn nv12(
+        y: &'a [u8], uv: &'a [u8], width: u16, height: u16, matrix: YuvMatrix,
+    ) -> Result<Self> {
+        if uv.len() != 2 * chroma_len(width, height) {
+            let size = y.len() + uv.len();
+            return Err(Error::InvalidImageLength { size, width, height });
+        }
+        Self::new(y, Chroma::Interleaved(uv), width, height, matrix)
+    }
+
+    #[inline]
+    const fn new(
+        y: &'a [u8], chroma: Chroma<'a>, width: u16, height: u16, matrix: YuvMatrix,
+    ) -> Result<Self> {
+        if y.len() != width as usize * height as usize {
+            let size = y.len()
+                + match chroma {
+                    Chroma::Planar(u, v) => u.len() + v.len(),
+                    Chroma::Interleaved(uv) => uv.len(),
+                };
+            return Err(Error::InvalidImageLength { size, width, height });
+        }
+        Ok(Self { y, chroma, width: width as usize, matrix })
+    }
+
+    /// Replaces the matrix the samples are converted with.
+    #[inline]
+    pub fn set_matrix(&mut self, matrix: YuvMatrix) {
+        self.matrix = matrix;
+    }
+
+    /// Converts the pixel at index `i` (in row-major order) to RGB.
+    #[inline]
+    pub fn pixel(&self, i: usize) -> [u8; 3] {
+        let (u, v) = self.chroma(i % self.width, i / self.width);
+        self.matrix.to_rgb(self.y[i], u, v)
+    }
+
+    /// Converts the pixels from index `start` on to RGB, filling `out`.
+    #[inline]
+    pub fn read(&self, start: usize, out: &mut [u8]) {
+        let (mut x, mut y) = (start % self.width, start / self.width);
+        for (px, &luma) in out.chunks_exact_mut(3).zip(&self.y[start..]) {
+            let (u, v) = self.chroma(x, y);
+            px.copy_from_slice(&self.matrix.to_rgb(luma, u, v));
+            x += 1;
+            if x == self.width {
+                (x, y) = (0, y + 1);
+            }
+        }
+    }
+
+    /// Chroma sample covering the pixel at `(x, y)`.
+    #[inline]
+    fn chroma(&self, x: usize, y: usize) -> (u8, u8) {
+        let pos = y / 2 * ((self.width + 1) / 2) + x / 2;
+        match self.chroma {
+            Chroma::Planar(u, v) => (u[pos], v[pos]),
+            Chroma::Interleaved(uv) => (uv[2 * pos], uv[2 * pos + 1]),
+        }
+    }
+}
+
+/// Number of samples in each chroma plane.
+#[inline]
+const fn chroma_len(width: u16, height: u16) -> usize {
+    (width as usize + 1) / 2 * ((height as usize + 1) / 2)
+}
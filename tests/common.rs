@@ -0,0 +1,11 @@
+/// Pixels mixing runs, small differences and distant colors, so that every op is used.
+pub fn pixels(width: u32, height: u32, channels: u8) -> Vec<u8> {
+    let len = width as usize * height as usize * usize::from(channels);
+    let mut value = 0_u8;
+    let step = |i| match i % 23 {
+        0..=11 => 0,
+        12..=17 => 1,
+        _ => 97,
+    };
+    (0..len).map(|i| (value = value.wrapping_add(step(i)), value).1).collect()
+}
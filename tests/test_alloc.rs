@@ -0,0 +1,41 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+
+use qoi::{decode_to_vec, Decoder, Error, Header, Image};
+
+/// Refuses any allocation over 256 MiB, like a server with a memory limit would.
+struct Capped;
+
+const MAX_ALLOC: usize = 256 << 20;
+
+unsafe impl GlobalAlloc for Capped {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() > MAX_ALLOC {
+            return std::ptr::null_mut();
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Capped = Capped;
+
+#[test]
+fn test_out_of_memory() {
+    // a valid (blank) image whose pixels need well over the allocation cap
+    let (width, height) = (20_000, 19_000);
+    let header = Header::try_new(width, height, Some(8)).unwrap();
+    let mut encoded = header.encode().unwrap().to_vec();
+    encoded.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+    assert!(matches!(decode_to_vec(&encoded), Err(Error::OutOfMemory)));
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    assert!(matches!(decoder.decode_to_vec(), Err(Error::OutOfMemory)));
+    assert!(matches!(Image::new(width, height), Err(Error::OutOfMemory)));
+
+    // allocations under the cap still go through
+    assert_eq!(Image::new(256, 256).unwrap().pixels().len(), 256 * 256 * 4);
+}
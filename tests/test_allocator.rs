@@ -0,0 +1,67 @@
+#![cfg(feature = "allocator-api2")]
+
+mod common;
+
+use std::cell::Cell;
+use std::ptr::NonNull;
+
+use allocator_api2::alloc::{AllocError, Allocator, Global, Layout};
+
+use qoi::{decode_to_vec, decode_to_vec_in, encode_to_vec, encode_to_vec_in, Decoder, Error};
+
+use common::pixels;
+
+/// Allocator that counts the bytes allocated through it and refuses to go over a budget.
+struct Budget {
+    allocated: Cell<usize>,
+    limit: usize,
+}
+
+impl Budget {
+    const fn new(limit: usize) -> Self {
+        Self { allocated: Cell::new(0), limit }
+    }
+}
+
+unsafe impl Allocator for &Budget {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let allocated = self.allocated.get() + layout.size();
+        if allocated > self.limit {
+            return Err(AllocError);
+        }
+        self.allocated.set(allocated);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        Global.deallocate(ptr, layout);
+    }
+}
+
+#[test]
+fn test_allocator_in() {
+    let pixels = pixels(16, 8, 4);
+    let budget = Budget::new(usize::MAX);
+    let encoded = encode_to_vec_in(&pixels, 16, 8, &budget).unwrap();
+    assert_eq!(&encoded[..], encode_to_vec(&pixels, 16, 8).unwrap());
+    // the output is allocated once, for the largest possible encoded size
+    let n_encode = budget.allocated.get();
+    assert_eq!(n_encode, encoded.capacity());
+
+    let (header, decoded) = decode_to_vec_in(&encoded, &budget).unwrap();
+    assert_eq!((header, &decoded[..]), (decode_to_vec(&encoded).unwrap().0, &pixels[..]));
+    assert_eq!(budget.allocated.get() - n_encode, pixels.len());
+
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    assert_eq!(&decoder.decode_to_vec_in(&budget).unwrap()[..], &pixels[..]);
+}
+
+#[test]
+fn test_allocator_in_out_of_memory() {
+    let pixels = pixels(16, 8, 4);
+    let encoded = encode_to_vec(&pixels, 16, 8).unwrap();
+    let budget = Budget::new(pixels.len() - 1);
+    assert!(matches!(encode_to_vec_in(&pixels, 16, 8, &budget), Err(Error::OutOfMemory)));
+    assert!(matches!(decode_to_vec_in(&encoded, &budget), Err(Error::OutOfMemory)));
+    assert_eq!(budget.allocated.get(), 0);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use qoi::analyze::suggest_crop;
+use qoi::consts::QOI_PADDING_SIZE;
+use qoi::ops::OpKind;
+use qoi::{encode_to_vec, explain, Decoder, Encoder, EncoderOptions, OpCost, Rect};
+
+use common::pixels;
+
+const WIDTH: u16 = 20;
+const HEIGHT: u16 = 10;
+
+/// Crop suggested for an image of `background` with the pixels in `content` taken from a
+/// noisy opaque image.
+fn crop(background: [u8; 4], content: impl Fn(u16, u16) -> bool) -> Option<Rect> {
+    let noise = pixels(WIDTH.into(), HEIGHT.into(), 4);
+    let coords = (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| (x, y)));
+    let image: Vec<u8> = coords
+        .zip(noise.chunks_exact(4))
+        .flat_map(
+            |((x, y), px)| {
+                if content(x, y) {
+                    [px[0], px[1], px[2] | 1, 0xff]
+                } else {
+                    background
+                }
+            },
+        )
+        .collect();
+    suggest_crop(encode_to_vec(image, WIDTH, HEIGHT).unwrap()).unwrap()
+}
+
+#[test]
+fn test_suggest_crop() {
+    let black = [0, 0, 0, 0xff];
+    assert_eq!(crop(black, |_, _| false), None);
+    assert_eq!(crop([0x12, 0x34, 0x56, 0x78], |_, _| false), None);
+
+    let block = crop(black, |x, y| (5..12).contains(&x) && (3..7).contains(&y));
+    assert_eq!(block, Some(Rect { x: 5, y: 3, width: 7, height: 4 }));
+    let pixel = crop(black, |x, y| (x, y) == (WIDTH - 1, HEIGHT - 1));
+    assert_eq!(pixel, Some(Rect { x: WIDTH - 1, y: HEIGHT - 1, width: 1, height: 1 }));
+    let whole = crop(black, |_, _| true);
+    assert_eq!(whole, Some(Rect { x: 0, y: 0, width: WIDTH, height: HEIGHT }));
+
+    // transparent bars on every side, with content rows whose runs cross row boundaries
+    let border = crop([0; 4], |x, y| {
+        (2..WIDTH - 2).contains(&x) && (1..HEIGHT - 1).contains(&y) && (y != 4 || x == 2)
+    });
+    assert_eq!(border, Some(Rect { x: 2, y: 1, width: WIDTH - 4, height: HEIGHT - 2 }));
+}
+
+#[test]
+fn test_suggest_crop_errors() {
+    let mut encoded = encode_to_vec(vec![0; 4 * 20 * 10], 20, 10).unwrap();
+    let n = encoded.len();
+    encoded[n - 1] ^= 1;
+    assert!(matches!(suggest_crop(&encoded), Err(qoi::Error::InvalidPadding { .. })));
+}
+
+#[test]
+fn test_explain() {
+    let pixels = pixels(37, 21, 4);
+    for options in [EncoderOptions::new(), EncoderOptions::new().restart_interval(4)] {
+        let mut encoder = Encoder::new(&pixels, 37, 21).unwrap().with_options(options);
+        let encoded = encoder.encode_to_vec().unwrap();
+        let explanation = explain(&encoded).unwrap();
+        assert_eq!((explanation.cols, explanation.rows), (3, 2));
+        assert_eq!(explanation.block_bytes.len(), 6);
+
+        // every op is counted once, both per kind and per block
+        let kinds =
+            [OpKind::Index, OpKind::Diff, OpKind::Luma, OpKind::Run, OpKind::Rgb, OpKind::Rgba];
+        let costs: Vec<OpCost> = kinds.into_iter().map(|kind| explanation.op_cost(kind)).collect();
+        let ops_len =
+            Decoder::new(&encoded).unwrap().header().length.unwrap() as usize - QOI_PADDING_SIZE;
+        assert_eq!(costs.iter().map(|cost| cost.n_bytes).sum::<usize>(), ops_len);
+        assert_eq!(explanation.block_bytes.iter().sum::<usize>(), ops_len);
+        assert_eq!(costs.iter().map(|cost| cost.n_pixels).sum::<usize>(), 37 * 21);
+        assert_eq!(explanation.overhead, encoded.len() - ops_len);
+        assert!(costs.iter().all(|cost| cost.n_bytes >= cost.n_ops));
+
+        // the last column of blocks is 5 pixels wide, the last row 5 pixels high
+        let bits = explanation.block_bytes[5] as f32 * 8. / 25.;
+        assert_eq!(explanation.bits_per_pixel(2, 1), Some(bits));
+        assert_eq!(explanation.bits_per_pixel(3, 0), None);
+    }
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use std::io::Cursor;
+
+use qoi::{AnimOptions, AnimationDecoder, AnimationEncoder, RecordingWriter};
+
+use common::pixels;
+
+#[test]
+fn test_anim_keyframe_policy() {
+    let first = pixels(32, 32, 4);
+    let mut small_change = first.clone();
+    small_change[..8].fill(0x55);
+    // everything but the first row changes, so the changed region isn't the whole canvas
+    let mut cut: Vec<u8> = first.iter().map(|b| b.wrapping_mul(7) ^ 0x3c).collect();
+    cut[..32 * 4].copy_from_slice(&small_change[..32 * 4]);
+    let frames = [&first, &small_change, &cut];
+
+    for (options, keyframes) in [
+        (AnimOptions::new(), [true, false, false]),
+        (AnimOptions::new().max_delta_percent(50), [true, false, true]),
+        (AnimOptions::new().keyframe_interval(2), [true, false, true]),
+    ] {
+        let mut encoder =
+            AnimationEncoder::new(Vec::new(), 32, 32).unwrap().with_anim_options(options);
+        for frame in frames {
+            encoder.add_frame(frame, 40).unwrap();
+        }
+        let encoded = encoder.finish().unwrap();
+        let mut decoder = AnimationDecoder::new(&encoded).unwrap();
+        for (i, frame) in frames.into_iter().enumerate() {
+            let info = decoder.next_frame().unwrap().unwrap();
+            assert_eq!(info.keyframe, keyframes[i]);
+            assert_eq!(decoder.frame(), &frame[..]);
+        }
+    }
+}
+
+#[test]
+fn test_anim_seek_time() {
+    let frames: Vec<Vec<u8>> = (0..6_u8).map(|i| vec![i * 40; 8 * 8 * 3]).collect();
+    let mut encoder = AnimationEncoder::new(Vec::new(), 8, 8).unwrap().keyframe_interval(3);
+    for (frame, delay) in frames.iter().zip([100, 50, 0, 200, 100, 100]) {
+        encoder.add_frame(frame, delay).unwrap();
+    }
+    let encoded = encoder.finish().unwrap();
+    let mut decoder = AnimationDecoder::new(&encoded).unwrap();
+    assert_eq!(decoder.duration_ms(), 550);
+    assert_eq!((decoder.frame_start_ms(3), decoder.frame_start_ms(6)), (Some(150), None));
+    // the third frame has no delay, so it's never the one displayed
+    for (time, index) in [(0, 0), (99, 0), (100, 1), (150, 3), (420, 4), (1000, 5)] {
+        assert_eq!(decoder.seek_time(time).unwrap(), index);
+        assert_eq!(decoder.next().unwrap().unwrap().1, frames[index]);
+    }
+}
+
+#[test]
+fn test_recording_recover() {
+    let frames: Vec<Vec<u8>> = (0..4_u8).map(|i| vec![i * 50; 8 * 8 * 4]).collect();
+    let timestamps = [1000, 1040, 1100, 1180];
+    let mut recording = RecordingWriter::new(Cursor::new(Vec::new()), 8, 8).unwrap();
+    for (frame, &timestamp) in frames.iter().zip(&timestamps) {
+        recording.add_frame(frame, timestamp).unwrap();
+    }
+    let finished = recording.finish(1200).unwrap().into_inner();
+    let mut decoder = AnimationDecoder::new(&finished).unwrap();
+    for (frame, delay) in frames.iter().zip([40, 60, 80, 20]) {
+        assert_eq!(decoder.next_frame().unwrap().unwrap().delay_ms, delay);
+        assert_eq!(decoder.frame(), &frame[..]);
+    }
+
+    // crash in the middle of writing the last frame: the table is rebuilt from the records
+    let name = format!("qoi-test-recording-recover-{}.qoia", std::process::id());
+    let path = std::env::temp_dir().join(name);
+    let table_len = 4 * 28 + 16;
+    std::fs::write(&path, &finished[..finished.len() - table_len - 3]).unwrap();
+    assert_eq!(RecordingWriter::recover(&path).unwrap(), 3);
+    let recovered = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut decoder = AnimationDecoder::new(&recovered).unwrap();
+    assert_eq!(decoder.len(), 3);
+    for (frame, delay) in frames.iter().zip([40, 60, 80]) {
+        assert_eq!(decoder.next_frame().unwrap().unwrap().delay_ms, delay);
+        assert_eq!(decoder.frame(), &frame[..]);
+    }
+}
+
+#[test]
+fn test_recording_recover_corrupted_record() {
+    let frames: Vec<Vec<u8>> = (0..4_u8).map(|i| vec![i * 50; 8 * 8 * 4]).collect();
+    let mut recording = RecordingWriter::new(Cursor::new(Vec::new()), 8, 8).unwrap();
+    for (i, frame) in frames.iter().enumerate() {
+        recording.add_frame(frame, 1000 + 40 * i as u64).unwrap();
+    }
+    let mut data = recording.finish(1200).unwrap().into_inner();
+    data.truncate(data.len() - (4 * 28 + 16));
+
+    // walk the records and garble the offset of the third one
+    let mut pos = 12;
+    for _ in 0..2 {
+        let offset = u64::from_le_bytes(data[pos + 4..pos + 12].try_into().unwrap());
+        let length = u32::from_le_bytes(data[pos + 12..pos + 16].try_into().unwrap());
+        pos = (offset + u64::from(length)) as usize;
+    }
+    data[pos + 4..pos + 12].copy_from_slice(&u64::MAX.to_le_bytes());
+
+    let name = format!("qoi-test-recording-corrupted-{}.qoia", std::process::id());
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, &data).unwrap();
+    assert_eq!(RecordingWriter::recover(&path).unwrap(), 2);
+    let recovered = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut decoder = AnimationDecoder::new(&recovered).unwrap();
+    assert_eq!(decoder.len(), 2);
+    for frame in &frames[..2] {
+        decoder.next_frame().unwrap().unwrap();
+        assert_eq!(decoder.frame(), &frame[..]);
+    }
+}
@@ -0,0 +1,124 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use qoi::{AsyncDecoder, Channels, ColorSpace, Encoder, EncoderOptions, HeaderFormat, NinePatch};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use common::pixels;
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+/// Runs a future that never waits for anything but the reader below.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        if let Poll::Ready(out) = future.as_mut().poll(&mut cx) {
+            return out;
+        }
+    }
+}
+
+/// Reader handing out a few bytes at a time, and nothing every other time it's polled.
+struct Trickle<'a> {
+    data: &'a [u8],
+    pending: bool,
+}
+
+impl AsyncRead for Trickle<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.pending = !self.pending;
+        if self.pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let n = self.data.len().min(buf.remaining()).min(3);
+        buf.put_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Decodes the rest of an image a couple of rows at a time.
+fn decode_rows<R: AsyncRead + Unpin>(decoder: &mut AsyncDecoder<R>) -> Vec<u8> {
+    let mut rows =
+        vec![0; decoder.header().width as usize * decoder.channels().as_u8() as usize * 2];
+    let mut decoded = Vec::new();
+    while let n @ 1.. = block_on(decoder.decode_next_rows(&mut rows)).unwrap() {
+        decoded.extend_from_slice(&rows[..n]);
+    }
+    decoded
+}
+
+#[test]
+fn test_async_decode_stream_of_images() {
+    let pixels = pixels(23, 9, 4);
+    for header_format in [HeaderFormat::GameMaker, HeaderFormat::Reference] {
+        let options = EncoderOptions::new().header_format(header_format);
+        let mut encoder = Encoder::new(&pixels, 23, 9).unwrap().with_options(options);
+        let mut stream = Vec::new();
+        block_on(encoder.encode_to_async_stream(&mut stream)).unwrap();
+        block_on(encoder.encode_to_async_stream(&mut stream)).unwrap();
+
+        let mut decoder = block_on(AsyncDecoder::new(Trickle { data: &stream, pending: false }));
+        for _ in 0..2 {
+            let mut next = decoder.unwrap();
+            assert_eq!(decode_rows(&mut next), pixels);
+            decoder = block_on(next.next_image());
+        }
+        assert!(decoder.is_err());
+    }
+}
+
+#[test]
+fn test_async_extension_block() {
+    let nine_patch = NinePatch::new((3, 20), (2, 7));
+    let (rgb, la, rgba) = (pixels(23, 9, 3), pixels(23, 9, 2), pixels(23, 9, 4));
+    let mut stream = Vec::new();
+    let encoder = Encoder::new(&rgb, 23, 9).unwrap().with_colorspace(ColorSpace::Linear);
+    block_on(encoder.with_nine_patch(nine_patch).unwrap().encode_to_async_stream(&mut stream))
+        .unwrap();
+    block_on(Encoder::new(&rgba, 23, 9).unwrap().encode_to_async_stream(&mut stream)).unwrap();
+    let options = EncoderOptions::new().restart_interval(2);
+    let mut encoder = Encoder::new(&la, 23, 9).unwrap().with_options(options);
+    block_on(encoder.encode_to_async_stream(&mut stream)).unwrap();
+
+    let reader = Trickle { data: &stream, pending: false };
+    let mut decoder = block_on(AsyncDecoder::new(reader)).unwrap().with_channels(Channels::Rgb);
+    // the block follows the last row, so the header doesn't know about it yet
+    assert_eq!(decoder.header().colorspace, None);
+    assert_eq!(decode_rows(&mut decoder), rgb);
+    block_on(decoder.finish()).unwrap();
+    assert_eq!(decoder.channels(), Channels::Rgb);
+    assert_eq!(decoder.header().colorspace, Some(ColorSpace::Linear));
+    assert_eq!(decoder.header().nine_patch, Some(nine_patch));
+    let (width, height) = (decoder.header().width, decoder.header().height);
+
+    // no block: the header of the next image follows right away
+    let mut decoder = block_on(decoder.next_image()).unwrap();
+    assert_eq!(decode_rows(&mut decoder), rgba);
+    block_on(decoder.finish()).unwrap();
+    assert_eq!((decoder.channels(), decoder.header().colorspace), (Channels::Rgba, None));
+
+    // rows left undecoded are skipped
+    let mut decoder = block_on(decoder.next_image()).unwrap();
+    assert_eq!((decoder.header().width, decoder.header().height), (width, height));
+    block_on(decoder.finish()).unwrap();
+    assert_eq!(decoder.channels(), Channels::La);
+    assert_eq!(decoder.rows_decoded(), 9);
+    assert!(block_on(decoder.next_image()).is_err());
+}
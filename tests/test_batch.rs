@@ -0,0 +1,96 @@
+#![cfg(feature = "batch")]
+
+mod common;
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use qoi::batch::{convert_dir, BatchOptions};
+use qoi::{decode_to_vec, encode_to_vec};
+
+use common::pixels;
+
+/// Creates an empty directory in the temporary directory, unique to a test and a process.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("qoi-test-batch-{name}-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    dir
+}
+
+/// Converts a single file and returns the decoded output, or `None` if it failed.
+fn convert(dir: &Path, name: &str, contents: &[u8]) -> Option<Vec<u8>> {
+    let (src, dst) = (dir.join("src"), dir.join("dst"));
+    let _ = fs::remove_dir_all(&src);
+    fs::create_dir(&src).unwrap();
+    fs::write(src.join(name), contents).unwrap();
+    let report = convert_dir(&src, &dst, BatchOptions::new().threads(1), |_| {}).unwrap();
+    let output = dst.join(name).with_extension("qoi");
+    report.is_success().then(|| decode_to_vec(fs::read(output).unwrap()).unwrap().1)
+}
+
+fn luma_alpha(luma: &[u8]) -> Vec<u8> {
+    luma.iter().flat_map(|&luma| [luma, 0xff]).collect()
+}
+
+#[test]
+fn test_load_pnm() {
+    let dir = test_dir("pnm");
+    let rgb = pixels(5, 3, 3);
+    let ppm = [&b"P6\n# written by hand\n5 3 # dimensions\n255\n"[..], &rgb].concat();
+    assert_eq!(convert(&dir, "image.ppm", &ppm).unwrap(), rgb);
+
+    let pgm = [&b"P5 3 2\t255 "[..], &[0, 10, 20, 30, 40, 255]].concat();
+    assert_eq!(convert(&dir, "image.pgm", &pgm).unwrap(), luma_alpha(&[0, 10, 20, 30, 40, 255]));
+
+    // other maximum values are rescaled, with two bytes per sample above 255
+    let pgm = [&b"P5 4 1 15\n"[..], &[0, 1, 8, 15]].concat();
+    assert_eq!(convert(&dir, "low.pgm", &pgm).unwrap(), luma_alpha(&[0, 17, 136, 255]));
+    let samples: Vec<u8> = [0u16, 1, 500, 1000].iter().flat_map(|s| s.to_be_bytes()).collect();
+    let pgm = [&b"P5 4 1 1000\n"[..], &samples].concat();
+    assert_eq!(convert(&dir, "deep.pnm", &pgm).unwrap(), luma_alpha(&[0, 0, 128, 255]));
+
+    // truncated rasters and malformed headers fail
+    assert_eq!(convert(&dir, "short.ppm", &ppm[..ppm.len() - 1]), None);
+    assert_eq!(convert(&dir, "ascii.ppm", b"P3 1 1 255 0 0 0"), None);
+    assert_eq!(convert(&dir, "header.ppm", b"P6 5 x 255\n"), None);
+    assert_eq!(convert(&dir, "max.pgm", b"P5 1 1 0\n\0"), None);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_convert_dir() {
+    let dir = test_dir("dir");
+    let (src, dst) = (dir.join("src"), dir.join("dst"));
+    fs::create_dir_all(src.join("sub")).unwrap();
+    let rgba = pixels(6, 4, 4);
+    let mut encoder = png::Encoder::new(File::create(src.join("a.png")).unwrap(), 6, 4);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.write_header().unwrap().write_image_data(&rgba).unwrap();
+    let rgb = pixels(2, 2, 3);
+    fs::write(src.join("a.ppm"), [&b"P6 2 2 255\n"[..], &rgb].concat()).unwrap();
+    fs::write(src.join("sub").join("b.qoi"), encode_to_vec(&rgb, 2, 2).unwrap()).unwrap();
+    fs::write(src.join("sub").join("broken.qoi"), b"not an image").unwrap();
+    fs::write(src.join("notes.txt"), b"").unwrap();
+
+    let n_calls = AtomicUsize::new(0);
+    let report = convert_dir(&src, &dst, BatchOptions::new(), |progress| {
+        assert_eq!(progress.total, 3);
+        n_calls.fetch_add(1, Ordering::Relaxed);
+    })
+    .unwrap();
+    assert_eq!(n_calls.into_inner(), 3);
+    assert_eq!(report.converted, [src.join("a.png"), src.join("sub").join("b.qoi")]);
+    assert_eq!(report.skipped, [src.join("notes.txt")]);
+    // `a.ppm` would overwrite the output of `a.png`, so it's reported instead
+    let failed: Vec<&Path> = report.failed.iter().map(|(path, _)| path.as_path()).collect();
+    assert_eq!(failed, [src.join("a.ppm"), src.join("sub").join("broken.qoi")]);
+
+    let decoded = |path: &Path| decode_to_vec(fs::read(dst.join(path)).unwrap()).unwrap().1;
+    assert_eq!(decoded(Path::new("a.qoi")), rgba);
+    assert_eq!(decoded(&Path::new("sub").join("b.qoi")), rgb);
+    assert!(!dst.join("sub").join("broken.qoi").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
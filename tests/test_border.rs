@@ -0,0 +1,64 @@
+mod common;
+
+use qoi::{decode_to_vec, encode_to_vec, pad_borders, pad_borders_to_buf, BorderMode, Error};
+
+use common::pixels;
+
+// source coordinates of the 3 + 2 * 4 padded columns of a 3 pixels wide image, which also
+// covers borders wider than the image itself
+const CLAMP: [usize; 11] = [0, 0, 0, 0, 0, 1, 2, 2, 2, 2, 2];
+const MIRROR: [usize; 11] = [2, 2, 1, 0, 0, 1, 2, 2, 1, 0, 0];
+const WRAP: [usize; 11] = [2, 0, 1, 2, 0, 1, 2, 0, 1, 2, 0];
+
+fn expected(src: &[u8], mode: BorderMode) -> Vec<u8> {
+    let map = match mode {
+        BorderMode::Clamp => CLAMP,
+        BorderMode::Mirror => MIRROR,
+        BorderMode::Wrap => WRAP,
+    };
+    let mut out = Vec::new();
+    for y in map {
+        for x in map {
+            out.extend_from_slice(&src[(y * 3 + x) * 4..][..4]);
+        }
+    }
+    out
+}
+
+#[test]
+fn test_pad_borders() {
+    let src = pixels(3, 3, 4);
+    let encoded = encode_to_vec(&src, 3, 3).unwrap();
+    for mode in [BorderMode::Clamp, BorderMode::Mirror, BorderMode::Wrap] {
+        let expected = expected(&src, mode);
+        let mut buf = vec![0; 11 * 11 * 4 + 3];
+        pad_borders_to_buf(&src, 3, 3, &mut buf, 4, mode).unwrap();
+        assert_eq!(buf[..expected.len()], expected, "{mode:?}");
+
+        let (header, decoded) = decode_to_vec(pad_borders(&encoded, 4, mode).unwrap()).unwrap();
+        assert_eq!((header.width, header.height), (11, 11));
+        assert_eq!(decoded, expected, "{mode:?}");
+    }
+}
+
+#[test]
+fn test_pad_borders_keeps_channels() {
+    let src = pixels(3, 3, 2);
+    let padded = pad_borders(encode_to_vec(&src, 3, 3).unwrap(), 1, BorderMode::Clamp).unwrap();
+    let (_, decoded) = decode_to_vec(padded).unwrap();
+    assert_eq!(decoded.len(), 5 * 5 * 2);
+    assert_eq!(decoded[..2], src[..2]);
+    assert_eq!(decoded[(5 + 1) * 2..][..6], src[..6]);
+}
+
+#[test]
+fn test_pad_borders_errors() {
+    let src = pixels(3, 3, 4);
+    let mut buf = vec![0; 5 * 5 * 4 - 1];
+    let err = pad_borders_to_buf(&src, 3, 3, &mut buf, 1, BorderMode::Wrap);
+    assert!(matches!(err, Err(Error::OutputBufferTooSmall { size: 99, required: 100 })));
+    let err = pad_borders_to_buf(&src[1..], 3, 3, &mut buf, 1, BorderMode::Wrap);
+    assert!(matches!(err, Err(Error::InvalidImageLength { .. })));
+    let err = pad_borders_to_buf(&src, 3, 3, &mut buf, u16::MAX, BorderMode::Wrap);
+    assert!(matches!(err, Err(Error::InvalidImageDimensions { .. })));
+}
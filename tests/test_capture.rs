@@ -0,0 +1,27 @@
+mod common;
+
+use qoi::{CaptureEncoder, Encoder, Error};
+
+use common::pixels;
+
+#[test]
+fn test_capture_encoder() {
+    let frames: Vec<Vec<u8>> = (0..5_u8)
+        .map(|i| pixels(16, 8, 4).iter().map(|b| b.wrapping_add(i)).collect())
+        .collect();
+    let mut capture = CaptureEncoder::new(Vec::new(), 16, 8).unwrap();
+    let mut expected = Vec::new();
+    for (i, frame) in frames.iter().enumerate() {
+        // frames that find both slots busy are dropped
+        if i % 2 == 0 {
+            capture.submit_frame(frame).unwrap();
+        } else if !capture.try_submit_frame(frame).unwrap() {
+            continue;
+        }
+        expected.extend(Encoder::new(frame, 16, 8).unwrap().encode_to_vec().unwrap());
+    }
+    let err = capture.submit_frame(&frames[0][4..]).unwrap_err();
+    assert!(matches!(err, Error::InvalidImageLength { .. }));
+    // encoded frames are written back to back in submission order
+    assert_eq!(capture.finish().unwrap(), expected);
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use qoi::channels::{extract, swap, Channel};
+use qoi::{decode_to_vec, encode_to_vec, Encoder, EncoderOptions, HeaderFormat};
+
+use common::pixels;
+
+#[test]
+fn test_extract_and_swap_channels() {
+    let pixels = pixels(16, 8, 4);
+    let encoded = encode_to_vec(&pixels, 16, 8).unwrap();
+    for (channel, i) in [(Channel::R, 0), (Channel::G, 1), (Channel::B, 2), (Channel::A, 3)] {
+        let (header, gray) = decode_to_vec(extract(&encoded, channel).unwrap()).unwrap();
+        assert_eq!((header.width, header.height), (16, 8));
+        let expected: Vec<u8> =
+            pixels.chunks_exact(4).flat_map(|px| [px[i], px[i], px[i], 0xff]).collect();
+        assert_eq!(gray, expected);
+    }
+
+    let (_, swapped) = decode_to_vec(swap(&encoded, Channel::R, Channel::A).unwrap()).unwrap();
+    let expected: Vec<u8> =
+        pixels.chunks_exact(4).flat_map(|px| [px[3], px[1], px[2], px[0]]).collect();
+    assert_eq!(swapped, expected);
+    // swapping is its own inverse
+    let swapped = encode_to_vec(&swapped, 16, 8).unwrap();
+    assert_eq!(decode_to_vec(swap(swapped, Channel::A, Channel::R).unwrap()).unwrap().1, pixels);
+}
+
+#[test]
+fn test_channels_with_reference_header() {
+    let pixels = pixels(16, 8, 4);
+    let options = EncoderOptions::new().header_format(HeaderFormat::Reference);
+    let mut encoder = Encoder::new(&pixels, 16, 8).unwrap().with_options(options);
+    let reference = encoder.encode_to_vec().unwrap();
+    let encoded = encode_to_vec(&pixels, 16, 8).unwrap();
+    assert_eq!(extract(&reference, Channel::G).unwrap(), extract(&encoded, Channel::G).unwrap());
+    let swap_rb = |data| swap(data, Channel::R, Channel::B).unwrap();
+    assert_eq!(swap_rb(&reference), swap_rb(&encoded));
+}
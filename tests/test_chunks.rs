@@ -0,0 +1,100 @@
+mod common;
+
+use std::io::Cursor;
+
+use qoi::{Channels, DecodeState, Encoder, EncoderOptions, Error, HeaderFormat, StreamEncoder};
+
+use common::pixels;
+
+#[test]
+fn test_encode_chunks() {
+    let pixels = pixels(37, 29, 4);
+    for options in [EncoderOptions::new(), EncoderOptions::new().restart_interval(4)] {
+        let mut encoder = Encoder::new(&pixels, 37, 29).unwrap().with_options(options);
+        let expected = encoder.encode_to_vec().unwrap();
+        for chunk_pixels in [0, 1, 7, 100_000] {
+            let mut buf = vec![0; encoder.required_buf_len()];
+            let parts: Vec<Vec<u8>> = encoder
+                .encode_chunks(&mut buf, chunk_pixels)
+                .map(|part| part.unwrap().to_vec())
+                .collect();
+            // the header comes last, once the length of the op stream is known
+            let (header, rest) = parts.split_last().unwrap();
+            assert_eq!(header[..], expected[..12]);
+            assert_eq!(rest.concat(), expected[12..]);
+            assert_eq!(buf[..expected.len()], expected);
+        }
+    }
+}
+
+#[test]
+fn test_encode_chunks_buffer_too_small() {
+    let pixels = pixels(8, 8, 4);
+    let mut encoder = Encoder::new(&pixels, 8, 8).unwrap();
+    let mut buf = vec![0; encoder.required_buf_len() - 1];
+    let mut chunks = encoder.encode_chunks(&mut buf, 16);
+    assert!(matches!(chunks.next(), Some(Err(Error::OutputBufferTooSmall { .. }))));
+}
+
+#[test]
+fn test_stream_encoder() {
+    for (channels, format) in [
+        (Channels::Rgba, HeaderFormat::GameMaker),
+        (Channels::Rgb, HeaderFormat::GameMaker),
+        (Channels::Rgb, HeaderFormat::Reference),
+    ] {
+        let pixels = pixels(37, 29, channels.as_u8());
+        let options = EncoderOptions::new().header_format(format);
+        let expected =
+            Encoder::new(&pixels, 37, 29).unwrap().with_options(options).encode_to_vec().unwrap();
+        // pushes that don't line up with pixels nor rows
+        let mut encoder =
+            StreamEncoder::with_options(Cursor::new(Vec::new()), 37, 29, channels, options)
+                .unwrap();
+        for chunk in pixels.chunks(7) {
+            encoder.push_pixels(chunk).unwrap();
+        }
+        assert_eq!(encoder.pixels_pushed(), 37 * 29);
+        assert_eq!(encoder.finish().unwrap().into_inner(), expected);
+    }
+
+    let pixels = pixels(8, 8, 4);
+    let mut encoder = StreamEncoder::new(Cursor::new(Vec::new()), 8, 8, Channels::Rgba).unwrap();
+    encoder.push_pixels(&pixels[..100]).unwrap();
+    assert!(matches!(encoder.push_pixels(&pixels), Err(Error::InvalidImageLength { .. })));
+    assert!(matches!(encoder.finish(), Err(Error::InvalidImageLength { size: 100, .. })));
+}
+
+#[test]
+fn test_decode_state() {
+    let pixels = pixels(23, 17, 4);
+    for format in [HeaderFormat::GameMaker, HeaderFormat::Reference] {
+        let options = EncoderOptions::new().header_format(format);
+        let encoded =
+            Encoder::new(&pixels, 23, 17).unwrap().with_options(options).encode_to_vec().unwrap();
+        // ops split anywhere, and output space for a few pixels at a time
+        for (chunk_len, out_len) in [(1, 4 * 23 * 17), (3, 4 * 5), (7, 4), (1000, 4 * 23 * 17)] {
+            let mut state = DecodeState::new();
+            let mut decoded = Vec::new();
+            let mut out = vec![0; out_len];
+            // the extension block following the end marker is left alone
+            for mut chunk in encoded.chunks(chunk_len) {
+                while !chunk.is_empty() && !state.is_done() {
+                    let (n_consumed, n_pixels) = state.feed(chunk, &mut out).unwrap();
+                    decoded.extend_from_slice(&out[..n_pixels * 4]);
+                    chunk = &chunk[n_consumed..];
+                }
+            }
+            assert!(state.is_done());
+            assert_eq!(state.header_format(), Some(format));
+            assert_eq!(decoded, pixels);
+        }
+    }
+
+    let mut encoded = Encoder::new(&pixels, 23, 17).unwrap().encode_to_vec().unwrap();
+    let length = qoi::decode_header(&encoded).unwrap().length.unwrap() as usize;
+    encoded[12 + length - 1] = 0xff;
+    let mut out = vec![0; pixels.len()];
+    let err = DecodeState::new().feed(&encoded, &mut out).unwrap_err();
+    assert!(matches!(err, Error::InvalidPadding { offset, .. } if offset == length - 8));
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output};
+
+use common::pixels;
+
+/// Creates an empty directory in the temporary directory, unique to the process.
+fn test_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("qoi-test-cli-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    dir
+}
+
+fn qoi_cli(args: &[&Path]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_qoi-cli")).args(args).output().unwrap()
+}
+
+#[test]
+fn test_cli_round_trip() {
+    let dir = test_dir();
+    let (png_in, qoi, png_out) = (dir.join("in.png"), dir.join("image.qoi"), dir.join("out.png"));
+    let pixels = pixels(23, 17, 4);
+    let mut encoder = png::Encoder::new(File::create(&png_in).unwrap(), 23, 17);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.write_header().unwrap().write_image_data(&pixels).unwrap();
+
+    let encode = Path::new("encode");
+    assert!(qoi_cli(&[encode, &png_in, &qoi]).status.success());
+    assert_eq!(qoi::decode_to_vec(fs::read(&qoi).unwrap()).unwrap().1, pixels);
+    assert!(qoi_cli(&[Path::new("decode"), &qoi, &png_out]).status.success());
+    assert!(qoi_cli(&[Path::new("diff"), &png_in, &png_out]).status.success());
+
+    let info = qoi_cli(&[Path::new("info"), &qoi]);
+    assert!(info.status.success());
+    assert!(String::from_utf8(info.stdout).unwrap().contains("dimensions:  23x17"));
+
+    // unknown commands are usage errors
+    assert_eq!(qoi_cli(&[Path::new("frobnicate")]).status.code(), Some(2));
+    fs::remove_dir_all(dir).unwrap();
+}
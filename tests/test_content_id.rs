@@ -0,0 +1,60 @@
+mod common;
+
+use qoi::{ContentId, Encoder, EncoderOptions, HeaderFormat, Sha256};
+
+fn sha256(chunks: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    chunks.iter().for_each(|chunk| hasher.update(chunk));
+    hasher.finalize().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn test_sha256_known_answers() {
+    // FIPS 180-2, appendix B
+    let cases: [(&[u8], &str); 3] = [
+        (b"", "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        (b"abc", "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        (
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq",
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+    ];
+    for (message, digest) in cases {
+        assert_eq!(sha256(&[message]), digest);
+    }
+
+    // a million 'a's, fed in chunks that straddle the block boundaries
+    let data = vec![b'a'; 1_000_000];
+    let mut chunks = Vec::new();
+    let mut rest = data.as_slice();
+    for len in [1, 63, 64, 65, 127, 3].iter().cycle() {
+        if rest.is_empty() {
+            break;
+        }
+        let (chunk, tail) = rest.split_at((*len).min(rest.len()));
+        chunks.push(chunk);
+        rest = tail;
+    }
+    let digest = "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0";
+    assert_eq!(sha256(&chunks), digest);
+}
+
+#[test]
+fn test_encoder_id_matches_content_id() {
+    for (channels, options) in [
+        (4, EncoderOptions::new()),
+        (3, EncoderOptions::new()),
+        (4, EncoderOptions::new().restart_interval(4)),
+        (3, EncoderOptions::new().header_format(HeaderFormat::Reference)),
+    ] {
+        let pixels = common::pixels(37, 29, channels);
+        let mut encoder = Encoder::new(&pixels, 37, 29).unwrap().with_options(options);
+        let (encoded, id) = encoder.encode_to_vec_with_id().unwrap();
+        assert_eq!(id, ContentId::of(&encoded).unwrap());
+        assert_eq!(encoded, encoder.encode_to_vec().unwrap());
+    }
+    let (a, b) = (common::pixels(37, 29, 4), common::pixels(29, 37, 4));
+    let id_a = Encoder::new(&a, 37, 29).unwrap().encode_to_vec_with_id().unwrap().1;
+    let id_b = Encoder::new(&b, 29, 37).unwrap().encode_to_vec_with_id().unwrap().1;
+    assert_ne!(id_a, id_b);
+}
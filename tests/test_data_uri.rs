@@ -0,0 +1,45 @@
+#![cfg(feature = "base64")]
+
+mod common;
+
+use qoi::consts::QOI_MEDIA_TYPE;
+use qoi::{from_data_uri, to_data_uri, Error};
+
+use common::pixels;
+
+#[test]
+fn test_data_uri_round_trip() {
+    let pixels = pixels(9, 7, 4);
+    let uri = to_data_uri(&pixels, 9, 7).unwrap();
+    assert!(uri.starts_with(&format!("data:{QOI_MEDIA_TYPE};base64,")));
+    let (header, decoded) = from_data_uri(&uri).unwrap();
+    assert_eq!((header.width, header.height, decoded), (9, 7, pixels.clone()));
+
+    // case of the scheme and parameters doesn't matter, nor does whitespace in the payload
+    let (metadata, payload) = uri.split_once(',').unwrap();
+    let wrapped: Vec<&str> =
+        payload.as_bytes().chunks(16).map(|line| std::str::from_utf8(line).unwrap()).collect();
+    let uri = format!(" {},\n{}\n", metadata.to_uppercase(), wrapped.join("\n  "));
+    assert_eq!(from_data_uri(&uri).unwrap().1, pixels);
+}
+
+#[test]
+fn test_data_uri_errors() {
+    let uri = to_data_uri(pixels(9, 7, 4), 9, 7).unwrap();
+    let payload = uri.split_once(',').unwrap().1;
+    let invalid = [
+        format!("data:image/qoi;base64,{payload}"),
+        format!("data:image/png;base64,{payload}"),
+        format!("data:{QOI_MEDIA_TYPE},{payload}"),
+        format!("http:{QOI_MEDIA_TYPE};base64,{payload}"),
+        format!("data:{QOI_MEDIA_TYPE};base64"),
+        format!("data:{QOI_MEDIA_TYPE};base64,{}", &payload[1..]),
+        format!("data:{QOI_MEDIA_TYPE};base64,{}!", &payload[..payload.len() - 1]),
+    ];
+    for uri in invalid {
+        assert!(matches!(from_data_uri(&uri), Err(Error::InvalidDataUri)), "{uri}");
+    }
+    // valid base64 that isn't an image fails to decode instead
+    let uri = format!("data:{QOI_MEDIA_TYPE};base64,AAAA");
+    assert!(!matches!(from_data_uri(&uri), Err(Error::InvalidDataUri) | Ok(_)));
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use qoi::{decode_delta, encode_to_vec, Error, Rect};
+
+use common::pixels;
+
+const WIDTH: u16 = 64;
+const HEIGHT: u16 = 8;
+
+/// Decodes a frame with the pixels of `changed` altered on top of the reference frame.
+fn delta(changed: impl Fn(u16, u16) -> bool) -> Vec<Rect> {
+    let reference = pixels(WIDTH.into(), HEIGHT.into(), 4);
+    let mut frame = reference.clone();
+    let coords = (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| (x, y)));
+    for ((x, y), px) in coords.zip(frame.chunks_exact_mut(4)) {
+        if changed(x, y) {
+            px[0] ^= 0x55;
+        }
+    }
+    let encoded = encode_to_vec(&frame, WIDTH, HEIGHT).unwrap();
+    let mut out = reference.clone();
+    let rects = decode_delta(&encoded, &reference, &mut out).unwrap();
+    assert_eq!(out, frame);
+
+    let contains = |rect: &Rect, x, y| {
+        (rect.x..rect.x + rect.width).contains(&x) && (rect.y..rect.y + rect.height).contains(&y)
+    };
+    for (y, x) in (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| (y, x))) {
+        let n_rects = rects.iter().filter(|rect| contains(rect, x, y)).count();
+        assert!(n_rects <= 1, "{x},{y} is covered by {n_rects} rects: {rects:?}");
+        assert!(!changed(x, y) || n_rects == 1, "{x},{y} isn't covered: {rects:?}");
+    }
+    assert!(rects.windows(2).all(|pair| (pair[0].y, pair[0].x) <= (pair[1].y, pair[1].x)));
+    rects
+}
+
+#[test]
+fn test_decode_delta() {
+    assert_eq!(delta(|_, _| false), []);
+    let rects = delta(|x, y| (3..9).contains(&x) && (2..5).contains(&y));
+    assert_eq!(rects, [Rect { x: 3, y: 2, width: 6, height: 3 }]);
+    // spans closer than the merge gap are grouped, others aren't
+    let rects = delta(|x, y| y == 1 && (x == 2 || x == 10 || x == 40));
+    assert_eq!(
+        rects,
+        [Rect { x: 2, y: 1, width: 9, height: 1 }, Rect { x: 40, y: 1, width: 1, height: 1 }]
+    );
+
+    let reference = pixels(WIDTH.into(), HEIGHT.into(), 4);
+    let encoded = encode_to_vec(&reference, WIDTH, HEIGHT).unwrap();
+    let err = decode_delta(&encoded, &reference[4..], vec![0; reference.len()]).unwrap_err();
+    assert!(matches!(err, Error::InvalidImageLength { .. }));
+}
+
+#[test]
+fn test_decode_delta_rgb() {
+    // RGB images take RGB reference frames and output
+    let reference = pixels(WIDTH.into(), HEIGHT.into(), 3);
+    let mut frame = reference.clone();
+    frame[(2 * usize::from(WIDTH) + 5) * 3] ^= 0x55;
+    let encoded = encode_to_vec(&frame, WIDTH, HEIGHT).unwrap();
+    let mut out = reference.clone();
+    let rects = decode_delta(&encoded, &reference, &mut out).unwrap();
+    assert_eq!(out, frame);
+    assert_eq!(rects, [Rect { x: 5, y: 2, width: 1, height: 1 }]);
+}
+
+#[test]
+fn test_decode_delta_merges_adjacent_areas() {
+    // two columns of changes, the right one starting lower, joined by a changed row: their
+    // bounding box also covers a small area next to the top of the left one, which has to be
+    // merged into it rather than left overlapping it
+    let rects = delta(|x, y| {
+        (x < 4 && y < 7)
+            || ((44..48).contains(&x) && (4..7).contains(&y))
+            || ((20..24).contains(&x) && y < 1)
+            || (x < 48 && y == 6)
+    });
+    assert_eq!(rects, [Rect { x: 0, y: 0, width: 48, height: 7 }]);
+
+    // the same with the two areas side by side, each growing into the other's columns
+    let rects = delta(|x, y| (x < 30 && y < 3) || ((20..50).contains(&x) && (3..6).contains(&y)));
+    assert_eq!(rects, [Rect { x: 0, y: 0, width: 50, height: 6 }]);
+}
@@ -0,0 +1,101 @@
+use qoi::{dither, quantize, Error, QuantizeReport};
+
+const WIDTH: u16 = 64;
+
+type Dither = fn(&mut [u8], u16, [u8; 3]) -> qoi::Result<()>;
+
+// a horizontal gray gradient with varying alpha, 8 rows high
+fn gradient() -> Vec<u8> {
+    (0..8 * u32::from(WIDTH))
+        .flat_map(|i| {
+            let value = (i % u32::from(WIDTH) * 4) as u8;
+            [value, value, value / 2, (i * 13) as u8]
+        })
+        .collect()
+}
+
+// a level of `bits` bits expanded to 8 bits by repeating it, e.g. 0b101 -> 0b10110110
+fn expand(level: u8, bits: u8) -> u8 {
+    let pattern = (0..8).fold(0_u64, |acc, _| acc << bits | u64::from(level));
+    (pattern >> (8 * bits - 8)) as u8
+}
+
+fn check_levels(pixels: &[u8], src: &[u8], bits: [u8; 3]) {
+    for (px, src) in pixels.chunks_exact(4).zip(src.chunks_exact(4)) {
+        for c in 0..3 {
+            assert_eq!(px[c], expand(px[c] >> (8 - bits[c]), bits[c]), "{px:?}");
+            // a dithered channel never moves more than one level away
+            let step = 0x100 >> bits[c];
+            assert!(u16::from(px[c].abs_diff(src[c])) < 2 * step, "{px:?} vs {src:?}");
+        }
+        assert_eq!(px[3], src[3]);
+    }
+}
+
+fn red_sum(pixels: &[u8]) -> i64 {
+    pixels.chunks_exact(4).map(|px| i64::from(px[0])).sum()
+}
+
+#[test]
+fn test_dither() {
+    let src = gradient();
+    let bits = [3, 2, 1];
+    let dithering: [Dither; 2] = [
+        |px, width, bits| dither::ordered(px, width, bits),
+        |px, width, bits| dither::floyd_steinberg(px, width, bits),
+    ];
+    for dither in dithering {
+        let mut pixels = src.clone();
+        dither(&mut pixels, WIDTH, bits).unwrap();
+        check_levels(&pixels, &src, bits);
+
+        // dithering preserves the mean tone of the gradient
+        let (sum, src_sum) = (red_sum(&pixels), red_sum(&src));
+        assert!((sum - src_sum).abs() * 50 < src_sum, "{sum} vs {src_sum}");
+
+        // pure black and white are kept as is
+        let mut extremes = [0, 0, 0, 7, 255, 255, 255, 9].repeat(4);
+        dither(&mut extremes, 4, bits).unwrap();
+        assert_eq!(extremes, [0, 0, 0, 7, 255, 255, 255, 9].repeat(4));
+    }
+}
+
+#[test]
+fn test_dither_errors() {
+    for width in [0, 3] {
+        let mut pixels = vec![0; 4 * 4];
+        let result = dither::ordered(&mut pixels, width, [5, 6, 5]);
+        assert!(matches!(result, Err(Error::InvalidImageLength { .. })), "{width}");
+        let result = dither::floyd_steinberg(&mut pixels, width, [5, 6, 5]);
+        assert!(matches!(result, Err(Error::InvalidImageLength { .. })), "{width}");
+    }
+}
+
+#[test]
+fn test_quantize() {
+    let src = gradient();
+    for bits in 1..=8 {
+        let (quantized, report) = quantize(&src, bits).unwrap();
+        let mut errors = Vec::new();
+        for (&value, &src) in quantized.iter().zip(&src) {
+            // rounded to the nearest level, never more than half a step away
+            assert_eq!(value, expand(value >> (8 - bits), bits), "{value} at {bits} bits");
+            assert!(u16::from(value.abs_diff(src)) * 2 <= 255 / ((1 << bits) - 1), "{src}");
+            errors.push(value.abs_diff(src));
+        }
+        let mean = errors.iter().map(|&e| f64::from(e)).sum::<f64>() / errors.len() as f64;
+        assert_eq!(report.max_error, errors.iter().copied().max().unwrap());
+        assert!((report.mean_error - mean).abs() < 1e-9, "{report:?} vs {mean}");
+        assert_eq!(quantize([0, 255], bits).unwrap().0, [0, 255]);
+    }
+    assert_eq!(quantize(&src, 8).unwrap(), (src.clone(), QuantizeReport::default()));
+    // out of range depths are clamped
+    assert_eq!(quantize(&src, 0).unwrap(), quantize(&src, 1).unwrap());
+    assert_eq!(quantize(&src, 9).unwrap().0, src);
+    assert_eq!(quantize([], 3).unwrap(), (Vec::new(), QuantizeReport::default()));
+
+    // fewer levels make for a smaller image
+    let encoded_len = |pixels: &[u8]| qoi::encode_to_vec(pixels, WIDTH, 8).unwrap().len();
+    let (quantized, _) = quantize(&src, 3).unwrap();
+    assert!(encoded_len(&quantized) < encoded_len(&src));
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use qoi::{decode_iter, decode_to_vec, decode_visit, encode_to_vec, Decoder, Error};
+
+use common::pixels;
+
+const HEADER_SIZE: usize = 12;
+
+#[test]
+fn test_error_at_offset() {
+    let (width, height) = (19, 11);
+    let encoded = encode_to_vec(pixels(width, height, 4), width, height).unwrap();
+    let n_pixels = (width * height) as usize;
+    let padding = Decoder::new(&encoded).unwrap().header().length.unwrap() as usize - 8;
+
+    let mut corrupted = encoded.clone();
+    corrupted[HEADER_SIZE + padding + 7] ^= 1;
+    let err = decode_to_vec(&corrupted).unwrap_err();
+    assert!(matches!(err, Error::InvalidPadding { .. }));
+    assert_eq!(err.at_offset(), Some((padding, n_pixels)));
+    let err = Decoder::from_stream(&corrupted[..]).unwrap().decode_to_vec().unwrap_err();
+    assert_eq!(err.at_offset(), Some((padding, n_pixels)));
+    let err = decode_iter(&corrupted).unwrap().find_map(Result::err).unwrap();
+    assert_eq!(err.at_offset(), Some((padding, n_pixels)));
+    let err = decode_visit(&corrupted, |_, _, _| {}).unwrap_err();
+    assert_eq!(err.at_offset(), Some((padding, n_pixels)));
+
+    let truncated = &encoded[..HEADER_SIZE + padding / 2];
+    let err = decode_to_vec(truncated).unwrap_err();
+    let (offset, pixel) = err.at_offset().unwrap();
+    assert!(matches!(err, Error::UnexpectedBufferEnd { .. }));
+    assert!(offset <= padding / 2 && pixel > 0 && pixel < n_pixels);
+
+    let err = decode_to_vec(&encoded[..5]).unwrap_err();
+    assert_eq!(err.at_offset(), Some((0, 0)));
+    assert_eq!(Error::InvalidNinePatch.at_offset(), None);
+}
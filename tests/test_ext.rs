@@ -0,0 +1,170 @@
+mod common;
+
+use qoi::{
+    decode_compat, decode_header, decode_to_vec, weave_fields, Channels, Decoder, DecoderOptions,
+    Encoder, EncoderOptions, Error, Field, FormatVersion, Header, NinePatch, RestartMarker,
+};
+
+const WIDTH: u16 = 37;
+const HEIGHT: u16 = 29;
+
+// layout of the extension block, see the module docs of `ext`
+const HEADER_SIZE: usize = 12;
+const EXT_HEADER_SIZE: usize = 8;
+const RECORD_HEADER_SIZE: usize = 5;
+const TAG_VERSION: u8 = 0x03;
+
+fn pixels(n_channels: u8) -> Vec<u8> {
+    common::pixels(WIDTH.into(), HEIGHT.into(), n_channels)
+}
+
+fn encode(encoder: qoi::Result<Encoder>) -> Vec<u8> {
+    encoder.and_then(|mut encoder| encoder.encode_to_vec()).unwrap()
+}
+
+fn rgba(options: EncoderOptions) -> Vec<u8> {
+    let pixels = pixels(4);
+    encode(Encoder::new(&pixels, WIDTH, HEIGHT).map(|encoder| encoder.with_options(options)))
+}
+
+fn band(pixels: &[u8], marker: RestartMarker) -> &[u8] {
+    let row_len = usize::from(WIDTH) * 4;
+    let start = usize::from(marker.row) * row_len;
+    &pixels[start..start + usize::from(marker.n_rows) * row_len]
+}
+
+fn ops_len(encoded: &[u8]) -> usize {
+    Decoder::new(encoded).unwrap().header().length.unwrap() as usize
+}
+
+#[test]
+fn test_plain_images_have_no_block() {
+    let encoded = rgba(EncoderOptions::new());
+    assert_eq!(Decoder::new(&encoded).unwrap().restart_markers(), None);
+    assert_eq!(encoded.len(), HEADER_SIZE + ops_len(&encoded));
+}
+
+#[test]
+fn test_restart_markers_round_trip() {
+    let encoded = rgba(EncoderOptions::new().restart_interval(4));
+    let decoder = Decoder::new(&encoded).unwrap();
+    let markers = decoder.restart_markers().unwrap();
+    assert_eq!((markers.interval(), markers.n_bands()), (4, 8));
+    assert_eq!(decode_to_vec(&encoded).unwrap().1, pixels(4));
+
+    let pixels = pixels(4);
+    for (i, marker) in markers.iter().enumerate() {
+        assert_eq!(decoder.decode_band_to_vec(i).unwrap(), band(&pixels, marker));
+    }
+    assert_eq!(markers.band(7).unwrap().n_rows, 1);
+    assert!(matches!(decoder.decode_band_to_vec(8), Err(Error::InvalidRestartMarker)));
+}
+
+#[test]
+fn test_restart_bands_are_independent() {
+    let mut encoded = rgba(EncoderOptions::new().restart_interval(4));
+    let marker = Decoder::new(&encoded).unwrap().restart_markers().unwrap().band(2).unwrap();
+    // overwrite the ops of the first two bands with runs of the wrong color
+    encoded[HEADER_SIZE..HEADER_SIZE + marker.offset].fill(0xc0);
+    let decoder = Decoder::new(&encoded).unwrap();
+    assert_eq!(decoder.decode_band_to_vec(2).unwrap(), band(&pixels(4), marker));
+}
+
+#[test]
+fn test_channels_round_trip() {
+    let pixels = pixels(2);
+    let encoded = encode(Encoder::new(&pixels, WIDTH, HEIGHT));
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    assert_eq!(decoder.channels(), Channels::La);
+    assert_eq!(decoder.decode_to_vec().unwrap(), pixels);
+    assert_eq!(Decoder::new(&rgba(EncoderOptions::new())).unwrap().channels(), Channels::Rgba);
+}
+
+#[test]
+fn test_nine_patch_round_trip() {
+    let nine_patch = NinePatch::new((3, 30), (4, 20)).with_padding((5, 31), (2, 25));
+    let pixels = pixels(4);
+    let encoder = Encoder::new(&pixels, WIDTH, HEIGHT);
+    let encoded = encode(encoder.and_then(|encoder| encoder.with_nine_patch(nine_patch)));
+    assert_eq!(Decoder::new(&encoded).unwrap().nine_patch(), Some(nine_patch));
+    let (header, decoded) = decode_to_vec(&encoded).unwrap();
+    assert_eq!((header.nine_patch, decoded), (Some(nine_patch), pixels.clone()));
+    assert_eq!(decode_header(&encoded).unwrap().nine_patch, Some(nine_patch));
+    // the extension block follows the op stream, so the bare header doesn't have it
+    assert_eq!(decode_header(&encoded[..HEADER_SIZE]).unwrap().nine_patch, None);
+    assert_eq!(Header::decode(&encoded).unwrap().nine_patch, None);
+    let stretch_out_of_bounds = NinePatch::new((3, 40), (4, 20));
+    let encoder = Encoder::new(&pixels, WIDTH, HEIGHT).unwrap();
+    assert!(encoder.with_nine_patch(stretch_out_of_bounds).is_err());
+}
+
+#[test]
+fn test_low_bits_round_trip() {
+    let n_pixels = u32::from(WIDTH) * u32::from(HEIGHT);
+    let words: Vec<u32> = (0..n_pixels).map(|i| i.wrapping_mul(0x9e37_79b9)).collect();
+    let encoded = encode(Encoder::from_rgb10a2(&words, WIDTH, HEIGHT));
+    let mut decoded = vec![0; words.len()];
+    Decoder::new(&encoded).unwrap().decode_to_rgb10a2(&mut decoded).unwrap();
+    assert_eq!(decoded, words);
+
+    // the low bits belong to the stored pixels, so they're dropped once those are changed
+    let options = DecoderOptions::new().flatten_onto([0x40, 0x80, 0xc0]);
+    let mut decoder = Decoder::new(&encoded).unwrap().with_options(options);
+    decoder.decode_to_rgb10a2(&mut decoded).unwrap();
+    let mut decoder = Decoder::new(&encoded).unwrap().with_options(options);
+    let expand = |v: u8| u32::from(v) << 2 | u32::from(v) >> 6;
+    let expected: Vec<u32> = (decoder.decode_to_vec().unwrap().chunks_exact(4))
+        .map(|px| expand(px[0]) | expand(px[1]) << 10 | expand(px[2]) << 20 | 3 << 30)
+        .collect();
+    assert_eq!(decoded, expected);
+}
+
+#[test]
+fn test_field_round_trip() {
+    let frame = pixels(4);
+    let encode_field = |field| encode(Encoder::from_field(&frame, WIDTH, HEIGHT, field));
+    let (top, bottom) = (encode_field(Field::Top), encode_field(Field::Bottom));
+    let mut decoder = Decoder::new(&bottom).unwrap();
+    assert_eq!((decoder.field(), decoder.header().height), (Some(Field::Bottom), 14));
+    let row_len = usize::from(WIDTH) * 4;
+    let rows = frame.chunks_exact(row_len).skip(1).step_by(2);
+    assert_eq!(decoder.decode_to_vec().unwrap(), rows.collect::<Vec<_>>().concat());
+    assert_eq!(weave_fields(&top, &bottom).unwrap().1, frame);
+    assert!(matches!(weave_fields(&bottom, &top), Err(Error::FieldMismatch)));
+}
+
+#[test]
+fn test_version_record_comes_first() {
+    let mut encoded = rgba(EncoderOptions::new().restart_interval(4));
+    let records = HEADER_SIZE + ops_len(&encoded) + EXT_HEADER_SIZE;
+    assert_eq!(encoded[records], TAG_VERSION);
+    assert_eq!(Decoder::new(&encoded).unwrap().version(), FormatVersion::INITIAL);
+
+    // images from newer versions of the format are only decoded in compat mode
+    encoded[records + RECORD_HEADER_SIZE] += 1;
+    assert!(matches!(Decoder::new(&encoded), Err(Error::UnsupportedVersion { .. })));
+    assert_eq!(decode_compat(&encoded).unwrap().1, pixels(4));
+}
+
+#[test]
+fn test_unknown_records_are_skipped() {
+    let mut encoded = rgba(EncoderOptions::new().restart_interval(8));
+    let len_pos = HEADER_SIZE + ops_len(&encoded) + 4;
+    let len = u32::from_le_bytes(encoded[len_pos..len_pos + 4].try_into().unwrap());
+    encoded[len_pos..len_pos + 4].copy_from_slice(&(len + 7).to_le_bytes());
+    encoded.extend_from_slice(&[0x7f, 2, 0, 0, 0, 0xab, 0xcd]);
+
+    let decoder = Decoder::new(&encoded).unwrap();
+    assert_eq!(decoder.restart_markers().unwrap().n_bands(), 4);
+    assert_eq!(decode_to_vec(&encoded).unwrap().1, pixels(4));
+
+    // an unknown record ahead of the version record doesn't hide it
+    let records = len_pos + 4;
+    encoded[len_pos..len_pos + 4].copy_from_slice(&(len + 14).to_le_bytes());
+    encoded.splice(records..records, [0x7e, 2, 0, 0, 0, 0x12, 0x34]);
+    encoded[records + 7 + RECORD_HEADER_SIZE + 1] = 0x20;
+    assert!(matches!(Decoder::new(&encoded), Err(Error::UnsupportedVersion { flags: 0x20, .. })));
+    let decoder = Decoder::new_compat(&encoded).unwrap();
+    assert_eq!(decoder.version(), FormatVersion { version: 1, flags: 0x20 });
+    assert_eq!(decoder.restart_markers().unwrap().n_bands(), 4);
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "ffi")]
+
+mod common;
+
+use std::ptr;
+
+use qoi::encode_to_vec;
+use qoi::ffi::{
+    qoi_decode, qoi_decode_header, qoi_encode, qoi_free, QoiBuffer, QoiHeader, QoiStatus,
+};
+
+use common::pixels;
+
+#[test]
+fn test_ffi_roundtrip() {
+    let (width, height) = (17, 9);
+    let pixels = pixels(width, height, 3);
+    let mut encoded = QoiBuffer { data: ptr::null_mut(), len: 0 };
+    let status = unsafe { qoi_encode(pixels.as_ptr(), pixels.len(), width, height, &mut encoded) };
+    assert_eq!(status, QoiStatus::Ok);
+    let bytes = unsafe { std::slice::from_raw_parts(encoded.data, encoded.len) };
+    assert_eq!(bytes, encode_to_vec(&pixels, width, height).unwrap());
+
+    let mut header = QoiHeader::default();
+    let status = unsafe { qoi_decode_header(encoded.data, encoded.len, &mut header) };
+    assert_eq!(status, QoiStatus::Ok);
+    assert_eq!((header.width, header.height, header.channels), (17, 9, 3));
+
+    let mut decoded = QoiBuffer { data: ptr::null_mut(), len: 0 };
+    let status = unsafe { qoi_decode(encoded.data, encoded.len, 4, &mut header, &mut decoded) };
+    assert_eq!(status, QoiStatus::Ok);
+    assert_eq!(header.channels, 4);
+    let rgba = unsafe { std::slice::from_raw_parts(decoded.data, decoded.len) };
+    assert!(rgba.chunks_exact(4).zip(pixels.chunks_exact(3)).all(|(a, b)| a[..3] == *b));
+    unsafe {
+        qoi_free(decoded);
+        qoi_free(encoded);
+    }
+}
+
+#[test]
+fn test_ffi_errors() {
+    let mut out = QoiBuffer { data: ptr::null_mut(), len: 0 };
+    let status = unsafe { qoi_encode(ptr::null(), 0, 1, 1, &mut out) };
+    assert_eq!(status, QoiStatus::InvalidArgument);
+    let pixels = [0u8; 4 * 6];
+    let status = unsafe { qoi_encode(pixels.as_ptr(), pixels.len(), 5, 1, &mut out) };
+    assert_eq!(status, QoiStatus::InvalidImageLength);
+    let status = unsafe { qoi_decode(pixels.as_ptr(), pixels.len(), 0, ptr::null_mut(), &mut out) };
+    assert_eq!(status, QoiStatus::InvalidMagic);
+    assert!(out.data.is_null());
+}
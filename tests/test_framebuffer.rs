@@ -0,0 +1,81 @@
+mod common;
+
+use qoi::{
+    encode_from_framebuffer, encode_from_framebuffer_u32, encode_to_vec, ByteOrder, Channels,
+    Decoder, Encoder, EncoderOptions, Error, PixelFormat,
+};
+
+use common::pixels;
+
+#[test]
+fn test_encode_from_framebuffer_u32() {
+    let (width, height, stride) = (11, 5, 14);
+    let pixels = pixels(width, height, 4);
+    let expected = encode_to_vec(&pixels, width as u16, height as u16).unwrap();
+    for (format, to_word) in [
+        (PixelFormat::RgbaU32Be, u32::from_be_bytes as fn([u8; 4]) -> u32),
+        (PixelFormat::RgbaU32Le, u32::from_le_bytes),
+    ] {
+        let mut words = vec![0; stride * height as usize];
+        for (row, src) in words.chunks_mut(stride).zip(pixels.chunks(width as usize * 4)) {
+            for (word, px) in row.iter_mut().zip(src.chunks_exact(4)) {
+                *word = to_word(px.try_into().unwrap());
+            }
+        }
+        let encoded = encode_from_framebuffer_u32(&words, width, height, stride, format).unwrap();
+        assert_eq!(encoded, expected);
+    }
+}
+
+#[test]
+fn test_decode_to_vec_as() {
+    let (width, height) = (11, 5);
+    let pixels = pixels(width, height, 4);
+    let encoded = encode_to_vec(&pixels, width, height).unwrap();
+    let bgra: Vec<u8> =
+        pixels.chunks_exact(4).flat_map(|px| [px[2], px[1], px[0], px[3]]).collect();
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    assert_eq!(decoder.decode_to_vec_as(PixelFormat::Bgra).unwrap(), bgra);
+    let stride = width as usize * 4;
+    let reencoded = encode_from_framebuffer(&bgra, width, height, stride, PixelFormat::Bgra);
+    assert_eq!(reencoded.unwrap(), encoded);
+
+    let mut rgb565 = vec![0; width as usize * height as usize];
+    Decoder::new(&encoded)
+        .unwrap()
+        .decode_to_rgb565(&mut rgb565, ByteOrder::BigEndian, false)
+        .unwrap();
+    let decoded = Decoder::new(&encoded).unwrap().decode_to_vec_as(PixelFormat::Rgb565Be).unwrap();
+    assert_eq!(decoded, rgb565.iter().flat_map(|word| word.to_ne_bytes()).collect::<Vec<_>>());
+    let mut xrgb = Decoder::new(&encoded).unwrap();
+    let err = xrgb.decode_to_buf_as(&mut [0; 16][..], PixelFormat::Xrgb).unwrap_err();
+    assert!(matches!(err, Error::OutputBufferTooSmall { size: 16, required: 220 }));
+}
+
+#[test]
+fn test_encoder_new_with_format() {
+    let (width, height) = (37, 29);
+    let rgba = pixels(width, height, 4);
+    let bgra: Vec<u8> = rgba.chunks_exact(4).flat_map(|px| [px[2], px[1], px[0], px[3]]).collect();
+    let options = EncoderOptions::new().verify_output(true);
+    let encoder = Encoder::new_with_format(&bgra, width, height, PixelFormat::Bgra).unwrap();
+    assert_eq!(encoder.channels(), Channels::Rgba);
+    let encoded = encoder.with_options(options).encode_to_vec().unwrap();
+    assert_eq!(encoded, encode_to_vec(&rgba, width, height).unwrap());
+
+    // the unused byte is ignored, and the image is encoded as RGB
+    let rgb: Vec<u8> = rgba.chunks_exact(4).flat_map(|px| [px[0], px[1], px[2]]).collect();
+    let xrgb: Vec<u8> = rgba.chunks_exact(4).flat_map(|px| [px[3], px[0], px[1], px[2]]).collect();
+    let mut encoder = Encoder::new_with_format(&xrgb, width, height, PixelFormat::Xrgb).unwrap();
+    assert_eq!(encoder.encode_to_vec().unwrap(), encode_to_vec(&rgb, width, height).unwrap());
+
+    let rgb565 = &rgba[..width as usize * height as usize * 2];
+    let mut encoder =
+        Encoder::new_with_format(rgb565, width, height, PixelFormat::Rgb565Be).unwrap();
+    let encoded = encoder.encode_to_vec().unwrap();
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    assert_eq!(decoder.decode_to_vec_as(PixelFormat::Rgb565Be).unwrap(), rgb565);
+
+    let result = Encoder::new_with_format(&rgb, width, height, PixelFormat::Bgra);
+    assert!(matches!(result, Err(Error::InvalidImageLength { .. })));
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::path::PathBuf;
+use std::{fs, process, thread};
+
+use qoi::{decode_to_vec, write_file_atomic};
+
+use common::pixels;
+
+/// Creates an empty directory in the temporary directory, unique to a test and a process.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("qoi-test-fs-{name}-{}", process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_write_file_atomic_concurrently() {
+    let dir = test_dir("concurrent");
+    let path = dir.join("image.qoi");
+    let frames: Vec<Vec<u8>> =
+        (0..8).map(|i| pixels(16, 8, 4).iter().map(|b| b ^ i).collect()).collect();
+    thread::scope(|scope| {
+        for frame in &frames {
+            let path = &path;
+            scope.spawn(move || write_file_atomic(path, frame, 16, 8).unwrap());
+        }
+    });
+    // the last write wins, and none of them left a temporary file behind
+    let decoded = decode_to_vec(fs::read(&path).unwrap()).unwrap().1;
+    assert!(frames.contains(&decoded));
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_write_file_atomic_skips_stale_files() {
+    let dir = test_dir("stale");
+    let path = dir.join("image.qoi");
+    // temporary files of this process left behind as if by a crash
+    for n in 0..32 {
+        fs::write(dir.join(format!(".image.qoi.{}.{n}.tmp", process::id())), b"stale").unwrap();
+    }
+    let pixels = pixels(16, 8, 4);
+    write_file_atomic(&path, &pixels, 16, 8).unwrap();
+    assert_eq!(decode_to_vec(fs::read(&path).unwrap()).unwrap().1, pixels);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 33);
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use qoi::{
+    Channels, ChecksumHasher, ChecksumKind, ColorSpace, Decoder, Encoder, EncoderOptions, Error,
+    Header, HeaderFormat,
+};
+
+use common::pixels;
+
+fn encode_reference(pixels: &[u8], options: EncoderOptions) -> Vec<u8> {
+    let options = options.header_format(HeaderFormat::Reference);
+    Encoder::new(pixels, 37, 29).unwrap().with_options(options).encode_to_vec().unwrap()
+}
+
+#[test]
+fn test_reference_header() {
+    for (n_channels, channels) in [(3, Channels::Rgb), (4, Channels::Rgba)] {
+        let pixels = pixels(37, 29, n_channels);
+        let encoded = encode_reference(&pixels, EncoderOptions::new());
+        assert_eq!(
+            encoded[..14],
+            [b"qoif", &[0, 0, 0, 37, 0, 0, 0, 29, n_channels, 0][..]].concat()
+        );
+        assert_eq!(HeaderFormat::detect(&encoded), Some(HeaderFormat::Reference));
+        let mut decoder = Decoder::new(&encoded).unwrap();
+        assert_eq!(decoder.header_format(), HeaderFormat::Reference);
+        assert_eq!(decoder.channels(), channels);
+        assert_eq!(decoder.decode_to_vec().unwrap(), pixels);
+    }
+}
+
+#[test]
+fn test_reference_header_without_extensions() {
+    // luma + alpha is stored as RGBA, and restart markers are dropped
+    let pixels = pixels(37, 29, 2);
+    let encoded = encode_reference(&pixels, EncoderOptions::new().restart_interval(4));
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    assert_eq!((decoder.channels(), decoder.restart_markers()), (Channels::Rgba, None));
+    let rgba: Vec<u8> =
+        pixels.chunks_exact(2).flat_map(|px| [px[0], px[0], px[0], px[1]]).collect();
+    assert_eq!(decoder.decode_to_vec().unwrap(), rgba);
+}
+
+#[test]
+fn test_encode_to_stream() {
+    // luma + alpha and restart markers only survive in the extension block of GameMaker images
+    let pixels = pixels(37, 29, 2);
+    for format in [HeaderFormat::GameMaker, HeaderFormat::Reference] {
+        let options = EncoderOptions::new().header_format(format).restart_interval(4);
+        let mut encoder = Encoder::new(&pixels, 37, 29).unwrap().with_options(options);
+        let mut streamed = Vec::new();
+        let n_written = encoder.encode_to_stream(&mut streamed).unwrap();
+        assert_eq!((n_written, &streamed), (streamed.len(), &encoder.encode_to_vec().unwrap()));
+        let mut decoder = Decoder::new(&streamed).unwrap();
+        assert_eq!(decoder.header_format(), format);
+        if format == HeaderFormat::GameMaker {
+            assert_eq!(decoder.channels(), Channels::La);
+            assert_eq!(decoder.restart_markers().unwrap().interval(), 4);
+            assert_eq!(decoder.decode_to_vec().unwrap(), pixels);
+        } else {
+            let rgba: Vec<u8> =
+                pixels.chunks_exact(2).flat_map(|px| [px[0], px[0], px[0], px[1]]).collect();
+            assert_eq!(decoder.decode_to_vec().unwrap(), rgba);
+        }
+    }
+}
+
+#[test]
+fn test_reference_header_invalid_fields() {
+    let mut encoded = encode_reference(&pixels(37, 29, 4), EncoderOptions::new());
+    encoded[13] = 2;
+    assert!(matches!(Header::decode_reference(&encoded), Err(Error::InvalidColorSpace { .. })));
+    encoded[12] = 5;
+    assert!(matches!(Decoder::new(&encoded), Err(Error::InvalidChannels { channels: 5 })));
+}
+
+#[test]
+fn test_colorspace() {
+    let pixels = pixels(37, 29, 3);
+    let encoder = || Encoder::new(&pixels, 37, 29).unwrap();
+    let encoded = encoder().encode_to_vec().unwrap();
+    assert_eq!(Decoder::new(&encoded).unwrap().header().colorspace, None);
+    for format in [HeaderFormat::GameMaker, HeaderFormat::Reference] {
+        let options = EncoderOptions::new().header_format(format);
+        let mut encoder = encoder().with_options(options).with_colorspace(ColorSpace::Linear);
+        let encoded = encoder.encode_to_vec().unwrap();
+        let mut decoder = Decoder::new(&encoded).unwrap();
+        assert_eq!(decoder.header().colorspace, Some(ColorSpace::Linear));
+        assert_eq!(decoder.decode_to_vec().unwrap(), pixels);
+    }
+}
+
+#[test]
+fn test_checksum() {
+    let pixels = pixels(37, 29, 4);
+    let options = EncoderOptions::new().restart_interval(4);
+    let mut encoder = Encoder::new(&pixels, 37, 29)
+        .unwrap()
+        .with_options(options)
+        .with_checksum(ChecksumKind::Crc32);
+    let mut encoded = encoder.encode_to_vec().unwrap();
+    assert_eq!(Decoder::new(&encoded).unwrap().decode_to_vec().unwrap(), pixels);
+    // the checksum is computed as the op stream is yielded when encoding in chunks
+    let mut buf = vec![0; encoder.required_buf_len()];
+    assert!(encoder.encode_chunks(&mut buf, 7).all(|part| part.is_ok()));
+    assert_eq!(buf[..encoded.len()], encoded);
+
+    encoded[20] ^= 1;
+    assert!(matches!(Decoder::new(&encoded), Err(Error::ChecksumMismatch)));
+}
+
+#[test]
+fn test_crc32_check_value() {
+    let mut hasher = ChecksumHasher::new(ChecksumKind::Crc32);
+    for chunk in b"123456789".chunks(4) {
+        hasher.update(chunk);
+    }
+    assert_eq!(hasher.finish().value, 0xcbf4_3926);
+    assert_eq!(ChecksumHasher::new(ChecksumKind::Crc32).finish().value, 0);
+}
+
+#[cfg(feature = "xxh3")]
+#[test]
+fn test_xxh3_checksum() {
+    let pixels = pixels(37, 29, 3);
+    let mut encoder = Encoder::new(&pixels, 37, 29).unwrap().with_checksum(ChecksumKind::Xxh3);
+    let mut encoded = encoder.encode_to_vec().unwrap();
+    let crc32 = encoder.with_checksum(ChecksumKind::Crc32).encode_to_vec().unwrap();
+    // 8 bytes instead of 4
+    assert_eq!(encoded.len(), crc32.len() + 4);
+    assert_eq!(Decoder::new(&encoded).unwrap().decode_to_vec().unwrap(), pixels);
+
+    encoded[20] ^= 1;
+    assert!(matches!(Decoder::new(&encoded), Err(Error::ChecksumMismatch)));
+}
+
+#[test]
+fn test_generic_dimensions() {
+    let header = Header::try_new(37, 29, None).unwrap();
+    assert_eq!(Header::from_dimensions(37_u32, 29_usize, None).unwrap(), header);
+    let err = Header::from_dimensions(70_000_u32, 29_u64, None).unwrap_err();
+    assert!(matches!(err, Error::InvalidImageDimensions { width: 65535, height: 29 }));
+    let pixels = pixels(37, 29, 4);
+    let encoded = Encoder::new(&pixels, 37_u32, 29_i64).unwrap().encode_to_vec().unwrap();
+    assert_eq!(encoded, Encoder::new(&pixels, 37_u16, 29_u16).unwrap().encode_to_vec().unwrap());
+}
+
+#[test]
+fn test_pixel_count_cap() {
+    // 400_040_001 pixels, just above the default 400Mp cap
+    let header = Header::try_new(20001, 20001, None);
+    if cfg!(feature = "large-images") {
+        assert_eq!(header.unwrap().n_pixels(), 400_040_001);
+        assert!(Header::try_new(u16::MAX, u16::MAX, None).is_ok());
+    } else {
+        let err = header.unwrap_err();
+        assert!(matches!(err, Error::InvalidImageDimensions { width: 20001, height: 20001 }));
+        assert!(Header::try_new(20000, 20000, None).is_ok());
+    }
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use qoi::{decode_headerless, encode_headerless, encode_to_vec, Channels, Decoder, Encoder, Error};
+
+use common::pixels;
+
+#[test]
+fn test_headerless_round_trip() {
+    let rgba = pixels(37, 29, 4);
+    let encoded = encode_headerless(&rgba, 37, 29).unwrap();
+    let full = encode_to_vec(&rgba, 37, 29).unwrap();
+    assert_eq!(encoded, full[12..]);
+    assert_eq!(decode_headerless(&encoded, 37, 29).unwrap(), rgba);
+
+    let rgb = pixels(37, 29, 3);
+    let encoded = Encoder::new(&rgb, 37, 29).unwrap().encode_headerless_to_vec().unwrap();
+    let decoder = Decoder::new_headerless(&encoded, 37, 29).unwrap();
+    assert_eq!(decoder.with_channels(Channels::Rgb).decode_to_vec().unwrap(), rgb);
+}
+
+#[test]
+fn test_headerless_errors() {
+    let pixels = pixels(37, 29, 4);
+    let mut encoder = Encoder::new(&pixels, 37, 29).unwrap();
+    let mut buf = [0; 16];
+    let err = encoder.encode_headerless_to_buf(&mut buf).unwrap_err();
+    assert!(matches!(err, Error::OutputBufferTooSmall { size: 16, .. }));
+    let encoded = encoder.encode_headerless_to_vec().unwrap();
+    let truncated = &encoded[..encoded.len() - 1];
+    assert!(matches!(decode_headerless(truncated, 37, 29), Err(Error::UnexpectedBufferEnd { .. })));
+    assert!(matches!(
+        decode_headerless(&encoded, 0, 29),
+        Err(Error::InvalidImageDimensions { .. })
+    ));
+}
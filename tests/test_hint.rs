@@ -0,0 +1,37 @@
+mod common;
+
+use qoi::{decode_to_vec, ContentHint, Encoder, EncoderOptions};
+
+use common::pixels;
+
+const HINTS: [ContentHint; 4] =
+    [ContentHint::Auto, ContentHint::Screenshot, ContentHint::Photo, ContentHint::PixelArt];
+
+fn encode(pixels: &[u8], options: EncoderOptions, tolerance: u8) -> Vec<u8> {
+    let encoder = Encoder::new(pixels, 31, 19).unwrap().with_options(options);
+    encoder.with_tolerance(tolerance).encode_to_vec().unwrap()
+}
+
+#[test]
+fn test_content_hints_give_the_same_size() {
+    // noise, flat areas and a small palette, so that every hint is wrong for some of it
+    let mut flat = pixels(31, 19, 4);
+    flat[100..1500].fill(0xee);
+    let palette: Vec<u8> =
+        (0..31 * 19).flat_map(|i| [[0, 0, 0, 0xff], [0xff; 4]][i / 3 % 2]).collect();
+    for pixels in [pixels(31, 19, 4), pixels(31, 19, 3), flat, palette] {
+        for (restart_interval, tolerance) in [(0, 0), (4, 0), (0, 6)] {
+            let options = EncoderOptions::new().restart_interval(restart_interval);
+            let expected = encode(&pixels, options, tolerance);
+            let decoded = decode_to_vec(&expected).unwrap().1;
+            if tolerance == 0 {
+                assert_eq!(decoded, pixels);
+            }
+            for hint in HINTS {
+                let encoded = encode(&pixels, options.content_hint(hint), tolerance);
+                assert_eq!(encoded.len(), expected.len(), "{hint:?}");
+                assert_eq!(decode_to_vec(&encoded).unwrap().1, decoded, "{hint:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "http")]
+
+mod common;
+
+use std::io::Read;
+
+use qoi::http::{accepts, Body, CONTENT_TYPE};
+use qoi::Encoder;
+
+use common::pixels;
+
+#[test]
+fn test_accepts() {
+    let accepted = [
+        "image/x-gamemaker-qoi",
+        "Image/X-GameMaker-QOI",
+        "text/html, image/x-gamemaker-qoi;q=0.8, */*;q=0.1",
+        " image/x-gamemaker-qoi ; level=1 ; q = 1",
+    ];
+    for accept in accepted {
+        assert!(accepts(accept), "{accept:?}");
+    }
+    let rejected = [
+        "",
+        "image/*, */*",
+        "image/qoi",
+        "image/x-gamemaker-qoi-extra",
+        "image/x-gamemaker-qoi;q=0",
+        "image/x-gamemaker-qoi; q=0.000",
+        "image/x-gamemaker-qoi;q=high",
+    ];
+    for accept in rejected {
+        assert!(!accepts(accept), "{accept:?}");
+    }
+}
+
+#[test]
+fn test_body() {
+    let pixels = pixels(16, 8, 4);
+    let mut encoder = Encoder::new(&pixels, 16, 8).unwrap();
+    let encoded = encoder.encode_to_vec().unwrap();
+    let mut body = Body::encode(&mut encoder).unwrap();
+    assert_eq!((body.content_type(), body.content_length()), (CONTENT_TYPE, encoded.len() as u64));
+    let mut head = [0; 10];
+    body.read_exact(&mut head).unwrap();
+    assert_eq!(body.content_length(), encoded.len() as u64 - 10);
+    let chunks: Vec<Vec<u8>> = body.into_chunks(7).collect();
+    assert!(chunks.iter().all(|chunk| (1..=7).contains(&chunk.len())));
+    assert_eq!([&head[..], &chunks.concat()].concat(), encoded);
+}
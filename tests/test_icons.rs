@@ -0,0 +1,22 @@
+use qoi::{decode_to_vec, encode_to_vec, icons};
+
+#[test]
+fn test_export_icons() {
+    // a wide opaque red image, so the icons are letterboxed
+    let pixels = [255, 0, 0, 255].repeat(300 * 200);
+    let encoded = encode_to_vec(&pixels, 300_u16, 200_u16).unwrap();
+    let sizes = [16, 256, 48, 32];
+    let icons = icons::export(&encoded, &sizes).unwrap();
+    assert_eq!(icons.iter().map(|(size, _)| *size).collect::<Vec<_>>(), sizes);
+    for (size, icon) in icons {
+        let (header, decoded) = decode_to_vec(&icon).unwrap();
+        assert_eq!((header.width, header.height), (size, size));
+        let size = size as usize;
+        let height = (200 * size + 150) / 300;
+        let top = (size - height) / 2;
+        for (y, row) in decoded.chunks_exact(size * 4).enumerate() {
+            let expected = if (top..top + height).contains(&y) { [255, 0, 0, 255] } else { [0; 4] };
+            assert!(row.chunks_exact(4).all(|px| px == expected), "size {size}, row {y}");
+        }
+    }
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use qoi::{decode_to_vec, Error, Image, Pixel};
+
+use common::pixels;
+
+fn image() -> Image {
+    Image::from_pixels(pixels(5, 3, 4), 5, 3).unwrap()
+}
+
+fn px(image: &Image, x: u16, y: u16) -> [u8; 4] {
+    image.get_pixel(x, y).unwrap().into()
+}
+
+#[test]
+fn test_image_round_trip() {
+    let image = image();
+    let encoded = image.encode().unwrap();
+    assert_eq!(decode_to_vec(&encoded).unwrap().1, image.pixels());
+    assert_eq!(Image::decode(&encoded).unwrap(), image);
+    assert!(matches!(Image::from_pixels(vec![0; 59], 5, 3), Err(Error::InvalidImageLength { .. })));
+}
+
+#[test]
+fn test_image_pixels() {
+    let mut image = image();
+    let pixels = pixels(5, 3, 4);
+    assert_eq!(px(&image, 3, 2), pixels[(2 * 5 + 3) * 4..][..4]);
+    assert_eq!(image.get_pixel(5, 0), None);
+    assert_eq!(image.get_pixel(0, 3), None);
+    image.set_pixel(4, 1, [1, 2, 3, 4]);
+    assert_eq!(image.get_pixel(4, 1), Some(Pixel::from([1, 2, 3, 4])));
+    assert_eq!(image.pixels()[(5 + 4) * 4..][..4], [1, 2, 3, 4]);
+}
+
+#[test]
+fn test_image_crop() {
+    let image = image();
+    let cropped = image.crop(1, 1, 3, 2).unwrap();
+    assert_eq!((cropped.width(), cropped.height()), (3, 2));
+    for (x, y) in [(0, 0), (2, 0), (0, 1), (2, 1)] {
+        assert_eq!(px(&cropped, x, y), px(&image, x + 1, y + 1));
+    }
+    assert_eq!(image.crop(0, 0, 5, 3).unwrap(), image);
+    assert!(matches!(image.crop(3, 0, 3, 1), Err(Error::InvalidImageDimensions { .. })));
+    assert!(matches!(image.crop(0, 2, 1, 2), Err(Error::InvalidImageDimensions { .. })));
+}
+
+#[test]
+fn test_image_flip() {
+    let image = image();
+    let mut flipped = image.clone();
+    flipped.flip_horizontal();
+    assert_eq!((px(&flipped, 0, 1), px(&flipped, 4, 2)), (px(&image, 4, 1), px(&image, 0, 2)));
+    flipped.flip_horizontal();
+    assert_eq!(flipped, image);
+
+    flipped.flip_vertical();
+    assert_eq!((px(&flipped, 1, 0), px(&flipped, 3, 2)), (px(&image, 1, 2), px(&image, 3, 0)));
+    // the middle row of an odd height stays in place
+    assert_eq!(px(&flipped, 2, 1), px(&image, 2, 1));
+    flipped.flip_vertical();
+    assert_eq!(flipped, image);
+}
+
+#[test]
+fn test_image_resize_nn() {
+    let image = image();
+    let resized = image.resize_nn(10, 6).unwrap();
+    assert_eq!((resized.width(), resized.height()), (10, 6));
+    assert_eq!(px(&resized, 7, 5), px(&image, 3, 2));
+    assert_eq!(resized.resize_nn(5, 3).unwrap(), image);
+    assert!(image.resize_nn(0, 3).is_err());
+}
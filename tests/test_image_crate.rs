@@ -0,0 +1,35 @@
+#![cfg(feature = "image")]
+
+mod common;
+
+use image::{DynamicImage, ExtendedColorType, ImageEncoder, RgbaImage};
+use qoi::{encode_to_vec, Decoder, Image, ImageWriter};
+
+use common::pixels;
+
+#[test]
+fn test_image_decoder_and_encoder() {
+    for channels in [2, 3, 4] {
+        let pixels = pixels(19, 7, channels);
+        let encoded = encode_to_vec(&pixels, 19_u16, 7_u16).unwrap();
+        let image = DynamicImage::from_decoder(Decoder::new(&encoded).unwrap()).unwrap();
+        assert_eq!((image.width(), image.height()), (19, 7));
+        assert_eq!(image.as_bytes(), pixels);
+
+        let color_type = image.color().into();
+        let mut out = Vec::new();
+        ImageWriter::new(&mut out).write_image(&pixels, 19, 7, color_type).unwrap();
+        assert_eq!(out, encoded);
+    }
+    let mut out = Vec::new();
+    let result = ImageWriter::new(&mut out).write_image(&[0; 19 * 7], 19, 7, ExtendedColorType::L8);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_rgba_image_conversions() {
+    let pixels = pixels(19, 7, 4);
+    let image = Image::try_from(RgbaImage::from_raw(19, 7, pixels.clone()).unwrap()).unwrap();
+    assert_eq!(image.pixels(), pixels);
+    assert_eq!(RgbaImage::from(image).into_raw(), pixels);
+}
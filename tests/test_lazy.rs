@@ -0,0 +1,65 @@
+mod common;
+
+use qoi::{
+    Channels, Decoder, DecoderOptions, Encoder, EncoderOptions, HeaderFormat, LazyImage, Pixel,
+};
+
+use common::pixels;
+
+fn encode(n_channels: u8, restart_interval: u16) -> Vec<u8> {
+    let pixels = pixels(23, 17, n_channels);
+    let options = EncoderOptions::new().restart_interval(restart_interval);
+    Encoder::new(&pixels, 23, 17).unwrap().with_options(options).encode_to_vec().unwrap()
+}
+
+#[test]
+fn test_lazy_image() {
+    for (n_channels, restart_interval) in [(4, 0), (4, 5), (2, 0), (2, 5)] {
+        let encoded = encode(n_channels, restart_interval);
+        let expected = Decoder::new(&encoded).unwrap().decode_to_vec().unwrap();
+        let row_len = 23 * usize::from(n_channels);
+        let mut image = LazyImage::new(&encoded).unwrap();
+        assert_eq!(image.channels().as_u8(), n_channels);
+        // rows out of order, going back to rows decoded before
+        for y in [9, 2, 16, 0, 9] {
+            let row = image.row(y).unwrap().unwrap();
+            assert_eq!(row, &expected[usize::from(y) * row_len..][..row_len]);
+        }
+        assert_eq!(image.row(17).unwrap(), None);
+        let mut px = Pixel::new();
+        px.read(&expected[(3 * 23 + 22) * usize::from(n_channels)..][..usize::from(n_channels)]);
+        assert_eq!(image.get_pixel(22, 3).unwrap(), Some(px));
+        assert_eq!(image.get_pixel(23, 3).unwrap(), None);
+        assert_eq!(image.into_pixels().unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_lazy_image_options() {
+    let options = DecoderOptions::new().alpha_threshold(0x40).flatten_onto([10, 20, 30]);
+    for (n_channels, restart_interval) in [(4, 0), (4, 5), (2, 0), (2, 5)] {
+        let encoded = encode(n_channels, restart_interval);
+        let mut decoder = Decoder::new(&encoded).unwrap().with_options(options);
+        let expected = decoder.decode_to_vec().unwrap();
+        let mut image = LazyImage::new(&encoded).unwrap();
+        // rows decoded before setting the options are decoded again
+        let _ = image.row(7).unwrap();
+        let mut image = image.with_options(options);
+        let row_len = 23 * usize::from(n_channels);
+        let row = image.row(7).unwrap().unwrap();
+        assert_eq!(row, &expected[7 * row_len..][..row_len], "{n_channels} {restart_interval}");
+        assert_eq!(image.into_pixels().unwrap(), expected, "{n_channels} {restart_interval}");
+    }
+}
+
+#[test]
+fn test_lazy_image_reference_header() {
+    let pixels = pixels(23, 17, 3);
+    let options = EncoderOptions::new().header_format(HeaderFormat::Reference);
+    let encoded =
+        Encoder::new(&pixels, 23, 17).unwrap().with_options(options).encode_to_vec().unwrap();
+    let mut image = LazyImage::new(&encoded).unwrap();
+    assert_eq!(image.channels(), Channels::Rgb);
+    assert_eq!(image.row(5).unwrap().unwrap(), &pixels[5 * 23 * 3..][..23 * 3]);
+    assert_eq!(image.into_pixels().unwrap(), pixels);
+}
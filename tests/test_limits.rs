@@ -0,0 +1,45 @@
+mod common;
+
+use qoi::{encode_to_vec, sanitize, Channels, Decoder, Encoder, EncoderOptions, Error, Limits};
+
+use common::pixels;
+
+#[test]
+fn test_limits() {
+    let encoded = encode_to_vec(pixels(16, 8, 4), 16, 8).unwrap();
+    let check = |limits: Limits| Decoder::new(&encoded).unwrap().with_limits(limits).map(|_| ());
+    assert!(check(Limits::new()).is_ok());
+    assert!(check(Limits::new().max_width(16).max_height(8).max_pixels(128)).is_ok());
+    assert!(check(Limits::new().max_input_len(encoded.len())).is_ok());
+    for limits in [
+        Limits::new().max_width(15),
+        Limits::new().max_height(7),
+        Limits::new().max_pixels(127),
+        Limits::new().max_input_len(encoded.len() - 1),
+    ] {
+        assert!(matches!(check(limits), Err(Error::LimitsExceeded)));
+    }
+}
+
+#[test]
+fn test_sanitize() {
+    let pixels = pixels(16, 8, 4);
+    let options = EncoderOptions::new().restart_interval(2);
+    let mut encoder = Encoder::new(&pixels, 16, 8).unwrap().with_options(options);
+    let mut encoded = encoder.encode_to_vec().unwrap();
+    encoded.extend_from_slice(b"trailing junk");
+    // extensions and trailing data are dropped, leaving the canonical encoding
+    let sanitized = sanitize(&encoded, Limits::new()).unwrap();
+    assert_eq!(sanitized, encode_to_vec(&pixels, 16, 8).unwrap());
+    assert_eq!(Decoder::new(&sanitized).unwrap().restart_markers(), None);
+    let err = sanitize(&encoded, Limits::new().max_pixels(100)).unwrap_err();
+    assert!(matches!(err, Error::LimitsExceeded));
+
+    // the channels are kept, which takes an extension block for RGB images
+    let rgb = common::pixels(16, 8, 3);
+    let mut encoder = Encoder::new(&rgb, 16, 8).unwrap().with_options(options);
+    let sanitized = sanitize(encoder.encode_to_vec().unwrap(), Limits::new()).unwrap();
+    assert_eq!(sanitized, encode_to_vec(&rgb, 16, 8).unwrap());
+    let decoder = Decoder::new(&sanitized).unwrap();
+    assert_eq!((decoder.channels(), decoder.restart_markers()), (Channels::Rgb, None));
+}
@@ -0,0 +1,115 @@
+use qoi::{decode_to_vec, Encoder, EncoderOptions};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 32;
+
+// a smooth gradient with a little noise, like a camera frame
+fn noisy_gradient() -> Vec<u8> {
+    (0..WIDTH * HEIGHT)
+        .flat_map(|i| {
+            let (value, noise) = ((i % WIDTH) as u8 * 2, (i * 7 % 5) as u8);
+            [value + noise, value + 2 * noise, value, if i % 97 == 0 { 0x80 } else { 0xff }]
+        })
+        .collect()
+}
+
+#[test]
+fn test_with_tolerance() {
+    let (width, height, pixels) = (WIDTH, HEIGHT, noisy_gradient());
+    let encode = |tolerance| {
+        let encoder = Encoder::new(&pixels, width, height).unwrap();
+        let options = EncoderOptions::new().verify_output(true);
+        encoder.with_options(options).with_tolerance(tolerance).encode_to_vec().unwrap()
+    };
+    let lossless = encode(0);
+    assert_eq!(decode_to_vec(&lossless).unwrap().1, pixels);
+
+    let lossy = encode(8);
+    assert!(lossy.len() * 2 < lossless.len(), "{} vs {}", lossy.len(), lossless.len());
+    let decoded = decode_to_vec(&lossy).unwrap().1;
+    for (px, expected) in decoded.chunks_exact(4).zip(pixels.chunks_exact(4)) {
+        assert!(px[..3].iter().zip(&expected[..3]).all(|(a, b)| a.abs_diff(*b) <= 8));
+        assert_eq!(px[3], expected[3]);
+    }
+
+    // same as a map filled with the tolerance, and a map set afterwards replaces it
+    let encoder = || Encoder::new(&pixels, width, height).unwrap();
+    let (map, zeros) = (vec![8; pixels.len() / 4], vec![0; pixels.len() / 4]);
+    assert_eq!(encoder().with_roi(&map).unwrap().encode_to_vec().unwrap(), lossy);
+    let encoded = encoder().with_tolerance(8).with_roi(&zeros).unwrap().encode_to_vec().unwrap();
+    assert_eq!(encoded, lossless);
+    // chunked encoding applies it to every chunk
+    let mut encoder = encoder().with_tolerance(8);
+    let mut buf = vec![0; encoder.required_buf_len()];
+    assert!(encoder.encode_chunks(&mut buf, 5).all(|part| part.is_ok()));
+    assert_eq!(buf[..lossy.len()], lossy);
+}
+
+#[test]
+fn test_with_roi() {
+    let pixels = noisy_gradient();
+    // lossless text in the top left quarter, a loose bound for the rest of the top half and a
+    // tight one for the bottom half
+    let tolerance = |i: u32| match (i % WIDTH < WIDTH / 2, i / WIDTH < HEIGHT / 2) {
+        (true, true) => 0,
+        (false, true) => 12,
+        (_, false) => 3,
+    };
+    let map: Vec<u8> = (0..WIDTH * HEIGHT).map(tolerance).collect();
+    let encoder = Encoder::new(&pixels, WIDTH, HEIGHT).unwrap();
+    let options = EncoderOptions::new().verify_output(true);
+    let encoded = encoder.with_options(options).with_roi(&map).unwrap().encode_to_vec().unwrap();
+    let decoded = decode_to_vec(&encoded).unwrap().1;
+
+    let pairs = decoded.chunks_exact(4).zip(pixels.chunks_exact(4));
+    let mut n_lossy = 0;
+    for (i, (px, expected)) in (0..).zip(pairs) {
+        let max_diff = px.iter().zip(expected).map(|(a, b)| a.abs_diff(*b)).max().unwrap();
+        assert!(max_diff <= tolerance(i), "pixel {i} is off by {max_diff}");
+        assert_eq!(px[3], expected[3]);
+        n_lossy += usize::from(max_diff > 0);
+    }
+    assert!(n_lossy > 0);
+
+    let encoder = Encoder::new(&pixels, WIDTH, HEIGHT).unwrap();
+    assert!(encoder.with_roi(&map[1..]).is_err());
+}
+
+#[test]
+fn test_nearest_color_index() {
+    // a few scattered colors in the middle of their 16x16x16 cells, jittered so that the
+    // exact hash of a pixel rarely finds its color in the index
+    let palette: Vec<[u8; 3]> = (0..24u32)
+        .map(|i| {
+            let cell = i.wrapping_mul(0x9e37_79b9) >> 20;
+            [cell >> 8, cell >> 4, cell].map(|v| (v & 15) as u8 * 16 + 8)
+        })
+        .collect();
+    let pixels: Vec<u8> = (0..WIDTH * HEIGHT)
+        .flat_map(|i| {
+            let [r, g, b] = palette[(i * 7919 % 24) as usize];
+            let jitter = (i * 31 % 7) as u8;
+            [r + jitter - 3, g + 3 - jitter, b + jitter, 0xff]
+        })
+        .collect();
+    let map = vec![6; (WIDTH * HEIGHT) as usize];
+    let encode = |nearest| {
+        let encoder = Encoder::new(&pixels, WIDTH, HEIGHT).unwrap();
+        let options = EncoderOptions::new().nearest_color_index(nearest).verify_output(true);
+        encoder.with_options(options).with_roi(&map).unwrap().encode_to_vec().unwrap()
+    };
+    let (plain, nearest) = (encode(false), encode(true));
+    assert!(nearest.len() * 2 < plain.len(), "{} vs {}", nearest.len(), plain.len());
+    let decoded = decode_to_vec(&nearest).unwrap().1;
+    for (px, expected) in decoded.chunks_exact(4).zip(pixels.chunks_exact(4)) {
+        assert!(px.iter().zip(expected).all(|(a, b)| a.abs_diff(*b) <= 6));
+    }
+
+    // lossless encoding has nothing to gain from it
+    let lossless = |nearest| {
+        let encoder = Encoder::new(&pixels, WIDTH, HEIGHT).unwrap();
+        let options = EncoderOptions::new().nearest_color_index(nearest);
+        encoder.with_options(options).encode_to_vec().unwrap()
+    };
+    assert_eq!(lossless(true), lossless(false));
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use std::env;
+use std::fs;
+
+use qoi::{decode_to_vec, metrics, Encoder, EncoderOptions};
+
+use common::pixels;
+
+fn errors(snapshot: &metrics::Snapshot, kind: &str) -> u64 {
+    snapshot.errors().find(|&(label, _)| label == kind).unwrap().1
+}
+
+// a single test, since the counters are shared by every thread of the process
+#[test]
+fn test_metrics() {
+    let pixels = pixels(16, 8, 4);
+    let path = env::temp_dir().join(format!("qoi-metrics-{}.qoi", std::process::id()));
+    metrics::reset();
+
+    // encoded in memory first with restart markers, streamed otherwise
+    let mut sizes = Vec::new();
+    for interval in [4, 0] {
+        let options = EncoderOptions::new().restart_interval(interval);
+        let mut encoder = Encoder::new(&pixels, 16, 8).unwrap().with_options(options);
+        sizes.push(encoder.encode_to_file(&path).unwrap());
+        assert_eq!(decode_to_vec(fs::read(&path).unwrap()).unwrap().1, pixels);
+    }
+    fs::remove_file(&path).unwrap();
+    let snapshot = metrics::snapshot();
+    assert_eq!(snapshot.images_encoded, 2);
+    assert_eq!(snapshot.bytes_encoded, sizes.iter().sum::<usize>() as u64);
+    assert_eq!(snapshot.images_decoded, 2);
+    assert_eq!(snapshot.bytes_decoded, 2 * pixels.len() as u64);
+    assert_eq!(snapshot.total_errors(), 0);
+
+    // failures to create the file are counted as well
+    let missing = env::temp_dir().join("qoi-metrics-missing").join("image.qoi");
+    for interval in [4, 0] {
+        let options = EncoderOptions::new().restart_interval(interval);
+        let mut encoder = Encoder::new(&pixels, 16, 8).unwrap().with_options(options);
+        assert!(encoder.encode_to_file(&missing).is_err());
+    }
+    let snapshot = metrics::snapshot();
+    assert_eq!((snapshot.images_encoded, errors(&snapshot, "io_error")), (2, 2));
+
+    assert!(decode_to_vec([0; 4]).is_err());
+    assert_eq!(metrics::snapshot().total_errors(), 3);
+    metrics::reset();
+    assert_eq!(metrics::snapshot(), metrics::Snapshot::default());
+}
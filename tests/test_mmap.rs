@@ -0,0 +1,84 @@
+#![cfg(feature = "mmap")]
+
+mod common;
+
+use std::path::PathBuf;
+
+use qoi::{decode_file, Decoder, Encoder, EncoderOptions, Error};
+
+use common::pixels;
+
+/// Path in the temporary directory, unique to a test and a process.
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("qoi-test-mmap-{name}-{}.qoi", std::process::id()))
+}
+
+/// Writes an image to a file in the temporary directory, unique to a test.
+fn write_image(name: &str, options: EncoderOptions) -> (PathBuf, Vec<u8>) {
+    let pixels = pixels(64, 40, 4);
+    let encoded = Encoder::new(&pixels, 64, 40).unwrap().with_options(options).encode_to_vec();
+    let path = temp_path(name);
+    std::fs::write(&path, encoded.unwrap()).unwrap();
+    (path, pixels)
+}
+
+#[test]
+fn test_open_mmap_bands() {
+    let (path, pixels) = write_image("bands", EncoderOptions::new().restart_interval(16));
+    let image = Decoder::open_mmap(&path).unwrap();
+    assert_eq!(&*image, std::fs::read(&path).unwrap());
+    let decoder = image.decoder().unwrap();
+    assert_eq!(decoder.restart_markers().unwrap().n_bands(), 3);
+    for band in 0..3 {
+        image.prefetch_band(band).unwrap();
+        let rows = &pixels[band * 16 * 64 * 4..((band + 1) * 16).min(40) * 64 * 4];
+        assert_eq!(decoder.decode_band_to_vec(band).unwrap(), rows);
+    }
+    assert!(matches!(image.prefetch_band(3), Err(Error::InvalidRestartMarker)));
+    assert_eq!(Decoder::new(&image).unwrap().decode_to_vec().unwrap(), pixels);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_open_mmap_errors() {
+    let (path, _) = write_image("plain", EncoderOptions::new());
+    let image = Decoder::open_mmap(&path).unwrap();
+    assert!(matches!(image.prefetch_band(0), Err(Error::InvalidRestartMarker)));
+    // truncating a mapped file is undefined behavior, so the mapping goes first
+    drop(image);
+    std::fs::write(&path, b"not an image").unwrap();
+    assert!(Decoder::open_mmap(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(Decoder::open_mmap(&path), Err(Error::IoError(_))));
+}
+
+#[test]
+fn test_encode_to_file_mmap() {
+    let pixels = pixels(64, 40, 4);
+    for options in [EncoderOptions::new(), EncoderOptions::new().restart_interval(16)] {
+        let mut encoder = Encoder::new(&pixels, 64, 40).unwrap().with_options(options);
+        let path = temp_path("encode");
+        let n_written = encoder.encode_to_file_mmap(&path).unwrap();
+        let encoded = encoder.encode_to_vec().unwrap();
+        assert_eq!(n_written, encoded.len());
+        assert_eq!(std::fs::read(&path).unwrap(), encoded);
+        std::fs::remove_file(path).unwrap();
+    }
+    // character devices can't be resized, so this goes through the buffered fallback
+    #[cfg(unix)]
+    {
+        let mut encoder = Encoder::new(&pixels, 64, 40).unwrap();
+        let n_written = encoder.encode_to_file_mmap("/dev/null").unwrap();
+        assert_eq!(n_written, encoder.encode_to_vec().unwrap().len());
+    }
+}
+
+#[test]
+fn test_decode_file() {
+    let (path, pixels) = write_image("decode", EncoderOptions::new());
+    let (header, decoded) = decode_file(&path).unwrap();
+    assert_eq!((header.width, header.height), (64, 40));
+    assert_eq!(decoded, pixels);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(decode_file(&path), Err(Error::IoError(_))));
+}
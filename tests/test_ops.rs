@@ -0,0 +1,78 @@
+mod common;
+
+use qoi::ops::{offsets, OpDecoder, OpKind};
+use qoi::{decode_header, decode_to_vec, encode_to_vec, Encoder, EncoderOptions, HeaderFormat};
+
+use common::pixels;
+
+const HEADER_SIZE: usize = 12;
+const PADDING_SIZE: usize = 8;
+
+fn encoded() -> Vec<u8> {
+    // a run of a single color and two gradients, so that every op kind shows up
+    let mut pixels = pixels(23, 17, 4);
+    pixels[40..400].fill(0x80);
+    for (i, px) in pixels[400..800].chunks_exact_mut(4).enumerate() {
+        let step = if i < 50 { 1 } else { 4 };
+        px[..3].fill((i * step) as u8);
+        px[3] = 0xff;
+    }
+    encode_to_vec(&pixels, 23, 17).unwrap()
+}
+
+#[test]
+fn test_offsets_cover_the_op_stream() {
+    let encoded = encoded();
+    let header = decode_header(&encoded).unwrap();
+    let ops: Vec<_> = offsets(&encoded).unwrap().collect();
+    let mut pos = HEADER_SIZE;
+    for &(offset, kind, _) in &ops {
+        assert_eq!((offset, OpKind::from_byte(encoded[offset])), (pos, kind));
+        pos += kind.n_bytes();
+    }
+    // the data length includes the end marker
+    assert_eq!(pos + PADDING_SIZE, HEADER_SIZE + header.length.unwrap() as usize);
+    let n_pixels: u32 = ops.iter().map(|&(_, _, n)| n).sum();
+    assert_eq!(n_pixels as usize, header.n_pixels());
+    for kind in [OpKind::Index, OpKind::Diff, OpKind::Luma, OpKind::Run, OpKind::Rgba] {
+        assert!(ops.iter().any(|op| op.1 == kind), "no {kind:?} op");
+    }
+
+    // a truncated stream ends the iteration early instead of yielding partial ops
+    let (_, kind, _) = ops[ops.len() / 2];
+    let truncated = &encoded[..ops[ops.len() / 2].0 + kind.n_bytes() - 1];
+    assert_eq!(offsets(truncated).unwrap().count(), ops.len() / 2);
+}
+
+#[test]
+fn test_op_decoder_matches_offsets() {
+    let encoded = encoded();
+    let (_, pixels) = decode_to_vec(&encoded).unwrap();
+    let mut decoder = OpDecoder::new(&encoded[HEADER_SIZE..]);
+    let mut decoded = Vec::new();
+    for (offset, kind, n_pixels) in offsets(&encoded).unwrap() {
+        let op = decoder.next_op().unwrap();
+        assert_eq!(
+            (op.offset + HEADER_SIZE, op.kind, op.n_pixels),
+            (offset, kind, n_pixels as usize)
+        );
+        for _ in 0..op.n_pixels {
+            decoded.extend_from_slice(&<[u8; 4]>::from(op.px));
+        }
+    }
+    assert_eq!(decoded, pixels);
+}
+
+#[test]
+fn test_offsets_with_reference_header() {
+    let encoded = encoded();
+    let (_, pixels) = decode_to_vec(&encoded).unwrap();
+    let options = EncoderOptions::new().header_format(HeaderFormat::Reference);
+    let mut encoder = Encoder::new(&pixels, 23, 17).unwrap().with_options(options);
+    let reference = encoder.encode_to_vec().unwrap();
+    let header = decode_header(&reference).unwrap();
+    assert_eq!((header.width, header.height, header.length), (23, 17, None));
+    // the op streams are the same, only the header is 2 bytes longer
+    let shifted = offsets(&encoded).unwrap().map(|(offset, kind, n)| (offset + 2, kind, n));
+    assert!(offsets(&reference).unwrap().eq(shifted));
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use qoi::{
+    ByteOrder, Decoder, DecoderOptions, Encoder, Error, PackedFormat, PixelFormat, YuvMatrix,
+};
+
+use common::pixels;
+
+#[test]
+fn test_alpha_threshold() {
+    let pixels = pixels(16, 8, 4);
+    let encoded = Encoder::new(&pixels, 16, 8).unwrap().encode_to_vec().unwrap();
+    let options = DecoderOptions::new().alpha_threshold(0x80);
+    let decoded = Decoder::new(&encoded).unwrap().with_options(options).decode_to_vec().unwrap();
+    for (px, expected) in decoded.chunks_exact(4).zip(pixels.chunks_exact(4)) {
+        assert_eq!(px[..3], expected[..3]);
+        assert_eq!(px[3], if expected[3] < 0x80 { 0 } else { 0xff });
+    }
+    assert!(decoded.chunks_exact(4).any(|px| px[3] == 0));
+    assert!(decoded.chunks_exact(4).any(|px| px[3] == 0xff));
+}
+
+#[test]
+fn test_max_unique_colors() {
+    // five colors in a repeating pattern, like pixel art
+    let palette = [[0, 0, 0, 0xff], [0xff, 0, 0, 0xff], [0, 0xff, 0, 0xff], [1, 2, 3, 4], [9; 4]];
+    let pixels: Vec<u8> = (0..16 * 8).flat_map(|i| palette[i * 7 / 3 % 5]).collect();
+    let encoded = Encoder::new(&pixels, 16, 8).unwrap().encode_to_vec().unwrap();
+    for limit in [4, 5] {
+        let options = DecoderOptions::new().max_unique_colors(limit);
+        let from_slice = Decoder::new(&encoded).unwrap().with_options(options).decode_to_vec();
+        let mut stream = Decoder::from_stream(&encoded[..]).unwrap().with_options(options);
+        let mut rows = vec![vec![0; 16 * 4]; 8];
+        let mut next_rows = rows.iter_mut();
+        let mut decoder = Decoder::new(&encoded).unwrap().with_options(options);
+        let into_rows = decoder.decode_rows_into(|_| next_rows.next().unwrap());
+        if limit == 5 {
+            assert_eq!(from_slice.unwrap(), pixels);
+            assert_eq!(stream.decode_to_vec().unwrap(), pixels);
+            into_rows.unwrap();
+            assert_eq!(rows.concat(), pixels);
+        } else {
+            assert!(matches!(from_slice, Err(Error::TooManyColors { limit: 4 })));
+            assert!(matches!(stream.decode_to_vec(), Err(Error::TooManyColors { limit: 4 })));
+            assert!(matches!(into_rows, Err(Error::TooManyColors { limit: 4 })));
+        }
+    }
+
+    // the other slice decoding methods count the colors up front too
+    let decoder = || {
+        let options = DecoderOptions::new().max_unique_colors(4);
+        Decoder::new(&encoded).unwrap().with_options(options)
+    };
+    let (mut bytes, mut words, mut halves) =
+        (vec![0; 16 * 8 * 4], vec![0; 16 * 8], vec![0; 16 * 8]);
+    let (mut u, mut v) = (vec![0; 8 * 4], vec![0; 8 * 4]);
+    for result in [
+        decoder().decode_to_buf_as(&mut bytes, PixelFormat::Bgra),
+        decoder().decode_to_u32_buf(&mut words, PackedFormat::Argb),
+        decoder().decode_to_rgb565(&mut halves, ByteOrder::LittleEndian, false),
+        decoder().decode_to_yuv420(&mut bytes, &mut u, &mut v, YuvMatrix::Bt601),
+        decoder().decode_to_rgb10a2(&mut words),
+    ] {
+        assert!(matches!(result, Err(Error::TooManyColors { limit: 4 })));
+    }
+}
+
+#[test]
+fn test_flatten_onto() {
+    let pixels = pixels(16, 8, 4);
+    let encoded = Encoder::new(&pixels, 16, 8).unwrap().encode_to_vec().unwrap();
+    let background = [0x20, 0x90, 0xf0];
+    let options = DecoderOptions::new().flatten_onto(background);
+    let decoded = Decoder::new(&encoded).unwrap().with_options(options).decode_to_vec().unwrap();
+    for (px, expected) in decoded.chunks_exact(4).zip(pixels.chunks_exact(4)) {
+        let a = f64::from(expected[3]) / 255.;
+        for ((&v, &fg), &bg) in px.iter().zip(&expected[..3]).zip(&background) {
+            assert_eq!(v, (f64::from(fg) * a + f64::from(bg) * (1. - a)).round() as u8);
+        }
+        assert_eq!(px[3], 0xff);
+    }
+    let mut stream = Decoder::from_stream(&encoded[..]).unwrap().with_options(options);
+    assert_eq!(stream.decode_to_vec().unwrap(), decoded);
+
+    // the threshold is applied first, so pixels are either kept or replaced by the background
+    let options = options.alpha_threshold(0x80);
+    let decoded = Decoder::new(&encoded).unwrap().with_options(options).decode_to_vec().unwrap();
+    for (px, expected) in decoded.chunks_exact(4).zip(pixels.chunks_exact(4)) {
+        let rgb = if expected[3] < 0x80 { &background } else { &expected[..3] };
+        assert_eq!((&px[..3], px[3]), (rgb, 0xff));
+    }
+}
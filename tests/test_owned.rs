@@ -0,0 +1,50 @@
+mod common;
+
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::thread;
+
+use qoi::{Encoder, Error};
+
+use common::pixels;
+
+#[test]
+fn test_owned_encoders_move_to_threads() {
+    let pixels = pixels(16, 8, 4);
+    let expected = Encoder::new(&pixels, 16, 8).unwrap().encode_to_vec().unwrap();
+    let shared: Arc<[u8]> = pixels.clone().into();
+    let mut from_owned = Encoder::from_owned(Arc::clone(&shared), 16, 8).unwrap();
+    // the shared frame is taken as is rather than copied
+    assert_eq!(Arc::strong_count(&shared), 2);
+    let mut into_owned = Encoder::new(&pixels, 16, 8).unwrap().into_owned();
+    let workers = [
+        thread::spawn(move || from_owned.encode_to_vec().unwrap()),
+        thread::spawn(move || into_owned.encode_to_vec().unwrap()),
+    ];
+    for worker in workers {
+        assert_eq!(worker.join().unwrap(), expected);
+    }
+    assert_eq!(Arc::strong_count(&shared), 1);
+}
+
+#[test]
+fn test_pixel_data_kinds() {
+    let pixels = pixels(16, 8, 4);
+    let expected = Encoder::new(&pixels, 16, 8).unwrap().encode_to_vec().unwrap();
+    let encode = |encoder: qoi::Result<Encoder>| encoder.unwrap().encode_to_vec().unwrap();
+    assert_eq!(encode(Encoder::new(pixels.as_slice(), 16, 8)), expected);
+    assert_eq!(encode(Encoder::new(pixels.clone(), 16, 8)), expected);
+    assert_eq!(encode(Encoder::new(pixels.clone().into_boxed_slice(), 16, 8)), expected);
+    assert_eq!(encode(Encoder::new(Cow::Borrowed(pixels.as_slice()), 16, 8)), expected);
+    assert_eq!(encode(Encoder::new(Cow::<[u8]>::Owned(pixels.clone()), 16, 8)), expected);
+
+    let shared: Arc<[u8]> = pixels.clone().into();
+    let encoder = Encoder::new(Arc::clone(&shared), 16, 8).unwrap();
+    assert_eq!(Arc::strong_count(&shared), 2);
+    assert_eq!(encode(Ok(encoder)), expected);
+    assert_eq!(Arc::strong_count(&shared), 1);
+
+    // owned data is checked against the dimensions the same way as borrowed data
+    let short = pixels[..pixels.len() - 1].to_vec();
+    assert!(matches!(Encoder::new(short, 16, 8), Err(Error::InvalidImageLength { .. })));
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use qoi::{decode_to_vec, Decoder, DecoderOptions, Encoder, Error, PackedFormat};
+
+use common::pixels;
+
+#[test]
+fn test_decode_to_u32_buf() {
+    let pixels = pixels(23, 17, 4);
+    let encoded = Encoder::new(&pixels, 23, 17).unwrap().encode_to_vec().unwrap();
+    let (_, rgba) = decode_to_vec(&encoded).unwrap();
+    for (format, order) in [
+        (PackedFormat::Argb, [3, 0, 1, 2]),
+        (PackedFormat::Rgba, [0, 1, 2, 3]),
+        (PackedFormat::Abgr, [3, 2, 1, 0]),
+    ] {
+        let mut out = vec![0; 23 * 17];
+        let mut decoder = Decoder::new(&encoded).unwrap();
+        assert_eq!(decoder.decode_to_u32_buf(&mut out, format).unwrap(), 23 * 17);
+        // the format gives the value of each word, from the most significant byte down
+        let expected = rgba.chunks_exact(4).map(|px| u32::from_be_bytes(order.map(|c| px[c])));
+        assert!(out.iter().copied().eq(expected), "{format:?}");
+    }
+    // 0xAABBGGRR words are laid out as RGBA bytes on little-endian targets
+    let mut out = vec![0; 23 * 17];
+    Decoder::new(&encoded).unwrap().decode_to_u32_buf(&mut out, PackedFormat::Abgr).unwrap();
+    if cfg!(target_endian = "little") {
+        assert!(out.iter().flat_map(|word| word.to_ne_bytes()).eq(rgba));
+    }
+}
+
+#[test]
+fn test_decode_to_u32_buf_options() {
+    let pixels = pixels(23, 17, 4);
+    let encoded = Encoder::new(&pixels, 23, 17).unwrap().encode_to_vec().unwrap();
+    let options = DecoderOptions::new().alpha_threshold(0x80);
+    let expected = Decoder::new(&encoded).unwrap().with_options(options).decode_to_vec().unwrap();
+    let mut out = vec![0; 23 * 17];
+    let mut decoder = Decoder::new(&encoded).unwrap().with_options(options);
+    decoder.decode_to_u32_buf(&mut out, PackedFormat::Rgba).unwrap();
+    assert!(out.iter().flat_map(|word| word.to_be_bytes()).eq(expected));
+
+    let mut out = vec![0; 23 * 17 - 1];
+    let result = Decoder::new(&encoded).unwrap().decode_to_u32_buf(&mut out, PackedFormat::Argb);
+    assert!(matches!(result, Err(Error::OutputBufferTooSmall { .. })));
+}
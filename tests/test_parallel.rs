@@ -0,0 +1,64 @@
+#![cfg(feature = "rayon")]
+
+mod common;
+
+use qoi::{decode_to_vec, Decoder, Encoder, EncoderOptions, HeaderFormat};
+
+use common::pixels;
+use rayon::ThreadPoolBuilder;
+
+// big enough for four strips
+const WIDTH: u32 = 512;
+const HEIGHT: u32 = 512;
+
+/// Encodes in parallel on a pool of `n_threads` threads, so that the strips are the same on
+/// every machine.
+fn encode_on(encoder: &mut Encoder, n_threads: usize) -> Vec<u8> {
+    let pool = ThreadPoolBuilder::new().num_threads(n_threads).build().unwrap();
+    pool.install(|| encoder.encode_to_vec_parallel()).unwrap()
+}
+
+fn encode(pixels: &[u8], options: EncoderOptions, n_threads: Option<usize>) -> Vec<u8> {
+    let mut encoder = Encoder::new(pixels, WIDTH, HEIGHT).unwrap().with_options(options);
+    match n_threads {
+        Some(n_threads) => encode_on(&mut encoder, n_threads),
+        None => encoder.encode_to_vec().unwrap(),
+    }
+}
+
+#[test]
+fn test_encode_parallel_matches_sequential() {
+    for channels in [3, 4] {
+        let pixels = pixels(WIDTH, HEIGHT, channels);
+        // without a restart interval, each of the four strips becomes a single band
+        let parallel = encode(&pixels, EncoderOptions::new(), Some(4));
+        assert_eq!(decode_to_vec(&parallel).unwrap().1, pixels);
+        let markers = Decoder::new(&parallel).unwrap().restart_markers().unwrap();
+        assert_eq!((markers.interval(), markers.n_bands()), (128, 4));
+        assert_eq!(parallel, encode(&pixels, EncoderOptions::new().restart_interval(128), None));
+
+        // with one, strips are made of whole bands: 32 bands over 3 strips
+        let options = EncoderOptions::new().restart_interval(16);
+        let parallel = encode(&pixels, options, Some(3));
+        assert_eq!(decode_to_vec(&parallel).unwrap().1, pixels);
+        assert_eq!(parallel, encode(&pixels, options, None));
+    }
+}
+
+#[test]
+fn test_encode_parallel_fallbacks() {
+    let pixels = pixels(WIDTH, HEIGHT, 4);
+    let sequential = encode(&pixels, EncoderOptions::new(), None);
+    assert_eq!(encode(&pixels, EncoderOptions::new(), Some(1)), sequential);
+
+    // the restart interval picked for the strips isn't kept by the encoder
+    let mut encoder = Encoder::new(&pixels, WIDTH, HEIGHT).unwrap();
+    encode_on(&mut encoder, 4);
+    assert_eq!(encoder.encode_to_vec().unwrap(), sequential);
+
+    // the reference header has no restart markers, the strips are just concatenated
+    let options = EncoderOptions::new().header_format(HeaderFormat::Reference);
+    let parallel = encode(&pixels, options, Some(4));
+    assert_eq!(decode_to_vec(&parallel).unwrap().1, pixels);
+    assert!(parallel.len() > encode(&pixels, options, None).len());
+}
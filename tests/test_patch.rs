@@ -0,0 +1,70 @@
+mod common;
+
+use qoi::{
+    decode_to_vec, patch_region, Channels, Decoder, Encoder, EncoderOptions, Error, NinePatch,
+};
+
+use common::pixels;
+
+#[test]
+fn test_patch_region() {
+    let (width, height) = (37, 29);
+    let mut pixels = pixels(width, height, 4);
+    let nine_patch = NinePatch::new((3, 30), (4, 20));
+    let encode = |pixels: &[u8]| {
+        let options = EncoderOptions::new().restart_interval(4);
+        let encoder = Encoder::new(pixels, width, height).unwrap().with_options(options);
+        encoder.with_nine_patch(nine_patch).unwrap().encode_to_vec().unwrap()
+    };
+    let encoded = encode(&pixels);
+
+    // a 5x6 region straddling the bands of rows 4..8 and 8..12
+    let patch: Vec<u8> = (0..5 * 6 * 4).map(|i| (i * 7) as u8).collect();
+    let patched = patch_region(&encoded, 10, 5, &patch, 5, 6).unwrap();
+    for (row, patch_row) in pixels.chunks_exact_mut(37 * 4).skip(5).zip(patch.chunks_exact(20)) {
+        row[10 * 4..15 * 4].copy_from_slice(patch_row);
+    }
+    let reencoded = encode(&pixels);
+    assert_eq!(decode_to_vec(&patched).unwrap().1, pixels);
+
+    // untouched bands are copied over, so they decode on their own like in a full re-encode
+    let decoder = Decoder::new(&patched).unwrap();
+    assert_eq!(decoder.nine_patch(), Some(nine_patch));
+    let markers = decoder.restart_markers().unwrap();
+    let expected = Decoder::new(&reencoded).unwrap();
+    assert_eq!(markers.n_bands(), expected.restart_markers().unwrap().n_bands());
+    for band in 0..markers.n_bands() {
+        assert_eq!(
+            decoder.decode_band_to_vec(band).unwrap(),
+            expected.decode_band_to_vec(band).unwrap()
+        );
+    }
+
+    assert!(matches!(
+        patch_region(&encoded, 35, 0, &patch, 5, 6),
+        Err(Error::InvalidImageDimensions { .. })
+    ));
+    let plain = Encoder::new(&pixels, width, height).unwrap().encode_to_vec().unwrap();
+    assert!(matches!(patch_region(plain, 0, 0, &patch, 5, 6), Err(Error::InvalidRestartMarker)));
+}
+
+#[test]
+fn test_patch_region_rgb() {
+    let (width, height) = (37, 29);
+    let mut pixels = pixels(width, height, 3);
+    let options = EncoderOptions::new().restart_interval(4);
+    let encoded = Encoder::new(&pixels, width, height)
+        .unwrap()
+        .with_options(options)
+        .encode_to_vec()
+        .unwrap();
+
+    // RGB images are patched with RGB pixels
+    let patch: Vec<u8> = (0..5 * 6 * 3).map(|i| (i * 7) as u8).collect();
+    let patched = patch_region(&encoded, 10, 5, &patch, 5, 6).unwrap();
+    for (row, patch_row) in pixels.chunks_exact_mut(37 * 3).skip(5).zip(patch.chunks_exact(15)) {
+        row[10 * 3..15 * 3].copy_from_slice(patch_row);
+    }
+    assert_eq!(Decoder::new(&patched).unwrap().channels(), Channels::Rgb);
+    assert_eq!(decode_to_vec(&patched).unwrap().1, pixels);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use qoi::{decode_to_pixel_vec, encode_to_vec, pixels_from_bytes, pixels_to_bytes, Error, Pixel};
+
+use common::pixels;
+
+#[test]
+fn test_pixel_conversions() {
+    let bytes = pixels(7, 5, 4);
+    let pixels = pixels_from_bytes(&bytes, 7, 5).unwrap();
+    assert_eq!(pixels.len(), 35);
+    for (px, expected) in pixels.iter().zip(bytes.chunks_exact(4)) {
+        assert_eq!(<[u8; 4]>::from(*px), expected);
+        assert_eq!([px.r(), px.g(), px.b(), px.a()], expected);
+    }
+    assert_eq!(pixels_to_bytes(&pixels).unwrap(), bytes);
+    assert_eq!(pixels_to_bytes(&[]).unwrap(), []);
+
+    let err = pixels_from_bytes(&bytes[4..], 7, 5).unwrap_err();
+    assert!(matches!(err, Error::InvalidImageLength { size: 136, width: 7, height: 5 }));
+    let err = pixels_from_bytes(pixels_to_bytes(&pixels).unwrap(), 7, 4).unwrap_err();
+    assert!(matches!(err, Error::InvalidImageLength { .. }));
+}
+
+#[test]
+fn test_decode_to_pixel_vec() {
+    let rgba = pixels(7, 5, 4);
+    let (header, decoded) = decode_to_pixel_vec(encode_to_vec(&rgba, 7, 5).unwrap()).unwrap();
+    assert_eq!((header.width, header.height), (7, 5));
+    assert_eq!(decoded, pixels_from_bytes(&rgba, 7, 5).unwrap());
+
+    // RGB images are expanded with opaque alpha
+    let rgb = pixels(7, 5, 3);
+    let decoded = decode_to_pixel_vec(encode_to_vec(&rgb, 7, 5).unwrap()).unwrap().1;
+    let expected: Vec<Pixel> =
+        rgb.chunks_exact(3).map(|px| Pixel::from([px[0], px[1], px[2], 0xff])).collect();
+    assert_eq!(decoded, expected);
+}
+
+#[test]
+fn test_pixel_hash_and_order() {
+    let hash = |value: &dyn Fn(&mut DefaultHasher)| {
+        let mut hasher = DefaultHasher::new();
+        value(&mut hasher);
+        hasher.finish()
+    };
+    let bytes = pixels(7, 5, 4);
+    let pixels: Vec<Pixel> =
+        bytes.chunks_exact(4).map(|px| <[u8; 4]>::try_from(px).unwrap().into()).collect();
+    for (a, b) in pixels.iter().zip(&pixels[1..]) {
+        assert_eq!(hash(&|h| a.hash(h)), hash(&|h| a.to_u32().hash(h)));
+        assert_eq!(a.cmp(b), a.to_u32().cmp(&b.to_u32()));
+    }
+}
+
+#[cfg(feature = "pod")]
+#[test]
+fn test_pod_casts() {
+    use std::mem::{align_of, size_of};
+
+    assert_eq!((size_of::<Pixel>(), align_of::<Pixel>()), (4, 1));
+    let bytes = pixels(7, 5, 4);
+    let pixels: &[Pixel] = bytemuck::cast_slice(&bytes);
+    assert_eq!(pixels, pixels_from_bytes(&bytes, 7, 5).unwrap());
+    assert_eq!(bytemuck::cast_slice::<Pixel, u8>(pixels), bytes);
+
+    let mut pixels = vec![Pixel::new(); 2];
+    bytemuck::cast_slice_mut::<Pixel, u8>(&mut pixels).copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(pixels, [Pixel::from([1, 2, 3, 4]), Pixel::from([5, 6, 7, 8])]);
+    assert_eq!(bytemuck::cast::<_, [u8; 4]>(pixels[1]), [5, 6, 7, 8]);
+    let zeroed: Pixel = bytemuck::Zeroable::zeroed();
+    assert_eq!(<[u8; 4]>::from(zeroed), [0; 4]);
+}
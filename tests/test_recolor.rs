@@ -0,0 +1,76 @@
+mod common;
+
+use qoi::{decode_to_vec, recolor, ChecksumKind, Decoder, Encoder, EncoderOptions, HeaderFormat};
+
+use common::pixels;
+
+const FROM: [u8; 4] = [10, 200, 30, 255];
+const TO: [u8; 4] = [250, 0, 90, 255];
+
+fn recolored(pixels: &[u8]) -> Vec<u8> {
+    let map = |px: &[u8]| if px == FROM { TO } else { px.try_into().unwrap() };
+    pixels.chunks_exact(4).flat_map(map).collect()
+}
+
+#[test]
+fn test_recolor_literal_ops_in_place() {
+    let mut pixels = pixels(16, 8, 4);
+    for i in [3, 40, 41, 42, 100] {
+        pixels[i * 4..i * 4 + 4].copy_from_slice(&FROM);
+    }
+    let options = EncoderOptions::new().restart_interval(2);
+    let encoded =
+        Encoder::new(&pixels, 16, 8).unwrap().with_options(options).encode_to_vec().unwrap();
+    let out = recolor(&encoded, FROM, TO).unwrap();
+    // only the literal ops are rewritten, so the extension block is still there
+    assert_eq!(out.len(), encoded.len());
+    assert_eq!(Decoder::new(&out).unwrap().restart_markers().unwrap().n_bands(), 4);
+    assert_eq!(decode_to_vec(&out).unwrap().1, recolored(&pixels));
+}
+
+#[test]
+fn test_recolor_falls_back_to_reencoding() {
+    // the second pixel is a small difference from the first one, so it's not a literal op
+    let pixels = [[9, 199, 29, 255], FROM, [0, 0, 0, 255], FROM].concat();
+    let encoded = qoi::encode_to_vec(&pixels, 2, 2).unwrap();
+    let out = recolor(&encoded, FROM, TO).unwrap();
+    assert_eq!(decode_to_vec(&out).unwrap().1, recolored(&pixels));
+    assert_eq!(recolor(&encoded, FROM, FROM).unwrap(), encoded);
+}
+
+#[test]
+fn test_recolor_reference_header() {
+    let mut pixels = pixels(16, 8, 4);
+    pixels[12..16].copy_from_slice(&FROM);
+    let options = EncoderOptions::new().header_format(HeaderFormat::Reference);
+    let encoded =
+        Encoder::new(&pixels, 16, 8).unwrap().with_options(options).encode_to_vec().unwrap();
+    let out = recolor(&encoded, FROM, TO).unwrap();
+    assert_eq!(out.len(), encoded.len());
+    assert_eq!(decode_to_vec(&out).unwrap().1, recolored(&pixels));
+
+    // re-encoding keeps the header format
+    let pixels = [[9, 199, 29, 255], FROM, [0, 0, 0, 255], FROM].concat();
+    let encoded =
+        Encoder::new(&pixels, 2, 2).unwrap().with_options(options).encode_to_vec().unwrap();
+    let out = recolor(&encoded, FROM, TO).unwrap();
+    assert_eq!(Decoder::new(&out).unwrap().header_format(), HeaderFormat::Reference);
+    assert_eq!(decode_to_vec(&out).unwrap().1, recolored(&pixels));
+}
+
+#[test]
+fn test_recolor_recomputes_checksum() {
+    let mut pixels = pixels(16, 8, 4);
+    pixels[12..16].copy_from_slice(&FROM);
+    let options = EncoderOptions::new().restart_interval(2);
+    let mut encoder = Encoder::new(&pixels, 16, 8)
+        .unwrap()
+        .with_options(options)
+        .with_checksum(ChecksumKind::Crc32);
+    let encoded = encoder.encode_to_vec().unwrap();
+    let out = recolor(&encoded, FROM, TO).unwrap();
+    assert_eq!(out.len(), encoded.len());
+    let mut decoder = Decoder::new(&out).unwrap();
+    assert_eq!(decoder.restart_markers().unwrap().n_bands(), 4);
+    assert_eq!(decoder.decode_to_vec().unwrap(), recolored(&pixels));
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use qoi::{decode_to_vec, dither, ByteOrder, Decoder, DecoderOptions, Encoder, Error};
+
+use common::pixels;
+
+fn rgb565(px: &[u8]) -> u16 {
+    (u16::from(px[0] >> 3) << 11) | (u16::from(px[1] >> 2) << 5) | u16::from(px[2] >> 3)
+}
+
+#[test]
+fn test_decode_to_rgb565() {
+    let pixels = pixels(23, 17, 4);
+    let encoded = Encoder::new(&pixels, 23, 17).unwrap().encode_to_vec().unwrap();
+    let (_, rgba) = decode_to_vec(&encoded).unwrap();
+    let mut dithered = rgba.clone();
+    dither::ordered(&mut dithered, 23, [5, 6, 5]).unwrap();
+
+    for (byte_order, to_bytes) in [
+        (ByteOrder::LittleEndian, u16::to_le_bytes as fn(u16) -> [u8; 2]),
+        (ByteOrder::BigEndian, u16::to_be_bytes),
+    ] {
+        for (dither, source) in [(false, &rgba), (true, &dithered)] {
+            let mut out = vec![0; 23 * 17 + 1];
+            let mut decoder = Decoder::new(&encoded).unwrap();
+            assert_eq!(decoder.decode_to_rgb565(&mut out, byte_order, dither).unwrap(), 23 * 17);
+            let expected =
+                source.chunks_exact(4).map(|px| u16::from_ne_bytes(to_bytes(rgb565(px))));
+            assert!(out[..23 * 17].iter().copied().eq(expected), "{byte_order:?} {dither}");
+        }
+    }
+
+    let mut out = vec![0; 23 * 17 - 1];
+    let result =
+        Decoder::new(&encoded).unwrap().decode_to_rgb565(&mut out, ByteOrder::BigEndian, false);
+    assert!(matches!(result, Err(Error::OutputBufferTooSmall { .. })));
+}
+
+#[test]
+fn test_decode_to_rgb565_flattened() {
+    let pixels = pixels(23, 17, 4);
+    let encoded = Encoder::new(&pixels, 23, 17).unwrap().encode_to_vec().unwrap();
+    let options = DecoderOptions::new().flatten_onto([0x40, 0x80, 0xc0]);
+    let flattened = Decoder::new(&encoded).unwrap().with_options(options).decode_to_vec().unwrap();
+    let mut dithered = flattened.clone();
+    dither::ordered(&mut dithered, 23, [5, 6, 5]).unwrap();
+
+    for (dither, source) in [(false, &flattened), (true, &dithered)] {
+        let mut out = vec![0; 23 * 17];
+        let mut decoder = Decoder::new(&encoded).unwrap().with_options(options);
+        decoder.decode_to_rgb565(&mut out, ByteOrder::LittleEndian, dither).unwrap();
+        let expected = source.chunks_exact(4).map(|px| u16::from_le(rgb565(px)));
+        assert!(out.iter().copied().eq(expected), "{dither}");
+    }
+}
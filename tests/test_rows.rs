@@ -0,0 +1,90 @@
+mod common;
+
+use std::io::ErrorKind;
+
+use qoi::{Decoder, Encoder, Error};
+
+use common::pixels;
+
+#[test]
+fn test_encode_from_rows() {
+    let pixels = pixels(21, 11, 3);
+    let expected = Encoder::new(&pixels, 21, 11).unwrap().encode_to_vec().unwrap();
+    // every row from a buffer of its own, as if stitched from tiles
+    let rows: Vec<Vec<u8>> = pixels.chunks(21 * 3).map(<[u8]>::to_vec).collect();
+    let mut encoder = Encoder::from_rows(rows.iter().map(Vec::as_slice), 21, 11).unwrap();
+    assert_eq!(encoder.encode_to_vec().unwrap(), expected);
+
+    let rows = pixels.chunks(21 * 3);
+    let err = Encoder::from_rows(rows.clone().take(10), 21, 11).err().unwrap();
+    assert!(matches!(err, Error::InvalidImageLength { .. }));
+    // right total size, but rows of different lengths
+    let mut rows: Vec<&[u8]> = rows.collect();
+    (rows[0], rows[1]) = (&pixels[..60], &pixels[60..63 * 2]);
+    let err = Encoder::from_rows(rows.into_iter(), 21, 11).err().unwrap();
+    assert!(matches!(err, Error::InvalidImageLength { .. }));
+}
+
+#[test]
+fn test_decode_rows_into() {
+    let pixels = pixels(21, 11, 4);
+    let encoded = Encoder::new(&pixels, 21, 11).unwrap().encode_to_vec().unwrap();
+    // separately allocated rows with a padded pitch, as in a ring of staging buffers
+    let row_len = 21 * 4;
+    let mut rows = vec![vec![0xaa; row_len + 12]; 11];
+    let mut slots = rows.iter_mut();
+    let mut indices = Vec::new();
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    decoder
+        .decode_rows_into(|y| {
+            indices.push(y);
+            slots.next().unwrap()
+        })
+        .unwrap();
+    assert_eq!(indices, (0..11).collect::<Vec<_>>());
+    for (row, expected) in rows.iter().zip(pixels.chunks_exact(row_len)) {
+        assert_eq!((&row[..row_len], &row[row_len..]), (expected, &[0xaa; 12][..]));
+    }
+
+    let mut short = [vec![0; row_len - 1]];
+    let mut slots = short.iter_mut();
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    let err = decoder.decode_rows_into(|_| slots.next().unwrap()).unwrap_err();
+    assert!(matches!(err, Error::OutputBufferTooSmall { .. }));
+}
+
+#[test]
+fn test_decode_next_rows() {
+    let (width, height) = (21, 11);
+    let pixels = pixels(width, height, 4);
+    let encoded = Encoder::new(&pixels, width, height).unwrap().encode_to_vec().unwrap();
+    let row_len = width as usize * 4;
+
+    // room for three and a half rows, so three rows come out of each call but the last one
+    let mut decoder = Decoder::from_stream(&encoded[..]).unwrap();
+    let mut buf = vec![0; row_len * 7 / 2];
+    let mut decoded = Vec::new();
+    while let n @ 1.. = decoder.decode_next_rows(&mut buf).unwrap() {
+        assert_eq!(n, row_len * 3.min(11 - decoded.len() / row_len));
+        decoded.extend_from_slice(&buf[..n]);
+        assert_eq!(usize::from(decoder.rows_decoded()), decoded.len() / row_len);
+    }
+    assert_eq!(decoded, pixels);
+    let err = Decoder::from_stream(&encoded[..]).unwrap().decode_next_rows(&mut buf[..10]);
+    assert!(matches!(err, Err(Error::OutputBufferTooSmall { size: 10, .. })));
+
+    // the stream ends halfway through the image: the call reaching that point fails, and
+    // only the rows of the calls before it count as decoded
+    let truncated = &encoded[..encoded.len() / 2];
+    let mut decoder = Decoder::from_stream(truncated).unwrap();
+    let mut n_decoded = 0;
+    let err = loop {
+        match decoder.decode_next_rows(&mut buf) {
+            Ok(n) => n_decoded += n / row_len,
+            Err(err) => break err,
+        }
+    };
+    assert!(matches!(err, Error::IoError(ref err) if err.kind() == ErrorKind::UnexpectedEof));
+    assert!((1..11).contains(&n_decoded));
+    assert_eq!(usize::from(decoder.rows_decoded()), n_decoded);
+}
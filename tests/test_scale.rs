@@ -0,0 +1,67 @@
+use qoi::{decode_resized, encode_to_vec, scale_nn, Error, ResizeFilter};
+
+#[test]
+fn test_scale_nn() {
+    // 4x2 image whose pixels are their own index
+    let src: Vec<u8> = (0..8).flat_map(|i| [i, i, i, 0xff]).collect();
+    let mut dst = [0; 2 * 4];
+    scale_nn(&src, 4, 2, &mut dst[..], 2, 1).unwrap();
+    // centers of the destination pixels fall on source pixels 1 and 3 of the second row
+    assert_eq!(dst, [5, 5, 5, 0xff, 7, 7, 7, 0xff]);
+
+    let mut up = [0; 8 * 4 * 4];
+    scale_nn(&src, 4, 2, &mut up[..], 8, 4).unwrap();
+    let indices: Vec<u8> = up.chunks_exact(4).map(|px| px[0]).collect();
+    let expected = [0, 0, 1, 1, 2, 2, 3, 3].repeat(2);
+    assert_eq!(indices, [expected.clone(), expected.iter().map(|i| i + 4).collect()].concat());
+
+    let mut same = [0; 8 * 4];
+    scale_nn(&src, 4, 2, &mut same[..], 4, 2).unwrap();
+    assert_eq!(same[..], src[..]);
+
+    let err = scale_nn(&src[1..], 4, 2, &mut dst[..], 2, 1).unwrap_err();
+    assert!(matches!(err, Error::InvalidImageLength { .. }));
+    let err = scale_nn(&src, 4, 2, &mut dst[..4], 2, 1).unwrap_err();
+    assert!(matches!(err, Error::OutputBufferTooSmall { size: 4, required: 8 }));
+}
+
+const FILTERS: [ResizeFilter; 3] =
+    [ResizeFilter::Nearest, ResizeFilter::Box, ResizeFilter::BoxLinear];
+
+#[test]
+fn test_decode_resized_solid() {
+    let color = [0x12, 0x9a, 0xe7, 0xc0];
+    let src = color.repeat(37 * 29);
+    let encoded = encode_to_vec(&src, 37, 29).unwrap();
+    for filter in FILTERS {
+        for (width, height) in [(10, 7), (37, 29), (50, 3), (1, 1)] {
+            let resized = decode_resized(&encoded, width, height, filter).unwrap();
+            assert_eq!(resized, color.repeat(usize::from(width) * usize::from(height)));
+        }
+    }
+}
+
+#[test]
+fn test_decode_resized_box() {
+    // a 2x2 checkerboard, averaged into a single pixel
+    let src = [[0, 0, 0, 0xff], [0xff; 4], [0xff; 4], [0, 0, 0, 0xff]].concat();
+    let encoded = encode_to_vec(&src, 2, 2).unwrap();
+    let resized = |filter| decode_resized(&encoded, 1, 1, filter).unwrap();
+    assert_eq!(resized(ResizeFilter::Box), [0x80, 0x80, 0x80, 0xff]);
+    // half of the light of white is #bcbcbc in sRGB
+    assert_eq!(resized(ResizeFilter::BoxLinear), [0xbc, 0xbc, 0xbc, 0xff]);
+
+    // transparent pixels don't darken their neighbors
+    let src = [[0xff, 0, 0, 0xff], [0; 4]].concat();
+    let encoded = encode_to_vec(&src, 2, 1).unwrap();
+    for filter in [ResizeFilter::Box, ResizeFilter::BoxLinear] {
+        assert_eq!(decode_resized(&encoded, 1, 1, filter).unwrap(), [0xff, 0, 0, 0x80]);
+    }
+
+    let mut truncated = encode_to_vec([0x40; 4 * 4], 2, 2).unwrap();
+    truncated.pop();
+    for filter in FILTERS {
+        let err = decode_resized(&truncated, 1, 1, filter).unwrap_err();
+        assert!(matches!(err, Error::UnexpectedBufferEnd { .. } | Error::InvalidPadding { .. }));
+    }
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use qoi::{encode_to_vec, Decoder, Encoder, Error};
+
+use common::pixels;
+
+#[test]
+fn test_decode_scatter() {
+    let (width, height) = (9, 10);
+    let pixels = pixels(width, height, 4);
+    let encoded = encode_to_vec(&pixels, width, height).unwrap();
+    let row_len = width as usize * 4;
+
+    let mut bufs = [vec![0xaa; 4 * row_len + 3], vec![0xaa; 4 * row_len], vec![0xaa; 2 * row_len]];
+    let mut dests: Vec<&mut [u8]> = bufs.iter_mut().map(|buf| &mut buf[..]).collect();
+    let size = Decoder::new(&encoded).unwrap().decode_scatter(&mut dests, 4).unwrap();
+    assert_eq!(size, pixels.len());
+    let decoded: Vec<u8> =
+        bufs.iter().flat_map(|buf| &buf[..buf.len() / row_len * row_len]).copied().collect();
+    assert_eq!(decoded, pixels);
+    assert_eq!(bufs[0][4 * row_len..], [0xaa; 3]);
+
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    let mut short = [vec![0; 4 * row_len], vec![0; 4 * row_len], vec![0; row_len]];
+    let mut dests: Vec<&mut [u8]> = short.iter_mut().map(|buf| &mut buf[..]).collect();
+    let err = decoder.decode_scatter(&mut dests, 4).unwrap_err();
+    assert!(
+        matches!(err, Error::OutputBufferTooSmall { size, required } if size == row_len && required == 2 * row_len)
+    );
+    let err = decoder.decode_scatter(&mut dests[..2], 4).unwrap_err();
+    assert!(matches!(err, Error::OutputBufferTooSmall { .. }));
+    let err = decoder.decode_scatter(&mut dests, 0).unwrap_err();
+    assert!(matches!(err, Error::OutputBufferTooSmall { .. }));
+}
+
+#[test]
+fn test_encode_gather() {
+    let (width, height) = (9, 10);
+    for channels in [2, 3, 4] {
+        let pixels = pixels(width, height, channels);
+        let encoded = encode_to_vec(&pixels, width, height).unwrap();
+        // segments of whole pixels that don't line up with the rows, including an empty one
+        let n = usize::from(channels);
+        let (a, rest) = pixels.split_at(5 * n);
+        let (b, c) = rest.split_at(31 * n);
+        let segments = [a, &[][..], b, c];
+        let mut encoder = Encoder::new_gather(&segments, width, height).unwrap();
+        assert_eq!(encoder.channels().as_u8(), channels);
+        assert_eq!(encoder.encode_to_vec().unwrap(), encoded);
+    }
+
+    let pixels = pixels(width, height, 4);
+    let (a, b) = pixels.split_at(4 * 17 + 2);
+    let err = Encoder::new_gather(&[a, b], width, height).err();
+    assert!(matches!(err, Some(Error::InvalidImageLength { size: 360, .. })));
+    let short = &pixels[..pixels.len() - 4];
+    let err = Encoder::new_gather(&[short, &[0; 3]], width, height).err();
+    assert!(matches!(err, Some(Error::InvalidImageLength { size: 359, .. })));
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use qoi::{
+    encode_to_vec, ByteOrder, Decoder, DecoderOptions, Encoder, EncoderOptions, Error,
+    HeaderFormat, PackedFormat, PixelFormat, YuvMatrix,
+};
+
+use common::pixels;
+
+fn decode_strict(data: &[u8]) -> Result<Vec<u8>, Error> {
+    Decoder::new(data)?.with_options(DecoderOptions::new().strict()).decode_to_vec()
+}
+
+#[test]
+fn test_strict_valid() {
+    let pixels = pixels(37, 29, 3);
+    for options in [
+        EncoderOptions::new(),
+        EncoderOptions::new().restart_interval(4),
+        EncoderOptions::new().header_format(HeaderFormat::Reference),
+    ] {
+        let encoded =
+            Encoder::new(&pixels, 37, 29).unwrap().with_options(options).encode_to_vec().unwrap();
+        let decoded = decode_strict(&encoded).unwrap();
+        assert_eq!(decoded, Decoder::new(&encoded).unwrap().decode_to_vec().unwrap());
+    }
+}
+
+#[test]
+fn test_strict_rejects() {
+    // a single run covers the four identical pixels
+    let mut encoded = encode_to_vec([0, 0, 0, 0xff].repeat(4), 2, 2).unwrap();
+    assert_eq!(encoded[12], 0xc3);
+
+    let mut trailing = encoded.clone();
+    trailing.extend_from_slice(&[1, 2, 3]);
+    assert!(Decoder::new(&trailing).unwrap().decode_to_vec().is_ok());
+    assert!(matches!(decode_strict(&trailing), Err(Error::TrailingData { len: 3 })));
+
+    let mut length = encoded.clone();
+    length[8] += 1;
+    length.push(0);
+    assert!(Decoder::new(&length).unwrap().decode_to_vec().is_ok());
+    assert!(matches!(
+        decode_strict(&length),
+        Err(Error::LengthMismatch { declared: 10, actual: 9 })
+    ));
+
+    encoded[12] = 0xc4;
+    assert!(Decoder::new(&encoded).unwrap().decode_to_vec().is_ok());
+    assert!(matches!(
+        decode_strict(&encoded),
+        Err(Error::PixelCountMismatch { expected: 4, actual: 5 })
+    ));
+}
+
+#[test]
+fn test_strict_every_entry_point() {
+    let pixels = pixels(6, 4, 4);
+    let mut encoded = Encoder::new(&pixels, 6, 4).unwrap().encode_to_vec().unwrap();
+    encoded.extend_from_slice(&[1, 2, 3]);
+    let decoder = || Decoder::new(&encoded).unwrap().with_options(DecoderOptions::new().strict());
+    let (mut bytes, mut words, mut halves) = (vec![0; 6 * 4 * 4], vec![0; 6 * 4], vec![0; 6 * 4]);
+    let (mut u, mut v) = (vec![0; 3 * 2], vec![0; 3 * 2]);
+    for result in [
+        decoder().decode_to_buf_as(&mut bytes, PixelFormat::Bgra),
+        decoder().decode_to_u32_buf(&mut words, PackedFormat::Argb),
+        decoder().decode_to_rgb565(&mut halves, ByteOrder::LittleEndian, false),
+        decoder().decode_to_yuv420(&mut bytes, &mut u, &mut v, YuvMatrix::Bt601),
+        decoder().decode_to_rgb10a2(&mut words),
+        decoder().decode_to_buf_with_stride(&mut bytes, 6 * 4),
+    ] {
+        assert!(matches!(result, Err(Error::TrailingData { len: 3 })));
+    }
+}
@@ -0,0 +1,25 @@
+mod common;
+
+use qoi::{encode_to_vec, Decoder, Error};
+
+use common::pixels;
+
+#[test]
+fn test_decode_to_buf_with_stride() {
+    let (width, height, stride) = (13, 7, 13 * 4 + 9);
+    let pixels = pixels(width, height, 4);
+    let encoded = encode_to_vec(&pixels, width as u16, height as u16).unwrap();
+    let mut buf = vec![0xaa; stride * (height as usize - 1) + width as usize * 4];
+    let size = Decoder::new(&encoded).unwrap().decode_to_buf_with_stride(&mut buf, stride).unwrap();
+    assert_eq!(size, buf.len());
+    for (row, expected) in buf.chunks(stride).zip(pixels.chunks(width as usize * 4)) {
+        assert_eq!(&row[..expected.len()], expected);
+        assert!(row[expected.len()..].iter().all(|&b| b == 0xaa));
+    }
+
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    let err = decoder.decode_to_buf_with_stride(&mut buf, width as usize * 4 - 1).unwrap_err();
+    assert!(matches!(err, Error::OutputBufferTooSmall { .. }));
+    let err = decoder.decode_to_buf_with_stride(&mut buf[1..], stride).unwrap_err();
+    assert!(matches!(err, Error::OutputBufferTooSmall { .. }));
+}
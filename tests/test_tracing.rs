@@ -0,0 +1,106 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+use qoi::{decode_to_vec, Decoder, DecoderOptions, Encoder};
+
+use common::pixels;
+
+/// A span along with the fields recorded on it, as `name=value` strings.
+#[derive(Debug)]
+struct SpanData {
+    name: &'static str,
+    fields: Vec<String>,
+}
+
+struct FieldVisitor<'a>(&'a mut Vec<String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push(format!("{}={value:?}", field.name()));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push(format!("{}={value}", field.name()));
+    }
+}
+
+static SPANS: Mutex<Vec<SpanData>> = Mutex::new(Vec::new());
+
+/// Collects every span of the process into `SPANS`, so that the test can look at them afterwards.
+struct Collector;
+
+impl Subscriber for Collector {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let mut fields = Vec::new();
+        attrs.record(&mut FieldVisitor(&mut fields));
+        let mut spans = SPANS.lock().unwrap();
+        spans.push(SpanData { name: attrs.metadata().name(), fields });
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = SPANS.lock().unwrap();
+        let span = &mut spans[span.into_u64() as usize - 1];
+        values.record(&mut FieldVisitor(&mut span.fields));
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+fn take_spans() -> Vec<SpanData> {
+    std::mem::take(&mut *SPANS.lock().unwrap())
+}
+
+fn has(spans: &[SpanData], name: &str, fields: &[&str]) -> bool {
+    spans.iter().any(|span| {
+        span.name == name && fields.iter().all(|field| span.fields.iter().any(|f| f == field))
+    })
+}
+
+// a single test, since the subscriber is global
+#[test]
+fn test_tracing_spans() {
+    tracing::subscriber::set_global_default(Collector).unwrap();
+    let pixels = pixels(16, 8, 4);
+
+    let encoded = Encoder::new(&pixels, 16, 8).unwrap().encode_to_vec().unwrap();
+    let spans = take_spans();
+    let bytes = format!("bytes={}", encoded.len());
+    let fields = ["method=encode_to_buf", "width=16", "height=8", "channels=4", &bytes];
+    assert!(has(&spans, "qoi::encode", &fields), "{spans:?}");
+    assert!(has(&spans, "qoi::encode_ops", &["n_pixels=128"]), "{spans:?}");
+
+    let options = DecoderOptions::new().max_unique_colors(1000);
+    let decoded = Decoder::new(&encoded).unwrap().with_options(options).decode_to_vec().unwrap();
+    let spans = take_spans();
+    let bytes = format!("bytes={}", decoded.len());
+    assert!(has(&spans, "qoi::decode", &["method=decode_to_buf", "width=16", &bytes]), "{spans:?}");
+    assert!(has(&spans, "qoi::check_colors", &["limit=1000"]), "{spans:?}");
+
+    // errors are recorded on the span of the call that failed
+    let truncated = &encoded[..encoded.len() / 2];
+    assert!(decode_to_vec(truncated).is_err());
+    let spans = take_spans();
+    assert!(
+        spans.iter().any(|span| span.fields.iter().any(|f| f.starts_with("error="))),
+        "{spans:?}"
+    );
+}
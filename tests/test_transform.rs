@@ -0,0 +1,65 @@
+mod common;
+
+use std::io::{self, Read};
+
+use qoi::{Decoder, Encoder, EncoderOptions, Header, HeaderFormat, TransformReader, XorTransform};
+
+use common::pixels;
+
+/// Reader handing out at most 5 bytes per call, so that transforms see odd offsets.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(5).min(self.0.len());
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_xor_transform_round_trip() {
+    let pixels = pixels(16, 8, 4);
+    let mut encoder = Encoder::new(&pixels, 16, 8).unwrap();
+    let plain = encoder.encode_to_vec().unwrap();
+    let key = XorTransform::new(*b"secret key");
+    let mut transformed = encoder.encode_to_vec_transformed(key).unwrap();
+    let mut buf = vec![0; encoder.required_buf_len()];
+    let n_written = encoder.encode_to_buf_transformed(&mut buf, key).unwrap();
+    assert_eq!(buf[..n_written], transformed);
+
+    // the header is left alone, everything after it is transformed
+    assert_eq!(transformed[..12], plain[..12]);
+    assert_ne!(transformed[12..], plain[12..]);
+    assert_eq!(Header::decode(&transformed).unwrap(), Header::decode(&plain).unwrap());
+
+    let reader = TransformReader::new(Trickle(&transformed), key);
+    let mut decoder = Decoder::from_stream(reader).unwrap();
+    assert_eq!(decoder.decode_to_vec().unwrap(), pixels);
+    let mut decoder = Decoder::new_transformed(&mut transformed, key).unwrap();
+    assert_eq!(decoder.decode_to_vec().unwrap(), pixels);
+}
+
+#[test]
+fn test_xor_transform_reference_header() {
+    let pixels = pixels(16, 8, 3);
+    let options = EncoderOptions::new().header_format(HeaderFormat::Reference);
+    let mut encoder = Encoder::new(&pixels, 16, 8).unwrap().with_options(options);
+    let plain = encoder.encode_to_vec().unwrap();
+    let key = XorTransform::new(*b"secret key");
+    let mut transformed = encoder.encode_to_vec_transformed(key).unwrap();
+    let mut buf = vec![0; encoder.required_buf_len()];
+    let n_written = encoder.encode_to_buf_transformed(&mut buf, key).unwrap();
+    assert_eq!(buf[..n_written], transformed);
+
+    // the whole 14-byte header is left alone
+    assert_eq!(transformed[..14], plain[..14]);
+    assert_ne!(transformed[14], plain[14]);
+
+    let reader = TransformReader::new(Trickle(&transformed), key);
+    let mut decoder = Decoder::from_stream(reader).unwrap();
+    assert_eq!(decoder.decode_to_vec().unwrap(), pixels);
+    let mut decoder = Decoder::new_transformed(&mut transformed, key).unwrap();
+    assert_eq!(decoder.decode_to_vec().unwrap(), pixels);
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use qoi::{Channels, Error, Header, Pixel, PixelsView};
+
+use common::pixels;
+
+#[test]
+fn test_pixels_view() {
+    let header = Header::try_new(5, 3, None).unwrap();
+    for (n_channels, channels) in [(2, Channels::La), (3, Channels::Rgb), (4, Channels::Rgba)] {
+        let data = pixels(5, 3, n_channels);
+        let view = PixelsView::new(&data, &header).unwrap();
+        assert_eq!((view.width(), view.height(), view.channels()), (5, 3, channels));
+        assert_eq!(view.as_bytes(), &data[..]);
+
+        let bpp = usize::from(n_channels);
+        assert_eq!(view.row(2), Some(&data[10 * bpp..]));
+        assert_eq!(view.row(3), None);
+        assert_eq!(view.rows().len(), 3);
+        assert!(view.rows().eq(data.chunks_exact(5 * bpp)));
+
+        let src = &data[(5 + 4) * bpp..][..bpp];
+        let expected = match n_channels {
+            2 => [src[0], src[0], src[0], src[1]],
+            3 => [src[0], src[1], src[2], 0xff],
+            _ => [src[0], src[1], src[2], src[3]],
+        };
+        assert_eq!(view.get(4, 1), Some(Pixel::from(expected)));
+        assert_eq!(view.get(5, 1), None);
+        assert_eq!(view.get(0, 3), None);
+
+        let all: Vec<_> = view.pixels().collect();
+        assert_eq!(all.len(), 15);
+        assert_eq!(all[5 + 4], (4, 1, Pixel::from(expected)));
+        assert!(all.iter().all(|&(x, y, px)| view.get(x, y) == Some(px)));
+    }
+}
+
+#[test]
+fn test_pixels_view_errors() {
+    let header = Header::try_new(5, 3, None).unwrap();
+    for len in [0, 15, 5 * 3 * 4 - 1, 5 * 3 * 5] {
+        let data = vec![0; len];
+        let err = PixelsView::new(&data, &header);
+        assert!(matches!(err, Err(Error::InvalidImageLength { .. })), "{len}");
+    }
+}
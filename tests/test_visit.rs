@@ -0,0 +1,23 @@
+mod common;
+
+use qoi::{decode_to_vec, decode_visit, encode_to_vec};
+
+use common::pixels;
+
+#[test]
+fn test_decode_visit() {
+    let (width, height) = (13, 7);
+    let encoded = encode_to_vec(pixels(width, height, 3), width, height).unwrap();
+    let (header, expected) = decode_to_vec(&encoded).unwrap();
+    let mut visited = vec![0; expected.len()];
+    let mut n_visited = 0;
+    let visited_header = decode_visit(&encoded, |x, y, px| {
+        let i = (y as usize * width as usize + x as usize) * 3;
+        visited[i..i + 3].copy_from_slice(&px[..3]);
+        n_visited += 1;
+    })
+    .unwrap();
+    assert_eq!(visited_header, header);
+    assert_eq!(n_visited, width * height);
+    assert_eq!(visited, expected);
+}
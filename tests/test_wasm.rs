@@ -0,0 +1,19 @@
+#![cfg(feature = "wasm")]
+
+mod common;
+
+use qoi::wasm::{decode, decode_header, encode, ImageInfo};
+
+use common::pixels;
+
+// errors are thrown as JavaScript values, which only exist on wasm targets
+#[test]
+fn test_wasm_roundtrip() {
+    let (width, height) = (13, 6);
+    let pixels = pixels(width, height, 3);
+    let encoded = encode(&pixels, width, height).unwrap();
+    let info = decode_header(&encoded).unwrap();
+    assert_eq!(info, ImageInfo { width: 13, height: 6, channels: 3 });
+    assert_eq!(decode(&encoded, 0).unwrap(), pixels);
+    assert_eq!(decode(&encoded, 4).unwrap().len(), pixels.len() / 3 * 4);
+}
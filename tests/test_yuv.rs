@@ -0,0 +1,82 @@
+mod common;
+
+use qoi::{decode_to_vec, Channels, Decoder, Encoder, EncoderOptions, Error, YuvMatrix};
+
+use common::pixels;
+
+/// BT.601 conversion of a limited range sample, in floating point.
+fn to_rgb(y: u8, u: u8, v: u8) -> [f64; 3] {
+    let (y, u, v) = (f64::from(y) - 16.0, f64::from(u) - 128.0, f64::from(v) - 128.0);
+    let rgb = [1.164 * y + 1.596 * v, 1.164 * y - 0.391 * u - 0.813 * v, 1.164 * y + 2.018 * u];
+    rgb.map(|x| x.clamp(0.0, 255.0))
+}
+
+#[test]
+fn test_from_yuv420() {
+    let (width, height) = (13, 7);
+    let (n_chroma, chroma_width) = (7 * 4, 7);
+    let y = pixels(width, height, 1);
+    let u = pixels(n_chroma, 1, 1);
+    let v: Vec<u8> = u.iter().rev().copied().collect();
+    let uv: Vec<u8> = u.iter().zip(&v).flat_map(|(&u, &v)| [u, v]).collect();
+
+    let options = EncoderOptions::new().restart_interval(2).verify_output(true);
+    let mut encoder =
+        Encoder::from_yuv420(&y, &u, &v, width, height).unwrap().with_options(options);
+    assert_eq!(encoder.channels(), Channels::Rgb);
+    let encoded = encoder.encode_to_vec().unwrap();
+    let mut buf = vec![0; encoder.required_buf_len()];
+    assert!(encoder.encode_chunks(&mut buf, 5).all(|part| part.is_ok()));
+    assert_eq!(buf[..encoded.len()], encoded);
+    let nv12 = Encoder::from_nv12(&y, &uv, width, height).unwrap().with_options(options);
+    assert_eq!(nv12.into_owned().encode_to_vec().unwrap(), encoded);
+
+    let (_, rgb) = decode_to_vec(&encoded).unwrap();
+    for (i, px) in rgb.chunks_exact(3).enumerate() {
+        let (x, row) = (i % width as usize, i / width as usize);
+        let c = row / 2 * chroma_width + x / 2;
+        let expected = to_rgb(y[i], u[c], v[c]);
+        for (&actual, expected) in px.iter().zip(expected) {
+            assert!((f64::from(actual) - expected).abs() <= 1.0, "{px:?} vs {expected:?}");
+        }
+    }
+
+    let bt709 = Encoder::from_yuv420(&y, &u, &v, width, height).unwrap();
+    let bt709 = bt709.with_yuv_matrix(YuvMatrix::Bt709).encode_to_vec().unwrap();
+    assert_ne!(Decoder::new(&bt709).unwrap().decode_to_vec().unwrap(), rgb);
+    assert!(Encoder::from_nv12(&y, &u, width, height).is_err());
+}
+
+#[test]
+fn test_decode_to_yuv420() {
+    // 3x3 pixels: the chroma samples cover 2x2, 1x2, 2x1 and 1x1 pixels
+    let pixels = [[0xff; 4], [0, 0, 0, 0xff], [0xff; 4]].repeat(3).concat();
+    let encoded = Encoder::new(&pixels, 3, 3).unwrap().encode_to_vec().unwrap();
+    let (mut y, mut u, mut v) = ([0; 9], [0; 4], [0; 4]);
+    let mut decoder = Decoder::new(&encoded).unwrap();
+    assert!(matches!(
+        decoder.decode_to_yuv420(&mut y, &mut u[..3], &mut v, YuvMatrix::Bt601),
+        Err(Error::OutputBufferTooSmall { size: 3, required: 4 })
+    ));
+    assert_eq!(decoder.decode_to_yuv420(&mut y, &mut u, &mut v, YuvMatrix::Bt601).unwrap(), 9);
+    assert_eq!(y, [235, 16, 235].repeat(3)[..]);
+    assert_eq!((u, v), ([128; 4], [128; 4]));
+
+    // converting colors within the RGB gamut back and forth only loses a little precision
+    let (width, height) = (16, 8);
+    let y: Vec<u8> = (0..width * height).map(|i| 60 + (i % 128) as u8).collect();
+    let u: Vec<u8> = (0..32).map(|i| 112 + i as u8).collect();
+    let v: Vec<u8> = (0..32).map(|i| 144 - i as u8).collect();
+    for matrix in [YuvMatrix::Bt601, YuvMatrix::Bt709] {
+        let encoder = Encoder::from_yuv420(&y, &u, &v, width, height).unwrap();
+        let encoded = encoder.with_yuv_matrix(matrix).encode_to_vec().unwrap();
+        let (mut y2, mut u2, mut v2) = (vec![0; y.len()], vec![0; 32], vec![0; 32]);
+        Decoder::new(&encoded)
+            .unwrap()
+            .decode_to_yuv420(&mut y2, &mut u2, &mut v2, matrix)
+            .unwrap();
+        for (a, b) in y.iter().chain(&u).chain(&v).zip(y2.iter().chain(&u2).chain(&v2)) {
+            assert!(a.abs_diff(*b) <= 2, "{a} vs {b}");
+        }
+    }
+}